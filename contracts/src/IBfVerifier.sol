@@ -0,0 +1,17 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+/// @title Brainfuck zkVM verifier interface.
+/// @notice Verifies the shard proofs of Brainfuck programs. No implementation ships yet: the
+///         interface pins the calldata layout encoded by `bf_sdk::evm`.
+interface IBfVerifier {
+    /// @notice Reverts unless the proof is valid.
+    /// @param programVKey The digest of the verifying key of the program (`bf_sdk::vk_digest`).
+    /// @param publicValues The public values of every shard, in order, each a big-endian uint32.
+    /// @param proofBytes The bincode-serialized shard proofs.
+    function verifyProof(
+        bytes32 programVKey,
+        bytes calldata publicValues,
+        bytes calldata proofBytes
+    ) external view;
+}
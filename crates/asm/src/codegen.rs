@@ -0,0 +1,120 @@
+use crate::Statement;
+
+struct Codegen {
+    code: String,
+    /// The cell the data pointer is at.
+    ptr: usize,
+    /// The first free scratch cell.
+    scratch: usize,
+}
+
+impl Codegen {
+    fn goto(&mut self, cell: usize) {
+        let (c, n) = match cell >= self.ptr {
+            true => ('>', cell - self.ptr),
+            false => ('<', self.ptr - cell),
+        };
+        self.code.extend(std::iter::repeat_n(c, n));
+        self.ptr = cell;
+    }
+
+    fn add(&mut self, cell: usize, value: u8) {
+        self.goto(cell);
+        // Go the short way around the byte.
+        match value <= 128 {
+            true => self.code.extend(std::iter::repeat_n('+', value as usize)),
+            false => self.code.extend(std::iter::repeat_n('-', 256 - value as usize)),
+        }
+    }
+
+    fn clear(&mut self, cell: usize) {
+        self.goto(cell);
+        self.code.push_str("[-]");
+    }
+
+    /// Emits `body` in a loop on `cond`, with the pointer at `cond` at both ends.
+    fn repeat(&mut self, cond: usize, body: impl FnOnce(&mut Self)) {
+        self.goto(cond);
+        self.code.push('[');
+        body(self);
+        self.goto(cond);
+        self.code.push(']');
+    }
+
+    /// Adds `src` to every cell of `dsts`, clearing `src`.
+    fn drain(&mut self, src: usize, dsts: &[usize]) {
+        self.repeat(src, |cg| {
+            cg.add(src, 255);
+            for &dst in dsts {
+                cg.add(dst, 1);
+            }
+        });
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Add(cell, value) => self.add(*cell, *value),
+            Statement::Sub(cell, value) => self.add(*cell, value.wrapping_neg()),
+            Statement::Set(cell, value) => {
+                self.clear(*cell);
+                self.add(*cell, *value);
+            }
+            Statement::Read(cell) => {
+                self.goto(*cell);
+                self.code.push(',');
+            }
+            Statement::Print(cell) => {
+                self.goto(*cell);
+                self.code.push('.');
+            }
+            Statement::Move(src, dst) => self.drain(*src, &[*dst]),
+            Statement::Copy(src, dst) => {
+                let tmp = self.alloc();
+                self.drain(*src, &[*dst, tmp]);
+                self.drain(tmp, &[*src]);
+                self.free();
+            }
+            Statement::While(cond, body) => {
+                self.repeat(*cond, |cg| cg.statements(body));
+            }
+            Statement::If(cond, body) => {
+                // Copy the condition into a flag, and clear the flag after the body so the loop
+                // runs at most once.
+                let flag = self.alloc();
+                let tmp = self.alloc();
+                self.drain(*cond, &[flag, tmp]);
+                self.drain(tmp, &[*cond]);
+                self.free();
+                self.repeat(flag, |cg| {
+                    cg.statements(body);
+                    cg.clear(flag);
+                });
+                self.free();
+            }
+        }
+    }
+
+    fn statements(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            self.statement(statement);
+        }
+    }
+
+    /// Allocates a scratch cell, which is zero.
+    fn alloc(&mut self) -> usize {
+        self.scratch += 1;
+        self.scratch - 1
+    }
+
+    /// Frees the last allocated scratch cell, which must be zero again.
+    fn free(&mut self) {
+        self.scratch -= 1;
+    }
+}
+
+/// Compiles parsed statements to Brainfuck.
+pub fn compile_statements(num_vars: usize, statements: &[Statement]) -> String {
+    let mut cg = Codegen { code: String::new(), ptr: 0, scratch: num_vars };
+    cg.statements(statements);
+    cg.code
+}
@@ -0,0 +1,107 @@
+//! A tiny structured language that compiles to Brainfuck.
+//!
+//! A program is a list of statements, one per line. `#` starts a comment.
+//!
+//! ```text
+//! var n, a, b        # variables live on fixed cells, in declaration order
+//! read n             # n = next input byte
+//! set a 1            # a = 1
+//! add b 'A'          # b += 65; constants are decimal or character literals
+//! sub n 1            # n -= 1
+//! move a b           # b += a; a = 0
+//! copy a b           # b += a
+//! while n {          # loop while n != 0
+//!   sub n 1
+//! }
+//! if a {             # run the block once if a != 0
+//!   print b          # output b
+//! }
+//! ```
+//!
+//! Arithmetic wraps modulo 256. `if` and `copy` use scratch cells placed after the variables,
+//! and the body of an `if` must not move its condition.
+
+mod codegen;
+mod parser;
+
+pub use codegen::compile_statements;
+pub use parser::{parse, Statement};
+
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("line {line}: {message}")]
+pub struct AsmError {
+    /// The 1-based line of the error.
+    pub line: usize,
+    pub message: String,
+}
+
+impl AsmError {
+    pub(crate) fn new(line: usize, message: impl Into<String>) -> Self {
+        Self { line, message: message.into() }
+    }
+}
+
+/// Compiles a program to Brainfuck.
+pub fn compile(source: &str) -> Result<String, AsmError> {
+    let (num_vars, statements) = parse(source)?;
+    Ok(compile_statements(num_vars, &statements))
+}
+
+#[cfg(test)]
+mod tests {
+    use bf_core_executor::{Executor, Program};
+
+    use super::*;
+
+    fn run(source: &str, input: Vec<u8>) -> Vec<u8> {
+        let code = compile(source).unwrap();
+        let mut runtime = Executor::new(Program::from(&code).unwrap(), input);
+        runtime.run().unwrap();
+        runtime.state.output_stream
+    }
+
+    #[test]
+    fn test_fibonacci() {
+        let source = "
+            var n, a, b, t
+            read n
+            set b 1
+            while n {
+                sub n 1
+                copy b t    # t = b
+                move a b    # b = a + b
+                move t a    # a = old b
+            }
+            print a
+        ";
+        assert_eq!(run(source, vec![10]), vec![55]);
+        assert_eq!(run(source, vec![17]), vec![(1597 % 256) as u8]);
+    }
+
+    #[test]
+    fn test_if() {
+        let source = "
+            var x, y
+            read x
+            set y 'N'
+            if x {
+                set y 'Y'
+            }
+            print y
+            print x
+        ";
+        assert_eq!(run(source, vec![0]), b"N\0");
+        assert_eq!(run(source, vec![7]), b"Y\x07");
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(compile("add x 1").unwrap_err().line, 1);
+        assert_eq!(compile("var x\nwhile x {").unwrap_err().line, 2);
+        assert_eq!(compile("var x\n}").unwrap_err().line, 2);
+        assert_eq!(compile("var x\nadd x 256").unwrap_err().line, 2);
+        assert!(compile("var x, x").is_err());
+    }
+}
@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+
+use crate::AsmError;
+
+/// A statement, with variables resolved to their cells.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Statement {
+    Add(usize, u8),
+    Sub(usize, u8),
+    Set(usize, u8),
+    Read(usize),
+    Print(usize),
+    Move(usize, usize),
+    Copy(usize, usize),
+    While(usize, Vec<Statement>),
+    If(usize, Vec<Statement>),
+}
+
+enum Block {
+    While(usize),
+    If(usize),
+}
+
+/// Parses a program, returning the number of variables and the statements.
+pub fn parse(source: &str) -> Result<(usize, Vec<Statement>), AsmError> {
+    let mut vars = HashMap::new();
+    // The statements of the enclosing blocks, with the block that is being parsed on top.
+    let mut stack: Vec<(usize, Option<Block>, Vec<Statement>)> = vec![(0, None, vec![])];
+
+    for (idx, raw) in source.lines().enumerate() {
+        let line = idx + 1;
+        let text = raw.split('#').next().unwrap().trim();
+        if text.is_empty() {
+            continue;
+        }
+
+        let (keyword, rest) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+        let args = rest.split([',', ' ', '\t']).filter(|a| !a.is_empty()).collect::<Vec<_>>();
+
+        if keyword == "var" {
+            if stack.len() > 1 {
+                return Err(AsmError::new(line, "variables must be declared at the top level"));
+            }
+            for name in &args {
+                if !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                    return Err(AsmError::new(line, format!("invalid variable `{name}`")));
+                }
+                let cell = vars.len();
+                if vars.insert(name.to_string(), cell).is_some() {
+                    return Err(AsmError::new(line, format!("`{name}` is already declared")));
+                }
+            }
+            continue;
+        }
+
+        let var = |i: usize| -> Result<usize, AsmError> {
+            let name = args.get(i).ok_or_else(|| AsmError::new(line, "missing variable"))?;
+            vars.get(*name)
+                .copied()
+                .ok_or_else(|| AsmError::new(line, format!("undeclared variable `{name}`")))
+        };
+        let constant = |i: usize| -> Result<u8, AsmError> {
+            let arg = args.get(i).ok_or_else(|| AsmError::new(line, "missing constant"))?;
+            parse_constant(arg)
+                .ok_or_else(|| AsmError::new(line, format!("invalid constant `{arg}`")))
+        };
+        let arity = |n: usize| -> Result<(), AsmError> {
+            match args.len() == n {
+                true => Ok(()),
+                false => Err(AsmError::new(line, format!("`{keyword}` expects {n} arguments"))),
+            }
+        };
+
+        let statement = match keyword {
+            "add" => {
+                arity(2)?;
+                Statement::Add(var(0)?, constant(1)?)
+            }
+            "sub" => {
+                arity(2)?;
+                Statement::Sub(var(0)?, constant(1)?)
+            }
+            "set" => {
+                arity(2)?;
+                Statement::Set(var(0)?, constant(1)?)
+            }
+            "read" => {
+                arity(1)?;
+                Statement::Read(var(0)?)
+            }
+            "print" => {
+                arity(1)?;
+                Statement::Print(var(0)?)
+            }
+            "move" | "copy" => {
+                arity(2)?;
+                let (src, dst) = (var(0)?, var(1)?);
+                if src == dst {
+                    return Err(AsmError::new(line, "source and destination must differ"));
+                }
+                match keyword {
+                    "move" => Statement::Move(src, dst),
+                    _ => Statement::Copy(src, dst),
+                }
+            }
+            "while" | "if" => {
+                if args.len() != 2 || args[1] != "{" {
+                    return Err(AsmError::new(line, format!("expected `{keyword} <var> {{`")));
+                }
+                let cond = var(0)?;
+                let block = match keyword {
+                    "while" => Block::While(cond),
+                    _ => Block::If(cond),
+                };
+                stack.push((line, Some(block), vec![]));
+                continue;
+            }
+            "}" => {
+                if !args.is_empty() || stack.len() == 1 {
+                    return Err(AsmError::new(line, "unexpected `}`"));
+                }
+                let (_, block, body) = stack.pop().unwrap();
+                match block.unwrap() {
+                    Block::While(cond) => Statement::While(cond, body),
+                    Block::If(cond) => Statement::If(cond, body),
+                }
+            }
+            _ => return Err(AsmError::new(line, format!("unknown statement `{keyword}`"))),
+        };
+        stack.last_mut().unwrap().2.push(statement);
+    }
+
+    if stack.len() > 1 {
+        let (line, _, _) = stack.pop().unwrap();
+        return Err(AsmError::new(line, "unclosed block"));
+    }
+    Ok((vars.len(), stack.pop().unwrap().2))
+}
+
+/// Parses a decimal constant or a character literal such as `'A'`.
+fn parse_constant(arg: &str) -> Option<u8> {
+    match arg.strip_prefix('\'').and_then(|a| a.strip_suffix('\'')) {
+        Some(c) if c.len() == 1 && c.is_ascii() => Some(c.as_bytes()[0]),
+        Some(_) => None,
+        None => arg.parse().ok(),
+    }
+}
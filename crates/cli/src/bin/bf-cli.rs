@@ -0,0 +1,47 @@
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+
+use bf_cli::commands::{
+    bench::BenchCmd, build::BuildCmd, coverage::CoverageCmd, disasm::DisasmCmd, gas::GasCmd,
+    minimize::MinimizeCmd, profile::ProfileCmd, proof_size::ProofSizeCmd, prove::ProveCmd,
+    trace::TraceCmd, visualize::VisualizeCmd,
+};
+
+/// Tools for developing and proving Brainfuck programs.
+#[derive(Parser)]
+#[command(name = "bf-cli", author, version, about, long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    Bench(BenchCmd),
+    Build(BuildCmd),
+    Coverage(CoverageCmd),
+    Disasm(DisasmCmd),
+    Gas(GasCmd),
+    Minimize(MinimizeCmd),
+    Profile(ProfileCmd),
+    ProofSize(ProofSizeCmd),
+    Prove(ProveCmd),
+    Trace(TraceCmd),
+    Visualize(VisualizeCmd),
+}
+
+fn main() -> Result<()> {
+    match Cli::parse().command {
+        Command::Bench(cmd) => cmd.run(),
+        Command::Build(cmd) => cmd.run(),
+        Command::Coverage(cmd) => cmd.run(),
+        Command::Disasm(cmd) => cmd.run(),
+        Command::Gas(cmd) => cmd.run(),
+        Command::Minimize(cmd) => cmd.run(),
+        Command::Profile(cmd) => cmd.run(),
+        Command::ProofSize(cmd) => cmd.run(),
+        Command::Prove(cmd) => cmd.run(),
+        Command::Trace(cmd) => cmd.run(),
+        Command::Visualize(cmd) => cmd.run(),
+    }
+}
@@ -0,0 +1,32 @@
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+
+use bf_cli::commands::{new::NewCmd, prove::ProveCmd};
+
+#[derive(Parser)]
+#[command(name = "cargo", bin_name = "cargo")]
+enum Cargo {
+    Bf(BfCli),
+}
+
+/// Scaffold, execute and prove Brainfuck programs.
+#[derive(clap::Args)]
+#[command(author, version, about, long_about = None)]
+struct BfCli {
+    #[command(subcommand)]
+    command: BfCmd,
+}
+
+#[derive(Subcommand)]
+enum BfCmd {
+    New(NewCmd),
+    Prove(ProveCmd),
+}
+
+fn main() -> Result<()> {
+    let Cargo::Bf(args) = Cargo::parse();
+    match args.command {
+        BfCmd::New(cmd) => cmd.run(),
+        BfCmd::Prove(cmd) => cmd.run(),
+    }
+}
@@ -0,0 +1,157 @@
+use std::{fs, path::PathBuf, time::Instant};
+
+use anyhow::Result;
+use clap::Parser;
+
+use bf_sdk::{BfProver, CoreSC, CpuProver, ProverClient};
+use bf_stark::{koala_bear_poseidon2::FriParameters, serialized_size};
+
+use crate::{read_program, read_stdin};
+
+/// The program proven when none is given: it multiplies 8 by 8 and prints the product.
+pub const REFERENCE_PROGRAM: &str = "++++++++[>++++++++<-]>.";
+
+/// Measures the proving time and the proof size under FRI configurations.
+#[derive(Parser, Debug)]
+pub struct BenchCmd {
+    /// The `.bf` program to prove. Defaults to a small reference program.
+    pub program: Option<PathBuf>,
+
+    /// Comma-separated stdin bytes, e.g. `17,3`.
+    #[arg(long)]
+    pub stdin: Option<String>,
+
+    /// Prove under every combination of the given parameters, instead of the standard ones.
+    #[arg(long)]
+    pub sweep: bool,
+
+    /// The log blowups of the sweep.
+    #[arg(long, value_delimiter = ',', default_value = "1,2")]
+    pub log_blowups: Vec<usize>,
+
+    /// The numbers of queries of the sweep.
+    #[arg(long, value_delimiter = ',', default_value = "16,42,84")]
+    pub num_queries: Vec<usize>,
+
+    /// The proof of work bits of the sweep.
+    #[arg(long, value_delimiter = ',', default_value = "8,16")]
+    pub pow_bits: Vec<usize>,
+
+    /// Where to write the CSV. Defaults to stdout.
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+}
+
+/// The measurements of a proof under a FRI configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BenchRow {
+    pub parameters: FriParameters,
+    pub prove_ms: u128,
+    pub proof_bytes: u64,
+}
+
+impl BenchCmd {
+    pub fn run(&self) -> Result<()> {
+        let program = match &self.program {
+            Some(path) => read_program(path)?,
+            None => REFERENCE_PROGRAM.to_string(),
+        };
+        let stdin = read_stdin(self.stdin.as_deref(), None)?;
+
+        let configurations = if self.sweep {
+            sweep(&self.log_blowups, &self.num_queries, &self.pow_bits)
+        } else {
+            vec![FriParameters::standard()]
+        };
+        let mut rows = vec![];
+        for parameters in configurations {
+            eprintln!(
+                "proving with log_blowup={} num_queries={} pow_bits={}",
+                parameters.log_blowup, parameters.num_queries, parameters.proof_of_work_bits
+            );
+            rows.push(bench(&program, &stdin, parameters)?);
+        }
+
+        match &self.output {
+            Some(path) => fs::write(path, to_csv(&rows))?,
+            None => print!("{}", to_csv(&rows)),
+        }
+        Ok(())
+    }
+}
+
+/// Every combination of the given parameters.
+pub fn sweep(
+    log_blowups: &[usize],
+    num_queries: &[usize],
+    pow_bits: &[usize],
+) -> Vec<FriParameters> {
+    let mut configurations = vec![];
+    for &log_blowup in log_blowups {
+        for &num_queries in num_queries {
+            for &proof_of_work_bits in pow_bits {
+                configurations.push(FriParameters { log_blowup, num_queries, proof_of_work_bits });
+            }
+        }
+    }
+    configurations
+}
+
+/// Proves and verifies `program` under `parameters`, timing the proving only.
+pub fn bench(program: &str, stdin: &[u8], parameters: FriParameters) -> Result<BenchRow> {
+    let prover = BfProver::from_config(CoreSC::with_fri_parameters(parameters));
+    let client = ProverClient {
+        prover: Box::new(CpuProver::from_prover(prover)),
+        config: Default::default(),
+    };
+    let (pk, vk) = client.setup(program);
+
+    let start = Instant::now();
+    let proof = client.prove(&pk, stdin.to_vec()).run()?;
+    let prove_ms = start.elapsed().as_millis();
+    client.verify(&proof, &vk)?;
+
+    Ok(BenchRow { parameters, prove_ms, proof_bytes: serialized_size(&proof.proof) })
+}
+
+/// The rows as CSV, with a header.
+pub fn to_csv(rows: &[BenchRow]) -> String {
+    let mut csv =
+        "log_blowup,num_queries,pow_bits,security_bits,prove_ms,proof_bytes\n".to_string();
+    for row in rows {
+        let parameters = &row.parameters;
+        csv.push_str(&format!(
+            "{},{},{},{},{},{}\n",
+            parameters.log_blowup,
+            parameters.num_queries,
+            parameters.proof_of_work_bits,
+            parameters.conjectured_security_bits(),
+            row.prove_ms,
+            row.proof_bytes
+        ));
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bench_sweep() {
+        let configurations = sweep(&[1], &[8, 16], &[4]);
+        assert_eq!(configurations.len(), 2);
+
+        let rows = configurations
+            .into_iter()
+            .map(|parameters| bench(REFERENCE_PROGRAM, &[], parameters).unwrap())
+            .collect::<Vec<_>>();
+        assert!(rows[0].proof_bytes < rows[1].proof_bytes);
+
+        let csv = to_csv(&rows);
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].starts_with("1,8,4,12,"));
+        assert!(lines[2].starts_with("1,16,4,20,"));
+    }
+}
@@ -0,0 +1,52 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::{Context, Result};
+use clap::Parser;
+
+use crate::read_source;
+
+/// Compiles a `bf-lang` program to Brainfuck.
+#[derive(Parser, Debug)]
+pub struct BuildCmd {
+    /// The `.bfl` program to compile.
+    pub source: PathBuf,
+
+    /// Where to write the Brainfuck program. Defaults to the source with the `.bf` extension.
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+}
+
+impl BuildCmd {
+    pub fn run(&self) -> Result<()> {
+        let source = read_source(&self.source)?;
+        let code = bf_lang::compile(&source)
+            .with_context(|| format!("failed to compile {}", self.source.display()))?;
+
+        let output = self.output.clone().unwrap_or_else(|| self.source.with_extension("bf"));
+        fs::write(&output, &code)
+            .with_context(|| format!("failed to write {}", output.display()))?;
+        println!("wrote {} ({} commands)", output.display(), code.len());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{execute, read_program};
+
+    use super::*;
+
+    #[test]
+    fn test_build() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("double.bfl");
+        fs::write(&source, "let x = read();\nwrite(x * 2);\n").unwrap();
+
+        BuildCmd { source: source.clone(), output: None }.run().unwrap();
+        let program = read_program(&source.with_extension("bf")).unwrap();
+        assert_eq!(execute(&program, vec![21]).unwrap().state.output_stream, [42]);
+
+        fs::write(&source, "write(y);").unwrap();
+        assert!(BuildCmd { source, output: None }.run().is_err());
+    }
+}
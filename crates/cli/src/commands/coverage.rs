@@ -0,0 +1,62 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::{Context, Result};
+use clap::Parser;
+
+use bf_core_executor::{source_positions, Coverage};
+
+use crate::{execute, is_command, read_source, read_stdin};
+
+/// Reports the instructions and loop branches a run of a program covers.
+#[derive(Parser, Debug)]
+pub struct CoverageCmd {
+    /// The `.bf` program to run.
+    pub program: PathBuf,
+
+    /// Comma-separated stdin bytes, e.g. `17,3`.
+    #[arg(long)]
+    pub stdin: Option<String>,
+
+    /// A file whose contents are used as stdin.
+    #[arg(long, conflicts_with = "stdin")]
+    pub stdin_file: Option<PathBuf>,
+
+    /// Writes the coverage in the lcov format to this file, e.g. for `genhtml`.
+    #[arg(long)]
+    pub lcov: Option<PathBuf>,
+}
+
+impl CoverageCmd {
+    pub fn run(&self) -> Result<()> {
+        let source = read_source(&self.program)?;
+        let stdin = read_stdin(self.stdin.as_deref(), self.stdin_file.as_deref())?;
+        let runtime = execute(&source.chars().filter(is_command).collect::<String>(), stdin)?;
+
+        let coverage = Coverage::from_record(&runtime.record);
+        let positions = source_positions(&source);
+        let (branches, covered_branches) = coverage.branches();
+        println!(
+            "{}/{} instructions, {}/{} loop branches",
+            coverage.covered_pcs(),
+            coverage.pcs.len(),
+            covered_branches,
+            branches
+        );
+        for (pc, position) in coverage.pcs.iter().zip(&positions) {
+            if pc.hits == 0 {
+                println!(
+                    "not covered: {}:{} {}",
+                    position.line,
+                    position.column,
+                    pc.opcode.mnemonic()
+                );
+            }
+        }
+
+        if let Some(path) = &self.lcov {
+            let lcov = coverage.to_lcov(&self.program.display().to_string(), &positions);
+            fs::write(path, lcov).with_context(|| format!("failed to write {}", path.display()))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,118 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Parser;
+
+use bf_core_executor::{Instruction, Opcode, Program};
+
+use crate::{command_positions, is_command, read_source};
+
+/// Prints the instruction stream the machine proves.
+#[derive(Parser, Debug)]
+pub struct DisasmCmd {
+    /// The `.bf` program to disassemble.
+    pub program: PathBuf,
+
+    /// Collapse runs of the same `+`, `-`, `>` or `<` into one line.
+    #[arg(long)]
+    pub batched: bool,
+}
+
+impl DisasmCmd {
+    pub fn run(&self) -> Result<()> {
+        let source = read_source(&self.program)?;
+        let program = Program::from(&source.chars().filter(is_command).collect::<String>())?;
+        let positions = command_positions(&source);
+
+        println!("{:>11} {:>5} {:>6} {:>7} {:>10}", "pc", "depth", "instr", "target", "source");
+        for line in disassemble(&program, self.batched) {
+            let (src_line, src_col) = positions[line.start as usize];
+            let pcs = if line.len > 1 {
+                format!("{}..{}", line.start, line.start + line.len - 1)
+            } else {
+                line.start.to_string()
+            };
+            let instr = match line.len {
+                1 => line.instruction.opcode.mnemonic().to_string(),
+                n => format!("{}x{n}", line.instruction.opcode.mnemonic()),
+            };
+            let target = match line.instruction.opcode {
+                Opcode::LoopStart | Opcode::LoopEnd | Opcode::Set => {
+                    line.instruction.op_a.to_string()
+                }
+                _ => String::new(),
+            };
+            println!(
+                "{pcs:>11} {:>5} {}{instr:<6} {target:>7} {:>10}",
+                line.depth,
+                "  ".repeat(line.depth),
+                format!("{src_line}:{src_col}"),
+            );
+        }
+        Ok(())
+    }
+}
+
+/// One line of a disassembly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisasmLine {
+    /// The pc of the first instruction.
+    pub start: u32,
+    /// The number of instructions on this line.
+    pub len: u32,
+    pub instruction: Instruction,
+    /// The loop nesting depth.
+    pub depth: usize,
+}
+
+/// Disassembles a program, optionally collapsing runs of repeatable instructions.
+pub fn disassemble(program: &Program, batched: bool) -> Vec<DisasmLine> {
+    let mut lines: Vec<DisasmLine> = vec![];
+    let mut depth = 0;
+    for (pc, instruction) in program.instructions.iter().enumerate() {
+        if instruction.opcode == Opcode::LoopEnd {
+            depth -= 1;
+        }
+
+        let repeatable = matches!(
+            instruction.opcode,
+            Opcode::Add | Opcode::Sub | Opcode::MemStepForward | Opcode::MemStepBackward
+        );
+        match lines.last_mut() {
+            Some(last) if batched && repeatable && last.instruction == *instruction => {
+                last.len += 1;
+            }
+            _ => lines.push(DisasmLine {
+                start: pc as u32,
+                len: 1,
+                instruction: *instruction,
+                depth,
+            }),
+        }
+
+        if instruction.opcode == Opcode::LoopStart {
+            depth += 1;
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disassemble() {
+        let program = Program::from("++[->>+<<]").unwrap();
+
+        let lines = disassemble(&program, false);
+        assert_eq!(lines.len(), 10);
+        assert_eq!(lines[2].instruction.op_a, 9);
+        assert_eq!(lines[3].depth, 1);
+        assert_eq!(lines[9].depth, 0);
+
+        let lines = disassemble(&program, true);
+        let summary = lines.iter().map(|line| (line.start, line.len)).collect::<Vec<_>>();
+        assert_eq!(summary, vec![(0, 2), (2, 1), (3, 1), (4, 2), (6, 1), (7, 2), (9, 1)]);
+    }
+}
@@ -0,0 +1,75 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::Result;
+use clap::Parser;
+
+use bf_sdk::{
+    config::FriPreset,
+    gas::{estimate_verification_cost, GasModel, ProofLayout, VerificationCost},
+    BfProofWithPublicValues,
+};
+
+/// Estimates the gas of verifying a proof on Ethereum with a FRI verifier.
+#[derive(Parser, Debug)]
+pub struct GasCmd {
+    /// The proof file, as written by `bf-cli prove --output`.
+    pub proof: PathBuf,
+
+    /// The FRI preset the proof was generated with: `standard` or `fast`.
+    #[arg(long, default_value = "standard")]
+    pub fri_preset: FriPreset,
+
+    /// Print the estimate as JSON.
+    #[arg(long)]
+    pub json: bool,
+}
+
+impl GasCmd {
+    pub fn run(&self) -> Result<()> {
+        let proof = BfProofWithPublicValues::from_bytes(&fs::read(&self.proof)?)?;
+        let cost = estimate_gas(&proof, self.fri_preset);
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&cost)?);
+        } else {
+            println!(
+                "{:>12}{:>14}{:>14}{:>14}",
+                "calldata", "calldata gas", "execution gas", "total gas"
+            );
+            println!(
+                "{:>12}{:>14}{:>14}{:>14}",
+                cost.calldata_len,
+                cost.calldata_gas,
+                cost.execution_gas,
+                cost.total_gas()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// The estimated gas of verifying `proof` on Ethereum.
+pub fn estimate_gas(proof: &BfProofWithPublicValues, fri_preset: FriPreset) -> VerificationCost {
+    let layout = ProofLayout::from_proof(proof, &fri_preset.parameters());
+    estimate_verification_cost(&layout, &GasModel::ethereum())
+}
+
+#[cfg(test)]
+mod tests {
+    use bf_sdk::{ProverClient, ProverClientConfig};
+    use bf_stark::serialized_size;
+
+    use super::*;
+
+    #[test]
+    fn test_estimate_gas() {
+        let config = ProverClientConfig { fri_preset: FriPreset::Fast, ..Default::default() };
+        let client = ProverClient::from_config(config).unwrap();
+        let (pk, _) = client.setup("++[->+<]>.");
+        let proof = client.prove(&pk, vec![]).run().unwrap();
+
+        let cost = estimate_gas(&proof, FriPreset::Fast);
+        assert!(cost.calldata_len as u64 > serialized_size(&proof.proof));
+        assert!(cost.total_gas() > cost.calldata_gas);
+    }
+}
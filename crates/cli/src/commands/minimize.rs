@@ -0,0 +1,195 @@
+//! Shrinking of failing programs by delta debugging.
+//!
+//! A candidate fails when the fast executor runs it to completion within the cycle limit but
+//! proving or verifying it does not succeed. Every proving attempt runs `bf-cli prove` in a child
+//! process, so that panics and the exits of the `debug` constraint checks are caught as well.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::{self, Command, Stdio},
+};
+
+use anyhow::{ensure, Result};
+use clap::Parser;
+
+use bf_core_executor::{Executor, Program};
+
+use crate::{read_program, read_stdin};
+
+/// Shrinks a program and its input that fail to prove or verify to a minimal reproducer.
+#[derive(Parser, Debug)]
+pub struct MinimizeCmd {
+    /// The failing `.bf` program.
+    pub program: PathBuf,
+
+    /// Comma-separated stdin bytes, e.g. `17,3`.
+    #[arg(long)]
+    pub stdin: Option<String>,
+
+    /// A file whose contents are used as stdin.
+    #[arg(long, conflicts_with = "stdin")]
+    pub stdin_file: Option<PathBuf>,
+
+    /// Candidates running for more cycles are discarded.
+    #[arg(long, default_value_t = 1 << 20)]
+    pub max_cycles: u64,
+
+    /// Where to write the minimized program. The minimized stdin is written next to it, with
+    /// the `.stdin` extension.
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+}
+
+impl MinimizeCmd {
+    pub fn run(&self) -> Result<()> {
+        let program = read_program(&self.program)?;
+        let stdin = read_stdin(self.stdin.as_deref(), self.stdin_file.as_deref())?;
+
+        let dir = env::temp_dir().join(format!("bf-minimize-{}", process::id()));
+        fs::create_dir_all(&dir)?;
+        let mut attempts = 0;
+        let mut fails = |program: &[char], stdin: &[u8]| {
+            attempts += 1;
+            let program = program.iter().collect::<String>();
+            executes(&program, stdin, self.max_cycles)
+                && proving_fails(&dir, &program, stdin).unwrap_or(false)
+        };
+
+        let program = program.chars().collect::<Vec<_>>();
+        ensure!(fails(&program, &stdin), "the program executes, proves and verifies fine");
+
+        let (program, stdin) = minimize(program, stdin, &mut fails);
+        fs::remove_dir_all(&dir)?;
+
+        let program = program.into_iter().collect::<String>();
+        println!("minimized after {attempts} attempts");
+        println!("program: {program}");
+        println!("stdin: {stdin:?}");
+        if let Some(path) = &self.output {
+            fs::write(path, &program)?;
+            fs::write(path.with_extension("stdin"), &stdin)?;
+            println!("wrote {}", path.display());
+        }
+        Ok(())
+    }
+}
+
+/// Whether the executor runs `program` on `stdin` to completion within `max_cycles`.
+pub fn executes(program: &str, stdin: &[u8], max_cycles: u64) -> bool {
+    let Ok(program) = Program::from(program) else {
+        return false;
+    };
+    if program.instructions.is_empty() {
+        return false;
+    }
+    Executor::new(program, stdin.to_vec()).with_max_cycles(max_cycles).run().is_ok()
+}
+
+/// Whether `bf-cli prove` fails on `program` and `stdin`, using the fast FRI preset.
+fn proving_fails(dir: &Path, program: &str, stdin: &[u8]) -> Result<bool> {
+    let program_path = dir.join("candidate.bf");
+    let stdin_path = dir.join("candidate.stdin");
+    fs::write(&program_path, program)?;
+    fs::write(&stdin_path, stdin)?;
+
+    let status = Command::new(env::current_exe()?)
+        .arg("prove")
+        .arg(&program_path)
+        .arg("--stdin-file")
+        .arg(&stdin_path)
+        .env("BF_FRI_PRESET", "fast")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()?;
+    Ok(!status.success())
+}
+
+/// Alternately shrinks the program and the input until neither can be shrunk further.
+pub fn minimize(
+    mut program: Vec<char>,
+    mut stdin: Vec<u8>,
+    fails: &mut impl FnMut(&[char], &[u8]) -> bool,
+) -> (Vec<char>, Vec<u8>) {
+    loop {
+        let size = (program.len(), stdin.len());
+        program = ddmin(program, |program| fails(program, &stdin));
+        stdin = ddmin(stdin, |stdin| fails(&program, stdin));
+        // ddmin never tries the empty input.
+        if !stdin.is_empty() && fails(&program, &[]) {
+            stdin.clear();
+        }
+        if (program.len(), stdin.len()) == size {
+            return (program, stdin);
+        }
+    }
+}
+
+/// Zeller's ddmin: returns a subsequence of `items` that still satisfies `fails`, and from which
+/// no single chunk can be removed at the finest granularity. `fails(items)` must hold.
+pub fn ddmin<T: Clone>(mut items: Vec<T>, mut fails: impl FnMut(&[T]) -> bool) -> Vec<T> {
+    let mut chunks = 2;
+    while items.len() >= 2 {
+        let chunk_size = items.len().div_ceil(chunks);
+        let mut reduced = false;
+
+        // Try each chunk on its own, then each complement.
+        for start in (0..items.len()).step_by(chunk_size) {
+            let end = (start + chunk_size).min(items.len());
+            if fails(&items[start..end]) {
+                items = items[start..end].to_vec();
+                chunks = 2;
+                reduced = true;
+                break;
+            }
+        }
+        if !reduced {
+            for start in (0..items.len()).step_by(chunk_size) {
+                let end = (start + chunk_size).min(items.len());
+                let complement = [&items[..start], &items[end..]].concat();
+                if fails(&complement) {
+                    items = complement;
+                    chunks = (chunks - 1).max(2);
+                    reduced = true;
+                    break;
+                }
+            }
+        }
+
+        if !reduced {
+            if chunk_size == 1 {
+                break;
+            }
+            chunks = (chunks * 2).min(items.len());
+        }
+    }
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ddmin() {
+        let items = (0..50).collect::<Vec<_>>();
+        let minimized = ddmin(items, |items| items.contains(&7) && items.contains(&31));
+        assert_eq!(minimized, [7, 31]);
+    }
+
+    #[test]
+    fn test_minimize() {
+        // Fails whenever the program prints a 2, standing in for a proving failure.
+        let mut fails = |program: &[char], stdin: &[u8]| {
+            let program = program.iter().collect::<String>();
+            executes(&program, stdin, 1000)
+                && crate::execute(&program, stdin.to_vec())
+                    .is_ok_and(|runtime| runtime.state.output_stream.contains(&2))
+        };
+
+        let program = "+>,[-<+>]<+.>++.".chars().collect();
+        let (program, stdin) = minimize(program, vec![1, 9, 9], &mut fails);
+        assert_eq!(program.into_iter().collect::<String>(), "++.");
+        assert!(stdin.is_empty());
+    }
+}
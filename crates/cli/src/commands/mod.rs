@@ -0,0 +1,12 @@
+pub mod bench;
+pub mod build;
+pub mod coverage;
+pub mod disasm;
+pub mod gas;
+pub mod minimize;
+pub mod new;
+pub mod profile;
+pub mod proof_size;
+pub mod prove;
+pub mod trace;
+pub mod visualize;
@@ -0,0 +1,95 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::{bail, Result};
+use clap::Parser;
+
+/// Scaffolds a host crate with a guest `.bf` directory.
+#[derive(Parser, Debug)]
+pub struct NewCmd {
+    /// The directory to create. Its name is used as the crate name.
+    pub path: PathBuf,
+}
+
+const MANIFEST: &str = r#"[package]
+name = "{name}"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+bf-derive = { git = "https://github.com/felicityin/zkvm-brainfuck" }
+bf-sdk = { git = "https://github.com/felicityin/zkvm-brainfuck" }
+"#;
+
+const MAIN: &str = r#"use bf_sdk::{utils, ProverClient};
+
+/// The guest program, checked and stripped at compile time.
+const PROGRAM: &str = bf_derive::include_bf!("guest/main.bf");
+
+fn main() {
+    // Setup logging.
+    utils::setup_logger();
+
+    // Create an input stream and write '17' to it.
+    let stdin = vec![17];
+
+    // Execute the guest without generating a proof.
+    let client = ProverClient::new();
+    let output = client.execute(PROGRAM, stdin.clone()).run().unwrap();
+    println!("result: {output:?}");
+
+    // Generate and verify the proof.
+    let (pk, vk) = client.setup(PROGRAM);
+    let proof = client.prove(&pk, stdin).run().unwrap();
+    client.verify(&proof, &vk).expect("verification failed");
+    println!("verified proof");
+}
+"#;
+
+const GUEST: &str = "\
+Reads n and prints the nth Fibonacci number modulo 256
+,>+>+<<
+[->>[->+>+<<]<[->>+<<]>>[-<+>]>[-<<<+>>>]<<<<]
+>>.
+";
+
+impl NewCmd {
+    pub fn run(&self) -> Result<()> {
+        if self.path.exists() {
+            bail!("{} already exists", self.path.display());
+        }
+        let Some(name) = self.path.file_name().and_then(|name| name.to_str()) else {
+            bail!("invalid crate path {}", self.path.display());
+        };
+
+        fs::create_dir_all(self.path.join("src"))?;
+        fs::create_dir_all(self.path.join("guest"))?;
+        fs::write(self.path.join("Cargo.toml"), MANIFEST.replace("{name}", name))?;
+        fs::write(self.path.join("src/main.rs"), MAIN)?;
+        fs::write(self.path.join("guest/main.bf"), GUEST)?;
+        fs::write(self.path.join(".gitignore"), "/target\n")?;
+
+        println!("Created {name}. Run it with `cargo run --release`.");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("my-guest");
+        NewCmd { path: path.clone() }.run().unwrap();
+
+        let manifest = fs::read_to_string(path.join("Cargo.toml")).unwrap();
+        assert!(manifest.contains("name = \"my-guest\""));
+        let guest = fs::read_to_string(path.join("guest/main.bf")).unwrap();
+        let guest = guest.chars().filter(crate::is_command).collect::<String>();
+        let output = bf_sdk::ProverClient::new().execute(&guest, vec![17]).run().unwrap();
+        assert_eq!(output, vec![85]);
+
+        assert!(NewCmd { path }.run().is_err());
+    }
+}
@@ -0,0 +1,81 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Parser;
+use hashbrown::HashMap;
+use p3_koala_bear::KoalaBear;
+
+use bf_core_executor::Profile;
+use bf_core_machine::brainfuck::BfAir;
+use bf_stark::air::MachineAir;
+
+use crate::{chip_name, command_positions, execute, is_command, read_source, read_stdin};
+
+/// Profiles the cycles spent at each program counter.
+#[derive(Parser, Debug)]
+pub struct ProfileCmd {
+    /// The `.bf` program to profile.
+    pub program: PathBuf,
+
+    /// Comma-separated stdin bytes, e.g. `17,3`.
+    #[arg(long)]
+    pub stdin: Option<String>,
+
+    /// A file whose contents are used as stdin.
+    #[arg(long, conflicts_with = "stdin")]
+    pub stdin_file: Option<PathBuf>,
+
+    /// The number of program counters to show.
+    #[arg(long, default_value_t = 20)]
+    pub top: usize,
+}
+
+impl ProfileCmd {
+    pub fn run(&self) -> Result<()> {
+        let source = read_source(&self.program)?;
+        let stdin = read_stdin(self.stdin.as_deref(), self.stdin_file.as_deref())?;
+        let runtime = execute(&source.chars().filter(is_command).collect::<String>(), stdin)?;
+
+        let profile = Profile::from_record(&runtime.record);
+        let positions = command_positions(&source);
+
+        // Every cycle fills a row of the CPU chip and a row of the chip of its instruction.
+        let costs = BfAir::<KoalaBear>::chips()
+            .into_iter()
+            .map(|chip| (chip.name(), chip.cost()))
+            .collect::<HashMap<_, _>>();
+        let cells_per_cycle = |opcode| costs["Cpu"] + costs[chip_name(opcode)];
+        let total_cells =
+            profile.pcs.iter().map(|pc| pc.cycles * cells_per_cycle(pc.opcode)).sum::<u64>();
+
+        println!("{} cycles, ~{} trace cells", profile.total_cycles, total_cells);
+        println!(
+            "{:>6} {:>10} {:>4} {:>12} {:>8} {:>14} {:>8} {:>12}",
+            "pc", "source", "op", "cycles", "cycles%", "cells", "cells%", "chip"
+        );
+        for pc in profile.hottest(self.top) {
+            let (line, col) = positions[pc.pc as usize];
+            let cells = pc.cycles * cells_per_cycle(pc.opcode);
+            println!(
+                "{:>6} {:>10} {:>4} {:>12} {:>7.2}% {:>14} {:>7.2}% {:>12}",
+                pc.pc,
+                format!("{line}:{col}"),
+                pc.opcode.mnemonic(),
+                pc.cycles,
+                percent(pc.cycles, profile.total_cycles),
+                cells,
+                percent(cells, total_cells),
+                chip_name(pc.opcode),
+            );
+        }
+        Ok(())
+    }
+}
+
+fn percent(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        100.0 * part as f64 / total as f64
+    }
+}
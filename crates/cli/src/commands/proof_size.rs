@@ -0,0 +1,78 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::Result;
+use clap::Parser;
+
+use bf_sdk::BfProofWithPublicValues;
+use bf_stark::{koala_bear_poseidon2::shard_proof_size_breakdown, serialized_size, ProofSection};
+
+/// Breaks the size of a proof down by component.
+#[derive(Parser, Debug)]
+pub struct ProofSizeCmd {
+    /// The proof file, as written by `bf-cli prove --output`.
+    pub proof: PathBuf,
+
+    /// Print the breakdown as JSON.
+    #[arg(long)]
+    pub json: bool,
+}
+
+impl ProofSizeCmd {
+    pub fn run(&self) -> Result<()> {
+        let bytes = fs::read(&self.proof)?;
+        let proof = BfProofWithPublicValues::from_bytes(&bytes)?;
+        let breakdown = proof_size_breakdown(&proof, bytes.len() as u64);
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&breakdown)?);
+        } else {
+            print!("{breakdown}");
+        }
+        Ok(())
+    }
+}
+
+/// The breakdown of a proof file of `file_bytes` bytes: the shard proofs, and the inputs, outputs
+/// and metadata stored next to them.
+pub fn proof_size_breakdown(proof: &BfProofWithPublicValues, file_bytes: u64) -> ProofSection {
+    ProofSection::node(
+        "proof file",
+        file_bytes,
+        vec![
+            ProofSection::node(
+                format!("shard proofs ({})", proof.proof.len()),
+                serialized_size(&proof.proof),
+                proof.proof.iter().map(shard_proof_size_breakdown).collect(),
+            ),
+            ProofSection::leaf("stdin", &proof.stdin),
+            ProofSection::leaf("output", &proof.output),
+            ProofSection::leaf("metadata", &proof.metadata),
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use bf_sdk::{config::FriPreset, ProverClient, ProverClientConfig};
+
+    use super::*;
+
+    #[test]
+    fn test_proof_size_breakdown() {
+        let config = ProverClientConfig { fri_preset: FriPreset::Fast, ..Default::default() };
+        let client = ProverClient::from_config(config).unwrap();
+        let (pk, _) = client.setup("++[->+<]>.");
+        let proof = client.prove(&pk, vec![]).run().unwrap();
+        let bytes = proof.to_bytes().unwrap();
+
+        let breakdown = proof_size_breakdown(&proof, bytes.len() as u64);
+        let shards = &breakdown.children[0];
+        assert_eq!(shards.bytes, serialized_size(&proof.proof));
+        assert!(breakdown.children.iter().map(|child| child.bytes).sum::<u64>() <= breakdown.bytes);
+        assert!(shards.children.iter().map(|child| child.bytes).sum::<u64>() <= shards.bytes);
+        let shard = &shards.children[0];
+        assert!(shard.children.iter().map(|child| child.bytes).sum::<u64>() <= shard.bytes);
+        assert_eq!(shard.children[1].children.len(), proof.proof[0].opened_values.chips.len());
+        assert!(breakdown.to_string().contains("query proofs"));
+    }
+}
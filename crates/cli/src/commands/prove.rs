@@ -0,0 +1,80 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::Result;
+use clap::Parser;
+
+use bf_sdk::{utils, ProverClient};
+
+use crate::{read_program, read_stdin};
+
+/// Executes a program, then proves and verifies its execution.
+#[derive(Parser, Debug)]
+pub struct ProveCmd {
+    /// The `.bf` program to prove.
+    pub program: PathBuf,
+
+    /// Comma-separated stdin bytes, e.g. `17,3`.
+    #[arg(long)]
+    pub stdin: Option<String>,
+
+    /// A file whose contents are used as stdin.
+    #[arg(long, conflicts_with = "stdin")]
+    pub stdin_file: Option<PathBuf>,
+
+    /// Comma-separated private input bytes, read by `;` and left out of the proof.
+    #[arg(long)]
+    pub private_input: Option<String>,
+
+    /// Only execute the program, without proving.
+    #[arg(long)]
+    pub execute_only: bool,
+
+    /// Where to write the proof.
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+
+    /// Write the spans of the execution and of the proving to this file, in the JSON format of
+    /// `chrome://tracing`, instead of logging.
+    #[arg(long)]
+    pub chrome_trace: Option<PathBuf>,
+}
+
+impl ProveCmd {
+    pub fn run(&self) -> Result<()> {
+        let _guard = match &self.chrome_trace {
+            Some(path) => Some(utils::setup_chrome_tracer(path)),
+            None => {
+                utils::setup_logger();
+                None
+            }
+        };
+
+        let program = read_program(&self.program)?;
+        let stdin = read_stdin(self.stdin.as_deref(), self.stdin_file.as_deref())?;
+        let private_input = read_stdin(self.private_input.as_deref(), None)?;
+        let client = ProverClient::from_env();
+
+        let output =
+            client.execute(&program, stdin.clone()).private_input(private_input.clone()).run()?;
+        println!("output: {output:?}");
+        if self.execute_only {
+            return Ok(());
+        }
+
+        let (pk, vk) = client.setup(&program);
+        let name = self.program.file_stem().map(|stem| stem.to_string_lossy().into_owned());
+        let mut prove = client.prove(&pk, stdin).private_input(private_input);
+        if let Some(name) = name {
+            prove = prove.program_name(name);
+        }
+        let proof = prove.run()?;
+        client.verify(&proof, &vk)?;
+        println!("verified proof of {} cycles", proof.metadata.cycles);
+
+        if let Some(path) = &self.output {
+            fs::write(path, proof.to_bytes()?)?;
+            println!("wrote proof to {}", path.display());
+        }
+        Ok(())
+    }
+}
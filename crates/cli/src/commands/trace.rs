@@ -0,0 +1,158 @@
+//! Export of the execution record as JSON.
+//!
+//! The exported document has the following shape, with event fields named as in
+//! [`bf_core_executor::events`]:
+//!
+//! ```json
+//! {
+//!   "version": 3,
+//!   "program": ",>+<[-]",
+//!   "stdin": [17],
+//!   "stdout": [85],
+//!   "cycles": 42,
+//!   "cpu": [{ "clk": 0, "pc": 0, "next_pc": 1, "mp": 0, "next_mp": 0, "tape": 0, "next_tape": 0,
+//!             "mv": 0, "next_mv": 17, "mv_access": { "Read": { ... } },
+//!             "next_mv_access": { "Write": { ... } } }],
+//!   "add": [{ "pc": 2, "opcode": "Add", "mv": 0, "next_mv": 1 }],
+//!   "sub": [...],
+//!   "set": [{ "pc": 5, "opcode": "Set", "mv": 3, "next_mv": 2 }],
+//!   "jump": [{ "pc": 4, "next_pc": 5, "opcode": "LoopStart", "dst": 6, "mv": 1 }],
+//!   "memory_instr": [{ "clk": 2, "pc": 1, "opcode": "MemStepForward", "tape": 0, "mp": 0,
+//!                      "next_mp": 1, "other_mp": 0 }],
+//!   "io": [{ "clk": 0, "pc": 0, "opcode": "Input", "mp": 0, "mv": 17 }],
+//!   "memory": [{ "tape": 0, "addr": 0, "initial_mem_access": { "timestamp": 0, "value": 0 },
+//!               "final_mem_access": { "timestamp": 9, "value": 85 } }],
+//!   "byte_lookups": [{ "event": { "opcode": "U8Range", "value_u8": 17, "value_u16": 0 },
+//!                      "count": 2 }]
+//! }
+//! ```
+//!
+//! `version` is bumped whenever the shape changes.
+
+use std::{fs, path::PathBuf};
+
+use anyhow::Result;
+use clap::Parser;
+use serde::Serialize;
+
+use bf_core_executor::events::{
+    AluEvent, ByteLookupEvent, CpuEvents, EventVec, IoEvent, JumpEvent, MemInstrEvent, MemoryEvent,
+};
+use bf_core_executor::Executor;
+
+use crate::{execute, read_program, read_stdin};
+
+/// The version of the exported JSON document.
+pub const TRACE_EXPORT_VERSION: u32 = 3;
+
+/// Dumps the execution record as JSON.
+#[derive(Parser, Debug)]
+pub struct TraceCmd {
+    /// The `.bf` program to trace.
+    pub program: PathBuf,
+
+    /// Comma-separated stdin bytes, e.g. `17,3`.
+    #[arg(long)]
+    pub stdin: Option<String>,
+
+    /// A file whose contents are used as stdin.
+    #[arg(long, conflicts_with = "stdin")]
+    pub stdin_file: Option<PathBuf>,
+
+    /// Where to write the JSON document. Defaults to stdout.
+    #[arg(long)]
+    pub json: Option<PathBuf>,
+}
+
+impl TraceCmd {
+    pub fn run(&self) -> Result<()> {
+        let program = read_program(&self.program)?;
+        let stdin = read_stdin(self.stdin.as_deref(), self.stdin_file.as_deref())?;
+        let runtime = execute(&program, stdin.clone())?;
+
+        let export = TraceExport::new(&program, &stdin, &runtime);
+        let json = serde_json::to_string_pretty(&export)?;
+        match &self.json {
+            Some(path) => fs::write(path, json)?,
+            None => println!("{json}"),
+        }
+        Ok(())
+    }
+}
+
+/// A byte lookup and the number of times it occurred.
+#[derive(Debug, Serialize)]
+pub struct ByteLookupCount {
+    pub event: ByteLookupEvent,
+    pub count: usize,
+}
+
+/// The exported execution record. See the module documentation for the schema.
+#[derive(Debug, Serialize)]
+pub struct TraceExport<'a> {
+    pub version: u32,
+    pub program: &'a str,
+    pub stdin: &'a [u8],
+    pub stdout: &'a [u8],
+    pub cycles: u64,
+    pub cpu: &'a CpuEvents,
+    pub add: &'a EventVec<AluEvent>,
+    pub sub: &'a EventVec<AluEvent>,
+    pub set: &'a EventVec<AluEvent>,
+    pub jump: &'a EventVec<JumpEvent>,
+    pub memory_instr: &'a EventVec<MemInstrEvent>,
+    pub io: &'a EventVec<IoEvent>,
+    pub memory: &'a EventVec<MemoryEvent>,
+    pub byte_lookups: Vec<ByteLookupCount>,
+}
+
+impl<'a> TraceExport<'a> {
+    pub fn new(program: &'a str, stdin: &'a [u8], runtime: &'a Executor) -> Self {
+        let record = &runtime.record;
+
+        // Sort the lookups so that the export is deterministic.
+        let mut byte_lookups = record
+            .byte_lookups
+            .iter()
+            .map(|(event, count)| ByteLookupCount { event: *event, count: *count })
+            .collect::<Vec<_>>();
+        byte_lookups.sort_by_key(|lookup| {
+            (lookup.event.opcode, lookup.event.value_u8, lookup.event.value_u16)
+        });
+
+        Self {
+            version: TRACE_EXPORT_VERSION,
+            program,
+            stdin,
+            stdout: &runtime.state.output_stream,
+            cycles: record.cpu_events.len() as u64,
+            cpu: &record.cpu_events,
+            add: &record.add_events,
+            sub: &record.sub_events,
+            set: &record.set_events,
+            jump: &record.jump_events,
+            memory_instr: &record.memory_instr_events,
+            io: &record.io_events,
+            memory: &record.cpu_memory_access,
+            byte_lookups,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace_export() {
+        let runtime = execute(",>+<[-].", vec![3]).unwrap();
+        let export = TraceExport::new(",>+<[-].", &[3], &runtime);
+        let json: serde_json::Value = serde_json::to_value(&export).unwrap();
+
+        assert_eq!(json["version"], TRACE_EXPORT_VERSION);
+        assert_eq!(json["stdout"], serde_json::json!([0]));
+        assert_eq!(json["cpu"].as_array().unwrap().len() as u64, export.cycles);
+        assert_eq!(json["io"][0]["opcode"], "Input");
+        assert_eq!(json["sub"].as_array().unwrap().len(), 3);
+    }
+}
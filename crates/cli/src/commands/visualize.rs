@@ -0,0 +1,325 @@
+//! Export of the execution record as a self-contained HTML page.
+//!
+//! The page shows the pc over time, a heat map of the tape, the loop structure of the program
+//! and the number of rows of every chip. The data is embedded as JSON, so the page works
+//! offline and can be attached to bug reports.
+
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+use anyhow::Result;
+use clap::Parser;
+use p3_koala_bear::KoalaBear;
+use serde::Serialize;
+
+use bf_core_executor::{ExecutionRecord, Executor, Opcode};
+use bf_core_machine::brainfuck::BfAir;
+use bf_stark::{air::MachineAir, MachineRecord};
+
+use crate::{execute, read_program, read_stdin};
+
+/// The maximum number of cycles drawn on the timeline. Longer runs are sampled.
+pub const MAX_TIMELINE_POINTS: usize = 5000;
+
+/// Renders the execution record as an HTML page.
+#[derive(Parser, Debug)]
+pub struct VisualizeCmd {
+    /// The `.bf` program to visualize.
+    pub program: PathBuf,
+
+    /// Comma-separated stdin bytes, e.g. `17,3`.
+    #[arg(long)]
+    pub stdin: Option<String>,
+
+    /// A file whose contents are used as stdin.
+    #[arg(long, conflicts_with = "stdin")]
+    pub stdin_file: Option<PathBuf>,
+
+    /// Where to write the HTML page.
+    #[arg(long, short, default_value = "trace.html")]
+    pub output: PathBuf,
+}
+
+impl VisualizeCmd {
+    pub fn run(&self) -> Result<()> {
+        let program = read_program(&self.program)?;
+        let stdin = read_stdin(self.stdin.as_deref(), self.stdin_file.as_deref())?;
+        let runtime = execute(&program, stdin)?;
+
+        fs::write(&self.output, render_html(&Visualization::new(&program, &runtime))?)?;
+        println!("wrote {}", self.output.display());
+        Ok(())
+    }
+}
+
+/// A memory cell of the first tape touched during execution.
+#[derive(Debug, Serialize)]
+pub struct TapeCell {
+    pub addr: u32,
+    /// The number of cycles spent with the memory pointer on this cell.
+    pub cycles: u64,
+    pub final_value: u8,
+}
+
+/// A `[ ... ]` loop of the program.
+#[derive(Debug, Serialize)]
+pub struct LoopInfo {
+    pub start: u32,
+    pub end: u32,
+    pub depth: usize,
+    /// The number of times the loop start was reached.
+    pub entries: u64,
+    /// The number of times the loop body was run.
+    pub iterations: u64,
+}
+
+/// The number of rows of a chip, padded as in the proof.
+#[derive(Debug, Serialize)]
+pub struct ChipRows {
+    pub name: String,
+    pub rows: usize,
+    pub cells: u64,
+}
+
+/// The data embedded in the page.
+#[derive(Debug, Serialize)]
+pub struct Visualization<'a> {
+    pub program: &'a str,
+    pub stdout: &'a [u8],
+    pub cycles: u64,
+    /// `(clk, pc)` of the sampled cycles.
+    pub timeline: Vec<(u32, u32)>,
+    pub tape: Vec<TapeCell>,
+    pub loops: Vec<LoopInfo>,
+    pub chips: Vec<ChipRows>,
+}
+
+impl<'a> Visualization<'a> {
+    pub fn new(program: &'a str, runtime: &'a Executor) -> Self {
+        let record = &runtime.record;
+
+        let events = &record.cpu_events;
+        let step = events.len().div_ceil(MAX_TIMELINE_POINTS).max(1);
+        let timeline =
+            events.clk.iter().zip(&events.pc).step_by(step).map(|(&clk, &pc)| (clk, pc)).collect();
+
+        let mut tape = BTreeMap::<u32, TapeCell>::new();
+        let first_tape_mps = events.mp.iter().zip(&events.tape).filter(|&(_, &t)| t == 0);
+        for (&mp, _) in first_tape_mps {
+            tape.entry(mp).or_insert(TapeCell { addr: mp, cycles: 0, final_value: 0 }).cycles += 1;
+        }
+        for event in record.cpu_memory_access.iter().filter(|event| event.tape == 0) {
+            if let (Some(cell), Some(last)) = (tape.get_mut(&event.addr), event.final_mem_access) {
+                cell.final_value = last.value;
+            }
+        }
+
+        Self {
+            program,
+            stdout: &runtime.state.output_stream,
+            cycles: record.cpu_events.len() as u64,
+            timeline,
+            tape: tape.into_values().collect(),
+            loops: loops(runtime),
+            chips: chip_rows(record),
+        }
+    }
+}
+
+/// Collects the loops of the program, in order of their start.
+fn loops(runtime: &Executor) -> Vec<LoopInfo> {
+    let mut entries = BTreeMap::<u32, u64>::new();
+    let mut iterations = BTreeMap::<u32, u64>::new();
+    for event in &runtime.record.jump_events {
+        let start = match event.opcode {
+            Opcode::LoopStart => {
+                *entries.entry(event.pc).or_default() += 1;
+                event.pc
+            }
+            // A loop end jumps back right after its loop start.
+            _ => event.dst - 1,
+        };
+        if event.mv != 0 {
+            *iterations.entry(start).or_default() += 1;
+        }
+    }
+
+    let mut loops = vec![];
+    let mut depth = 0;
+    for (pc, instruction) in runtime.program.instructions.iter().enumerate() {
+        let pc = pc as u32;
+        match instruction.opcode {
+            Opcode::LoopStart => {
+                loops.push(LoopInfo {
+                    start: pc,
+                    end: instruction.op_a,
+                    depth,
+                    entries: entries.get(&pc).copied().unwrap_or_default(),
+                    iterations: iterations.get(&pc).copied().unwrap_or_default(),
+                });
+                depth += 1;
+            }
+            Opcode::LoopEnd => depth -= 1,
+            _ => {}
+        }
+    }
+    loops
+}
+
+/// Computes the padded number of rows of every chip included in the proof of `record`.
+fn chip_rows(record: &ExecutionRecord) -> Vec<ChipRows> {
+    let chips = BfAir::<KoalaBear>::chips();
+
+    let mut record = record.clone();
+    for chip in &chips {
+        let mut output = ExecutionRecord::default();
+        chip.generate_dependencies(&record, &mut output);
+        record.append(&mut output);
+    }
+
+    chips
+        .iter()
+        .filter(|chip| chip.included(&record))
+        .map(|chip| {
+            let rows = chip.num_rows(&record).unwrap_or_else(|| {
+                let trace = chip.generate_trace(&record, &mut ExecutionRecord::default());
+                trace.values.len() / trace.width
+            });
+            ChipRows { name: chip.name(), rows, cells: rows as u64 * chip.cost() }
+        })
+        .collect()
+}
+
+/// Renders the page, embedding `data` as JSON.
+pub fn render_html(data: &Visualization) -> Result<String> {
+    // Keep the JSON from closing the script element.
+    let json = serde_json::to_string(data)?.replace("</", "<\\/");
+    Ok(TEMPLATE.replace("__DATA__", &json))
+}
+
+const TEMPLATE: &str = r##"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Brainfuck execution trace</title>
+<style>
+  body { font-family: sans-serif; margin: 2em; color: #222; }
+  h2 { margin-top: 1.5em; }
+  code { background: #f4f4f4; padding: 0 .2em; }
+  canvas { border: 1px solid #ccc; }
+  table { border-collapse: collapse; }
+  td, th { padding: .2em .6em; text-align: right; }
+  .tape { display: flex; flex-wrap: wrap; gap: 2px; }
+  .cell { width: 3.2em; height: 2.4em; font-size: .7em; display: flex; flex-direction: column;
+          align-items: center; justify-content: center; border-radius: 2px; }
+  .bar { background: #4a7bd0; height: .8em; }
+</style>
+</head>
+<body>
+<h1>Execution trace</h1>
+<p id="summary"></p>
+
+<h2>Timeline</h2>
+<p>Program counter of every cycle, by clock.</p>
+<canvas id="timeline" width="1000" height="300"></canvas>
+
+<h2>Tape</h2>
+<p>Cycles spent on every memory cell, with its final value.</p>
+<div id="tape" class="tape"></div>
+
+<h2>Loops</h2>
+<table id="loops">
+  <tr><th>start</th><th>end</th><th style="text-align:left">loop</th><th>entries</th>
+      <th>iterations</th></tr>
+</table>
+
+<h2>Chips</h2>
+<table id="chips"><tr><th>chip</th><th>rows</th><th>cells</th><th></th></tr></table>
+
+<script>
+const DATA = __DATA__;
+
+const text = (tag, content, style) => {
+  const el = document.createElement(tag);
+  el.textContent = content;
+  if (style) el.style.cssText = style;
+  return el;
+};
+const row = (table, cells) => {
+  const tr = document.createElement("tr");
+  cells.forEach((cell) => tr.appendChild(cell instanceof Node ? cell : text("td", cell)));
+  table.appendChild(tr);
+};
+
+document.getElementById("summary").textContent =
+  `${DATA.program.length} instructions, ${DATA.cycles} cycles, stdout: [${DATA.stdout}]`;
+
+// Timeline.
+const canvas = document.getElementById("timeline");
+const ctx = canvas.getContext("2d");
+const maxClk = Math.max(1, ...DATA.timeline.map(([clk]) => clk));
+const maxPc = Math.max(1, DATA.program.length - 1);
+ctx.fillStyle = "#4a7bd0";
+for (const [clk, pc] of DATA.timeline) {
+  const x = (clk / maxClk) * (canvas.width - 4) + 2;
+  const y = canvas.height - 2 - (pc / maxPc) * (canvas.height - 4);
+  ctx.fillRect(x - 1, y - 1, 2, 2);
+}
+
+// Tape heat map.
+const maxCycles = Math.max(1, ...DATA.tape.map((cell) => cell.cycles));
+const tape = document.getElementById("tape");
+for (const cell of DATA.tape) {
+  const heat = Math.log1p(cell.cycles) / Math.log1p(maxCycles);
+  const el = document.createElement("div");
+  el.className = "cell";
+  el.style.background = `hsl(${240 - 240 * heat}, 80%, ${85 - 35 * heat}%)`;
+  el.title = `addr ${cell.addr}: ${cell.cycles} cycles, final value ${cell.final_value}`;
+  el.appendChild(text("span", `#${cell.addr}`));
+  el.appendChild(text("b", cell.final_value));
+  tape.appendChild(el);
+}
+
+// Loop structure.
+const loops = document.getElementById("loops");
+for (const l of DATA.loops) {
+  const body = DATA.program.slice(l.start, l.end + 1);
+  const code = text("td", body.length > 60 ? body.slice(0, 57) + "..." : body,
+    `text-align:left; font-family:monospace; padding-left:${0.6 + 1.5 * l.depth}em`);
+  row(loops, [l.start, l.end, code, l.entries, l.iterations]);
+}
+
+// Chip rows.
+const chips = document.getElementById("chips");
+const maxCells = Math.max(1, ...DATA.chips.map((chip) => chip.cells));
+for (const chip of DATA.chips) {
+  const bar = document.createElement("td");
+  bar.style.textAlign = "left";
+  bar.appendChild(text("div", "", `width:${(300 * chip.cells) / maxCells}px`)).className = "bar";
+  row(chips, [chip.name, chip.rows, chip.cells, bar]);
+}
+</script>
+</body>
+</html>
+"##;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_visualization() {
+        let program = "++[>+<-]>.";
+        let runtime = execute(program, vec![]).unwrap();
+        let data = Visualization::new(program, &runtime);
+
+        assert_eq!(data.cycles, runtime.state.global_clk);
+        assert_eq!(data.tape.iter().map(|cell| cell.final_value).collect::<Vec<_>>(), [0, 2]);
+        assert_eq!(data.loops.len(), 1);
+        assert_eq!((data.loops[0].entries, data.loops[0].iterations), (1, 2));
+        assert!(data.chips.iter().any(|chip| chip.name == "Cpu"));
+
+        let html = render_html(&data).unwrap();
+        assert!(html.contains(r#""program":"++[>+<-]>.""#));
+        assert!(!html.contains("__DATA__"));
+    }
+}
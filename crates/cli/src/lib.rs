@@ -0,0 +1,71 @@
+//! Command line tools for the Brainfuck zkVM.
+
+pub mod commands;
+
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result};
+
+use bf_core_executor::{Executor, Opcode, Program};
+
+/// Reads a Brainfuck program from a file, dropping everything but the eight commands.
+pub fn read_program(path: &Path) -> Result<String> {
+    Ok(read_source(path)?.chars().filter(is_command).collect())
+}
+
+/// Reads the source of a Brainfuck program, comments included.
+pub fn read_source(path: &Path) -> Result<String> {
+    fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))
+}
+
+/// Parses a program and runs it to completion, returning the executor with the full record.
+pub fn execute(program: &str, stdin: Vec<u8>) -> Result<Executor> {
+    let program = Program::from(program)?;
+    let mut runtime = Executor::new(program, stdin);
+    runtime.run()?;
+    Ok(runtime)
+}
+
+/// The name of the chip proving the instructions with the given opcode.
+pub fn chip_name(opcode: Opcode) -> &'static str {
+    match opcode {
+        Opcode::Add | Opcode::Sub => "AddSub",
+        Opcode::Set => "Set",
+        Opcode::LoopStart | Opcode::LoopEnd => "Jump",
+        Opcode::MemStepForward | Opcode::MemStepBackward | Opcode::SwitchTape => "MemoryInstrs",
+        Opcode::Input | Opcode::PrivateInput | Opcode::Output => "IO",
+    }
+}
+
+/// Returns the 1-based `(line, column)` of every command in `source`, indexed by pc.
+pub fn command_positions(source: &str) -> Vec<(usize, usize)> {
+    source
+        .lines()
+        .enumerate()
+        .flat_map(|(line, text)| {
+            text.chars()
+                .enumerate()
+                .filter(|(_, c)| is_command(c))
+                .map(move |(col, _)| (line + 1, col + 1))
+        })
+        .collect()
+}
+
+/// Whether `c` is one of the eight Brainfuck commands, or the private input command `;`.
+pub fn is_command(c: &char) -> bool {
+    matches!(c, '+' | '-' | '>' | '<' | '[' | ']' | ',' | '.' | ';')
+}
+
+/// Builds stdin from either comma-separated bytes (e.g. `17,3`) or the contents of a file.
+pub fn read_stdin(bytes: Option<&str>, file: Option<&Path>) -> Result<Vec<u8>> {
+    if let Some(file) = file {
+        return fs::read(file).with_context(|| format!("failed to read {}", file.display()));
+    }
+    match bytes {
+        Some(bytes) if !bytes.trim().is_empty() => bytes
+            .split(',')
+            .map(|b| b.trim().parse::<u8>().with_context(|| format!("invalid stdin byte {b:?}")))
+            .collect(),
+        _ => Ok(vec![]),
+    }
+}
@@ -0,0 +1,36 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use bf_core_executor::{Executor, Program};
+use bf_core_executor_fuzz::{interpret, FinalState, FuzzCase, Outcome, MAX_CYCLES};
+
+fuzz_target!(|case: FuzzCase| {
+    let source = case.source();
+    let program = Program::from(&source).expect("generated programs are valid");
+    let mut runtime = Executor::new(program, case.input.clone());
+
+    match interpret(&source, &case.input, MAX_CYCLES) {
+        Outcome::Halted(expected) => {
+            runtime.run().expect("the reference interpreter halted");
+            let memory = runtime
+                .state
+                .memory_access
+                .iter()
+                .filter(|(_, record)| record.value != 0)
+                .map(|(addr, record)| (addr, record.value))
+                .collect();
+            let actual = FinalState {
+                mem_ptr: runtime.state.mem_ptr,
+                memory,
+                output: runtime.state.output_stream,
+            };
+            assert_eq!(expected, actual, "program: {source}");
+        }
+        Outcome::InputExhausted => {
+            assert!(runtime.run().is_err(), "program: {source}");
+        }
+        // The executor has no cycle cap, so it is not run on programs that may not halt.
+        Outcome::CycleLimit => {}
+    }
+});
@@ -0,0 +1,29 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use bf_core_executor::{Opcode, Program};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(code) = std::str::from_utf8(data) else {
+        return;
+    };
+    let Ok(program) = Program::from(code) else {
+        return;
+    };
+
+    // The other characters are comments, which only a strict parse rejects.
+    let commands = code.chars().filter(|c| "><+-.,;[]".contains(*c)).count();
+    assert_eq!(program.instructions.len(), commands);
+    let uncommented = code.chars().all(|c| "><+-.,;[]".contains(c) || c.is_ascii_whitespace());
+    assert_eq!(Program::from_strict(code).is_ok(), uncommented);
+
+    // Every loop start jumps to its loop end, which jumps back right after the loop start.
+    for (pc, instruction) in program.instructions.iter().enumerate() {
+        if instruction.opcode == Opcode::LoopStart {
+            let end = program.instructions[instruction.op_a as usize];
+            assert_eq!(end.opcode, Opcode::LoopEnd);
+            assert_eq!(end.op_a as usize, pc + 1);
+        }
+    }
+});
@@ -0,0 +1,126 @@
+//! Shared helpers of the executor fuzz targets: a generator of valid programs and a reference
+//! interpreter to compare the executor against.
+
+use std::collections::BTreeMap;
+
+use arbitrary::Arbitrary;
+use bf_core_executor::TAPE_LEN;
+
+/// The number of instructions after which the reference interpreter gives up.
+pub const MAX_CYCLES: u64 = 1 << 16;
+
+/// A command of a generated program. Loops are always balanced.
+#[derive(Arbitrary, Debug)]
+pub enum Op {
+    Right,
+    Left,
+    Inc,
+    Dec,
+    Output,
+    Input,
+    Loop(Vec<Op>),
+}
+
+/// A valid program and the input it runs on.
+#[derive(Arbitrary, Debug)]
+pub struct FuzzCase {
+    pub ops: Vec<Op>,
+    pub input: Vec<u8>,
+}
+
+impl FuzzCase {
+    /// Renders the program as Brainfuck source.
+    pub fn source(&self) -> String {
+        let mut source = String::new();
+        render(&self.ops, &mut source);
+        source
+    }
+}
+
+fn render(ops: &[Op], source: &mut String) {
+    for op in ops {
+        match op {
+            Op::Right => source.push('>'),
+            Op::Left => source.push('<'),
+            Op::Inc => source.push('+'),
+            Op::Dec => source.push('-'),
+            Op::Output => source.push('.'),
+            Op::Input => source.push(','),
+            Op::Loop(body) => {
+                source.push('[');
+                render(body, source);
+                source.push(']');
+            }
+        }
+    }
+}
+
+/// The state of a program the reference interpreter ran to completion.
+#[derive(Debug, PartialEq, Eq)]
+pub struct FinalState {
+    pub mem_ptr: u32,
+    /// The non-zero memory cells.
+    pub memory: BTreeMap<u32, u8>,
+    pub output: Vec<u8>,
+}
+
+/// How a run of the reference interpreter ended.
+#[derive(Debug)]
+pub enum Outcome {
+    Halted(FinalState),
+    InputExhausted,
+    CycleLimit,
+}
+
+/// Runs `source` with 8-bit wrapping cells and a wrapping 32-bit memory pointer.
+///
+/// `source` must only contain balanced commands.
+pub fn interpret(source: &str, input: &[u8], max_cycles: u64) -> Outcome {
+    let code = source.as_bytes();
+    let mut jumps = vec![0; code.len()];
+    let mut stack = vec![];
+    for (pc, &c) in code.iter().enumerate() {
+        match c {
+            b'[' => stack.push(pc),
+            b']' => {
+                let start = stack.pop().expect("unbalanced program");
+                jumps[start] = pc;
+                jumps[pc] = start;
+            }
+            _ => {}
+        }
+    }
+
+    let mut memory = BTreeMap::new();
+    let mut mem_ptr = 0u32;
+    let mut input = input.iter();
+    let mut output = vec![];
+    let mut pc = 0;
+    let mut cycles = 0;
+    while pc < code.len() {
+        if cycles == max_cycles {
+            return Outcome::CycleLimit;
+        }
+        cycles += 1;
+
+        let cell = memory.entry(mem_ptr).or_insert(0u8);
+        match code[pc] {
+            b'>' => mem_ptr = (mem_ptr + 1) % TAPE_LEN,
+            b'<' => mem_ptr = mem_ptr.checked_sub(1).unwrap_or(TAPE_LEN - 1),
+            b'+' => *cell = cell.wrapping_add(1),
+            b'-' => *cell = cell.wrapping_sub(1),
+            b'.' => output.push(*cell),
+            b',' => match input.next() {
+                Some(&value) => *cell = value,
+                None => return Outcome::InputExhausted,
+            },
+            b'[' if *cell == 0 => pc = jumps[pc],
+            b']' if *cell != 0 => pc = jumps[pc],
+            _ => {}
+        }
+        pc += 1;
+    }
+
+    memory.retain(|_, value| *value != 0);
+    Outcome::Halted(FinalState { mem_ptr, memory, output })
+}
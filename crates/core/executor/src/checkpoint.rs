@@ -0,0 +1,169 @@
+//! Checkpoints of an execution, to prove it elsewhere or later, or to debug the prover on a
+//! captured record.
+//!
+//! A checkpoint serializes with serde, carrying its version, and has a compact binary framing:
+//!
+//! | bytes | content                                          |
+//! |-------|--------------------------------------------------|
+//! | 4     | [`CHECKPOINT_MAGIC`]                             |
+//! | 1     | [`CHECKPOINT_VERSION`]                           |
+//! | 8     | the length of the payload, little-endian         |
+//! | 32    | the SHA-256 digest of the payload                |
+//! | ...   | the payload: the bincode-serialized [Checkpoint] |
+
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::{ExecutionRecord, ExecutionState, Executor};
+
+/// The magic bytes that prefix a framed checkpoint.
+pub const CHECKPOINT_MAGIC: [u8; 4] = *b"BFCK";
+
+/// The current version of the checkpoint format.
+pub const CHECKPOINT_VERSION: u8 = 3;
+
+/// The length of the header: magic, version, payload length and digest.
+const HEADER_LEN: usize = CHECKPOINT_MAGIC.len() + 1 + 8 + 32;
+
+/// Errors of decoding a checkpoint.
+#[derive(Error, Debug)]
+pub enum CheckpointError {
+    /// The bytes do not start with [`CHECKPOINT_MAGIC`].
+    #[error("Not a checkpoint")]
+    Magic,
+
+    /// The checkpoint has another version than [`CHECKPOINT_VERSION`].
+    #[error("Unsupported checkpoint version: {0}")]
+    UnsupportedVersion(u8),
+
+    /// The bytes are shorter or longer than the header says.
+    #[error("Invalid checkpoint length")]
+    Length,
+
+    /// The payload does not match its digest.
+    #[error("Checkpoint digest mismatch")]
+    DigestMismatch,
+
+    /// The payload failed to (de)serialize.
+    #[error("Failed to serialize checkpoint: {0}")]
+    Serialization(#[from] bincode::Error),
+}
+
+/// The state and the record of an execution.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    #[serde(deserialize_with = "deserialize_version")]
+    version: u8,
+    /// The state of the execution.
+    pub state: ExecutionState,
+    /// The record of the execution.
+    pub record: ExecutionRecord,
+}
+
+fn deserialize_version<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u8, D::Error> {
+    let version = u8::deserialize(deserializer)?;
+    if version != CHECKPOINT_VERSION {
+        return Err(D::Error::custom(CheckpointError::UnsupportedVersion(version)));
+    }
+    Ok(version)
+}
+
+impl Checkpoint {
+    /// Creates a checkpoint of the current version.
+    #[must_use]
+    pub const fn new(state: ExecutionState, record: ExecutionRecord) -> Self {
+        Self { version: CHECKPOINT_VERSION, state, record }
+    }
+
+    /// The version of the format.
+    #[must_use]
+    pub const fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// Frames the checkpoint, see the [module documentation](self).
+    pub fn to_bytes(&self) -> Result<Vec<u8>, CheckpointError> {
+        let payload = bincode::serialize(self)?;
+        let mut bytes = Vec::with_capacity(HEADER_LEN + payload.len());
+        bytes.extend_from_slice(&CHECKPOINT_MAGIC);
+        bytes.push(CHECKPOINT_VERSION);
+        bytes.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&Sha256::digest(&payload));
+        bytes.extend_from_slice(&payload);
+        Ok(bytes)
+    }
+
+    /// Decodes a framed checkpoint, checking its version and its digest.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CheckpointError> {
+        if bytes.len() < HEADER_LEN || bytes[..CHECKPOINT_MAGIC.len()] != CHECKPOINT_MAGIC {
+            return Err(CheckpointError::Magic);
+        }
+        let version = bytes[CHECKPOINT_MAGIC.len()];
+        if version != CHECKPOINT_VERSION {
+            return Err(CheckpointError::UnsupportedVersion(version));
+        }
+
+        let (len, digest) = bytes[CHECKPOINT_MAGIC.len() + 1..HEADER_LEN].split_at(8);
+        let payload = &bytes[HEADER_LEN..];
+        if u64::from_le_bytes(len.try_into().unwrap()) != payload.len() as u64 {
+            return Err(CheckpointError::Length);
+        }
+        if Sha256::digest(payload).as_slice() != digest {
+            return Err(CheckpointError::DigestMismatch);
+        }
+        Ok(bincode::deserialize(payload)?)
+    }
+}
+
+impl Executor {
+    /// A checkpoint of the state and the record of the execution so far, usually taken after
+    /// [`Executor::run`] to prove the execution elsewhere.
+    ///
+    /// Between the shards of [`Executor::shards`], the record is the one of the next shard.
+    #[must_use]
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint::new(self.state.clone(), self.record.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Program;
+
+    #[test]
+    fn test_checkpoint() {
+        let program = Program::from(",[->+<]>.").unwrap();
+        let mut runtime = Executor::new(program, vec![5]);
+        runtime.run().unwrap();
+
+        let checkpoint = runtime.checkpoint();
+        let bytes = checkpoint.to_bytes().unwrap();
+        let decoded = Checkpoint::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.version(), CHECKPOINT_VERSION);
+        assert_eq!(decoded.state.global_clk, runtime.state.global_clk);
+        assert_eq!(decoded.record.output(), [5]);
+
+        // The serde form carries the version too.
+        let mut other_version = checkpoint.clone();
+        other_version.version += 1;
+        let payload = bincode::serialize(&other_version).unwrap();
+        assert!(bincode::deserialize::<Checkpoint>(&payload).is_err());
+
+        let mut tampered = bytes.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(matches!(Checkpoint::from_bytes(&tampered), Err(CheckpointError::DigestMismatch)));
+        assert!(matches!(
+            Checkpoint::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(CheckpointError::Length)
+        ));
+        let mut wrong_version = bytes.clone();
+        wrong_version[4] += 1;
+        assert!(matches!(
+            Checkpoint::from_bytes(&wrong_version),
+            Err(CheckpointError::UnsupportedVersion(_))
+        ));
+        assert!(matches!(Checkpoint::from_bytes(b"BFPF"), Err(CheckpointError::Magic)));
+    }
+}
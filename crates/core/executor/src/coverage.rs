@@ -0,0 +1,197 @@
+use alloc::{string::String, vec::Vec};
+use core::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{ExecutionRecord, Opcode};
+
+/// How often a loop instruction jumped and fell through.
+///
+/// `[` jumps to the `]` of its loop when the cell is zero, and `]` jumps back to the start of
+/// its loop when the cell is not.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BranchCoverage {
+    /// The number of times the instruction jumped.
+    pub taken: u64,
+    /// The number of times the instruction fell through to the next one.
+    pub not_taken: u64,
+}
+
+/// The coverage of one program counter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PcCoverage {
+    /// The program counter.
+    pub pc: u32,
+    /// The opcode of the instruction at `pc`.
+    pub opcode: Opcode,
+    /// The number of times the instruction was executed.
+    pub hits: u64,
+    /// The outcomes of the instruction, if it is a loop instruction.
+    pub branch: Option<BranchCoverage>,
+}
+
+/// The instructions and loop branches an execution covered.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Coverage {
+    /// One entry per instruction of the program, indexed by pc.
+    pub pcs: Vec<PcCoverage>,
+}
+
+/// The position of an instruction in the source of its program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourcePosition {
+    /// The offset of the command in the source, in characters.
+    pub offset: usize,
+    /// The 1-based line of the command.
+    pub line: usize,
+    /// The 1-based column of the command, in characters.
+    pub column: usize,
+}
+
+impl Coverage {
+    /// Builds the coverage of the execution that produced `record`.
+    #[must_use]
+    pub fn from_record(record: &ExecutionRecord) -> Self {
+        let mut pcs = record
+            .program
+            .instructions
+            .iter()
+            .enumerate()
+            .map(|(pc, instruction)| PcCoverage {
+                pc: pc as u32,
+                opcode: instruction.opcode,
+                hits: 0,
+                branch: matches!(instruction.opcode, Opcode::LoopStart | Opcode::LoopEnd)
+                    .then(BranchCoverage::default),
+            })
+            .collect::<Vec<_>>();
+        for (&pc, &mv) in record.cpu_events.pc.iter().zip(&record.cpu_events.mv) {
+            let coverage = &mut pcs[pc as usize];
+            coverage.hits += 1;
+            if let Some(branch) = &mut coverage.branch {
+                // The jump target of `[]` is the next instruction, so the cell tells the
+                // outcome rather than the next pc.
+                if (coverage.opcode == Opcode::LoopStart) == (mv == 0) {
+                    branch.taken += 1;
+                } else {
+                    branch.not_taken += 1;
+                }
+            }
+        }
+        Self { pcs }
+    }
+
+    /// The number of instructions executed at least once.
+    #[must_use]
+    pub fn covered_pcs(&self) -> usize {
+        self.pcs.iter().filter(|pc| pc.hits > 0).count()
+    }
+
+    /// The number of loop branches, two per loop instruction, and the number of them taken at
+    /// least once.
+    #[must_use]
+    pub fn branches(&self) -> (usize, usize) {
+        let branches = self.pcs.iter().filter_map(|pc| pc.branch);
+        branches.fold((0, 0), |(total, covered), branch| {
+            (total + 2, covered + (branch.taken > 0) as usize + (branch.not_taken > 0) as usize)
+        })
+    }
+
+    /// Exports the coverage in the lcov tracefile format, for the program saved as `path`.
+    ///
+    /// The lines are those of `positions`, see [`source_positions`]. A line counts the hits of
+    /// its most executed instruction, and each loop instruction has a branch block named after
+    /// its pc, in which branch 0 jumps and branch 1 falls through.
+    #[must_use]
+    pub fn to_lcov(&self, path: &str, positions: &[SourcePosition]) -> String {
+        let mut lines: Vec<(usize, u64)> = Vec::new();
+        let mut branches = String::new();
+        for (coverage, position) in self.pcs.iter().zip(positions) {
+            match lines.last_mut() {
+                Some((line, hits)) if *line == position.line => *hits = (*hits).max(coverage.hits),
+                _ => lines.push((position.line, coverage.hits)),
+            }
+            if let Some(branch) = coverage.branch {
+                for (index, taken) in [branch.taken, branch.not_taken].into_iter().enumerate() {
+                    // lcov marks the branches of a block that never ran with `-`.
+                    let _ = match coverage.hits {
+                        0 => writeln!(branches, "BRDA:{},{},{index},-", position.line, coverage.pc),
+                        _ => writeln!(
+                            branches,
+                            "BRDA:{},{},{index},{taken}",
+                            position.line, coverage.pc
+                        ),
+                    };
+                }
+            }
+        }
+
+        let mut lcov = String::new();
+        let _ = writeln!(lcov, "TN:\nSF:{path}");
+        for (line, hits) in &lines {
+            let _ = writeln!(lcov, "DA:{line},{hits}");
+        }
+        let (total_branches, covered_branches) = self.branches();
+        lcov.push_str(&branches);
+        let _ = writeln!(lcov, "BRF:{total_branches}\nBRH:{covered_branches}");
+        let covered_lines = lines.iter().filter(|(_, hits)| *hits > 0).count();
+        let _ = writeln!(lcov, "LF:{}\nLH:{covered_lines}\nend_of_record", lines.len());
+        lcov
+    }
+}
+
+/// The position of every instruction of the program parsed from `source`, by pc.
+///
+/// Every character that is not a command is skipped, so the positions are also those of a
+/// source with comments, once the comments are stripped before parsing.
+#[must_use]
+pub fn source_positions(source: &str) -> Vec<SourcePosition> {
+    let (mut line, mut column) = (1, 1);
+    let mut positions = Vec::new();
+    for (offset, c) in source.chars().enumerate() {
+        if matches!(c, '>' | '<' | '+' | '-' | '.' | ',' | ';' | '[' | ']')
+            || (cfg!(feature = "second-tape") && c == '~')
+        {
+            positions.push(SourcePosition { offset, line, column });
+        }
+        if c == '\n' {
+            (line, column) = (line + 1, 1);
+        } else {
+            column += 1;
+        }
+    }
+    positions
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{source_positions, Coverage, Executor, Program};
+
+    #[test]
+    fn test_coverage() {
+        let source = "+[-]\n[+]\n>.";
+        let program = Program::from(source).unwrap();
+        let mut runtime = Executor::new(program, vec![]);
+        runtime.run().unwrap();
+
+        let coverage = Coverage::from_record(&runtime.record);
+        assert_eq!(coverage.pcs.len(), 9);
+        assert_eq!(coverage.covered_pcs(), 8);
+        // The first loop runs its body once, and the second one is skipped to its `]`.
+        let branches = coverage.pcs.iter().map(|pc| pc.branch).collect::<Vec<_>>();
+        assert_eq!(branches[1].unwrap().not_taken, 1);
+        assert_eq!(branches[3].unwrap().not_taken, 1);
+        assert_eq!(branches[4].unwrap().taken, 1);
+        assert_eq!(branches[6].unwrap().not_taken, 1);
+        assert_eq!(coverage.pcs[5].hits, 0);
+        assert_eq!(coverage.branches(), (8, 4));
+
+        let positions = source_positions(source);
+        assert_eq!(positions[4].offset, 5);
+        assert_eq!((positions[7].line, positions[7].column), (3, 1));
+        let lcov = coverage.to_lcov("test.bf", &positions);
+        assert!(lcov.starts_with("TN:\nSF:test.bf\nDA:1,1\nDA:2,1\nDA:3,1\n"));
+        assert!(lcov.contains("BRDA:2,4,0,1\nBRDA:2,4,1,0\nBRDA:2,6,0,0\nBRDA:2,6,1,1\n"));
+        assert!(lcov.ends_with("BRF:8\nBRH:4\nLF:3\nLH:3\nend_of_record\n"));
+    }
+}
@@ -0,0 +1,102 @@
+#[cfg(feature = "stark")]
+use bf_primitives::poseidon2::{permute, WIDTH};
+#[cfg(feature = "stark")]
+use p3_field::FieldAlgebra;
+
+/// The number of field elements in an I/O digest.
+pub const IO_DIGEST_LEN: usize = 8;
+
+/// The index of the number of bytes read from the input stream in the public values of a shard.
+pub const INPUT_COUNT_PUBLIC_VALUE: usize = 2 * IO_DIGEST_LEN;
+
+/// The index of the number of bytes written to the output stream in the public values of a shard.
+pub const OUTPUT_COUNT_PUBLIC_VALUE: usize = INPUT_COUNT_PUBLIC_VALUE + 1;
+
+/// The index of the number of cycles in the public values of a shard.
+pub const CYCLES_PUBLIC_VALUE: usize = OUTPUT_COUNT_PUBLIC_VALUE + 1;
+
+/// The number of field elements of a [`ShardBoundary`](crate::ShardBoundary).
+pub const SHARD_BOUNDARY_LEN: usize = 5;
+
+/// The index of the state of the execution at the start of the shard in the public values.
+pub const START_PUBLIC_VALUES: usize = CYCLES_PUBLIC_VALUE + 1;
+
+/// The index of the state of the execution after the shard in the public values.
+pub const NEXT_PUBLIC_VALUES: usize = START_PUBLIC_VALUES + SHARD_BOUNDARY_LEN;
+
+/// The index of the flag set in the public values of the last shard of an execution.
+pub const LAST_SHARD_PUBLIC_VALUE: usize = NEXT_PUBLIC_VALUES + SHARD_BOUNDARY_LEN;
+
+/// The index of the number of instructions of the program in the public values.
+pub const PROGRAM_LEN_PUBLIC_VALUE: usize = LAST_SHARD_PUBLIC_VALUE + 1;
+
+/// The number of public values of a shard: the digest of the bytes the shard read from the
+/// input stream, the digest of the bytes it wrote to the output stream, the numbers of bytes it
+/// read and wrote, the number of cycles of the shard, the states of the execution at its start
+/// and after it, whether it is the last one, and the length of the program.
+///
+/// Each shard digests its own part of the streams, so a shard without I/O has zero digests.
+pub const NUM_PUBLIC_VALUES: usize = PROGRAM_LEN_PUBLIC_VALUE + 1;
+
+/// A running digest of the bytes read from the input stream, or written to the output stream.
+///
+/// The digest is a Poseidon2 sponge of rate 1: each byte is added to the first element of the
+/// state, which is then permuted, and the digest is the first [`IO_DIGEST_LEN`] elements of the
+/// state, 248 bits for the KoalaBear field. The IO chip evaluates the same permutations over its
+/// rows and exposes the digests as the public values.
+#[cfg(feature = "stark")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IoDigest<F> {
+    /// The state of the sponge after the bytes absorbed so far.
+    pub state: [F; WIDTH],
+}
+
+#[cfg(feature = "stark")]
+impl<F: FieldAlgebra + Copy> Default for IoDigest<F> {
+    fn default() -> Self {
+        Self { state: [F::ZERO; WIDTH] }
+    }
+}
+
+#[cfg(feature = "stark")]
+impl<F: FieldAlgebra + Copy> IoDigest<F> {
+    /// Absorbs the next byte of the stream.
+    pub fn absorb(&mut self, byte: u8) {
+        self.state[0] += F::from_canonical_u8(byte);
+        permute(&mut self.state);
+    }
+
+    /// The digest of the bytes absorbed so far.
+    #[must_use]
+    pub fn value(&self) -> [F; IO_DIGEST_LEN] {
+        core::array::from_fn(|i| self.state[i])
+    }
+}
+
+/// Computes the digest of the given bytes.
+#[cfg(feature = "stark")]
+#[must_use]
+pub fn io_digest<F: FieldAlgebra + Copy>(bytes: &[u8]) -> [F; IO_DIGEST_LEN] {
+    let mut digest = IoDigest::default();
+    bytes.iter().for_each(|&byte| digest.absorb(byte));
+    digest.value()
+}
+
+#[cfg(all(test, feature = "stark"))]
+mod tests {
+    use p3_koala_bear::KoalaBear;
+
+    use super::*;
+
+    #[test]
+    fn test_io_digest() {
+        let empty = io_digest::<KoalaBear>(&[]);
+        assert_eq!(empty, [KoalaBear::ZERO; IO_DIGEST_LEN]);
+
+        // Zeros, and the order of the bytes, change the digest.
+        assert_ne!(io_digest::<KoalaBear>(&[0]), empty);
+        assert_ne!(io_digest::<KoalaBear>(&[0, 1]), io_digest::<KoalaBear>(&[1]));
+        assert_ne!(io_digest::<KoalaBear>(&[1, 2]), io_digest::<KoalaBear>(&[2, 1]));
+        assert_ne!(io_digest::<KoalaBear>(&[1]), io_digest::<KoalaBear>(&[0, 0]));
+    }
+}
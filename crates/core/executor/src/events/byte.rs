@@ -1,4 +1,5 @@
-use std::hash::Hash;
+use alloc::{vec, vec::Vec};
+use core::hash::Hash;
 
 use hashbrown::HashMap;
 use p3_field::{Field, PrimeField32};
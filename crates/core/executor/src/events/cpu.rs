@@ -1,6 +1,8 @@
-use serde::{Deserialize, Serialize};
+use alloc::vec::Vec;
 
-use crate::events::MemoryRecordEnum;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::events::{EventVec, MemoryRecordEnum, EVENT_CHUNK_LEN};
 
 /// CPU Event.
 ///
@@ -18,6 +20,10 @@ pub struct CpuEvent {
     pub mp: u32,
     /// For memory instructions.
     pub next_mp: u32,
+    /// The tape.
+    pub tape: u32,
+    /// The tape after the instruction, which only a tape switch changes.
+    pub next_tape: u32,
     /// The memory value.
     pub mv: u8,
     /// For ALU instructions.
@@ -27,3 +33,249 @@ pub struct CpuEvent {
     /// The next_mv memory record.
     pub next_mv_access: Option<MemoryRecordEnum>,
 }
+
+/// The CPU events of an execution, stored column by column.
+///
+/// Each field of [`CpuEvent`] has its own [`EventVec`], so trace generation can fill a trace
+/// column from a contiguous slice instead of extracting the field from every event. All the
+/// columns have the same length and chunk boundaries: add events with [`CpuEvents::push`].
+///
+/// Serialized as a sequence of [`CpuEvent`]s, like an `EventVec<CpuEvent>`.
+#[derive(Debug, Clone, Default)]
+pub struct CpuEvents {
+    pub clk: EventVec<u32>,
+    pub pc: EventVec<u32>,
+    pub next_pc: EventVec<u32>,
+    pub mp: EventVec<u32>,
+    pub next_mp: EventVec<u32>,
+    pub tape: EventVec<u32>,
+    pub next_tape: EventVec<u32>,
+    pub mv: EventVec<u8>,
+    pub next_mv: EventVec<u8>,
+    pub mv_access: EventVec<Option<MemoryRecordEnum>>,
+    pub next_mv_access: EventVec<Option<MemoryRecordEnum>>,
+}
+
+impl CpuEvents {
+    /// Creates an empty list.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            clk: EventVec::new(),
+            pc: EventVec::new(),
+            next_pc: EventVec::new(),
+            mp: EventVec::new(),
+            next_mp: EventVec::new(),
+            tape: EventVec::new(),
+            next_tape: EventVec::new(),
+            mv: EventVec::new(),
+            next_mv: EventVec::new(),
+            mv_access: EventVec::new(),
+            next_mv_access: EventVec::new(),
+        }
+    }
+
+    /// The number of events.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.clk.len()
+    }
+
+    /// Whether the list has no events.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.clk.is_empty()
+    }
+
+    /// Appends an event.
+    #[inline]
+    pub fn push(&mut self, event: CpuEvent) {
+        self.clk.push(event.clk);
+        self.pc.push(event.pc);
+        self.next_pc.push(event.next_pc);
+        self.mp.push(event.mp);
+        self.next_mp.push(event.next_mp);
+        self.tape.push(event.tape);
+        self.next_tape.push(event.next_tape);
+        self.mv.push(event.mv);
+        self.next_mv.push(event.next_mv);
+        self.mv_access.push(event.mv_access);
+        self.next_mv_access.push(event.next_mv_access);
+    }
+
+    /// Moves all the events of `other` to the end of this list.
+    pub fn append(&mut self, other: &mut Self) {
+        self.clk.append(&mut other.clk);
+        self.pc.append(&mut other.pc);
+        self.next_pc.append(&mut other.next_pc);
+        self.mp.append(&mut other.mp);
+        self.next_mp.append(&mut other.next_mp);
+        self.tape.append(&mut other.tape);
+        self.next_tape.append(&mut other.next_tape);
+        self.mv.append(&mut other.mv);
+        self.next_mv.append(&mut other.next_mv);
+        self.mv_access.append(&mut other.mv_access);
+        self.next_mv_access.append(&mut other.next_mv_access);
+    }
+
+    /// The event at `index`, if any.
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<CpuEvent> {
+        (index < self.len())
+            .then(|| self.chunk(index / EVENT_CHUNK_LEN).event(index % EVENT_CHUNK_LEN))
+    }
+
+    /// The number of chunks, see [`EventVec::chunk`].
+    #[must_use]
+    pub fn num_chunks(&self) -> usize {
+        self.clk.num_chunks()
+    }
+
+    /// The columns of the chunk at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not less than [`CpuEvents::num_chunks`].
+    #[must_use]
+    pub fn chunk(&self, index: usize) -> CpuEventChunk<'_> {
+        fn column<T>(chunk: Option<&[T]>) -> &[T] {
+            chunk.expect("chunk index out of bounds")
+        }
+
+        CpuEventChunk {
+            clk: column(self.clk.chunk(index)),
+            pc: column(self.pc.chunk(index)),
+            next_pc: column(self.next_pc.chunk(index)),
+            mp: column(self.mp.chunk(index)),
+            next_mp: column(self.next_mp.chunk(index)),
+            tape: column(self.tape.chunk(index)),
+            next_tape: column(self.next_tape.chunk(index)),
+            mv: column(self.mv.chunk(index)),
+            next_mv: column(self.next_mv.chunk(index)),
+            mv_access: column(self.mv_access.chunk(index)),
+            next_mv_access: column(self.next_mv_access.chunk(index)),
+        }
+    }
+
+    /// Iterates over the chunks in order.
+    pub fn chunks(&self) -> impl Iterator<Item = CpuEventChunk<'_>> + Send {
+        (0..self.num_chunks()).map(move |index| self.chunk(index))
+    }
+
+    /// Iterates over the events in order.
+    pub fn iter(&self) -> impl Iterator<Item = CpuEvent> + '_ {
+        self.chunks().flat_map(CpuEventChunk::iter)
+    }
+}
+
+/// The columns of up to [`EVENT_CHUNK_LEN`] consecutive [`CpuEvent`]s.
+#[derive(Debug, Clone, Copy)]
+pub struct CpuEventChunk<'a> {
+    pub clk: &'a [u32],
+    pub pc: &'a [u32],
+    pub next_pc: &'a [u32],
+    pub mp: &'a [u32],
+    pub next_mp: &'a [u32],
+    pub tape: &'a [u32],
+    pub next_tape: &'a [u32],
+    pub mv: &'a [u8],
+    pub next_mv: &'a [u8],
+    pub mv_access: &'a [Option<MemoryRecordEnum>],
+    pub next_mv_access: &'a [Option<MemoryRecordEnum>],
+}
+
+impl<'a> CpuEventChunk<'a> {
+    /// The number of events.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.clk.len()
+    }
+
+    /// Whether the chunk has no events.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.clk.is_empty()
+    }
+
+    /// Gathers the event at `index` from the columns.
+    #[must_use]
+    pub fn event(&self, index: usize) -> CpuEvent {
+        CpuEvent {
+            clk: self.clk[index],
+            pc: self.pc[index],
+            next_pc: self.next_pc[index],
+            mp: self.mp[index],
+            next_mp: self.next_mp[index],
+            tape: self.tape[index],
+            next_tape: self.next_tape[index],
+            mv: self.mv[index],
+            next_mv: self.next_mv[index],
+            mv_access: self.mv_access[index],
+            next_mv_access: self.next_mv_access[index],
+        }
+    }
+
+    /// Iterates over the events in order.
+    pub fn iter(self) -> impl Iterator<Item = CpuEvent> + 'a {
+        (0..self.len()).map(move |index| self.event(index))
+    }
+}
+
+impl FromIterator<CpuEvent> for CpuEvents {
+    fn from_iter<I: IntoIterator<Item = CpuEvent>>(iter: I) -> Self {
+        let mut events = Self::new();
+        iter.into_iter().for_each(|event| events.push(event));
+        events
+    }
+}
+
+impl Serialize for CpuEvents {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de> Deserialize<'de> for CpuEvents {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::<CpuEvent>::deserialize(deserializer).map(|events| events.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(clk: u32) -> CpuEvent {
+        CpuEvent {
+            clk,
+            pc: clk % 7,
+            next_pc: clk % 7 + 1,
+            mp: clk % 3,
+            next_mp: clk % 3,
+            tape: 0,
+            next_tape: 0,
+            mv: clk as u8,
+            next_mv: clk as u8 ^ 1,
+            mv_access: None,
+            next_mv_access: None,
+        }
+    }
+
+    #[test]
+    fn test_cpu_events() {
+        let n = EVENT_CHUNK_LEN as u32 + 3;
+        let mut events: CpuEvents = (0..n).map(event).collect();
+        assert_eq!(events.len(), n as usize);
+        assert_eq!(events.num_chunks(), 2);
+        assert_eq!(events.chunk(1).len(), 3);
+        assert_eq!(events.get(EVENT_CHUNK_LEN + 1).map(|e| e.next_mv), Some(event(n - 2).next_mv));
+        assert!(events.get(n as usize).is_none());
+        assert!(events.iter().map(|e| e.clk).eq(0..n));
+        assert!(events.pc.iter().zip(0..n).all(|(&pc, clk)| pc == clk % 7));
+
+        let mut other: CpuEvents = (n..2 * n).map(event).collect();
+        events.append(&mut other);
+        assert!(other.is_empty());
+        assert!(events.iter().map(|e| e.mv).eq((0..2 * n).map(|clk| clk as u8)));
+    }
+}
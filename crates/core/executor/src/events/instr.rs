@@ -63,17 +63,21 @@ pub struct MemInstrEvent {
     pub pc: u32,
     /// The opcode.
     pub opcode: Opcode,
+    /// The tape before the instruction.
+    pub tape: u32,
     /// The memory pointer.
     pub mp: u32,
     /// The next memory pointer.
     pub next_mp: u32,
+    /// The memory pointer of the other tape.
+    pub other_mp: u32,
 }
 
 impl MemInstrEvent {
-    /// Create a new [`MemInstrEvent`].
+    /// Create a new [`MemInstrEvent`] on the first tape, before any tape switch.
     #[must_use]
     pub fn new(clk: u32, pc: u32, opcode: Opcode, mp: u32, next_mp: u32) -> Self {
-        Self { clk, pc, opcode, mp, next_mp }
+        Self { clk, pc, opcode, tape: 0, mp, next_mp, other_mp: 0 }
     }
 }
 
@@ -83,6 +87,8 @@ impl MemInstrEvent {
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 #[repr(C)]
 pub struct IoEvent {
+    /// The clk.
+    pub clk: u32,
     /// The program counter.
     pub pc: u32,
     /// The opcode.
@@ -94,9 +100,9 @@ pub struct IoEvent {
 }
 
 impl IoEvent {
-    /// Create a new [`MemInstrEvent`].
+    /// Create a new [`IoEvent`].
     #[must_use]
-    pub fn new(pc: u32, opcode: Opcode, mp: u32, mv: u8) -> Self {
-        Self { pc, opcode, mp, mv }
+    pub fn new(clk: u32, pc: u32, opcode: Opcode, mp: u32, mv: u8) -> Self {
+        Self { clk, pc, opcode, mp, mv }
     }
 }
@@ -2,15 +2,19 @@ use serde::{Deserialize, Serialize};
 
 /// Memory Event.
 ///
-/// This object encapsulates the information needed to prove a memory access operation.
+/// This object encapsulates the information needed to initialize or finalize a memory cell in
+/// the memory table of a shard. A cell is initialized by the shard that first accesses it and
+/// finalized by the one that last accesses it.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MemoryEvent {
+    /// The tape of the address.
+    pub tape: u32,
     /// The address.
     pub addr: u32,
-    /// The initial memory access.
-    pub initial_mem_access: MemoryRecord,
-    /// The final memory access.
-    pub final_mem_access: MemoryRecord,
+    /// The record of the cell before its first access, if the shard initializes the cell.
+    pub initial_mem_access: Option<MemoryRecord>,
+    /// The record of the cell after its last access, if the shard finalizes the cell.
+    pub final_mem_access: Option<MemoryRecord>,
 }
 
 /// Memory Record.
@@ -4,10 +4,12 @@ mod byte;
 mod cpu;
 mod instr;
 mod memory;
+mod storage;
 mod utils;
 
 pub use byte::*;
 pub use cpu::*;
 pub use instr::*;
 pub use memory::*;
+pub use storage::*;
 pub use utils::*;
@@ -0,0 +1,200 @@
+use alloc::vec::Vec;
+use core::{fmt, iter::Flatten, ops::Index, slice};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// The number of events per chunk of an [`EventVec`].
+pub const EVENT_CHUNK_LEN: usize = 1 << 14;
+
+/// An append-only list of events, stored in chunks of [`EVENT_CHUNK_LEN`] events.
+///
+/// Unlike a `Vec`, pushing never moves the events already stored once the first chunk is full,
+/// so long executions do not repeatedly reallocate and copy their traces. Every chunk but the
+/// last is full, which keeps indexing constant time.
+#[derive(Clone)]
+pub struct EventVec<T> {
+    chunks: Vec<Vec<T>>,
+    len: usize,
+}
+
+impl<T> EventVec<T> {
+    /// Creates an empty list.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { chunks: Vec::new(), len: 0 }
+    }
+
+    /// The number of events.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the list has no events.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Appends an event.
+    #[inline]
+    pub fn push(&mut self, event: T) {
+        match self.chunks.last_mut() {
+            Some(chunk) if chunk.len() < EVENT_CHUNK_LEN => chunk.push(event),
+            _ => {
+                // The first chunk grows on demand, so that short executions stay small.
+                let mut chunk = if self.chunks.is_empty() {
+                    Vec::new()
+                } else {
+                    Vec::with_capacity(EVENT_CHUNK_LEN)
+                };
+                chunk.push(event);
+                self.chunks.push(chunk);
+            }
+        }
+        self.len += 1;
+    }
+
+    /// Moves all the events of `other` to the end of this list.
+    ///
+    /// The chunks of `other` are moved without copying when this list ends on a chunk boundary.
+    pub fn append(&mut self, other: &mut Self) {
+        if self.len % EVENT_CHUNK_LEN == 0 {
+            self.chunks.append(&mut other.chunks);
+            self.len += other.len;
+            other.len = 0;
+        } else {
+            for event in core::mem::take(other) {
+                self.push(event);
+            }
+        }
+    }
+
+    /// The event at `index`, if any.
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.chunks.get(index / EVENT_CHUNK_LEN)?.get(index % EVENT_CHUNK_LEN)
+    }
+
+    /// Iterates over the events in order.
+    pub fn iter(&self) -> Flatten<slice::Iter<'_, Vec<T>>> {
+        self.chunks.iter().flatten()
+    }
+
+    /// Splits the events into slices of at most `size` events, in order.
+    ///
+    /// Slices never span two chunks, so some may be shorter than `size`.
+    pub fn chunks(&self, size: usize) -> impl Iterator<Item = &[T]> {
+        self.chunks.iter().flat_map(move |chunk| chunk.chunks(size))
+    }
+
+    /// The number of chunks.
+    #[must_use]
+    pub fn num_chunks(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// The chunk at `index`, holding the events `index * EVENT_CHUNK_LEN` onwards.
+    #[must_use]
+    pub fn chunk(&self, index: usize) -> Option<&[T]> {
+        self.chunks.get(index).map(Vec::as_slice)
+    }
+}
+
+impl<T> Default for EventVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Index<usize> for EventVec<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        match self.get(index) {
+            Some(event) => event,
+            None => panic!("index {index} out of bounds for {} events", self.len),
+        }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a EventVec<T> {
+    type Item = &'a T;
+    type IntoIter = Flatten<slice::Iter<'a, Vec<T>>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T> IntoIterator for EventVec<T> {
+    type Item = T;
+    type IntoIter = Flatten<alloc::vec::IntoIter<Vec<T>>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.chunks.into_iter().flatten()
+    }
+}
+
+impl<T> FromIterator<T> for EventVec<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut events = Self::new();
+        iter.into_iter().for_each(|event| events.push(event));
+        events
+    }
+}
+
+impl<T> From<Vec<T>> for EventVec<T> {
+    fn from(events: Vec<T>) -> Self {
+        events.into_iter().collect()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for EventVec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// Serialized as a flat sequence, like a `Vec`.
+impl<T: Serialize> Serialize for EventVec<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for EventVec<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::deserialize(deserializer).map(Self::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_vec() {
+        let n = 2 * EVENT_CHUNK_LEN + 5;
+        let mut events: EventVec<usize> = (0..n).collect();
+        assert_eq!(events.len(), n);
+        assert_eq!(events[EVENT_CHUNK_LEN + 1], EVENT_CHUNK_LEN + 1);
+        assert_eq!(events.get(n), None);
+        assert!(events.iter().copied().eq(0..n));
+        assert_eq!(events.chunks(1000).map(<[usize]>::len).sum::<usize>(), n);
+        assert!(events.chunks(1000).all(|chunk| chunk.len() <= 1000));
+        assert_eq!(events.num_chunks(), 3);
+        assert_eq!(events.chunk(2).map(<[usize]>::len), Some(5));
+        assert_eq!(events.chunk(3), None);
+
+        // Appending to a partial chunk copies, appending on a boundary moves.
+        let mut other: EventVec<usize> = (n..n + EVENT_CHUNK_LEN).collect();
+        events.append(&mut other);
+        assert!(other.is_empty());
+        let mut aligned: EventVec<usize> = (0..EVENT_CHUNK_LEN).collect();
+        aligned.append(&mut events);
+        assert_eq!(aligned.len(), 2 * EVENT_CHUNK_LEN + n);
+        assert_eq!(aligned[EVENT_CHUNK_LEN + n - 1], n - 1);
+        assert_eq!(aligned[aligned.len() - 1], n + EVENT_CHUNK_LEN - 1);
+    }
+}
@@ -1,4 +1,9 @@
-use std::fmt::Display;
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::Display;
 
 /// Returns a tuple containing everything needed to to correctly display a table of counts
 /// (e.g. `opcode_counts`):
@@ -1,6 +1,5 @@
-use std::sync::Arc;
+use alloc::{format, string::String, sync::Arc, vec::Vec};
 
-use hashbrown::HashMap;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
@@ -8,13 +7,20 @@ use crate::events::*;
 use crate::instruction::Instruction;
 use crate::opcode::Opcode;
 use crate::program::Program;
-use crate::record::{ExecutionRecord, MemoryAccessRecord};
+use crate::record::{ExecutionRecord, MemoryAccessRecord, ShardBoundary};
 use crate::state::ExecutionState;
+use crate::tape::{Tape, TAPE_LEN};
 
 /// The default increment for the program counter.  Is used for all instructions except
 /// for branches and jumps.
 pub const DEFAULT_PC_INC: u32 = 1;
 
+/// The bound on the timestamps of memory accesses.
+///
+/// The memory argument range checks the timestamps and their differences to 24 bits, so a
+/// record with a timestamp of `MAX_CLK` or more cannot be proven.
+pub const MAX_CLK: u32 = 1 << 24;
+
 /// An executor for the zkVM.
 ///
 /// The executor is responsible for executing a user program and tracing important events which
@@ -33,8 +39,32 @@ pub struct Executor {
     /// The memory accesses for the current cycle.
     pub memory_accesses: MemoryAccessRecord,
 
-    /// Memory access events.
-    pub memory_events: HashMap<u32, MemoryEvent>,
+    /// The index of the last shard that accessed each memory cell, `None` for the cells never
+    /// accessed.
+    pub last_access_shard: Tape<Option<u32>>,
+
+    /// The index of the last shard that accessed each cell of the second tape.
+    pub second_last_access_shard: Tape<Option<u32>>,
+
+    /// The index of the current shard, [`Executor::record`] being its record.
+    pub shard_index: u32,
+
+    /// The maximum number of cycles to execute, if any.
+    pub max_cycles: Option<u64>,
+
+    /// The number of cycles of each shard, if the execution is split into shards.
+    pub shard_size: Option<u64>,
+}
+
+/// The records of the memory cells after the last cycle of an execution, on each tape.
+type FinalMemory = [Tape<MemoryRecord>; 2];
+
+/// An iterator over the records of the shards of an execution, see [`Executor::shards`].
+pub struct Shards<'a> {
+    executor: &'a mut Executor,
+    /// The memory after the execution, from a first run of the program.
+    final_memory: Option<FinalMemory>,
+    done: bool,
 }
 
 /// Errors that the [`Executor`] can throw.
@@ -51,6 +81,14 @@ pub enum ExecutionError {
     /// An error occurred while writing to memory.
     #[error("Memory write error: {0}")]
     MemoryWriteError(String),
+
+    /// The program did not halt within the cycle limit.
+    #[error("Cycle limit of {0} exceeded")]
+    CycleLimitExceeded(u64),
+
+    /// The clk of the next cycle would reach [`MAX_CLK`].
+    #[error("Clock overflow: the clk {0} would reach the bound of 2^24")]
+    ClockOverflow(u32),
 }
 
 impl Executor {
@@ -63,25 +101,204 @@ impl Executor {
         // Create a default record with the program.
         let record = ExecutionRecord::new(program.clone());
 
-        Self { program, record, state: ExecutionState::new(input), ..Default::default() }
+        let mut executor =
+            Self { program, record, state: ExecutionState::new(input), ..Default::default() };
+
+        // The image cells are in the memory table even if the program never touches them, so
+        // that all the image is checked. The first shard initializes them.
+        for (addr, &value) in executor.program.image.iter().enumerate() {
+            let record = MemoryRecord { timestamp: 0, value };
+            *executor.state.memory_access.get_mut(addr as u32) = record;
+            executor.touch(addr as u32, record);
+        }
+        executor
+    }
+
+    /// Create a new [`Executor`] whose first tape starts with `image` from address 0.
+    ///
+    /// The image becomes part of the program, so the keys set up for
+    /// [`Executor::program`](Self::program) commit to it.
+    #[must_use]
+    pub fn new_with_image(program: Program, input: Vec<u8>, image: &[u8]) -> Self {
+        Self::new(Program { image: image.to_vec(), ..program }, input)
+    }
+
+    /// Create a new [`Executor`] that reads `private_input` with [`Opcode::PrivateInput`].
+    ///
+    /// Unlike `input`, the private input is not committed in the public values of a proof.
+    #[must_use]
+    pub fn new_with_private_input(
+        program: Program,
+        input: Vec<u8>,
+        private_input: Vec<u8>,
+    ) -> Self {
+        let mut executor = Self::new(program, input);
+        executor.state.private_input_stream = private_input;
+        executor
+    }
+
+    /// Stops the execution with an error after `max_cycles` cycles.
+    #[must_use]
+    pub fn with_max_cycles(mut self, max_cycles: u64) -> Self {
+        self.max_cycles = Some(max_cycles);
+        self
+    }
+
+    /// Splits the execution into shards of `shard_size` cycles, the last one being shorter, see
+    /// [`Executor::shards`].
+    ///
+    /// The clk runs on from one shard to the next, so the whole execution is still bounded by
+    /// [`MAX_CLK`].
+    ///
+    /// ### Panics
+    ///
+    /// Panics if `shard_size` is zero.
+    #[must_use]
+    pub fn with_shard_size(mut self, shard_size: u64) -> Self {
+        assert!(shard_size > 0, "shards must have at least one cycle");
+        self.shard_size = Some(shard_size);
+        self
     }
 
     /// Executes the program.
     /// This function will return an error if the program execution fails.
+    ///
+    /// The events of the whole execution are kept in [`Executor::record`], whatever the
+    /// [shard size](Executor::with_shard_size): see [`Executor::shards`] to split them.
     pub fn run(&mut self) -> Result<(), ExecutionError> {
-        while !self.execute_cycle()? {}
+        self.execute_shard(None)?;
+        self.finalize_memory(None);
+        Ok(())
+    }
 
-        for (_, event) in self.memory_events.drain() {
-            self.record.cpu_memory_access.push(event);
-        }
+    /// Executes the program shard by shard, yielding the record of each shard as soon as it is
+    /// finished, so that a shard can be proven and dropped before the next one runs.
+    ///
+    /// Each shard has the i/o events of its cycles, and the memory events of the cells it
+    /// initializes or finalizes: a cell is initialized by the shard that first accesses it and
+    /// finalized by the one that last accesses it, the accesses in between chaining through the
+    /// memory lookups of the shards. To know which shard last accesses a cell, the program first
+    /// runs without keeping its events.
+    ///
+    /// The iterator stops after the last shard or the first error.
+    pub fn shards(&mut self) -> Shards<'_> {
+        Shards { executor: self, final_memory: None, done: false }
+    }
 
+    /// Executes the cycles of the current shard, until the program halts or the shard has
+    /// `shard_size` cycles.
+    fn execute_shard(&mut self, shard_size: Option<u64>) -> Result<(), ExecutionError> {
+        while !self.execute_cycle()? {
+            if let Some(max_cycles) = self.max_cycles {
+                if self.state.global_clk >= max_cycles {
+                    return Err(ExecutionError::CycleLimitExceeded(max_cycles));
+                }
+            }
+            if let Some(shard_size) = shard_size {
+                if self.record.cpu_events.len() as u64 >= shard_size {
+                    break;
+                }
+            }
+        }
         Ok(())
     }
 
+    /// Runs the rest of the program without keeping its events, returning the records of the
+    /// memory cells after the last cycle.
+    fn final_memory(&self) -> Result<FinalMemory, ExecutionError> {
+        let mut executor = Self {
+            program: self.program.clone(),
+            state: self.state.clone(),
+            record: ExecutionRecord::new(self.program.clone()),
+            max_cycles: self.max_cycles,
+            ..Default::default()
+        };
+        while !executor.is_halted() {
+            // Only the memory matters, so the events are dropped every chunk of cycles.
+            executor.execute_shard(Some(EVENT_CHUNK_LEN as u64))?;
+            executor.record = ExecutionRecord::new(self.program.clone());
+        }
+        Ok([executor.state.memory_access, executor.state.second_memory_access])
+    }
+
+    /// Ends the current shard: sets the state after it, and finalizes the cells it accessed for
+    /// the last time, according to `final_memory` if the program has not halted.
+    ///
+    /// The cells that the shard neither initializes nor finalizes are dropped from its memory
+    /// events.
+    fn finalize_memory(&mut self, final_memory: Option<&FinalMemory>) {
+        self.record.next = self.boundary();
+        let final_memory = final_memory.filter(|_| !self.is_halted());
+        let events = core::mem::take(&mut self.record.cpu_memory_access);
+        self.record.cpu_memory_access = events
+            .into_iter()
+            .filter_map(|mut event| {
+                let (memory, final_memory) = match event.tape {
+                    0 => (&self.state.memory_access, final_memory.map(|[first, _]| first)),
+                    _ => (&self.state.second_memory_access, final_memory.map(|[_, second]| second)),
+                };
+                let record = *memory.get(event.addr).expect("accessed cells are on the tape");
+                // The shard finalizes the cell if no later shard accesses it.
+                let last_record =
+                    final_memory.map_or(Some(&record), |memory| memory.get(event.addr));
+                if last_record.map(|last| last.timestamp) == Some(record.timestamp) {
+                    event.final_mem_access = Some(record);
+                }
+                (event.initial_mem_access.is_some() || event.final_mem_access.is_some())
+                    .then_some(event)
+            })
+            .collect();
+    }
+
+    /// Ends the current shard and starts the record of the next one, returning the record of the
+    /// finished shard.
+    fn finish_shard(&mut self, final_memory: Option<&FinalMemory>) -> ExecutionRecord {
+        self.finalize_memory(final_memory);
+        let start = self.record.next;
+        self.shard_index += 1;
+        let record = ExecutionRecord { start, ..ExecutionRecord::new(self.program.clone()) };
+        core::mem::replace(&mut self.record, record)
+    }
+
+    /// The state of the execution before the next cycle, where a shard ends and the next one
+    /// starts.
+    #[must_use]
+    pub fn boundary(&self) -> ShardBoundary {
+        ShardBoundary {
+            clk: self.state.clk,
+            pc: self.state.pc,
+            tape: self.state.tape,
+            mp: self.state.mem_ptr,
+            other_mp: self.state.other_mem_ptr,
+        }
+    }
+
+    /// Whether the program has run to completion.
+    #[must_use]
+    pub fn is_halted(&self) -> bool {
+        self.state.pc as usize == self.program.instructions.len()
+    }
+
+    /// Executes a single cycle, returning whether the program has halted.
+    ///
+    /// Does nothing once the program has halted. Unlike [`Executor::run`], the cycle limit is
+    /// not enforced and the accessed cells are not finalized.
+    pub fn step(&mut self) -> Result<bool, ExecutionError> {
+        if self.is_halted() {
+            return Ok(true);
+        }
+        self.execute_cycle()
+    }
+
     /// Executes one cycle of the program, returning whether the program has finished.
     #[inline]
     #[allow(clippy::too_many_lines)]
     fn execute_cycle(&mut self) -> Result<bool, ExecutionError> {
+        // A cycle accesses memory at `clk + 1` and `clk + 2`.
+        if self.state.clk + 2 >= MAX_CLK {
+            return Err(ExecutionError::ClockOverflow(self.state.clk));
+        }
+
         // Fetch the instruction at the current program counter.
         let instruction = self.fetch();
 
@@ -109,19 +326,27 @@ impl Executor {
         let mut next_mv: u8 = 0;
         let mut mv: u8 = 0;
         let mp = self.state.mem_ptr;
+        let tape = self.state.tape;
+        let other_mp = self.state.other_mem_ptr;
 
         // Execute the instruction.
         match instruction.opcode {
-            Opcode::MemStepForward | Opcode::MemStepBackward => self.execute_memory(instruction),
-            Opcode::Add | Opcode::Sub => (next_mv, mv) = self.execute_alu(instruction),
+            Opcode::MemStepForward | Opcode::MemStepBackward | Opcode::SwitchTape => {
+                self.execute_memory(instruction);
+            }
+            Opcode::Add | Opcode::Sub | Opcode::Set => {
+                (next_mv, mv) = self.execute_alu(instruction);
+            }
             Opcode::LoopStart | Opcode::LoopEnd => {
                 (mv, next_pc) = self.execute_jump(instruction);
                 jmp_dst = next_pc;
             }
-            Opcode::Input | Opcode::Output => mv = self.execute_io(instruction),
+            Opcode::Input | Opcode::PrivateInput | Opcode::Output => {
+                mv = self.execute_io(instruction)?;
+            }
         }
 
-        self.emit_events(next_pc, instruction, jmp_dst, mp, next_mv, mv);
+        self.emit_events(next_pc, instruction, jmp_dst, tape, mp, other_mp, next_mv, mv);
 
         // Update the program counter.
         self.state.pc = next_pc;
@@ -133,12 +358,17 @@ impl Executor {
 
     /// Execute a memory instruction.
     fn execute_memory(&mut self, instruction: &Instruction) {
-        let mp = match instruction.opcode {
-            Opcode::MemStepForward => self.state.mem_ptr.wrapping_add(1),
-            Opcode::MemStepBackward => self.state.mem_ptr.wrapping_sub(1),
+        match instruction.opcode {
+            Opcode::MemStepForward => self.state.mem_ptr = (self.state.mem_ptr + 1) % TAPE_LEN,
+            Opcode::MemStepBackward => {
+                self.state.mem_ptr = self.state.mem_ptr.checked_sub(1).unwrap_or(TAPE_LEN - 1);
+            }
+            Opcode::SwitchTape => {
+                core::mem::swap(&mut self.state.mem_ptr, &mut self.state.other_mem_ptr);
+                self.state.tape ^= 1;
+            }
             _ => unreachable!(),
-        };
-        self.state.mem_ptr = mp;
+        }
     }
 
     /// Execute an ALU instruction.
@@ -147,6 +377,7 @@ impl Executor {
         let next_mv = match instruction.opcode {
             Opcode::Add => mv.wrapping_add(1),
             Opcode::Sub => mv.wrapping_sub(1),
+            Opcode::Set => instruction.op_a as u8,
             _ => unreachable!(),
         };
         self.rw_cpu(self.state.mem_ptr, next_mv, self.state.clk + 2, true);
@@ -177,17 +408,35 @@ impl Executor {
     }
 
     /// Execute an IO instruction.
-    fn execute_io(&mut self, instruction: &Instruction) -> u8 {
+    fn execute_io(&mut self, instruction: &Instruction) -> Result<u8, ExecutionError> {
         match instruction.opcode {
             Opcode::Input => {
-                let input = self.state.input_stream[self.state.input_stream_ptr];
+                let Some(&input) = self.state.input_stream.get(self.state.input_stream_ptr) else {
+                    return Err(ExecutionError::ExecutionError(format!(
+                        "input stream exhausted at pc {}",
+                        self.state.pc
+                    )));
+                };
+                self.state.input_stream_ptr += 1;
+                self.rw_cpu(self.state.mem_ptr, input, self.state.clk + 1, false);
+                Ok(input)
+            }
+            Opcode::PrivateInput => {
+                let ptr = self.state.private_input_stream_ptr;
+                let Some(&input) = self.state.private_input_stream.get(ptr) else {
+                    return Err(ExecutionError::ExecutionError(format!(
+                        "private input stream exhausted at pc {}",
+                        self.state.pc
+                    )));
+                };
+                self.state.private_input_stream_ptr += 1;
                 self.rw_cpu(self.state.mem_ptr, input, self.state.clk + 1, false);
-                input
+                Ok(input)
             }
             Opcode::Output => {
                 let output = self.rr_cpu(self.state.mem_ptr, self.state.clk + 1);
                 self.state.output_stream.push(output);
-                output
+                Ok(output)
             }
             _ => unreachable!(),
         }
@@ -200,7 +449,9 @@ impl Executor {
         next_pc: u32,
         instruction: &Instruction,
         jmp_dst: u32,
+        tape: u32,
         mp: u32,
+        other_mp: u32,
         next_mv: u8,
         mv: u8,
     ) {
@@ -210,6 +461,8 @@ impl Executor {
             next_pc,
             mp,
             next_mp: self.state.mem_ptr,
+            tape,
+            next_tape: self.state.tape,
             next_mv,
             mv,
             next_mv_access: self.memory_accesses.next_mv,
@@ -217,12 +470,11 @@ impl Executor {
         });
 
         if instruction.is_alu_instruction() {
-            self.record.add_events.push(AluEvent::new(
-                self.state.pc,
-                instruction.opcode,
-                next_mv,
-                mv,
-            ));
+            let event = AluEvent::new(self.state.pc, instruction.opcode, next_mv, mv);
+            match instruction.opcode {
+                Opcode::Set => self.record.set_events.push(event),
+                _ => self.record.add_events.push(event),
+            }
         }
         if instruction.is_jump_instruction() {
             self.record.jump_events.push(JumpEvent::new(
@@ -234,17 +486,25 @@ impl Executor {
             ));
         }
         if instruction.is_memory_instruction() {
-            self.record.memory_instr_events.push(MemInstrEvent::new(
+            self.record.memory_instr_events.push(MemInstrEvent {
+                clk: self.state.clk,
+                pc: self.state.pc,
+                opcode: instruction.opcode,
+                tape,
+                mp,
+                next_mp: self.state.mem_ptr,
+                other_mp,
+            });
+        }
+        if instruction.is_io_instruction() {
+            self.record.io_events.push(IoEvent::new(
                 self.state.clk,
                 self.state.pc,
                 instruction.opcode,
                 mp,
-                self.state.mem_ptr,
+                mv,
             ));
         }
-        if instruction.is_io_instruction() {
-            self.record.io_events.push(IoEvent::new(self.state.pc, instruction.opcode, mp, mv));
-        }
 
         self.memory_accesses.mv = None;
         self.memory_accesses.next_mv = None;
@@ -272,21 +532,11 @@ impl Executor {
 
     /// Read a register and create an access record.
     pub fn rr_traced(&mut self, addr: u32, timestamp: u32) -> MemoryReadRecord {
-        let record: &mut MemoryRecord =
-            self.state.memory_access.entry(addr).or_insert(MemoryRecord { value: 0, timestamp: 0 });
+        let record = self.memory_mut().get_mut(addr);
         let prev_record = *record;
         record.timestamp = timestamp;
-
-        self.memory_events
-            .entry(addr)
-            .and_modify(|e| {
-                e.final_mem_access = *record;
-            })
-            .or_insert(MemoryEvent {
-                addr,
-                initial_mem_access: prev_record,
-                final_mem_access: *record,
-            });
+        let record = *record;
+        self.touch(addr, prev_record);
 
         // Construct the memory read record.
         MemoryReadRecord {
@@ -298,22 +548,12 @@ impl Executor {
 
     /// Write a word to a register and create an access record.
     pub fn rw_traced(&mut self, addr: u32, value: u8, timestamp: u32) -> MemoryWriteRecord {
-        let record: &mut MemoryRecord =
-            self.state.memory_access.entry(addr).or_insert(MemoryRecord { value: 0, timestamp: 0 });
+        let record = self.memory_mut().get_mut(addr);
         let prev_record = *record;
         record.value = value;
         record.timestamp = timestamp;
-
-        self.memory_events
-            .entry(addr)
-            .and_modify(|e| {
-                e.final_mem_access = *record;
-            })
-            .or_insert(MemoryEvent {
-                addr,
-                initial_mem_access: prev_record,
-                final_mem_access: *record,
-            });
+        let record = *record;
+        self.touch(addr, prev_record);
 
         // Construct the memory write record.
         MemoryWriteRecord {
@@ -323,14 +563,79 @@ impl Executor {
             prev_timestamp: prev_record.timestamp,
         }
     }
+
+    /// The memory of the current tape.
+    #[inline]
+    fn memory_mut(&mut self) -> &mut Tape<MemoryRecord> {
+        match self.state.tape {
+            0 => &mut self.state.memory_access,
+            _ => &mut self.state.second_memory_access,
+        }
+    }
+
+    /// Adds a memory cell of the current tape to the memory events of the shard on its first
+    /// access in the shard, with its record `prev_record` if no shard accessed it before.
+    #[inline]
+    fn touch(&mut self, addr: u32, prev_record: MemoryRecord) {
+        let tape = self.state.tape;
+        let last_access_shard = match tape {
+            0 => &mut self.last_access_shard,
+            _ => &mut self.second_last_access_shard,
+        };
+        let shard = last_access_shard.get_mut(addr);
+        if *shard != Some(self.shard_index) {
+            let initial_mem_access = shard.is_none().then_some(prev_record);
+            *shard = Some(self.shard_index);
+            self.record.cpu_memory_access.push(MemoryEvent {
+                tape,
+                addr,
+                initial_mem_access,
+                final_mem_access: None,
+            });
+        }
+    }
+}
+
+impl Shards<'_> {
+    /// The state of the execution after the last shard yielded, e.g. to checkpoint it with the
+    /// record of the shard.
+    #[must_use]
+    pub fn state(&self) -> &ExecutionState {
+        &self.executor.state
+    }
+
+    /// Executes the next shard, running the program once first to find its final memory.
+    fn execute_shard(&mut self) -> Result<ExecutionRecord, ExecutionError> {
+        if self.executor.shard_size.is_some() && self.final_memory.is_none() {
+            self.final_memory = Some(self.executor.final_memory()?);
+        }
+        self.executor.execute_shard(self.executor.shard_size)?;
+        Ok(self.executor.finish_shard(self.final_memory.as_ref()))
+    }
+}
+
+impl Iterator for Shards<'_> {
+    type Item = Result<ExecutionRecord, ExecutionError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = self.execute_shard();
+        self.done = !matches!(&result, Ok(record) if !record.is_last_shard());
+        Some(result)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use test_artifacts::{FIBO_BF, HELLO_BF, LOOP_BF, MOVE_BF, PRINTA_BF};
 
-    use crate::executor::Executor;
+    use crate::executor::{ExecutionError, Executor, MAX_CLK};
+    use crate::opcode::Opcode;
     use crate::program::Program;
+    use crate::record::{ExecutionRecord, ShardBoundary};
+    use crate::tape::{TAPE_LEN, TAPE_PAGE_LEN};
 
     #[test]
     fn test_add_sub_run() {
@@ -346,6 +651,114 @@ mod tests {
         let mut runtime = Executor::new(program, vec![]);
         runtime.run().unwrap();
         assert_eq!(1, runtime.state.mem_ptr);
+
+        // The pointer wraps around at the order of the field, where the proofs reduce it.
+        let mut runtime = Executor::new(Program::from("<<>").unwrap(), vec![]);
+        runtime.run().unwrap();
+        assert_eq!(TAPE_LEN - 1, runtime.state.mem_ptr);
+    }
+
+    #[test]
+    fn test_memory_events() {
+        let program = Program::from("<+>++").unwrap();
+        let mut runtime = Executor::new(program, vec![]);
+        runtime.run().unwrap();
+
+        let events: Vec<_> = runtime.record.cpu_memory_access.iter().collect();
+        assert_eq!(events.len(), 2);
+        assert_eq!((events[0].addr, events[0].final_mem_access.unwrap().value), (TAPE_LEN - 1, 1));
+        assert_eq!((events[1].addr, events[1].final_mem_access.unwrap().value), (0, 2));
+        assert!(events.iter().all(|event| event.initial_mem_access.unwrap().timestamp == 0));
+    }
+
+    #[test]
+    fn test_strided_memory() {
+        // Moving the pointer does not touch the cells it passes, so only their pages are kept.
+        let stride = ">".repeat(3 * TAPE_PAGE_LEN);
+        let program = Program::from(&format!("+{stride}++{stride}+++")).unwrap();
+        let mut runtime = Executor::new(program, vec![]);
+        runtime.run().unwrap();
+
+        let stats = runtime.state.memory_access.stats();
+        assert_eq!((stats.pages, stats.page_table_len), (3, 7));
+        assert_eq!(runtime.record.cpu_memory_access.len(), 3);
+        let last = runtime.state.memory_access.get(6 * TAPE_PAGE_LEN as u32);
+        assert_eq!(last.map(|record| record.value), Some(3));
+    }
+
+    #[test]
+    fn test_private_input() {
+        let program = Program::from(",.;.;+.").unwrap();
+        let mut runtime = Executor::new_with_private_input(program, vec![1], vec![7, 9]);
+        runtime.run().unwrap();
+        assert_eq!(runtime.state.output_stream, [1, 7, 10]);
+
+        // The private reads are told apart from the public ones, which alone are committed.
+        let reads: Vec<_> = runtime
+            .record
+            .io_events
+            .iter()
+            .filter(|e| e.opcode != Opcode::Output)
+            .map(|e| (e.opcode, e.mv))
+            .collect();
+        assert_eq!(
+            reads,
+            [(Opcode::Input, 1), (Opcode::PrivateInput, 7), (Opcode::PrivateInput, 9)]
+        );
+
+        let program = Program::from(";").unwrap();
+        let mut runtime = Executor::new(program, vec![1]);
+        assert!(matches!(runtime.run(), Err(ExecutionError::ExecutionError(_))));
+    }
+
+    #[test]
+    fn test_memory_image() {
+        let program = Program::from(".>+.").unwrap();
+        let mut runtime = Executor::new_with_image(program, vec![], &[3, 4, 5]);
+        runtime.run().unwrap();
+        assert_eq!(runtime.state.output_stream, [3, 5]);
+
+        // The untouched cell 2 is still in the memory table.
+        let cells: Vec<_> = runtime
+            .record
+            .cpu_memory_access
+            .iter()
+            .map(|e| {
+                (e.addr, e.initial_mem_access.unwrap().value, e.final_mem_access.unwrap().value)
+            })
+            .collect();
+        assert_eq!(cells, [(0, 3, 3), (1, 4, 5), (2, 5, 5)]);
+    }
+
+    #[test]
+    fn test_second_tape() {
+        use crate::Instruction;
+
+        // `+>~++~.~.`: the tapes keep their own cells and memory pointers.
+        let instructions = [
+            Opcode::Add,
+            Opcode::MemStepForward,
+            Opcode::SwitchTape,
+            Opcode::Add,
+            Opcode::Add,
+            Opcode::SwitchTape,
+            Opcode::Output,
+            Opcode::SwitchTape,
+            Opcode::Output,
+        ];
+        let program = Program::new(instructions.into_iter().map(Instruction::new).collect());
+        let mut runtime = Executor::new(program, vec![]);
+        runtime.run().unwrap();
+        let state = &runtime.state;
+        assert_eq!(state.output_stream, [0, 2]);
+        assert_eq!((state.tape, state.mem_ptr, state.other_mem_ptr), (1, 0, 1));
+
+        let events: Vec<_> = runtime.record.cpu_memory_access.iter().collect();
+        let cells: Vec<_> =
+            events.iter().map(|e| (e.tape, e.addr, e.final_mem_access.unwrap().value)).collect();
+        assert_eq!(cells, [(0, 0, 1), (1, 0, 2), (0, 1, 0)]);
+        let switch = runtime.record.memory_instr_events[1];
+        assert_eq!((switch.tape, switch.mp, switch.next_mp, switch.other_mp), (0, 1, 0, 0));
     }
 
     #[test]
@@ -364,6 +777,104 @@ mod tests {
         assert_eq!(1, runtime.state.output_stream[0]);
     }
 
+    #[test]
+    fn test_io_advances_input() {
+        let program = Program::from(",>,.<.").unwrap();
+        let mut runtime = Executor::new(program, vec![1, 2]);
+        runtime.run().unwrap();
+        assert_eq!(vec![2, 1], runtime.state.output_stream);
+
+        let program = Program::from(",,").unwrap();
+        let mut runtime = Executor::new(program, vec![1]);
+        assert!(runtime.run().is_err());
+    }
+
+    #[test]
+    fn test_step() {
+        let program = Program::from("+[-].").unwrap();
+        let mut runtime = Executor::new(program, vec![]);
+        let mut cycles = 0;
+        while !runtime.step().unwrap() {
+            cycles += 1;
+        }
+        assert_eq!(cycles + 1, runtime.state.global_clk);
+        assert!(runtime.is_halted());
+        assert!(runtime.step().unwrap());
+        assert_eq!(vec![0], runtime.state.output_stream);
+    }
+
+    #[test]
+    fn test_max_cycles() {
+        let program = Program::from("+[]").unwrap();
+        let mut runtime = Executor::new(program, vec![]).with_max_cycles(100);
+        assert!(matches!(runtime.run(), Err(ExecutionError::CycleLimitExceeded(100))));
+    }
+
+    #[test]
+    fn test_shards() {
+        let program = Program::from(FIBO_BF).unwrap();
+        let mut runtime = Executor::new(program.clone(), vec![17]);
+        runtime.run().unwrap();
+        let mut sharded = Executor::new(program, vec![17]).with_shard_size(100);
+        let shards = sharded.shards().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(sharded.state.output_stream, runtime.state.output_stream);
+
+        assert_eq!(shards.len() as u64, runtime.state.global_clk.div_ceil(100));
+        assert_eq!(shards[0].start, ShardBoundary::default());
+        for pair in shards.windows(2) {
+            assert_eq!(pair[0].cpu_events.len(), 100);
+            assert_eq!(pair[0].next, pair[1].start);
+            assert_eq!(pair[1].start.clk, pair[0].start.clk + 200);
+            assert!(!pair[0].is_last_shard());
+        }
+        let last = shards.last().unwrap();
+        assert!(last.is_last_shard());
+        assert_eq!(last.next, runtime.record.next);
+
+        // Each shard has the i/o of its cycles.
+        let output = shards.iter().flat_map(ExecutionRecord::output).collect::<Vec<_>>();
+        assert_eq!(output, runtime.state.output_stream);
+        assert!(!shards[0].is_last_shard() && !shards[0].io_events.is_empty());
+
+        // Each cell is initialized and finalized once, as in the unsharded run, by the shards
+        // that first and last access it.
+        let cells = |records: &[ExecutionRecord]| {
+            let events = records.iter().flat_map(|record| record.cpu_memory_access.iter());
+            let (mut initial, mut last) = (Vec::new(), Vec::new());
+            for event in events {
+                let cell = (event.tape, event.addr);
+                initial.extend(event.initial_mem_access.map(|record| (cell, record.value)));
+                last.extend(event.final_mem_access.map(|record| (cell, record.timestamp)));
+            }
+            initial.sort_unstable();
+            last.sort_unstable();
+            (initial, last)
+        };
+        assert_eq!(cells(&shards), cells(core::slice::from_ref(&runtime.record)));
+        for shard in &shards {
+            for event in shard.cpu_memory_access.iter() {
+                let accesses = [event.initial_mem_access, event.final_mem_access];
+                assert!(accesses.iter().any(Option::is_some));
+                if let Some(record) = event.final_mem_access {
+                    assert!(record.timestamp == 0 || record.timestamp > shard.start.clk);
+                    assert!(record.timestamp <= shard.next.clk);
+                }
+            }
+        }
+        let memory_events = shards.iter().map(|shard| shard.cpu_memory_access.len()).sum::<usize>();
+        assert!(last.cpu_memory_access.len() < memory_events);
+    }
+
+    #[test]
+    fn test_clock_overflow() {
+        let program = Program::from("+[]").unwrap();
+        let mut runtime = Executor::new(program, vec![]);
+        runtime.state.clk = MAX_CLK - 6;
+        assert!(runtime.step().is_ok());
+        let result = runtime.run();
+        assert!(matches!(result, Err(ExecutionError::ClockOverflow(clk)) if clk == MAX_CLK - 2));
+    }
+
     #[test]
     fn test_printa_run() {
         let program = Program::from(PRINTA_BF).unwrap();
@@ -1,3 +1,4 @@
+use alloc::format;
 use core::fmt::Debug;
 use serde::{Deserialize, Serialize};
 
@@ -23,10 +24,15 @@ impl Instruction {
         Self { opcode, op_a }
     }
 
+    /// Create a new instruction setting the current memory cell to `imm`.
+    pub const fn new_set(imm: u8) -> Self {
+        Self { opcode: Opcode::Set, op_a: imm as u32 }
+    }
+
     /// Returns if the instruction is an ALU instruction.
     #[must_use]
     pub const fn is_alu_instruction(&self) -> bool {
-        matches!(self.opcode, Opcode::Add | Opcode::Sub)
+        matches!(self.opcode, Opcode::Add | Opcode::Sub | Opcode::Set)
     }
 
     /// Returns if the instruction is a Jump instruction.
@@ -38,13 +44,13 @@ impl Instruction {
     /// Returns if the instruction is a Memory instruction.
     #[must_use]
     pub const fn is_memory_instruction(&self) -> bool {
-        matches!(self.opcode, Opcode::MemStepForward | Opcode::MemStepBackward)
+        matches!(self.opcode, Opcode::MemStepForward | Opcode::MemStepBackward | Opcode::SwitchTape)
     }
 
     /// Returns if the instruction is a Memory instruction.
     #[must_use]
     pub const fn is_io_instruction(&self) -> bool {
-        matches!(self.opcode, Opcode::Input | Opcode::Output)
+        matches!(self.opcode, Opcode::Input | Opcode::PrivateInput | Opcode::Output)
     }
 
     #[must_use]
@@ -62,6 +68,8 @@ impl Instruction {
             '-' => Self::new(Opcode::Sub),
             '.' => Self::new(Opcode::Output),
             ',' => Self::new(Opcode::Input),
+            ';' => Self::new(Opcode::PrivateInput),
+            '~' => Self::new(Opcode::SwitchTape),
             '[' => Self::new_jmp(Opcode::LoopStart, operand.unwrap()),
             ']' => Self::new_jmp(Opcode::LoopEnd, operand.unwrap()),
             _ => unreachable!(),
@@ -70,7 +78,7 @@ impl Instruction {
 }
 
 impl Debug for Instruction {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self.opcode {
             Opcode::LoopStart => f.write_str(&format!("[{}", self.op_a))?,
             Opcode::LoopEnd => f.write_str(&format!("]{}", self.op_a))?,
@@ -80,6 +88,9 @@ impl Debug for Instruction {
             Opcode::MemStepBackward => f.write_str("<")?,
             Opcode::Input => f.write_str(",")?,
             Opcode::Output => f.write_str(".")?,
+            Opcode::Set => f.write_str(&format!("={}", self.op_a))?,
+            Opcode::SwitchTape => f.write_str("~")?,
+            Opcode::PrivateInput => f.write_str(";")?,
         }
         Ok(())
     }
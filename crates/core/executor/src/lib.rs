@@ -1,14 +1,30 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+mod checkpoint;
+mod coverage;
+mod digest;
 pub mod events;
 mod executor;
 mod instruction;
 mod opcode;
+mod profile;
 mod program;
 mod record;
 mod state;
+mod tape;
 
+#[cfg(feature = "std")]
+pub use checkpoint::*;
+pub use coverage::*;
+pub use digest::*;
 pub use executor::*;
 pub use instruction::*;
 pub use opcode::*;
+pub use profile::*;
 pub use program::*;
 pub use record::*;
 pub use state::*;
+pub use tape::*;
@@ -1,4 +1,4 @@
-use std::fmt::Display;
+use core::fmt::Display;
 
 use enum_map::Enum;
 use p3_field::Field;
@@ -26,6 +26,15 @@ pub enum Opcode {
     Input = 6,
     /// '.': outputs the value of the currently indicated memory cell
     Output = 7,
+    /// '[-]' followed by `op_a` times '+': sets the currently indicated memory cell to `op_a`.
+    /// Only emitted by [`Program::optimize`](crate::Program::optimize).
+    Set = 8,
+    /// '~': switches to the other tape, each tape keeping its own memory pointer.
+    /// Only parsed with the `second-tape` feature.
+    SwitchTape = 9,
+    /// ';': reads a byte from the private input stream and stores it in the currently indicated
+    /// memory cell. Unlike ',', the byte is not committed in the public values.
+    PrivateInput = 10,
 }
 
 /// Byte Opcode.
@@ -54,6 +63,9 @@ impl Opcode {
             Opcode::MemStepBackward => "<",
             Opcode::Input => ",",
             Opcode::Output => ".",
+            Opcode::Set => "=",
+            Opcode::SwitchTape => "~",
+            Opcode::PrivateInput => ";",
         }
     }
 
@@ -65,7 +77,7 @@ impl Opcode {
 }
 
 impl Display for Opcode {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.write_str(self.mnemonic())
     }
 }
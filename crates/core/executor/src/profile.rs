@@ -0,0 +1,76 @@
+use alloc::vec::Vec;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{ExecutionRecord, Opcode};
+
+/// The number of cycles spent at one program counter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PcProfile {
+    /// The program counter.
+    pub pc: u32,
+    /// The opcode of the instruction at `pc`.
+    pub opcode: Opcode,
+    /// The number of times the instruction was executed.
+    pub cycles: u64,
+}
+
+/// A per-pc profile of an execution.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Profile {
+    /// One entry per instruction of the program, indexed by pc.
+    pub pcs: Vec<PcProfile>,
+    /// The total number of cycles.
+    pub total_cycles: u64,
+}
+
+impl Profile {
+    /// Builds the profile of the execution that produced `record`.
+    #[must_use]
+    pub fn from_record(record: &ExecutionRecord) -> Self {
+        let mut pcs = record
+            .program
+            .instructions
+            .iter()
+            .enumerate()
+            .map(|(pc, instruction)| PcProfile {
+                pc: pc as u32,
+                opcode: instruction.opcode,
+                cycles: 0,
+            })
+            .collect::<Vec<_>>();
+        for &pc in &record.cpu_events.pc {
+            pcs[pc as usize].cycles += 1;
+        }
+        Self { pcs, total_cycles: record.cpu_events.len() as u64 }
+    }
+
+    /// The `n` program counters with the most cycles, hottest first.
+    #[must_use]
+    pub fn hottest(&self, n: usize) -> Vec<PcProfile> {
+        let mut pcs = self.pcs.iter().filter(|pc| pc.cycles > 0).copied().collect::<Vec<_>>();
+        pcs.sort_by(|a, b| b.cycles.cmp(&a.cycles).then(a.pc.cmp(&b.pc)));
+        pcs.truncate(n);
+        pcs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Executor, Profile, Program};
+
+    #[test]
+    fn test_profile() {
+        let program = Program::from("+++++[-].").unwrap();
+        let mut runtime = Executor::new(program, vec![]);
+        runtime.run().unwrap();
+
+        let profile = Profile::from_record(&runtime.record);
+        assert_eq!(profile.total_cycles, runtime.record.cpu_events.len() as u64);
+        assert_eq!(profile.pcs.iter().map(|pc| pc.cycles).sum::<u64>(), profile.total_cycles);
+
+        // The loop body runs five times.
+        assert_eq!(profile.pcs[6].cycles, 5);
+        assert_eq!(profile.hottest(1)[0].cycles, 5);
+    }
+}
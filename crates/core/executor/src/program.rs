@@ -1,29 +1,58 @@
-use anyhow::Result;
+use alloc::{vec, vec::Vec};
+
+use anyhow::{bail, Result};
+#[cfg(feature = "stark")]
 use p3_field::PrimeField32;
 use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "stark")]
 use bf_stark::air::MachineProgram;
 
-use crate::instruction::Instruction;
+use crate::{instruction::Instruction, opcode::Opcode};
+
+/// The maximum number of instructions of a program.
+///
+/// The pcs, jump targets and the pc after the last instruction are at most this, far below the
+/// KoalaBear modulus `2^31 - 2^24 + 1`, so pc arithmetic in the field never wraps around. It is
+/// also the largest trace the two-adicity of KoalaBear allows.
+pub const MAX_PROGRAM_LEN: usize = 1 << 24;
 
 /// A program that can be executed by the ZKM.
 #[derive(PartialEq, Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Program {
     pub instructions: Vec<Instruction>,
+    /// The initial contents of the first tape from address 0. The other cells start at zero.
+    #[serde(default)]
+    pub image: Vec<u8>,
 }
 
 impl Program {
     #[must_use]
     pub fn new(instructions: Vec<Instruction>) -> Self {
-        Self { instructions }
+        Self { instructions, image: Vec::new() }
     }
 
     /// Initialize a Brainfuck Program from an appropriate file
+    ///
+    /// Any character other than a command is a comment, and unbalanced brackets are rejected.
+    /// `;` reads from the private input stream, and with the `second-tape` feature, `~` switches
+    /// to the other tape. See [`Program::from_strict`] to reject comments.
     pub fn from(code: &str) -> Result<Program> {
+        Self::from_with_max_len(code, MAX_PROGRAM_LEN, false)
+    }
+
+    /// Initialize a Brainfuck Program as [`Program::from`] does, rejecting any character that
+    /// is neither a command nor ASCII whitespace, e.g. to catch a typo in a program without
+    /// comments.
+    pub fn from_strict(code: &str) -> Result<Program> {
+        Self::from_with_max_len(code, MAX_PROGRAM_LEN, true)
+    }
+
+    fn from_with_max_len(code: &str, max_len: usize, strict: bool) -> Result<Program> {
         // keeps track of loop beginnings while (potentially nested) loops are being compiled
         let mut loop_stack = vec![];
         let mut instructions = Vec::new();
-        for c in code.chars() {
+        for (i, c) in code.chars().enumerate() {
             // to allow skipping a loop and jumping back to the loop's beginning, the respective start and end positions
             // are recorded in the program
             if c == '[' {
@@ -32,15 +61,68 @@ impl Program {
                 loop_stack.push(instructions.len() - 1);
             } else if c == ']' {
                 // record loop's end in beginning
-                let start_pos = loop_stack.pop().unwrap();
+                let Some(start_pos) = loop_stack.pop() else {
+                    bail!("unmatched ']' at character {i}");
+                };
                 instructions[start_pos].op_a = instructions.len() as u32;
                 // record loop's start
                 instructions.push(Instruction::decode_from(c, Some((start_pos + 1) as u32)));
-            } else if c != ' ' && c != '\n' && c != '\r' {
+            } else if matches!(c, '>' | '<' | '+' | '-' | '.' | ',' | ';')
+                || (cfg!(feature = "second-tape") && c == '~')
+            {
                 instructions.push(Instruction::decode_from(c, None));
+            } else if strict && !c.is_ascii_whitespace() {
+                bail!("unexpected character {c:?} at character {i}");
+            }
+            if instructions.len() > max_len {
+                bail!("program longer than {max_len} instructions");
             }
         }
-        Ok(Self { instructions })
+        if let Some(start_pos) = loop_stack.pop() {
+            bail!("unmatched '[' at instruction {start_pos}");
+        }
+        Ok(Self::new(instructions))
+    }
+
+    /// Rewrites each clear loop `[-]` and the `+`s following it into a single
+    /// [`Opcode::Set`], and remaps the jump targets accordingly.
+    #[must_use]
+    pub fn optimize(self) -> Program {
+        let Program { instructions: old, image } = self;
+        // The new index of each old instruction, and of the pc after the last one.
+        let mut map = Vec::with_capacity(old.len() + 1);
+        let mut instructions = Vec::with_capacity(old.len());
+        let mut i = 0;
+        while i < old.len() {
+            let is_clear_loop = old[i].opcode == Opcode::LoopStart
+                && old[i].op_a as usize == i + 2
+                && old[i + 1].opcode == Opcode::Sub;
+            if is_clear_loop {
+                let mut end = i + 3;
+                while end < old.len() && old[end].opcode == Opcode::Add {
+                    end += 1;
+                }
+                map.resize(end, instructions.len());
+                instructions.push(Instruction::new_set((end - i - 3) as u8));
+                i = end;
+            } else {
+                map.push(instructions.len());
+                instructions.push(old[i]);
+                i += 1;
+            }
+        }
+        map.push(instructions.len());
+
+        for instruction in &mut instructions {
+            match instruction.opcode {
+                Opcode::LoopStart => instruction.op_a = map[instruction.op_a as usize] as u32,
+                Opcode::LoopEnd => {
+                    instruction.op_a = map[instruction.op_a as usize - 1] as u32 + 1;
+                }
+                _ => {}
+            }
+        }
+        Self { instructions, image }
     }
 
     #[must_use]
@@ -50,4 +132,67 @@ impl Program {
     }
 }
 
+#[cfg(feature = "stark")]
 impl<F: PrimeField32> MachineProgram<F> for Program {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_rejects_invalid_programs() {
+        assert!(Program::from("+[-]\n.").is_ok());
+        assert!(Program::from("+]").is_err());
+        assert!(Program::from("[+").is_err());
+        assert_eq!(Program::from_strict("+~").is_ok(), cfg!(feature = "second-tape"));
+
+        // Comments are skipped, unless parsing strictly, which only skips whitespace.
+        let commented = Program::from("add one + then print it .").unwrap();
+        assert_eq!(commented, Program::from("+.").unwrap());
+        assert!(Program::from("a]").is_err());
+        assert_eq!(
+            Program::from_strict("+\t[-]\r\n\x0c.").unwrap(),
+            Program::from("+[-].").unwrap()
+        );
+        assert!(Program::from_strict("+ a").is_err());
+    }
+
+    #[test]
+    fn test_from_rejects_long_programs() {
+        assert!(Program::from_with_max_len("+[-]", 4, true).is_ok());
+        assert!(Program::from_with_max_len("+[-].", 4, true).is_err());
+        assert!(Program::from_with_max_len("+ \n[-]\n", 4, true).is_ok());
+        assert!(Program::from_with_max_len("+ a comment [-]", 4, false).is_ok());
+    }
+
+    #[test]
+    fn test_optimize() {
+        let program = Program::from("+[>[-]+++<-]").unwrap().optimize();
+        assert_eq!(
+            program.instructions,
+            [
+                Instruction::new(Opcode::Add),
+                Instruction::new_jmp(Opcode::LoopStart, 6),
+                Instruction::new(Opcode::MemStepForward),
+                Instruction::new_set(3),
+                Instruction::new(Opcode::MemStepBackward),
+                Instruction::new(Opcode::Sub),
+                Instruction::new_jmp(Opcode::LoopEnd, 2),
+            ]
+        );
+
+        let program = Program::from("[[-]]").unwrap().optimize();
+        assert_eq!(
+            program.instructions,
+            [
+                Instruction::new_jmp(Opcode::LoopStart, 2),
+                Instruction::new_set(0),
+                Instruction::new_jmp(Opcode::LoopEnd, 1),
+            ]
+        );
+
+        // Other loops are left as they are.
+        let program = Program::from("+[-+][--]").unwrap();
+        assert_eq!(program.clone().optimize(), program);
+    }
+}
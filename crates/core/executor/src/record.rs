@@ -1,12 +1,19 @@
-use std::sync::Arc;
+use alloc::{sync::Arc, vec::Vec};
 
 use hashbrown::HashMap;
 use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "stark")]
 use bf_stark::MachineRecord;
+#[cfg(feature = "stark")]
+use p3_field::Field;
 
 use crate::events::*;
 use crate::program::Program;
+#[cfg(feature = "stark")]
+use crate::IoDigest;
+use crate::Opcode;
+use crate::{NEXT_PUBLIC_VALUES, SHARD_BOUNDARY_LEN, START_PUBLIC_VALUES};
 
 /// A record of the execution of a program.
 ///
@@ -16,21 +23,64 @@ pub struct ExecutionRecord {
     /// The program.
     pub program: Arc<Program>,
     /// A trace of the CPU events which get emitted during execution.
-    pub cpu_events: Vec<CpuEvent>,
+    pub cpu_events: CpuEvents,
     /// A trace of the ADD events.
-    pub add_events: Vec<AluEvent>,
+    pub add_events: EventVec<AluEvent>,
     /// A trace of the SUB events.
-    pub sub_events: Vec<AluEvent>,
+    pub sub_events: EventVec<AluEvent>,
+    /// A trace of the SET events.
+    pub set_events: EventVec<AluEvent>,
     /// A trace of the jump events.
-    pub jump_events: Vec<JumpEvent>,
+    pub jump_events: EventVec<JumpEvent>,
     /// A trace of the i/o events.
-    pub io_events: Vec<IoEvent>,
+    pub io_events: EventVec<IoEvent>,
     /// A trace of the memory instructions.
-    pub memory_instr_events: Vec<MemInstrEvent>,
+    pub memory_instr_events: EventVec<MemInstrEvent>,
     /// A trace of the memory events.
-    pub cpu_memory_access: Vec<MemoryEvent>,
+    pub cpu_memory_access: EventVec<MemoryEvent>,
     /// A trace of the byte lookups that are needed.
     pub byte_lookups: HashMap<ByteLookupEvent, usize>,
+    /// The state of the execution at the start of the shard.
+    pub start: ShardBoundary,
+    /// The state of the execution after the last cycle of the shard.
+    pub next: ShardBoundary,
+}
+
+/// The state of the execution between two shards, as committed in the public values of both.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShardBoundary<T = u32> {
+    /// The clk of the next cycle.
+    pub clk: T,
+    /// The pc of the next instruction.
+    pub pc: T,
+    /// The current tape.
+    pub tape: T,
+    /// The memory pointer of the current tape.
+    pub mp: T,
+    /// The memory pointer of the other tape.
+    pub other_mp: T,
+}
+
+impl<T: Clone> ShardBoundary<T> {
+    /// Reads the boundary at `offset` in the public values, [`START_PUBLIC_VALUES`] or
+    /// [`NEXT_PUBLIC_VALUES`].
+    ///
+    /// ### Panics
+    ///
+    /// Panics if the public values are too short.
+    #[must_use]
+    pub fn from_public_values(public_values: &[T], offset: usize) -> Self {
+        let values = &public_values[offset..offset + SHARD_BOUNDARY_LEN];
+        let [clk, pc, tape, mp, other_mp] = core::array::from_fn(|i| values[i].clone());
+        Self { clk, pc, tape, mp, other_mp }
+    }
+
+    /// The fields of the boundary, in the order of the public values.
+    #[must_use]
+    pub fn to_array(&self) -> [T; SHARD_BOUNDARY_LEN] {
+        let Self { clk, pc, tape, mp, other_mp } = self.clone();
+        [clk, pc, tape, mp, other_mp]
+    }
 }
 
 /// A memory access record.
@@ -48,6 +98,28 @@ impl ExecutionRecord {
     pub fn new(program: Arc<Program>) -> Self {
         Self { program, ..Default::default() }
     }
+
+    /// The bytes read by the input instructions, in order.
+    #[must_use]
+    pub fn input(&self) -> Vec<u8> {
+        self.io_bytes(Opcode::Input)
+    }
+
+    /// The bytes written by the output instructions, in order.
+    #[must_use]
+    pub fn output(&self) -> Vec<u8> {
+        self.io_bytes(Opcode::Output)
+    }
+
+    /// Whether the shard is the last one of the execution, after which the program halted.
+    #[must_use]
+    pub fn is_last_shard(&self) -> bool {
+        self.next.pc as usize == self.program.instructions.len()
+    }
+
+    fn io_bytes(&self, opcode: Opcode) -> Vec<u8> {
+        self.io_events.iter().filter(|event| event.opcode == opcode).map(|event| event.mv).collect()
+    }
 }
 
 impl ByteRecord for ExecutionRecord {
@@ -68,21 +140,63 @@ impl ByteRecord for ExecutionRecord {
     }
 }
 
+#[cfg(feature = "stark")]
 impl MachineRecord for ExecutionRecord {
     fn append(&mut self, other: &mut ExecutionRecord) {
         self.cpu_events.append(&mut other.cpu_events);
         self.add_events.append(&mut other.add_events);
         self.sub_events.append(&mut other.sub_events);
+        self.set_events.append(&mut other.set_events);
         self.jump_events.append(&mut other.jump_events);
         self.io_events.append(&mut other.io_events);
         self.memory_instr_events.append(&mut other.memory_instr_events);
 
         if self.byte_lookups.is_empty() {
-            self.byte_lookups = std::mem::take(&mut other.byte_lookups);
+            self.byte_lookups = core::mem::take(&mut other.byte_lookups);
         } else {
-            self.add_byte_lookup_events_from_maps(vec![&other.byte_lookups]);
+            self.add_byte_lookup_events_from_maps(alloc::vec![&other.byte_lookups]);
         }
 
         self.cpu_memory_access.append(&mut other.cpu_memory_access);
     }
+
+    /// The digests of the bytes read by the input instructions and written by the output
+    /// instructions of the shard, see [`IoDigest`], followed by the numbers of bytes read and
+    /// written, the number of cycles, the boundaries of the shard, whether it is the last one and
+    /// the length of the program.
+    fn public_values<F: Field>(&self) -> Vec<F> {
+        let (mut input, mut output) = (IoDigest::<F>::default(), IoDigest::<F>::default());
+        let (mut input_count, mut output_count) = (0, 0);
+        for event in self.io_events.iter() {
+            match event.opcode {
+                Opcode::Input => {
+                    input.absorb(event.mv);
+                    input_count += 1;
+                }
+                Opcode::Output => {
+                    output.absorb(event.mv);
+                    output_count += 1;
+                }
+                _ => {}
+            }
+        }
+        let cycles = F::from_canonical_usize(self.cpu_events.len());
+        // The memory pointers wrap around at `TAPE_LEN`, so all the fields are canonical.
+        let [start, next] =
+            [self.start, self.next].map(|boundary| boundary.to_array().map(F::from_canonical_u32));
+        let last_shard = F::from_bool(self.is_last_shard());
+        let counts = [input_count, output_count].map(F::from_canonical_usize);
+        let program_len = F::from_canonical_usize(self.program.instructions.len());
+        let values: [&[F]; 8] = [
+            &input.value(),
+            &output.value(),
+            &counts,
+            &[cycles],
+            &start,
+            &next,
+            &[last_shard],
+            &[program_len],
+        ];
+        values.concat()
+    }
 }
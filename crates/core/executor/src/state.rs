@@ -1,7 +1,9 @@
-use hashbrown::HashMap;
+use alloc::vec::Vec;
+
 use serde::{Deserialize, Serialize};
 
 use crate::events::MemoryRecord;
+use crate::tape::Tape;
 
 /// Holds data describing the current state of a program's execution.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -11,11 +13,21 @@ pub struct ExecutionState {
     pub pc: u32,
 
     /// The memory register which instructions operate over.
-    pub memory_access: HashMap<u32, MemoryRecord>,
+    pub memory_access: Tape<MemoryRecord>,
 
     // Memory pointer
     pub mem_ptr: u32,
 
+    /// The current tape, 0 or 1. Only [`Opcode::SwitchTape`](crate::Opcode::SwitchTape) moves
+    /// to the second tape.
+    pub tape: u32,
+
+    /// The memory pointer of the other tape.
+    pub other_mem_ptr: u32,
+
+    /// The memory of the second tape, `memory_access` being the memory of the first one.
+    pub second_memory_access: Tape<MemoryRecord>,
+
     /// The global clock keeps track of how many instructions have been executed.
     pub global_clk: u64,
 
@@ -28,6 +40,12 @@ pub struct ExecutionState {
     /// A ptr to the current position in the input stream.
     pub input_stream_ptr: usize,
 
+    /// A stream of private input values, which the proof does not reveal.
+    pub private_input_stream: Vec<u8>,
+
+    /// A ptr to the current position in the private input stream.
+    pub private_input_stream_ptr: usize,
+
     /// A stream of public values from the program (global to entire program).
     pub output_stream: Vec<u8>,
 }
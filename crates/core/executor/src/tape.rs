@@ -0,0 +1,199 @@
+use alloc::{boxed::Box, vec::Vec};
+
+use serde::{Deserialize, Serialize};
+
+/// The number of cells of a [`Tape`], the order of the KoalaBear field.
+///
+/// The memory pointer is a field element in the proofs, so it wraps around modulo the order of
+/// the field rather than modulo `2^32`: moving left from cell 0 reaches cell `TAPE_LEN - 1`. The
+/// memory instructions chip range checks the pointers below the order, so that each cell has a
+/// single address.
+pub const TAPE_LEN: u32 = 0x7f00_0001;
+
+/// The base 2 logarithm of the number of cells in a page of a [`Tape`].
+pub const TAPE_PAGE_LOG_LEN: u32 = 10;
+
+/// The number of cells in a page of a [`Tape`].
+pub const TAPE_PAGE_LEN: usize = 1 << TAPE_PAGE_LOG_LEN;
+
+/// A page of cells, `None` until one of its cells is set.
+type Page<T> = Option<Box<[T]>>;
+
+/// Values indexed by tape address, stored in pages of [`TAPE_PAGE_LEN`] cells in two page tables
+/// growing from address 0 upwards and from `TAPE_LEN - 1` downwards.
+///
+/// The memory pointer moves one cell at a time and wraps around at [`TAPE_LEN`], so the accessed
+/// addresses form a contiguous range around 0, but moving the pointer does not access the cells
+/// it passes. A page is only allocated when one of its cells is set, so a program striding across
+/// a large range of addresses pays for the pages it writes to, and for one table entry per page
+/// it passes. Cells that were never set hold `T::default()`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Tape<T> {
+    /// The pages of the cells at `0, 1, ..`.
+    up: Vec<Page<T>>,
+    /// The pages of the cells at `TAPE_LEN - 1, TAPE_LEN - 2, ..`, each from its highest address.
+    down: Vec<Page<T>>,
+}
+
+/// The page-level statistics of a [`Tape`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TapeStats {
+    /// The number of allocated pages.
+    pub pages: usize,
+    /// The number of entries of the page tables, allocated or not.
+    pub page_table_len: usize,
+    /// The number of cells of the allocated pages.
+    pub cells: usize,
+}
+
+impl TapeStats {
+    /// The fraction of the pages spanned by the page tables that are allocated, or 0 for an
+    /// empty tape.
+    #[must_use]
+    pub fn occupancy(&self) -> f64 {
+        match self.page_table_len {
+            0 => 0.0,
+            len => self.pages as f64 / len as f64,
+        }
+    }
+}
+
+impl<T> Tape<T> {
+    /// Creates an empty tape.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { up: Vec::new(), down: Vec::new() }
+    }
+
+    /// The cell at `addr`, if its page is allocated.
+    #[must_use]
+    pub fn get(&self, addr: u32) -> Option<&T> {
+        if addr >= TAPE_LEN {
+            return None;
+        }
+        let (up, page, index) = locate(addr);
+        let pages = if up { &self.up } else { &self.down };
+        pages.get(page)?.as_ref().map(|cells| &cells[index])
+    }
+
+    /// Iterates over the cells of the allocated pages, from the lowest address upwards.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &T)> {
+        let up = self.up.iter().enumerate().flat_map(|(page, cells)| {
+            let base = (page * TAPE_PAGE_LEN) as u32;
+            cells.iter().flat_map(move |cells| {
+                cells.iter().enumerate().map(move |(i, cell)| (base + i as u32, cell))
+            })
+        });
+        let down = self.down.iter().enumerate().rev().flat_map(|(page, cells)| {
+            let base = (page * TAPE_PAGE_LEN) as u32;
+            cells.iter().flat_map(move |cells| {
+                let cells = cells.iter().enumerate().rev();
+                cells.map(move |(i, cell)| (TAPE_LEN - 1 - (base + i as u32), cell))
+            })
+        });
+        up.chain(down)
+    }
+
+    /// The page-level statistics of the tape.
+    #[must_use]
+    pub fn stats(&self) -> TapeStats {
+        let pages = self.up.iter().chain(&self.down).filter(|page| page.is_some()).count();
+        TapeStats {
+            pages,
+            page_table_len: self.up.len() + self.down.len(),
+            cells: pages * TAPE_PAGE_LEN,
+        }
+    }
+}
+
+impl<T: Default> Tape<T> {
+    /// The cell at `addr`, allocating its page if needed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `addr` is not below [`TAPE_LEN`].
+    #[inline]
+    pub fn get_mut(&mut self, addr: u32) -> &mut T {
+        assert!(addr < TAPE_LEN, "address {addr} is out of the tape");
+        let (up, page, index) = locate(addr);
+        let pages = if up { &mut self.up } else { &mut self.down };
+        if page >= pages.len() {
+            pages.resize_with(page + 1, || None);
+        }
+        let cells =
+            pages[page].get_or_insert_with(|| (0..TAPE_PAGE_LEN).map(|_| T::default()).collect());
+        &mut cells[index]
+    }
+}
+
+/// Whether `addr` is in the upward page table, with the indices of its page in the table and of
+/// its cell in the page. `addr` must be below [`TAPE_LEN`].
+#[inline]
+const fn locate(addr: u32) -> (bool, usize, usize) {
+    let (up, offset) =
+        if addr < TAPE_LEN / 2 { (true, addr) } else { (false, TAPE_LEN - 1 - addr) };
+    (up, (offset >> TAPE_PAGE_LOG_LEN) as usize, offset as usize & (TAPE_PAGE_LEN - 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_field::PrimeField32;
+    use p3_koala_bear::KoalaBear;
+
+    use super::*;
+
+    #[test]
+    fn test_tape() {
+        assert_eq!(TAPE_LEN, KoalaBear::ORDER_U32);
+
+        let mut tape = Tape::<u8>::new();
+        *tape.get_mut(2) = 5;
+        *tape.get_mut(TAPE_LEN - 1) = 7;
+        *tape.get_mut(TAPE_LEN - 2) = 9;
+
+        assert_eq!(tape.get(2), Some(&5));
+        assert_eq!(tape.get(1), Some(&0));
+        assert_eq!(tape.get(TAPE_PAGE_LEN as u32), None);
+        assert_eq!(tape.get(TAPE_LEN - 2), Some(&9));
+        assert_eq!(tape.get(TAPE_LEN - 1 - TAPE_PAGE_LEN as u32), None);
+        // The addresses past the end of the tape would alias the cells of the field elements.
+        assert_eq!(tape.get(TAPE_LEN), None);
+        assert_eq!(tape.get(u32::MAX), None);
+        let cells: Vec<_> = tape
+            .iter()
+            .filter(|(_, &value)| value != 0)
+            .map(|(addr, &value)| (addr, value))
+            .collect();
+        assert_eq!(cells, [(2, 5), (TAPE_LEN - 2, 9), (TAPE_LEN - 1, 7)]);
+        let addrs: Vec<_> = tape.iter().map(|(addr, _)| addr).collect();
+        assert_eq!(addrs.len(), 2 * TAPE_PAGE_LEN);
+        assert!(addrs.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn test_sparse_tape() {
+        let mut tape = Tape::<u8>::new();
+        let far = 1 << 24;
+        *tape.get_mut(far) = 1;
+        *tape.get_mut(far + 1) = 2;
+        *tape.get_mut(TAPE_LEN - 1 - far) = 3;
+
+        // Only the pages written to are allocated, not the ones the pointer passes.
+        let stats = tape.stats();
+        assert_eq!(stats.pages, 2);
+        assert_eq!(stats.cells, 2 * TAPE_PAGE_LEN);
+        assert_eq!(stats.page_table_len, 2 * ((far as usize >> TAPE_PAGE_LOG_LEN) + 1));
+        assert!(stats.occupancy() < 0.001);
+        assert_eq!(tape.get(0), None);
+        assert_eq!(tape.get(far + 1), Some(&2));
+        assert_eq!(tape.get(TAPE_LEN - 1 - far), Some(&3));
+
+        let cells: Vec<_> = tape
+            .iter()
+            .filter(|(_, &value)| value != 0)
+            .map(|(addr, &value)| (addr, value))
+            .collect();
+        assert_eq!(cells, [(far, 1), (far + 1, 2), (TAPE_LEN - 1 - far, 3)]);
+        assert_eq!(Tape::<u8>::new().stats(), TapeStats::default());
+    }
+}
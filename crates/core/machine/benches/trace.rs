@@ -0,0 +1,39 @@
+//! Benchmarks of trace population.
+//!
+//! Run them with `cargo bench -p bf-core-machine`.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use p3_koala_bear::KoalaBear;
+
+use bf_core_executor::{ExecutionRecord, Executor, Program};
+use bf_core_machine::{bytes::ByteChip, cpu::CpuChip, memory::MemoryChip};
+use bf_stark::air::MachineAir;
+
+type F = KoalaBear;
+
+fn bench_traces(c: &mut Criterion) {
+    let mut runtime = Executor::new(Program::from(test_artifacts::HELLO_BF).unwrap(), vec![]);
+    runtime.run().unwrap();
+    let record = runtime.record;
+
+    let mut group = c.benchmark_group("trace");
+    group.bench_function("Byte preprocessed", |b| b.iter(ByteChip::<F>::trace));
+    group.bench_function("Cpu", |b| {
+        b.iter(|| {
+            MachineAir::<F>::generate_trace(&CpuChip, &record, &mut ExecutionRecord::default())
+        })
+    });
+    group.bench_function("Memory", |b| {
+        b.iter(|| {
+            MachineAir::<F>::generate_trace(
+                &MemoryChip::new(),
+                &record,
+                &mut ExecutionRecord::default(),
+            )
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_traces);
+criterion_main!(benches);
@@ -13,10 +13,12 @@ pub trait MemoryAirBuilder: BaseAirBuilder {
     /// Constrain a memory read or write.
     ///
     /// This method verifies that a memory access timestamp clk is greater than the
-    /// previous access's timestamp.  It will also add to the memory argument.
+    /// previous access's timestamp.  It will also add to the memory argument, in which the
+    /// cells of each tape have their own address space.
     fn eval_memory_access<E: Into<Self::Expr> + Clone>(
         &mut self,
         clk: impl Into<Self::Expr>,
+        tape: impl Into<Self::Expr>,
         addr: impl Into<Self::Expr>,
         memory_access: &impl MemoryCols<E>,
         do_check: impl Into<Self::Expr>,
@@ -31,13 +33,16 @@ pub trait MemoryAirBuilder: BaseAirBuilder {
         self.eval_memory_access_timestamp(mem_access, do_check.clone(), clk.clone());
 
         // Add to the memory argument.
+        let tape = tape.into();
         let addr = addr.into();
         let prev_clk = mem_access.prev_clk.clone().into();
         let prev_values = once(prev_clk)
+            .chain(once(tape.clone()))
             .chain(once(addr.clone()))
             .chain(once(memory_access.prev_value().clone().into()))
             .collect();
         let current_values: Vec<<Self as AirBuilder>::Expr> = once(clk)
+            .chain(once(tape))
             .chain(once(addr.clone()))
             .chain(once(memory_access.value().clone().into()))
             .collect();
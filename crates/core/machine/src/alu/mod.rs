@@ -1,8 +1,12 @@
+mod set;
+
+pub use set::*;
+
 use core::borrow::{Borrow, BorrowMut};
 use hashbrown::HashMap;
 use itertools::Itertools;
 use p3_air::{Air, BaseAir};
-use p3_field::{PrimeField, PrimeField32};
+use p3_field::{FieldAlgebra, PrimeField, PrimeField32};
 use p3_matrix::{dense::RowMajorMatrix, Matrix};
 use p3_maybe_rayon::prelude::{ParallelBridge, ParallelIterator};
 
@@ -14,7 +18,7 @@ use bf_derive::AlignedBorrow;
 use bf_stark::air::{BfAirBuilder, MachineAir};
 
 use crate::operations::AddOperation;
-use crate::utils::{next_power_of_two, zeroed_f_vec};
+use crate::utils::{next_power_of_two, par_chunk_size, zeroed_f_vec};
 
 /// The number of main trace columns for `AddSubChip`.
 pub const NUM_ADD_SUB_COLS: usize = size_of::<AddSubCols<u8>>();
@@ -55,6 +59,10 @@ impl<F: PrimeField32> MachineAir<F> for AddSubChip {
         "AddSub".to_string()
     }
 
+    fn column_names(&self) -> Vec<String> {
+        AddSubCols::<F>::column_names()
+    }
+
     fn num_rows(&self, input: &Self::Record) -> Option<usize> {
         let nb_rows = next_power_of_two(input.add_events.len() + input.sub_events.len());
         Some(nb_rows)
@@ -65,36 +73,22 @@ impl<F: PrimeField32> MachineAir<F> for AddSubChip {
         input: &ExecutionRecord,
         _: &mut ExecutionRecord,
     ) -> RowMajorMatrix<F> {
-        // Generate the rows for the trace.
-        let chunk_size =
-            std::cmp::max((input.add_events.len() + input.sub_events.len()) / num_cpus::get(), 1);
-        let merged_events =
-            input.add_events.iter().chain(input.sub_events.iter()).collect::<Vec<_>>();
-        let padded_nb_rows = <AddSubChip as MachineAir<F>>::num_rows(self, input).unwrap();
-        let mut values = zeroed_f_vec(padded_nb_rows * NUM_ADD_SUB_COLS);
-
-        values.chunks_mut(chunk_size * NUM_ADD_SUB_COLS).enumerate().par_bridge().for_each(
-            |(i, rows)| {
-                rows.chunks_mut(NUM_ADD_SUB_COLS).enumerate().for_each(|(j, row)| {
-                    let idx = i * chunk_size + j;
-                    let cols: &mut AddSubCols<F> = row.borrow_mut();
-
-                    if idx < merged_events.len() {
-                        let mut byte_lookup_events = Vec::new();
-                        let event = &merged_events[idx];
-                        self.event_to_row(event, cols, &mut byte_lookup_events);
-                    }
-                });
-            },
-        );
+        self.trace_and_lookups(input).0
+    }
 
-        // Convert the trace to a row major matrix.
-        RowMajorMatrix::new(values, NUM_ADD_SUB_COLS)
+    fn generate_trace_and_dependencies(
+        &self,
+        input: &ExecutionRecord,
+        output: &mut ExecutionRecord,
+    ) -> RowMajorMatrix<F> {
+        let (trace, blu_batches) = self.trace_and_lookups(input);
+        output.add_byte_lookup_events_from_maps(blu_batches.iter().collect_vec());
+        trace
     }
 
     fn generate_dependencies(&self, input: &Self::Record, output: &mut Self::Record) {
-        let chunk_size =
-            std::cmp::max((input.add_events.len() + input.sub_events.len()) / num_cpus::get(), 1);
+        let nb_events = input.add_events.len() + input.sub_events.len();
+        let chunk_size = par_chunk_size::<F>(nb_events, NUM_ADD_SUB_COLS);
 
         let event_iter =
             input.add_events.chunks(chunk_size).chain(input.sub_events.chunks(chunk_size));
@@ -125,6 +119,42 @@ impl<F: PrimeField32> MachineAir<F> for AddSubChip {
 }
 
 impl AddSubChip {
+    /// Generates the trace, along with the byte lookups of each chunk of rows.
+    fn trace_and_lookups<F: PrimeField32>(
+        &self,
+        input: &ExecutionRecord,
+    ) -> (RowMajorMatrix<F>, Vec<HashMap<ByteLookupEvent, usize>>) {
+        // Generate the rows for the trace.
+        let nb_events = input.add_events.len() + input.sub_events.len();
+        let chunk_size = par_chunk_size::<F>(nb_events, NUM_ADD_SUB_COLS);
+        let merged_events =
+            input.add_events.iter().chain(input.sub_events.iter()).collect::<Vec<_>>();
+        let padded_nb_rows = <AddSubChip as MachineAir<F>>::num_rows(self, input).unwrap();
+        let mut values = zeroed_f_vec(padded_nb_rows * NUM_ADD_SUB_COLS);
+
+        let blu_batches = values
+            .chunks_mut(chunk_size * NUM_ADD_SUB_COLS)
+            .enumerate()
+            .par_bridge()
+            .map(|(i, rows)| {
+                let mut blu: HashMap<ByteLookupEvent, usize> = HashMap::new();
+                rows.chunks_mut(NUM_ADD_SUB_COLS).enumerate().for_each(|(j, row)| {
+                    let idx = i * chunk_size + j;
+                    let cols: &mut AddSubCols<F> = row.borrow_mut();
+
+                    if idx < merged_events.len() {
+                        let event = &merged_events[idx];
+                        self.event_to_row(event, cols, &mut blu);
+                    }
+                });
+                blu
+            })
+            .collect::<Vec<_>>();
+
+        // Convert the trace to a row major matrix.
+        (RowMajorMatrix::new(values, NUM_ADD_SUB_COLS), blu_batches)
+    }
+
     /// Create a row from an event.
     fn event_to_row<F: PrimeField>(
         &self,
@@ -180,6 +210,7 @@ where
             Opcode::Add.as_field::<AB::F>(),
             local.add_operation.value,
             local.operand_1,
+            AB::Expr::ZERO,
             local.is_add,
         );
 
@@ -188,6 +219,7 @@ where
             Opcode::Sub.as_field::<AB::F>(),
             local.operand_1,
             local.add_operation.value,
+            AB::Expr::ZERO,
             local.is_sub,
         );
     }
@@ -209,7 +241,7 @@ mod tests {
     fn generate_trace() {
         #[allow(clippy::field_reassign_with_default)]
         let shard = ExecutionRecord {
-            add_events: vec![AluEvent::new(0, Opcode::Add, 11, 10)],
+            add_events: vec![AluEvent::new(0, Opcode::Add, 11, 10)].into(),
             ..Default::default()
         };
         let chip = AddSubChip;
@@ -218,6 +250,26 @@ mod tests {
         println!("{:?}", trace.values)
     }
 
+    #[test]
+    fn generate_trace_and_dependencies() {
+        let shard = ExecutionRecord {
+            add_events: vec![AluEvent::new(0, Opcode::Add, 11, 10)].into(),
+            sub_events: vec![AluEvent::new(4, Opcode::Sub, 0, 1)].into(),
+            ..Default::default()
+        };
+        let chip = AddSubChip;
+        let mut expected = ExecutionRecord::default();
+        MachineAir::<KoalaBear>::generate_dependencies(&chip, &shard, &mut expected);
+
+        let mut output = ExecutionRecord::default();
+        let trace: RowMajorMatrix<KoalaBear> =
+            chip.generate_trace_and_dependencies(&shard, &mut output);
+        let expected_trace: RowMajorMatrix<KoalaBear> =
+            chip.generate_trace(&shard, &mut ExecutionRecord::default());
+        assert_eq!(trace.values, expected_trace.values);
+        assert_eq!(output.byte_lookups, expected.byte_lookups);
+    }
+
     #[test]
     fn prove_koala_bear() {
         let config = KoalaBearPoseidon2::new();
@@ -238,9 +290,9 @@ mod tests {
         let chip = AddSubChip;
         let trace: RowMajorMatrix<KoalaBear> =
             chip.generate_trace(&shard, &mut ExecutionRecord::default());
-        let proof = prove::<KoalaBearPoseidon2, _>(&config, &chip, &mut challenger, trace);
+        let proof = prove::<KoalaBearPoseidon2, _>(&config, &chip, &mut challenger, trace, &[]);
 
         let mut challenger = config.challenger();
-        verify(&config, &chip, &mut challenger, &proof).unwrap();
+        verify(&config, &chip, &mut challenger, &proof, &[]).unwrap();
     }
 }
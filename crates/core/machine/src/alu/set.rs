@@ -0,0 +1,201 @@
+use core::borrow::{Borrow, BorrowMut};
+use hashbrown::HashMap;
+use itertools::Itertools;
+use p3_air::{Air, AirBuilder, BaseAir};
+use p3_field::{FieldAlgebra, PrimeField, PrimeField32};
+use p3_matrix::{dense::RowMajorMatrix, Matrix};
+use p3_maybe_rayon::prelude::{ParallelBridge, ParallelIterator};
+
+use bf_core_executor::{
+    events::{AluEvent, ByteLookupEvent, ByteRecord},
+    ByteOpcode, ExecutionRecord, Opcode, Program,
+};
+use bf_derive::AlignedBorrow;
+use bf_stark::air::MachineAir;
+
+use crate::{
+    air::BfCoreAirBuilder,
+    utils::{next_power_of_two, par_chunk_size, zeroed_f_vec},
+};
+
+/// The number of main trace columns for `SetChip`.
+pub const NUM_SET_COLS: usize = size_of::<SetCols<u8>>();
+
+/// A chip for [`Opcode::Set`], which sets the current memory cell to an immediate.
+#[derive(Default)]
+pub struct SetChip;
+
+/// The column layout for the chip.
+#[derive(AlignedBorrow, Default, Clone, Copy)]
+#[repr(C)]
+pub struct SetCols<T> {
+    /// The program counter.
+    pub pc: T,
+
+    /// The immediate of the instruction, which is the new memory value.
+    pub imm: T,
+
+    /// The memory value before the instruction.
+    pub mv: T,
+
+    /// Boolean to indicate whether the row is for a set operation.
+    pub is_real: T,
+}
+
+impl<F: PrimeField32> MachineAir<F> for SetChip {
+    type Record = ExecutionRecord;
+
+    type Program = Program;
+
+    fn name(&self) -> String {
+        "Set".to_string()
+    }
+
+    fn column_names(&self) -> Vec<String> {
+        SetCols::<F>::column_names()
+    }
+
+    fn num_rows(&self, input: &Self::Record) -> Option<usize> {
+        Some(next_power_of_two(input.set_events.len()))
+    }
+
+    fn generate_trace(
+        &self,
+        input: &ExecutionRecord,
+        output: &mut ExecutionRecord,
+    ) -> RowMajorMatrix<F> {
+        let events = input.set_events.iter().collect_vec();
+        let chunk_size = par_chunk_size::<F>(events.len(), NUM_SET_COLS);
+        let padded_nb_rows = <SetChip as MachineAir<F>>::num_rows(self, input).unwrap();
+        let mut values = zeroed_f_vec(padded_nb_rows * NUM_SET_COLS);
+
+        let blu_batches = values
+            .chunks_mut(chunk_size * NUM_SET_COLS)
+            .enumerate()
+            .par_bridge()
+            .map(|(i, rows)| {
+                let mut blu: HashMap<ByteLookupEvent, usize> = HashMap::new();
+                rows.chunks_mut(NUM_SET_COLS).enumerate().for_each(|(j, row)| {
+                    let idx = i * chunk_size + j;
+                    let cols: &mut SetCols<F> = row.borrow_mut();
+
+                    if idx < events.len() {
+                        self.event_to_row(events[idx], cols, &mut blu);
+                    }
+                });
+                blu
+            })
+            .collect::<Vec<_>>();
+
+        output.add_byte_lookup_events_from_maps(blu_batches.iter().collect_vec());
+
+        // Convert the trace to a row major matrix.
+        RowMajorMatrix::new(values, NUM_SET_COLS)
+    }
+
+    fn generate_trace_and_dependencies(
+        &self,
+        input: &ExecutionRecord,
+        output: &mut ExecutionRecord,
+    ) -> RowMajorMatrix<F> {
+        // The byte lookups are added to `output` while building the rows.
+        self.generate_trace(input, output)
+    }
+
+    fn included(&self, record: &Self::Record) -> bool {
+        !record.set_events.is_empty()
+    }
+
+    fn local_only(&self) -> bool {
+        true
+    }
+}
+
+impl SetChip {
+    /// Create a row from an event.
+    fn event_to_row<F: PrimeField>(
+        &self,
+        event: &AluEvent,
+        cols: &mut SetCols<F>,
+        blu: &mut impl ByteRecord,
+    ) {
+        cols.pc = F::from_canonical_u32(event.pc);
+        cols.imm = F::from_canonical_u8(event.next_mv);
+        cols.mv = F::from_canonical_u8(event.mv);
+        cols.is_real = F::ONE;
+        blu.add_u8_range_check(event.next_mv);
+    }
+}
+
+impl<F> BaseAir<F> for SetChip {
+    fn width(&self) -> usize {
+        NUM_SET_COLS
+    }
+}
+
+impl<AB> Air<AB> for SetChip
+where
+    AB: BfCoreAirBuilder,
+{
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local = main.row_slice(0);
+        let local: &SetCols<AB::Var> = (*local).borrow();
+
+        builder.assert_bool(local.is_real);
+
+        // The new memory value is the immediate, which the CPU reads from the instruction.
+        builder.receive_alu(
+            local.pc,
+            Opcode::Set.as_field::<AB::F>(),
+            local.imm,
+            local.mv,
+            local.imm,
+            local.is_real,
+        );
+        builder.send_byte(
+            AB::Expr::from_canonical_u8(ByteOpcode::U8Range as u8),
+            local.imm,
+            AB::Expr::ZERO,
+            local.is_real,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_koala_bear::KoalaBear;
+    use p3_matrix::{dense::RowMajorMatrix, Matrix};
+
+    use bf_core_executor::{events::AluEvent, ExecutionRecord, Opcode};
+    use bf_stark::{air::MachineAir, koala_bear_poseidon2::KoalaBearPoseidon2, StarkGenericConfig};
+
+    use super::SetChip;
+    use crate::utils::{uni_stark_prove as prove, uni_stark_verify as verify};
+
+    #[test]
+    fn prove_koala_bear() {
+        let config = KoalaBearPoseidon2::new();
+        let mut challenger = config.challenger();
+
+        let shard = ExecutionRecord {
+            set_events: vec![
+                AluEvent::new(0, Opcode::Set, 0, 7),
+                AluEvent::new(3, Opcode::Set, 255, 0),
+                AluEvent::new(5, Opcode::Set, 42, 42),
+            ]
+            .into(),
+            ..Default::default()
+        };
+
+        let chip = SetChip;
+        let mut output = ExecutionRecord::default();
+        let trace: RowMajorMatrix<KoalaBear> = chip.generate_trace(&shard, &mut output);
+        assert_eq!(trace.height(), 4);
+        assert_eq!(output.byte_lookups.values().sum::<usize>(), 3);
+
+        let proof = prove::<KoalaBearPoseidon2, _>(&config, &chip, &mut challenger, trace, &[]);
+        let mut challenger = config.challenger();
+        verify(&config, &chip, &mut challenger, &proof, &[]).unwrap();
+    }
+}
@@ -1,19 +1,23 @@
 use core::fmt;
+use p3_air::{Air, AirBuilder, BaseAir};
 use p3_field::PrimeField32;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_uni_stark::SymbolicAirBuilder;
 use strum_macros::{EnumDiscriminants, EnumIter};
 
 pub use bf_chips::*;
-use bf_stark::{air::MachineAir, Chip, StarkGenericConfig, StarkMachine};
+use bf_core_executor::{ExecutionRecord, Program};
+use bf_stark::{air::MachineAir, Chip, LookupBuilder, StarkGenericConfig, StarkMachine};
 
 /// A module for importing all the different MIPS chips.
 pub(crate) mod bf_chips {
     pub use crate::{
-        alu::AddSubChip,
+        alu::{AddSubChip, SetChip},
         bytes::ByteChip,
         cpu::CpuChip,
         io::IoChip,
         jump::JumpChip,
-        memory::{MemoryChip, MemoryInstructionsChip},
+        memory::{MemoryChip, MemoryImageChip, MemoryInstructionsChip},
         program::ProgramChip,
     };
 }
@@ -22,80 +26,129 @@ pub(crate) mod bf_chips {
 /// This enum contains all the different AIRs that are used in the zkMIPS IOP. Each variant is
 /// a different AIR that is used to encode a different part of the zkMIPS execution, and the
 /// different AIR variants have a joint lookup argument.
+///
+/// The chips of the machine are created in the order of the variants, from the `Default` of
+/// each chip. A variant can be gated with `#[cfg(feature = "...")]`, which leaves its chip out.
+/// Chips defined outside of this crate are added with [BfAir::machine_with_extensions].
 #[derive(bf_derive::MachineAir, EnumDiscriminants)]
 #[strum_discriminants(derive(Hash, EnumIter))]
-pub enum BfAir<F: PrimeField32> {
-    /// An AIR that contains a preprocessed program table and a lookup for the instructions.
-    Program(ProgramChip),
-    /// An AIR for the Memory.
-    Memory(MemoryChip),
+#[eval_trait_bound = "E: p3_air::Air<AB>"]
+pub enum BfAir<F: PrimeField32, E = NoExtension>
+where
+    E: MachineAir<F, Record = ExecutionRecord, Program = Program>,
+{
     /// An AIR for the CPU. Each row represents a cpu cycle.
     Cpu(CpuChip),
-    /// A lookup table for byte operations.
-    ByteLookup(ByteChip<F>),
+    /// An AIR that contains a preprocessed program table and a lookup for the instructions.
+    Program(ProgramChip),
     /// An AIR for the Add and Sub instruction.
     AddSub(AddSubChip),
+    /// An AIR for the Set instruction.
+    Set(SetChip),
     /// An AIR for the Jump instructions.
     Jump(JumpChip),
+    /// An AIR for the Memory.
+    Memory(MemoryChip),
+    /// A preprocessed table of the initial contents of the tape.
+    MemoryImage(MemoryImageChip),
+    /// A lookup table for byte operations.
+    ByteLookup(ByteChip<F>),
     /// An AIR for memory instructions.
     MemoryInstrs(MemoryInstructionsChip),
     /// An AIR for I/O instructions.
     IO(IoChip),
+    /// An AIR defined outside of this crate, such as a precompile.
+    #[extension]
+    Extension(E),
 }
 
 impl<F: PrimeField32> BfAir<F> {
+    /// Creates the machine of the chips given by the derived [BfAir::chips].
     pub fn machine<SC: StarkGenericConfig<Val = F>>(config: SC) -> StarkMachine<SC, Self> {
-        let chips = Self::chips();
-        StarkMachine::new(config, chips)
+        Self::machine_with_extensions(config, vec![])
     }
+}
 
-    /// Get all the different AIRs.
-    pub fn chips() -> Vec<Chip<F, Self>> {
-        let mut chips = vec![];
-
-        let cpu = Chip::new(BfAir::Cpu(CpuChip));
-        chips.push(cpu);
+impl<F: PrimeField32, E> BfAir<F, E>
+where
+    E: MachineAir<F, Record = ExecutionRecord, Program = Program>,
+    Self: Air<LookupBuilder<F>> + Air<SymbolicAirBuilder<F>>,
+{
+    /// Creates the machine of the built-in chips followed by the `extensions`.
+    ///
+    /// An extension interacts with the other chips through the lookups it sends and receives,
+    /// usually of its own [LookupKind::Custom](bf_stark::LookupKind::Custom) kind.
+    pub fn machine_with_extensions<SC: StarkGenericConfig<Val = F>>(
+        config: SC,
+        extensions: Vec<E>,
+    ) -> StarkMachine<SC, Self> {
+        let mut chips = Self::chips();
+        chips.extend(extensions.into_iter().map(|air| Chip::new(BfAir::Extension(air))));
+        StarkMachine::new(config, chips)
+    }
+}
 
-        let program = Chip::new(BfAir::Program(ProgramChip));
-        chips.push(program);
+/// The extension of a machine without extensions, which has no values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NoExtension {}
 
-        let add_sub = Chip::new(BfAir::AddSub(AddSubChip));
-        chips.push(add_sub);
+impl<F> BaseAir<F> for NoExtension {
+    fn width(&self) -> usize {
+        match *self {}
+    }
+}
 
-        let jump = Chip::new(BfAir::Jump(JumpChip));
-        chips.push(jump);
+impl<F: PrimeField32> MachineAir<F> for NoExtension {
+    type Record = ExecutionRecord;
 
-        let memory = Chip::new(BfAir::Memory(MemoryChip::new()));
-        chips.push(memory);
+    type Program = Program;
 
-        let byte = Chip::new(BfAir::ByteLookup(ByteChip::default()));
-        chips.push(byte);
+    fn name(&self) -> String {
+        match *self {}
+    }
 
-        let memory_instructions = Chip::new(BfAir::MemoryInstrs(MemoryInstructionsChip));
-        chips.push(memory_instructions);
+    fn generate_trace(&self, _: &ExecutionRecord, _: &mut ExecutionRecord) -> RowMajorMatrix<F> {
+        match *self {}
+    }
 
-        let io = Chip::new(BfAir::IO(IoChip));
-        chips.push(io);
+    fn included(&self, _: &ExecutionRecord) -> bool {
+        match *self {}
+    }
+}
 
-        chips
+impl<AB: AirBuilder> Air<AB> for NoExtension {
+    fn eval(&self, _: &mut AB) {
+        match *self {}
     }
 }
 
-impl<F: PrimeField32> fmt::Debug for BfAir<F> {
+impl<F: PrimeField32, E> fmt::Debug for BfAir<F, E>
+where
+    E: MachineAir<F, Record = ExecutionRecord, Program = Program>,
+{
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.name())
     }
 }
 
-impl<F: PrimeField32> PartialEq for BfAir<F> {
+impl<F: PrimeField32, E> PartialEq for BfAir<F, E>
+where
+    E: MachineAir<F, Record = ExecutionRecord, Program = Program>,
+{
     fn eq(&self, other: &Self) -> bool {
         self.name() == other.name()
     }
 }
 
-impl<F: PrimeField32> Eq for BfAir<F> {}
+impl<F: PrimeField32, E> Eq for BfAir<F, E> where
+    E: MachineAir<F, Record = ExecutionRecord, Program = Program>
+{
+}
 
-impl<F: PrimeField32> core::hash::Hash for BfAir<F> {
+impl<F: PrimeField32, E> core::hash::Hash for BfAir<F, E>
+where
+    E: MachineAir<F, Record = ExecutionRecord, Program = Program>,
+{
     fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
         self.name().hash(state);
     }
@@ -104,11 +157,160 @@ impl<F: PrimeField32> core::hash::Hash for BfAir<F> {
 #[cfg(test)]
 #[allow(non_snake_case)]
 pub mod tests {
-    use bf_core_executor::{Instruction, Opcode, Program};
-    use bf_stark::CpuProver;
+    use std::collections::HashSet;
+
+    use p3_field::FieldAlgebra;
+    use p3_koala_bear::KoalaBear;
+    use p3_matrix::Matrix;
+
+    use bf_core_executor::{Executor, Instruction, Opcode};
+    use bf_stark::{
+        koala_bear_poseidon2::KoalaBearPoseidon2, CpuProver, DynMachineAir, MachineProver,
+        Transcript,
+    };
     use test_artifacts::{FIBO_BF, HELLO_BF, LOOP_BF, MOVE_BF, PRINTA_BF};
 
-    use crate::utils::{run_test, setup_logger};
+    use super::*;
+    use crate::utils::{run_test, run_test_core, setup_logger};
+
+    #[test]
+    fn test_constraint_report() {
+        let machine = BfAir::machine(KoalaBearPoseidon2::new());
+        let report = machine.constraint_report();
+        assert_eq!(report.chips.len(), machine.chips().len());
+
+        // The IO chip exposes the I/O digests as the public values on its last row.
+        let io = report.chips.iter().find(|chip| chip.name == "IO").unwrap();
+        assert!(io.constraints.iter().any(|c| c.expr.contains("is_last_row")));
+        assert!(io.constraints.iter().any(|c| c.expr.contains("public[7]")));
+        assert!(!io.receives.is_empty());
+        assert!(report.to_string().contains("IO (preprocessed width 0"));
+    }
+
+    #[test]
+    fn test_column_names() {
+        let machine = BfAir::machine(KoalaBearPoseidon2::new());
+        for chip in machine.chips() {
+            let names = chip.column_names();
+            assert_eq!(names.len(), chip.width(), "{}", chip.name());
+            assert_eq!(names.iter().collect::<HashSet<_>>().len(), names.len(), "{}", chip.name());
+        }
+
+        let cpu = machine.chips().iter().find(|chip| chip.name() == "Cpu").unwrap();
+        let names = cpu.column_names();
+        assert_eq!(names[0], "clk_16bit_limb");
+        assert!(names.contains(&"instruction.op_a[0]".to_string()));
+    }
+
+    /// A chip constraining its single column to zero, with no trace of its own.
+    struct ZeroChip;
+
+    impl<F> BaseAir<F> for ZeroChip {
+        fn width(&self) -> usize {
+            1
+        }
+    }
+
+    impl<F: PrimeField32> MachineAir<F> for ZeroChip {
+        type Record = ExecutionRecord;
+
+        type Program = Program;
+
+        fn name(&self) -> String {
+            "Zero".to_string()
+        }
+
+        fn generate_trace(
+            &self,
+            _: &ExecutionRecord,
+            _: &mut ExecutionRecord,
+        ) -> RowMajorMatrix<F> {
+            RowMajorMatrix::new(vec![F::ZERO; 4], 1)
+        }
+
+        fn included(&self, _: &ExecutionRecord) -> bool {
+            false
+        }
+    }
+
+    impl<AB: AirBuilder> Air<AB> for ZeroChip {
+        fn eval(&self, builder: &mut AB) {
+            let main = builder.main();
+            let local = main.row_slice(0);
+            builder.assert_zero(local[0]);
+        }
+    }
+
+    #[test]
+    fn test_machine_with_extensions() {
+        let machine = BfAir::machine_with_extensions(KoalaBearPoseidon2::new(), vec![ZeroChip]);
+        let chips = machine.chips();
+        assert_eq!(chips.len(), BfAir::<KoalaBear>::chips().len() + 1);
+        assert_eq!(chips.last().unwrap().name(), "Zero");
+        assert!(machine.constraint_report().to_string().contains("Zero"));
+
+        // The extension has no preprocessed trace, so it leaves the preprocessed chips as is.
+        let program = Program::new(vec![Instruction::new(Opcode::Add)]);
+        let (_, vk) = machine.setup(&program);
+        let (_, base_vk) = BfAir::machine(KoalaBearPoseidon2::new()).setup(&program);
+        assert_eq!(vk.chip_ordering, base_vk.chip_ordering);
+    }
+
+    #[test]
+    fn test_dyn_chips_prove() {
+        let airs: Vec<
+            Box<dyn DynMachineAir<KoalaBearPoseidon2, Record = ExecutionRecord, Program = Program>>,
+        > = vec![
+            Box::new(CpuChip),
+            Box::new(ProgramChip),
+            Box::new(AddSubChip),
+            Box::new(SetChip),
+            Box::new(JumpChip),
+            Box::new(MemoryChip::new()),
+            Box::new(MemoryImageChip),
+            Box::new(ByteChip::<KoalaBear>::default()),
+            Box::new(MemoryInstructionsChip),
+            Box::new(IoChip),
+        ];
+        let machine = StarkMachine::from_dyn_chips(KoalaBearPoseidon2::new(), airs);
+        let prover = CpuProver::new(machine);
+
+        let program = Program::from(FIBO_BF).unwrap();
+        let (pk, vk) = prover.setup(&program);
+        let mut runtime = Executor::new(program, vec![17]);
+        runtime.run().unwrap();
+
+        let mut challenger = prover.config().challenger();
+        let proof = prover.prove(&pk, &mut runtime.record, &mut challenger).unwrap();
+        let mut challenger = prover.config().challenger();
+        prover.machine().verify(&vk, &proof, &mut challenger).unwrap();
+    }
+
+    #[test]
+    fn test_transcript() {
+        let machine = BfAir::machine(KoalaBearPoseidon2::new());
+        let prover = CpuProver::new(machine);
+        let program = Program::from(FIBO_BF).unwrap();
+        let (pk, vk) = prover.setup(&program);
+        let mut runtime = Executor::new(program, vec![17]);
+        runtime.run().unwrap();
+
+        let mut challenger = prover.config().challenger();
+        let prover_recorder = challenger.record();
+        let proof = prover.prove(&pk, &mut runtime.record, &mut challenger).unwrap();
+
+        let mut challenger = prover.config().challenger();
+        let verifier_recorder = challenger.record();
+        prover.machine().verify(&vk, &proof, &mut challenger).unwrap();
+
+        // Both sides draw the same challenges, and the transcripts serialize as they are.
+        let transcript = prover_recorder.transcript();
+        let verifier_transcript = verifier_recorder.transcript();
+        assert!(transcript.samples().count() > 0);
+        assert!(transcript.samples().eq(verifier_transcript.samples()));
+        let bytes = bincode::serialize(&transcript).unwrap();
+        assert_eq!(bincode::deserialize::<Transcript>(&bytes).unwrap(), transcript);
+    }
 
     #[test]
     fn test_instructions_prove() {
@@ -132,6 +334,13 @@ pub mod tests {
         run_test::<CpuProver<_, _>>(program, vec![]).unwrap();
     }
 
+    #[test]
+    fn test_set_prove() {
+        setup_logger();
+        let program = Program::from("+++[-]++.[-]>[-]+[-]-").unwrap().optimize();
+        run_test::<CpuProver<_, _>>(program, vec![]).unwrap();
+    }
+
     #[test]
     fn test_mem_prove() {
         setup_logger();
@@ -139,6 +348,45 @@ pub mod tests {
         run_test::<CpuProver<_, _>>(program, vec![]).unwrap();
     }
 
+    #[test]
+    fn test_second_tape_prove() {
+        setup_logger();
+        // `,>~+[-~.~]~<.`: the cells and pointers of each tape survive the switches in the loop.
+        let instructions = vec![
+            Instruction::new(Opcode::Input),
+            Instruction::new(Opcode::MemStepForward),
+            Instruction::new(Opcode::SwitchTape),
+            Instruction::new(Opcode::Add),
+            Instruction::new_jmp(Opcode::LoopStart, 9),
+            Instruction::new(Opcode::Sub),
+            Instruction::new(Opcode::SwitchTape),
+            Instruction::new(Opcode::Output),
+            Instruction::new(Opcode::SwitchTape),
+            Instruction::new_jmp(Opcode::LoopEnd, 5),
+            Instruction::new(Opcode::SwitchTape),
+            Instruction::new(Opcode::MemStepBackward),
+            Instruction::new(Opcode::Output),
+        ];
+        let program = Program::new(instructions);
+        run_test::<CpuProver<_, _>>(program.clone(), vec![7]).unwrap();
+
+        // The shards end on either tape, in the middle of the loop.
+        let runtime = Executor::new(program, vec![7]).with_shard_size(3);
+        run_test_core::<CpuProver<_, _>>(runtime).unwrap();
+    }
+
+    #[test]
+    fn test_memory_image_prove() {
+        setup_logger();
+        let mut program = Program::from(".>+.").unwrap();
+        program.image = vec![3, 4, 5];
+        run_test::<CpuProver<_, _>>(program.clone(), vec![]).unwrap();
+
+        // The image is loaded in the first shard, and its cells read in the later ones.
+        let runtime = Executor::new(program, vec![]).with_shard_size(1);
+        run_test_core::<CpuProver<_, _>>(runtime).unwrap();
+    }
+
     #[test]
     fn test_jmp_prove() {
         setup_logger();
@@ -153,6 +401,14 @@ pub mod tests {
         run_test::<CpuProver<_, _>>(program, vec![1]).unwrap();
     }
 
+    #[test]
+    fn test_private_input_prove() {
+        setup_logger();
+        let program = Program::from(",;.;+.,").unwrap();
+        let runtime = Executor::new_with_private_input(program, vec![1, 2], vec![7, 9]);
+        run_test_core::<CpuProver<_, _>>(runtime).unwrap();
+    }
+
     #[test]
     fn test_printa_prove() {
         setup_logger();
@@ -187,4 +443,21 @@ pub mod tests {
         let program = Program::from(FIBO_BF).unwrap();
         run_test::<CpuProver<_, _>>(program, vec![17]).unwrap();
     }
+
+    #[test]
+    fn test_sharded_prove() {
+        setup_logger();
+        let program = Program::from(FIBO_BF).unwrap();
+        let runtime = Executor::new(program, vec![17]).with_shard_size(100);
+        let proof = run_test_core::<CpuProver<_, _>>(runtime).unwrap();
+        assert!(proof.shard_proofs.len() > 1);
+
+        // Each shard has the I/O and the memory accesses of its cycles.
+        let first = &proof.shard_proofs[0];
+        assert!(
+            first.chip_ordering.contains_key("IO") && first.chip_ordering.contains_key("Memory")
+        );
+        let last = proof.shard_proofs.last().unwrap();
+        assert!(last.chip_ordering.contains_key("IO") && last.chip_ordering.contains_key("Memory"));
+    }
 }
@@ -5,12 +5,9 @@ pub mod trace;
 use core::borrow::BorrowMut;
 use std::marker::PhantomData;
 
-use itertools::Itertools;
 use p3_field::Field;
 use p3_matrix::dense::RowMajorMatrix;
 
-use bf_core_executor::{events::ByteLookupEvent, ByteOpcode};
-
 use self::cols::{BytePreprocessedCols, NUM_BYTE_PREPROCESSED_COLS};
 use crate::{bytes::trace::NUM_ROWS, utils::zeroed_f_vec};
 
@@ -35,26 +32,14 @@ impl<F: Field> ByteChip<F> {
             NUM_BYTE_PREPROCESSED_COLS,
         );
 
-        // Record all the necessary operations for each byte lookup.
-        let opcodes = ByteOpcode::all();
-
-        // Iterate over all options for pairs of bytes `a` and `b`.
-        for (row_index, (b, c)) in (0..=u8::MAX).cartesian_product(0..=u8::MAX).enumerate() {
-            let col: &mut BytePreprocessedCols<F> = initial_trace.row_mut(row_index).borrow_mut();
-
-            col.value_u8 = F::from_canonical_u8(c);
-
-            // Iterate over all operations for results and updating the table map.
-            for opcode in opcodes.iter() {
-                match opcode {
-                    ByteOpcode::U8Range => ByteLookupEvent::new(*opcode, b, 0),
-                    ByteOpcode::U16Range => {
-                        let v = ((b as u32) << 8) + c as u32;
-                        col.value_u16 = F::from_canonical_u32(v);
-                        ByteLookupEvent::new(*opcode, 0, v as u16)
-                    }
-                };
-            }
+        // The row of the pair of bytes `(b, c)` is `(b << 8) + c`, which is its `U16Range` value,
+        // and its `U8Range` value is `c`.
+        for (value, row) in
+            initial_trace.values.chunks_exact_mut(NUM_BYTE_PREPROCESSED_COLS).enumerate()
+        {
+            let col: &mut BytePreprocessedCols<F> = row.borrow_mut();
+            col.value_u8 = F::from_canonical_usize(value & 0xff);
+            col.value_u16 = F::from_canonical_usize(value);
         }
 
         initial_trace
@@ -23,6 +23,10 @@ impl<F: PrimeField32> MachineAir<F> for ByteChip<F> {
         "Byte".to_string()
     }
 
+    fn column_names(&self) -> Vec<String> {
+        ByteMultCols::<F>::column_names()
+    }
+
     fn preprocessed_width(&self) -> usize {
         NUM_BYTE_PREPROCESSED_COLS
     }
@@ -59,7 +63,15 @@ impl<F: PrimeField32> MachineAir<F> for ByteChip<F> {
         trace
     }
 
+    fn depends_on_dependencies(&self) -> bool {
+        true
+    }
+
     fn included(&self, _shard: &Self::Record) -> bool {
         true
     }
+
+    fn required(&self) -> bool {
+        true
+    }
 }
@@ -3,6 +3,9 @@ use p3_air::{Air, AirBuilder, BaseAir};
 use p3_field::FieldAlgebra;
 use p3_matrix::Matrix;
 
+use bf_core_executor::{
+    ShardBoundary, CYCLES_PUBLIC_VALUE, NEXT_PUBLIC_VALUES, START_PUBLIC_VALUES,
+};
 use bf_stark::air::{BaseAirBuilder, BfAirBuilder};
 
 use crate::{
@@ -34,6 +37,11 @@ where
         let clk =
             AB::Expr::from_canonical_u32(1u32 << 16) * local.clk_8bit_limb + local.clk_16bit_limb;
 
+        let public_values: Vec<AB::Expr> =
+            builder.public_values().iter().map(|&value| value.into()).collect();
+        let start = ShardBoundary::from_public_values(&public_values, START_PUBLIC_VALUES);
+        let end = ShardBoundary::from_public_values(&public_values, NEXT_PUBLIC_VALUES);
+
         // Program constraints.
         builder.send_program(local.pc, local.instruction, local.is_real);
 
@@ -44,10 +52,18 @@ where
         self.eval_registers::<AB>(builder, local, clk.clone());
 
         // Check that the clk is updated correctly.
-        self.eval_clk(builder, local, next, clk.clone());
+        self.eval_clk(builder, local, next, clk.clone(), &start);
+
+        // Check that the number of cycles is the public one, and that the shard ends where the
+        // next one starts.
+        let cycles = public_values[CYCLES_PUBLIC_VALUE].clone();
+        self.eval_cycles(builder, local, next, clk.clone(), cycles, &start, &end);
 
         // Check that the pc is updated correctly.
-        self.eval_pc(builder, local, next);
+        self.eval_pc(builder, local, next, &start);
+
+        // Check that the tape is updated correctly.
+        self.eval_tape(builder, local, next, &start);
 
         // Check that the is_real flag is correct.
         self.eval_is_real(builder, local, next);
@@ -69,31 +85,41 @@ impl CpuChip {
         local: &CpuCols<AB::Var>,
         clk: AB::Expr,
     ) {
-        builder.send_alu(local.pc, local.instruction.opcode, local.next_mv, local.mv, local.is_alu);
+        builder.send_alu(
+            local.pc,
+            local.instruction.opcode,
+            local.next_mv.0,
+            local.mv.0,
+            local.instruction.op_a[0],
+            local.is_alu,
+        );
 
         builder.send_jump(
             local.pc,
             local.next_pc,
             local.instruction.opcode,
-            local.mv,
+            local.mv.0,
             local.is_jump,
         );
 
         builder.send_memory_instr(
-            clk,
+            clk.clone(),
             local.pc,
             local.instruction.opcode,
+            local.tape,
             local.mp,
+            local.next_tape,
             local.next_mp,
             local.is_memory_instr,
         );
 
-        builder.send_io(local.pc, local.instruction.opcode, local.mp, local.mv, local.is_io);
+        builder.send_io(clk, local.pc, local.instruction.opcode, local.mp, local.mv.0, local.is_io);
     }
 
     /// Constraints related to the clk.
     ///
-    /// This method ensures that the clk starts at 0 and is transitioned appropriately.
+    /// This method ensures that the clk starts at the clk of the start of the shard, 0 for the
+    /// first one, and is transitioned appropriately.
     /// It will also check that clk values are within 24 bits.
     /// The range check are needed for the memory access timestamp check, which assumes those values are within 2^24.
     /// See [`MemoryAirBuilder::verify_mem_access_ts`].
@@ -103,9 +129,10 @@ impl CpuChip {
         local: &CpuCols<AB::Var>,
         next: &CpuCols<AB::Var>,
         clk: AB::Expr,
+        start: &ShardBoundary<AB::Expr>,
     ) {
-        // Verify that the first row has a clk value of 0.
-        builder.when_first_row().assert_zero(clk.clone());
+        // Verify that the first row has the clk of the start of the shard.
+        builder.when_first_row().assert_eq(clk.clone(), start.clk.clone());
 
         let expected_next_clk = clk.clone() + AB::Expr::from_canonical_u32(2);
 
@@ -122,15 +149,69 @@ impl CpuChip {
         );
     }
 
+    /// Constraints related to the number of cycles and to the end of the shard.
+    ///
+    /// The clk steps by 2 from the start of the shard, so the last real row has a clk of the
+    /// start clk plus twice the number of cycles minus 2. The state after the last real row is
+    /// the one at the start of the next shard.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn eval_cycles<AB: BfAirBuilder>(
+        &self,
+        builder: &mut AB,
+        local: &CpuCols<AB::Var>,
+        next: &CpuCols<AB::Var>,
+        clk: AB::Expr,
+        cycles: AB::Expr,
+        start: &ShardBoundary<AB::Expr>,
+        end: &ShardBoundary<AB::Expr>,
+    ) {
+        let expected_clk = start.clk.clone() + cycles * AB::F::TWO - AB::F::TWO;
+        let next_clk = clk.clone() + AB::F::TWO;
+
+        self.assert_eq_on_last_real_row(builder, local, next, clk, expected_clk);
+        self.assert_eq_on_last_real_row(builder, local, next, next_clk, end.clk.clone());
+        self.assert_eq_on_last_real_row(builder, local, next, local.next_pc.into(), end.pc.clone());
+        self.assert_eq_on_last_real_row(
+            builder,
+            local,
+            next,
+            local.next_tape.into(),
+            end.tape.clone(),
+        );
+    }
+
+    /// Asserts that `left` equals `right` on the last real row, the one followed by a padding row
+    /// or the last row of the trace.
+    fn assert_eq_on_last_real_row<AB: BfAirBuilder>(
+        &self,
+        builder: &mut AB,
+        local: &CpuCols<AB::Var>,
+        next: &CpuCols<AB::Var>,
+        left: AB::Expr,
+        right: AB::Expr,
+    ) {
+        builder
+            .when_transition()
+            .when(local.is_real - next.is_real)
+            .assert_eq(left.clone(), right.clone());
+        builder.when_last_row().when(local.is_real).assert_eq(left, right);
+    }
+
     /// Constraints related to the pc.
+    ///
+    /// The pc starts at the pc of the start of the shard, 0 for the first one.
     pub(crate) fn eval_pc<AB: BfAirBuilder>(
         &self,
         builder: &mut AB,
         local: &CpuCols<AB::Var>,
         next: &CpuCols<AB::Var>,
+        start: &ShardBoundary<AB::Expr>,
     ) {
+        builder.when_first_row().assert_eq(local.pc, start.pc.clone());
         builder.when_transition().when(next.is_real).assert_eq(local.next_pc, next.pc);
 
+        // The program lookup bounds `pc` by the program length, at most `MAX_PROGRAM_LEN`, so
+        // `pc + 1` does not wrap around the modulus.
         builder
             .when_transition()
             .when(local.is_real)
@@ -138,6 +219,26 @@ impl CpuChip {
             .assert_eq(local.next_pc, local.pc + AB::Expr::from_canonical_u32(1));
     }
 
+    /// Constraints related to the tape.
+    ///
+    /// The execution starts on the first tape, and only memory instructions, whose tapes are
+    /// checked by the memory instructions chip, switch to the other one. A shard starts on the
+    /// tape the previous one ended on.
+    pub(crate) fn eval_tape<AB: BfAirBuilder>(
+        &self,
+        builder: &mut AB,
+        local: &CpuCols<AB::Var>,
+        next: &CpuCols<AB::Var>,
+        start: &ShardBoundary<AB::Expr>,
+    ) {
+        builder.when_first_row().assert_eq(local.tape, start.tape.clone());
+        builder.when_transition().when(next.is_real).assert_eq(local.next_tape, next.tape);
+        builder
+            .when(local.is_real)
+            .when_not(local.is_memory_instr)
+            .assert_eq(local.next_tape, local.tape);
+    }
+
     /// Constraints related to the is_real column.
     ///
     /// This method checks that the is_real column is a boolean. It also checks that the first row
@@ -164,6 +265,7 @@ impl CpuChip {
     ) {
         builder.eval_memory_access(
             clk.clone() + AB::F::from_canonical_u32(1),
+            local.tape,
             local.mp,
             &local.mv_access,
             local.mv_accessed,
@@ -171,14 +273,15 @@ impl CpuChip {
 
         builder.eval_memory_access(
             clk.clone() + AB::F::from_canonical_u32(2),
+            local.tape,
             local.mp,
             &local.next_mv_access,
             local.next_mv_accessed,
         );
 
-        // Always range check the value in `mv`, as input instruction `,` may witness
+        // Always range check the value in `mv`, as input instructions `,` and `;` may witness
         // an invalid value and write it to memory.
-        builder.range_check_u8(local.mv.into(), local.is_real);
+        builder.range_check_u8(local.mv.0, local.is_real);
 
         // If we are performing an ALU​​, ​​JMP​​, or ​​OUTPUT instruction, then the value of `mv` is the previous value.
         builder.when(local.is_mv_immutable).assert_eq(local.mv_val(), local.mv_access.prev_value);
@@ -4,7 +4,7 @@ use p3_field::PrimeField;
 
 use bf_core_executor::Instruction;
 use bf_derive::AlignedBorrow;
-use bf_stark::Word;
+use bf_stark::{Byte, Word};
 
 use crate::memory::{MemoryCols, MemoryReadWriteCols, MemoryWriteCols};
 
@@ -44,11 +44,17 @@ pub struct CpuCols<T: Copy> {
     /// The next memory pointer.
     pub next_mp: T,
 
+    /// The tape the memory pointer points into.
+    pub tape: T,
+
+    /// The next tape.
+    pub next_tape: T,
+
     /// The memory value.
-    pub mv: T,
+    pub mv: Byte<T>,
 
     /// The next memory value.
-    pub next_mv: T,
+    pub next_mv: Byte<T>,
 
     /// Columns related to the instruction.
     pub instruction: InstructionCols<T>,
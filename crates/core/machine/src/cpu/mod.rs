@@ -5,8 +5,14 @@ mod trace;
 pub use cols::*;
 
 /// The maximum log degree of the CPU chip to avoid lookup multiplicity overflow.
+///
+/// Each cycle advances the clk by 2, so the clk of a table this tall stays below
+/// [`MAX_CLK`](bf_core_executor::MAX_CLK). The quotient domain, up to 4 times bigger, also fits
+/// in the two-adic subgroups of KoalaBear.
 pub const MAX_CPU_LOG_DEGREE: usize = 22;
 
+const _: () = assert!(2 << MAX_CPU_LOG_DEGREE < bf_core_executor::MAX_CLK as usize);
+
 /// A chip that implements the CPU.
 #[derive(Default)]
 pub struct CpuChip;
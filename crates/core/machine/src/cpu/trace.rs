@@ -1,14 +1,14 @@
-use std::borrow::BorrowMut;
+use std::{borrow::BorrowMut, mem::offset_of};
 
 use hashbrown::HashMap;
 use itertools::Itertools;
 use p3_field::{PrimeField, PrimeField32};
 use p3_matrix::dense::RowMajorMatrix;
-use p3_maybe_rayon::prelude::{ParallelBridge, ParallelIterator, ParallelSlice};
+use p3_maybe_rayon::prelude::{ParallelBridge, ParallelIterator};
 use tracing::instrument;
 
 use bf_core_executor::{
-    events::{ByteLookupEvent, ByteRecord, CpuEvent, MemoryRecordEnum},
+    events::{ByteLookupEvent, ByteRecord, CpuEvent, MemoryRecordEnum, EVENT_CHUNK_LEN},
     ExecutionRecord, Instruction, Program,
 };
 use bf_stark::air::MachineAir;
@@ -25,51 +25,44 @@ impl<F: PrimeField32> MachineAir<F> for CpuChip {
         "Cpu".to_string()
     }
 
+    fn column_names(&self) -> Vec<String> {
+        CpuCols::<F>::column_names()
+    }
+
     fn generate_trace(
         &self,
         input: &ExecutionRecord,
         _: &mut ExecutionRecord,
     ) -> RowMajorMatrix<F> {
-        let padded_nb_rows = input.cpu_events.len().next_power_of_two();
-        let mut values = zeroed_f_vec(padded_nb_rows * NUM_CPU_COLS);
-
-        let chunk_size = std::cmp::max(input.cpu_events.len() / num_cpus::get(), 1);
-        values.chunks_mut(chunk_size * NUM_CPU_COLS).enumerate().par_bridge().for_each(
-            |(i, rows)| {
-                rows.chunks_mut(NUM_CPU_COLS).enumerate().for_each(|(j, row)| {
-                    let idx = i * chunk_size + j;
-                    let cols: &mut CpuCols<F> = row.borrow_mut();
-
-                    if idx < input.cpu_events.len() {
-                        let mut byte_lookup_events = Vec::new();
-                        let event = &input.cpu_events[idx];
-                        let instruction = &input.program.fetch(event.pc);
-                        self.event_to_row(event, cols, &mut byte_lookup_events, instruction);
-                    }
-                });
-            },
-        );
+        self.trace_and_lookups(input).0
+    }
 
-        // Convert the trace to a row major matrix.
-        RowMajorMatrix::new(values, NUM_CPU_COLS)
+    #[instrument(name = "generate cpu trace and dependencies", level = "debug", skip_all)]
+    fn generate_trace_and_dependencies(
+        &self,
+        input: &ExecutionRecord,
+        output: &mut ExecutionRecord,
+    ) -> RowMajorMatrix<F> {
+        let (trace, blu_events) = self.trace_and_lookups(input);
+        output.add_byte_lookup_events_from_maps(blu_events.iter().collect_vec());
+        trace
     }
 
     #[instrument(name = "generate cpu dependencies", level = "debug", skip_all)]
     fn generate_dependencies(&self, input: &ExecutionRecord, output: &mut ExecutionRecord) {
-        // Generate the trace rows for each event.
-        let chunk_size = std::cmp::max(input.cpu_events.len() / num_cpus::get(), 1);
-
+        // Generate the trace rows for each chunk of events.
         let blu_events: Vec<_> = input
             .cpu_events
-            .par_chunks(chunk_size)
-            .map(|ops: &[CpuEvent]| {
+            .chunks()
+            .par_bridge()
+            .map(|chunk| {
                 // The blu map stores shard -> map(byte lookup event -> multiplicity).
                 let mut blu: HashMap<ByteLookupEvent, usize> = HashMap::new();
-                ops.iter().for_each(|op| {
+                chunk.iter().for_each(|op| {
                     let mut row = [F::ZERO; NUM_CPU_COLS];
                     let cols: &mut CpuCols<F> = row.as_mut_slice().borrow_mut();
                     let instruction = &input.program.fetch(op.pc);
-                    self.event_to_row::<F>(op, cols, &mut blu, instruction);
+                    self.event_to_row::<F>(&op, cols, &mut blu, instruction);
                 });
                 blu
             })
@@ -81,9 +74,86 @@ impl<F: PrimeField32> MachineAir<F> for CpuChip {
     fn included(&self, record: &Self::Record) -> bool {
         !record.cpu_events.is_empty()
     }
+
+    fn required(&self) -> bool {
+        true
+    }
 }
 
 impl CpuChip {
+    /// Generates the trace, along with the byte lookups of each chunk of events.
+    fn trace_and_lookups<F: PrimeField32>(
+        &self,
+        input: &ExecutionRecord,
+    ) -> (RowMajorMatrix<F>, Vec<HashMap<ByteLookupEvent, usize>>) {
+        let events = &input.cpu_events;
+        let padded_nb_rows = events.len().next_power_of_two();
+        let mut values = zeroed_f_vec(padded_nb_rows * NUM_CPU_COLS);
+
+        // Each chunk of events fills its own block of rows, column by column where the columns
+        // are plain copies of the events. The values are still converted one by one, as the
+        // trace holds field elements in Montgomery form.
+        let blu_events = values
+            .chunks_mut(EVENT_CHUNK_LEN * NUM_CPU_COLS)
+            .enumerate()
+            .par_bridge()
+            .map(|(i, rows)| {
+                let mut blu: HashMap<ByteLookupEvent, usize> = HashMap::new();
+                if i >= events.num_chunks() {
+                    return blu;
+                }
+                let chunk = events.chunk(i);
+
+                let mut u32_column = |col: usize, column: &[u32]| {
+                    fill_column(rows, col, column.iter().map(|&v| F::from_wrapped_u32(v)));
+                };
+                u32_column(offset_of!(CpuCols<u8>, pc), chunk.pc);
+                u32_column(offset_of!(CpuCols<u8>, next_pc), chunk.next_pc);
+                u32_column(offset_of!(CpuCols<u8>, mp), chunk.mp);
+                u32_column(offset_of!(CpuCols<u8>, next_mp), chunk.next_mp);
+                u32_column(offset_of!(CpuCols<u8>, tape), chunk.tape);
+                u32_column(offset_of!(CpuCols<u8>, next_tape), chunk.next_tape);
+                let clk_16bit_limbs = chunk.clk.iter().map(|&clk| clk & 0xffff).collect_vec();
+                let clk_8bit_limbs = chunk.clk.iter().map(|&clk| (clk >> 16) & 0xff).collect_vec();
+                u32_column(offset_of!(CpuCols<u8>, clk_16bit_limb), &clk_16bit_limbs);
+                u32_column(offset_of!(CpuCols<u8>, clk_8bit_limb), &clk_8bit_limbs);
+                fill_column(
+                    rows,
+                    offset_of!(CpuCols<u8>, mv),
+                    chunk.mv.iter().map(|&mv| F::from_canonical_u8(mv)),
+                );
+                fill_column(
+                    rows,
+                    offset_of!(CpuCols<u8>, next_mv),
+                    chunk.next_mv.iter().map(|&mv| F::from_canonical_u8(mv)),
+                );
+
+                // Range check the clk limbs, as `populate_clk` does.
+                for (&lo, &hi) in clk_16bit_limbs.iter().zip(&clk_8bit_limbs) {
+                    blu.add_u16_range_check(lo as u16);
+                    blu.add_u8_range_check(hi as u8);
+                }
+
+                // The remaining columns depend on the instruction and the memory records.
+                rows.chunks_mut(NUM_CPU_COLS).enumerate().take(chunk.len()).for_each(|(j, row)| {
+                    let cols: &mut CpuCols<F> = row.borrow_mut();
+                    let instruction = &input.program.fetch(chunk.pc[j]);
+                    self.populate_accesses(
+                        cols,
+                        chunk.mv_access[j],
+                        chunk.next_mv_access[j],
+                        &mut blu,
+                        instruction,
+                    );
+                });
+                blu
+            })
+            .collect::<Vec<_>>();
+
+        // Convert the trace to a row major matrix.
+        (RowMajorMatrix::new(values, NUM_CPU_COLS), blu_events)
+    }
+
     /// Create a row from an event.
     fn event_to_row<F: PrimeField32>(
         &self,
@@ -98,28 +168,49 @@ impl CpuChip {
         // Populate basic fields.
         cols.pc = F::from_canonical_u32(event.pc);
         cols.next_pc = F::from_canonical_u32(event.next_pc);
-        cols.instruction.populate(instruction);
-        cols.mp = F::from_canonical_u32(event.mp);
-        cols.next_mp = F::from_canonical_u32(event.next_mp);
+        cols.mp = F::from_wrapped_u32(event.mp);
+        cols.next_mp = F::from_wrapped_u32(event.next_mp);
+        cols.tape = F::from_canonical_u32(event.tape);
+        cols.next_tape = F::from_canonical_u32(event.next_tape);
+        cols.mv = event.mv.into();
+        cols.next_mv = event.next_mv.into();
+
+        self.populate_accesses(
+            cols,
+            event.mv_access,
+            event.next_mv_access,
+            blu_events,
+            instruction,
+        );
+    }
 
-        cols.mv = F::from_canonical_u8(event.mv);
-        cols.next_mv = F::from_canonical_u8(event.next_mv);
-        *cols.mv_access.value_mut() = cols.mv;
-        *cols.next_mv_access.value_mut() = cols.next_mv;
+    /// Populates the instruction, the memory accesses and the selectors, once the basic fields
+    /// are populated.
+    fn populate_accesses<F: PrimeField32>(
+        &self,
+        cols: &mut CpuCols<F>,
+        mv_access: Option<MemoryRecordEnum>,
+        next_mv_access: Option<MemoryRecordEnum>,
+        blu_events: &mut impl ByteRecord,
+        instruction: &Instruction,
+    ) {
+        cols.instruction.populate(instruction);
+        *cols.mv_access.value_mut() = cols.mv.0;
+        *cols.next_mv_access.value_mut() = cols.next_mv.0;
 
         // Populate memory accesses.
-        if let Some(record) = event.mv_access {
+        if let Some(record) = mv_access {
             cols.mv_access.populate(record, blu_events);
             cols.mv_accessed = F::from_bool(true);
         }
 
-        if let Some(MemoryRecordEnum::Write(record)) = event.next_mv_access {
+        if let Some(MemoryRecordEnum::Write(record)) = next_mv_access {
             cols.next_mv_access.populate(record, blu_events);
             cols.next_mv_accessed = F::from_bool(true);
         }
 
         // Populate range checks for mv.
-        blu_events.add_u8_range_check(cols.mv.as_canonical_u32() as u8);
+        blu_events.add_u8_range_check(cols.mv.0.as_canonical_u32() as u8);
 
         cols.is_mv_immutable = F::from_bool(instruction.is_mv_immutable());
 
@@ -148,3 +239,10 @@ impl CpuChip {
         blu_events.add_u8_range_check(clk_8bit_limb);
     }
 }
+
+/// Writes `values` to the column `col` of consecutive rows.
+///
+/// The column of a [CpuCols] field is its offset in `CpuCols<u8>`, which is `#[repr(C)]`.
+fn fill_column<F>(rows: &mut [F], col: usize, values: impl Iterator<Item = F>) {
+    rows.chunks_exact_mut(NUM_CPU_COLS).zip(values).for_each(|(row, value)| row[col] = value);
+}
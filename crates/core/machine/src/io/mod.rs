@@ -3,22 +3,37 @@ use std::{
     mem::size_of,
 };
 
-use p3_air::{Air, BaseAir};
-use p3_field::{PrimeField, PrimeField32};
+use hashbrown::HashMap;
+use itertools::Itertools;
+use p3_air::{Air, AirBuilder, BaseAir};
+use p3_field::{FieldAlgebra, PrimeField, PrimeField32};
 use p3_matrix::{dense::RowMajorMatrix, Matrix};
 use p3_maybe_rayon::prelude::{ParallelBridge, ParallelIterator};
 
-use bf_core_executor::{events::IoEvent, ExecutionRecord, Opcode, Program};
+use bf_core_executor::{
+    events::{ByteLookupEvent, ByteRecord, IoEvent},
+    ByteOpcode, ExecutionRecord, IoDigest, Opcode, Program, ShardBoundary,
+    INPUT_COUNT_PUBLIC_VALUE, IO_DIGEST_LEN, NEXT_PUBLIC_VALUES, OUTPUT_COUNT_PUBLIC_VALUE,
+    START_PUBLIC_VALUES,
+};
 use bf_derive::AlignedBorrow;
-use bf_stark::air::{BfAirBuilder, MachineAir};
+use bf_primitives::poseidon2::WIDTH;
+use bf_stark::air::MachineAir;
 
-use crate::utils::{next_power_of_two, zeroed_f_vec};
+use crate::{
+    air::BfCoreAirBuilder,
+    operations::Poseidon2Operation,
+    utils::{next_power_of_two, par_chunk_size, zeroed_f_vec},
+};
 
 pub(crate) const NUM_IO_COLS: usize = size_of::<IoCols<u8>>();
 
 #[derive(AlignedBorrow, Debug, Clone, Copy)]
 #[repr(C)]
 struct IoCols<T> {
+    /// The clk of the instruction.
+    pub clk: T,
+
     /// The address of the memory access.
     pub pc: T,
 
@@ -33,6 +48,45 @@ struct IoCols<T> {
 
     /// Boolean to indicate whether the row is for an output operation.
     pub is_output: T,
+
+    /// Boolean to indicate whether the row is for a private input operation, which reads the
+    /// private input stream and is left out of the digests.
+    pub is_private_input: T,
+
+    /// The 16 least significant bits of `clk - prev_clk - 1`, or of `clk - start.clk` on the
+    /// first row, where `start` is the state of the execution at the start of the shard.
+    pub clk_diff_16bit_limb: T,
+
+    /// The 8 most significant bits of `clk - prev_clk - 1`, or of `clk - start.clk` on the
+    /// first row.
+    pub clk_diff_8bit_limb: T,
+
+    /// The 16 least significant bits of `next.clk - clk - 1`, where `next` is the state of the
+    /// execution after the shard.
+    pub shard_end_diff_16bit_limb: T,
+
+    /// The 8 most significant bits of `next.clk - clk - 1`.
+    pub shard_end_diff_8bit_limb: T,
+
+    /// The number of inputs read before this row in the shard, so the position of the input of
+    /// an input row. The last row exposes the number of inputs read, so the verifier knows how
+    /// many bytes of the input stream the input digest covers.
+    pub input_index: T,
+
+    /// The number of outputs written before this row in the shard. The last row exposes the
+    /// number of outputs written, so the verifier can split the output stream between the
+    /// shards.
+    pub output_index: T,
+
+    /// The state of the sponge of the inputs up to this row, see [`IoDigest`].
+    pub input_state: [T; WIDTH],
+
+    /// The state of the sponge of the outputs up to this row.
+    pub output_state: [T; WIDTH],
+
+    /// The permutation absorbing the byte of an input or output row into its sponge. The other
+    /// rows permute zero.
+    pub permutation: Poseidon2Operation<T>,
 }
 
 pub struct IoChip;
@@ -65,78 +119,339 @@ impl<F: PrimeField32> MachineAir<F> for IoChip {
         "IO".to_string()
     }
 
-    fn generate_dependencies(&self, _input: &ExecutionRecord, _output: &mut ExecutionRecord) {
-        // Do nothing since this chip has no dependencies.
+    fn column_names(&self) -> Vec<String> {
+        IoCols::<F>::column_names()
     }
 
     fn generate_trace(
         &self,
         input: &ExecutionRecord,
-        _output: &mut ExecutionRecord,
+        output: &mut ExecutionRecord,
     ) -> RowMajorMatrix<F> {
+        let events = input.io_events.iter().collect_vec();
+
+        // The indices and the sponges chain from one row to the next, so compute them ahead of
+        // the rows, along with the input of the permutation of each row.
+        let (mut input_index, mut output_index) = (0u32, 0u32);
+        let mut input_digest = IoDigest::<F>::default();
+        let mut output_digest = IoDigest::<F>::default();
+        let chained = events
+            .iter()
+            .map(|event| {
+                let indices = (input_index, output_index);
+                let mut permutation_input = [F::ZERO; WIDTH];
+                match event.opcode {
+                    Opcode::Input => {
+                        input_index += 1;
+                        permutation_input = input_digest.state;
+                        permutation_input[0] += F::from_canonical_u8(event.mv);
+                        input_digest.absorb(event.mv);
+                    }
+                    Opcode::Output => {
+                        output_index += 1;
+                        permutation_input = output_digest.state;
+                        permutation_input[0] += F::from_canonical_u8(event.mv);
+                        output_digest.absorb(event.mv);
+                    }
+                    _ => {}
+                }
+                (indices, input_digest.state, output_digest.state, permutation_input)
+            })
+            .collect_vec();
+
         // Generate the rows for the trace.
-        let chunk_size = std::cmp::max((input.io_events.len()) / num_cpus::get(), 1);
-        let padded_nb_rows = next_power_of_two(input.io_events.len());
+        let chunk_size = par_chunk_size::<F>(events.len(), NUM_IO_COLS);
+        let padded_nb_rows = next_power_of_two(events.len());
         let mut values = zeroed_f_vec(padded_nb_rows * NUM_IO_COLS);
 
-        values
+        let blu_events = values
             .chunks_mut(chunk_size * NUM_IO_COLS)
             .enumerate()
             .par_bridge()
             .map(|(i, rows)| {
+                let mut blu: HashMap<ByteLookupEvent, usize> = HashMap::new();
                 rows.chunks_mut(NUM_IO_COLS).enumerate().for_each(|(j, row)| {
                     let idx = i * chunk_size + j;
                     let cols: &mut IoCols<F> = row.borrow_mut();
 
-                    if idx < input.io_events.len() {
-                        let event = &input.io_events[idx];
-                        self.event_to_row(event, cols);
+                    if idx < events.len() {
+                        let prev_clk = idx.checked_sub(1).map(|prev| events[prev].clk);
+                        self.event_to_row(events[idx], prev_clk, input, cols, &mut blu);
+                        let (indices, permutation_input);
+                        (indices, cols.input_state, cols.output_state, permutation_input) =
+                            chained[idx];
+                        cols.input_index = F::from_canonical_u32(indices.0);
+                        cols.output_index = F::from_canonical_u32(indices.1);
+                        cols.permutation.populate(permutation_input);
+                    } else {
+                        // Padding rows carry the sponges of the shard to the last row.
+                        cols.input_index = F::from_canonical_u32(input_index);
+                        cols.output_index = F::from_canonical_u32(output_index);
+                        cols.input_state = input_digest.state;
+                        cols.output_state = output_digest.state;
+                        cols.permutation.populate([F::ZERO; WIDTH]);
                     }
                 });
+                blu
             })
             .collect::<Vec<_>>();
 
+        output.add_byte_lookup_events_from_maps(blu_events.iter().collect_vec());
+
         // Convert the trace to a row major matrix.
         RowMajorMatrix::new(values, NUM_IO_COLS)
     }
 
+    fn generate_trace_and_dependencies(
+        &self,
+        input: &ExecutionRecord,
+        output: &mut ExecutionRecord,
+    ) -> RowMajorMatrix<F> {
+        // The byte lookups are added to `output` while building the rows.
+        self.generate_trace(input, output)
+    }
+
     fn included(&self, record: &Self::Record) -> bool {
-        !record.io_events.is_empty()
+        // The last row exposes the I/O digests of the shard as the public values. The last shard
+        // always has the table, so that the machine has one even without I/O, and the verifier
+        // requires zero digests and counts from the shards without it.
+        !record.io_events.is_empty() || record.is_last_shard()
     }
 
-    fn local_only(&self) -> bool {
+    fn required(&self) -> bool {
         true
     }
 }
 
 impl IoChip {
     /// Create a row from an event.
-    fn event_to_row<F: PrimeField>(&self, event: &IoEvent, cols: &mut IoCols<F>) {
+    fn event_to_row<F: PrimeField>(
+        &self,
+        event: &IoEvent,
+        prev_clk: Option<u32>,
+        shard: &ExecutionRecord,
+        cols: &mut IoCols<F>,
+        blu: &mut impl ByteRecord,
+    ) {
+        cols.clk = F::from_canonical_u32(event.clk);
         cols.pc = F::from_canonical_u32(event.pc);
-        cols.mp = F::from_canonical_u32(event.mp);
+        cols.mp = F::from_wrapped_u32(event.mp);
         cols.mv = F::from_canonical_u8(event.mv);
         cols.is_input = F::from_bool(matches!(event.opcode, Opcode::Input));
         cols.is_output = F::from_bool(matches!(event.opcode, Opcode::Output));
+        cols.is_private_input = F::from_bool(matches!(event.opcode, Opcode::PrivateInput));
+
+        let diff =
+            prev_clk.map_or(event.clk - shard.start.clk, |prev_clk| event.clk - prev_clk - 1);
+        let (diff_16bit_limb, diff_8bit_limb) = Self::clk_limbs(diff, blu);
+        cols.clk_diff_16bit_limb = F::from_canonical_u16(diff_16bit_limb);
+        cols.clk_diff_8bit_limb = F::from_canonical_u8(diff_8bit_limb);
+
+        let (end_16bit_limb, end_8bit_limb) = Self::clk_limbs(shard.next.clk - event.clk - 1, blu);
+        cols.shard_end_diff_16bit_limb = F::from_canonical_u16(end_16bit_limb);
+        cols.shard_end_diff_8bit_limb = F::from_canonical_u8(end_8bit_limb);
+    }
+
+    /// Splits a difference of clks into its 16 least and 8 most significant bits, range checking
+    /// them.
+    fn clk_limbs(diff: u32, blu: &mut impl ByteRecord) -> (u16, u8) {
+        let limbs = ((diff & 0xffff) as u16, ((diff >> 16) & 0xff) as u8);
+        blu.add_u16_range_check(limbs.0);
+        blu.add_u8_range_check(limbs.1);
+        limbs
+    }
+
+    /// Range checks the 16 least and 8 most significant bits of a difference of clks, returning
+    /// the difference.
+    fn eval_clk_limbs<AB: BfCoreAirBuilder>(
+        builder: &mut AB,
+        limbs: (AB::Var, AB::Var),
+        is_real: AB::Expr,
+    ) -> AB::Expr {
+        builder.send_byte(
+            AB::Expr::from_canonical_u8(ByteOpcode::U16Range as u8),
+            AB::Expr::ZERO,
+            limbs.0,
+            is_real.clone(),
+        );
+        builder.send_byte(
+            AB::Expr::from_canonical_u8(ByteOpcode::U8Range as u8),
+            limbs.1,
+            AB::Expr::ZERO,
+            is_real,
+        );
+        limbs.0 + limbs.1 * AB::Expr::from_canonical_u32(1 << 16)
+    }
+
+    /// Constrains the row `cols` to absorb its byte into the sponge of its stream, whose states
+    /// on the previous row are `prev_input` and `prev_output`.
+    fn eval_absorb<AB: AirBuilder>(
+        builder: &mut AB,
+        cols: &IoCols<AB::Var>,
+        prev_input: [AB::Expr; WIDTH],
+        prev_output: [AB::Expr; WIDTH],
+    ) {
+        let output = cols.permutation.output();
+        for k in 0..WIDTH {
+            let byte = if k == 0 { cols.mv.into() } else { AB::Expr::ZERO };
+            builder.assert_eq(
+                cols.permutation.input[k],
+                cols.is_input * (prev_input[k].clone() + byte.clone())
+                    + cols.is_output * (prev_output[k].clone() + byte),
+            );
+            builder.assert_eq(
+                cols.input_state[k],
+                prev_input[k].clone()
+                    + cols.is_input * (AB::Expr::from(output[k]) - prev_input[k].clone()),
+            );
+            builder.assert_eq(
+                cols.output_state[k],
+                prev_output[k].clone()
+                    + cols.is_output * (AB::Expr::from(output[k]) - prev_output[k].clone()),
+            );
+        }
     }
 }
 
 impl<AB> Air<AB> for IoChip
 where
-    AB: BfAirBuilder,
+    AB: BfCoreAirBuilder,
 {
     fn eval(&self, builder: &mut AB) {
         let main = builder.main();
-        let local = main.row_slice(0);
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
         let local: &IoCols<AB::Var> = (*local).borrow();
+        let next: &IoCols<AB::Var> = (*next).borrow();
+        let public_values: Vec<AB::Expr> =
+            builder.public_values().iter().map(|&value| value.into()).collect();
+        let start = ShardBoundary::from_public_values(&public_values, START_PUBLIC_VALUES);
+        let end = ShardBoundary::from_public_values(&public_values, NEXT_PUBLIC_VALUES);
 
-        let is_real = local.is_input + local.is_output;
+        let is_real = local.is_input + local.is_output + local.is_private_input;
+        let next_is_real = next.is_input + next.is_output + next.is_private_input;
         builder.assert_bool(local.is_input);
         builder.assert_bool(local.is_output);
+        builder.assert_bool(local.is_private_input);
         builder.assert_bool(is_real.clone());
 
         let opcode = local.is_input * Opcode::Input.as_field::<AB::F>()
-            + local.is_output * Opcode::Output.as_field::<AB::F>();
+            + local.is_output * Opcode::Output.as_field::<AB::F>()
+            + local.is_private_input * Opcode::PrivateInput.as_field::<AB::F>();
+
+        builder.receive_io(local.clk, local.pc, opcode, local.mp, local.mv, is_real.clone());
+
+        // The padding rows come after the real rows, whose clks strictly increase within the
+        // cycles of the shard, so the rows are in the order of execution. The I/O bus is global,
+        // so the bounds keep a shard from taking the I/O of another one.
+        builder.when_transition().when(next_is_real.clone()).assert_one(is_real.clone());
+        let clk_diff = Self::eval_clk_limbs(
+            builder,
+            (local.clk_diff_16bit_limb, local.clk_diff_8bit_limb),
+            is_real.clone(),
+        );
+        builder.when_first_row().when(is_real.clone()).assert_eq(local.clk - start.clk, clk_diff);
+        builder.when_transition().when(next_is_real).assert_eq(
+            next.clk - local.clk - AB::Expr::ONE,
+            next.clk_diff_16bit_limb
+                + next.clk_diff_8bit_limb * AB::Expr::from_canonical_u32(1 << 16),
+        );
+        let shard_end_diff = Self::eval_clk_limbs(
+            builder,
+            (local.shard_end_diff_16bit_limb, local.shard_end_diff_8bit_limb),
+            is_real.clone(),
+        );
+        builder.when(is_real).assert_eq(end.clk - local.clk - AB::Expr::ONE, shard_end_diff);
+
+        // Each input row reads the next byte of the input stream, so the reads can be neither
+        // reordered nor repeated. The private input rows don't move the index, and the CPU
+        // range checks the byte they witness. The output rows are counted the same way.
+        builder.when_first_row().assert_zero(local.input_index);
+        builder.when_transition().assert_eq(next.input_index, local.input_index + local.is_input);
+        builder.when_last_row().assert_eq(
+            local.input_index + local.is_input,
+            public_values[INPUT_COUNT_PUBLIC_VALUE].clone(),
+        );
+        builder.when_first_row().assert_zero(local.output_index);
+        builder
+            .when_transition()
+            .assert_eq(next.output_index, local.output_index + local.is_output);
+        builder.when_last_row().assert_eq(
+            local.output_index + local.is_output,
+            public_values[OUTPUT_COUNT_PUBLIC_VALUE].clone(),
+        );
+
+        // Each input or output row absorbs its byte into the sponge of its stream, starting
+        // from zero on the first row. The other rows permute zero and carry the states.
+        Poseidon2Operation::<AB::F>::eval(builder, &local.permutation);
+        let zero = || core::array::from_fn(|_| AB::Expr::ZERO);
+        Self::eval_absorb(&mut builder.when_first_row(), local, zero(), zero());
+        Self::eval_absorb(
+            &mut builder.when_transition(),
+            next,
+            local.input_state.map(Into::into),
+            local.output_state.map(Into::into),
+        );
+
+        // The last row exposes the digests.
+        for k in 0..IO_DIGEST_LEN {
+            builder.when_last_row().assert_eq(local.input_state[k], public_values[k].clone());
+            builder
+                .when_last_row()
+                .assert_eq(local.output_state[k], public_values[IO_DIGEST_LEN + k].clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Borrow;
+
+    use p3_field::FieldAlgebra;
+    use p3_koala_bear::KoalaBear;
+    use p3_matrix::{dense::RowMajorMatrix, Matrix};
+
+    use bf_core_executor::{events::IoEvent, io_digest, ExecutionRecord, Opcode, IO_DIGEST_LEN};
+    use bf_stark::air::MachineAir;
+
+    use super::{IoChip, IoCols, WIDTH};
+
+    #[test]
+    fn generate_trace() {
+        let mut shard = ExecutionRecord::default();
+        shard.io_events.push(IoEvent::new(0, 0, Opcode::Input, 0, 7));
+        shard.io_events.push(IoEvent::new(4, 2, Opcode::Output, 0, 7));
+        shard.io_events.push(IoEvent::new(10, 3, Opcode::Input, 1, 0));
+        shard.io_events.push(IoEvent::new(12, 4, Opcode::PrivateInput, 1, 9));
+        shard.next.clk = 14;
+
+        let mut output = ExecutionRecord::default();
+        let trace: RowMajorMatrix<KoalaBear> = IoChip.generate_trace(&shard, &mut output);
+        assert_eq!(trace.height(), 16);
+
+        let row = trace.row_slice(2);
+        let cols: &IoCols<KoalaBear> = (*row).borrow();
+        assert_eq!(cols.clk_diff_16bit_limb, KoalaBear::from_canonical_u32(5));
+        assert_eq!(cols.shard_end_diff_16bit_limb, KoalaBear::from_canonical_u32(3));
+        assert_eq!(cols.input_index, KoalaBear::ONE);
+        assert_eq!(cols.output_index, KoalaBear::ONE);
+        assert_eq!(cols.permutation.output(), cols.input_state);
+
+        let last = trace.row_slice(trace.height() - 1);
+        let cols: &IoCols<KoalaBear> = (*last).borrow();
+        assert_eq!(cols.input_index, KoalaBear::from_canonical_u32(2));
+        assert_eq!(cols.output_index, KoalaBear::ONE);
+        assert_eq!(cols.input_state[..IO_DIGEST_LEN], io_digest::<KoalaBear>(&[7, 0]));
+        assert_eq!(cols.output_state[..IO_DIGEST_LEN], io_digest::<KoalaBear>(&[7]));
+
+        // The private input is neither indexed nor digested, and permutes zero.
+        let row = trace.row_slice(3);
+        let cols: &IoCols<KoalaBear> = (*row).borrow();
+        assert_eq!(cols.is_private_input, KoalaBear::ONE);
+        assert_eq!(cols.permutation.input, [KoalaBear::ZERO; WIDTH]);
+        assert_eq!(cols.input_index, KoalaBear::from_canonical_u32(2));
+        assert_eq!(cols.input_state[..IO_DIGEST_LEN], io_digest::<KoalaBear>(&[7, 0]));
 
-        builder.receive_io(local.pc, opcode, local.mp, local.mv, is_real);
+        // Two u16 and two u8 range checks per row.
+        assert_eq!(output.byte_lookups.values().sum::<usize>(), 16);
     }
 }
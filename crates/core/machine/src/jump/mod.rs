@@ -11,13 +11,11 @@ pub struct JumpChip;
 mod tests {
     use p3_field::{Field, FieldAlgebra};
     use p3_koala_bear::KoalaBear;
-    use p3_matrix::dense::RowMajorMatrix;
 
-    use bf_core_executor::{events::JumpEvent, ExecutionRecord, Opcode};
-    use bf_stark::{air::MachineAir, koala_bear_poseidon2::KoalaBearPoseidon2, StarkGenericConfig};
+    use bf_core_executor::{events::JumpEvent, Opcode};
 
     use super::JumpChip;
-    use crate::utils::{uni_stark_prove as prove, uni_stark_verify as verify};
+    use crate::testing::{prove_chip, RecordBuilder};
 
     #[test]
     fn test_zero() {
@@ -33,21 +31,14 @@ mod tests {
 
     #[test]
     fn prove_jump() {
-        let config = KoalaBearPoseidon2::new();
-        let mut challenger = config.challenger();
-
-        let mut shard = ExecutionRecord::default();
-        shard.jump_events.push(JumpEvent::new(1, 5, Opcode::LoopStart, 5, 0));
-        shard.jump_events.push(JumpEvent::new(1, 2, Opcode::LoopStart, 5, 1));
-        shard.jump_events.push(JumpEvent::new(1, 5, Opcode::LoopEnd, 5, 5));
-        shard.jump_events.push(JumpEvent::new(1, 2, Opcode::LoopEnd, 5, 0));
-
-        let chip = JumpChip;
-        let trace: RowMajorMatrix<KoalaBear> =
-            chip.generate_trace(&shard, &mut ExecutionRecord::default());
-        let proof = prove::<KoalaBearPoseidon2, _>(&config, &chip, &mut challenger, trace);
-
-        let mut challenger = config.challenger();
-        verify(&config, &chip, &mut challenger, &proof).unwrap();
+        let shard = RecordBuilder::new()
+            .events([
+                JumpEvent::new(1, 5, Opcode::LoopStart, 5, 0),
+                JumpEvent::new(1, 2, Opcode::LoopStart, 5, 1),
+                JumpEvent::new(1, 5, Opcode::LoopEnd, 5, 5),
+                JumpEvent::new(1, 2, Opcode::LoopEnd, 5, 0),
+            ])
+            .build();
+        prove_chip(&JumpChip, &shard).unwrap();
     }
 }
@@ -11,7 +11,7 @@ use bf_core_executor::{
 };
 use bf_stark::air::MachineAir;
 
-use crate::utils::{next_power_of_two, zeroed_f_vec};
+use crate::utils::{next_power_of_two, par_chunk_size, zeroed_f_vec};
 
 use super::{JumpChip, JumpCols, NUM_JUMP_COLS};
 
@@ -24,6 +24,10 @@ impl<F: PrimeField32> MachineAir<F> for JumpChip {
         "Jump".to_string()
     }
 
+    fn column_names(&self) -> Vec<String> {
+        JumpCols::<F>::column_names()
+    }
+
     fn num_rows(&self, input: &Self::Record) -> Option<usize> {
         let nb_rows = next_power_of_two(input.jump_events.len());
         Some(nb_rows)
@@ -35,7 +39,7 @@ impl<F: PrimeField32> MachineAir<F> for JumpChip {
         output: &mut ExecutionRecord,
     ) -> RowMajorMatrix<F> {
         // Generate the rows for the trace.
-        let chunk_size = std::cmp::max((input.jump_events.len()) / num_cpus::get(), 1);
+        let chunk_size = par_chunk_size::<F>(input.jump_events.len(), NUM_JUMP_COLS);
         let padded_nb_rows = <JumpChip as MachineAir<F>>::num_rows(self, input).unwrap();
         let mut values = zeroed_f_vec(padded_nb_rows * NUM_JUMP_COLS);
 
@@ -64,6 +68,15 @@ impl<F: PrimeField32> MachineAir<F> for JumpChip {
         RowMajorMatrix::new(values, NUM_JUMP_COLS)
     }
 
+    fn generate_trace_and_dependencies(
+        &self,
+        input: &ExecutionRecord,
+        output: &mut ExecutionRecord,
+    ) -> RowMajorMatrix<F> {
+        // The byte lookups are added to `output` while building the rows.
+        self.generate_trace(input, output)
+    }
+
     fn included(&self, record: &Self::Record) -> bool {
         !record.jump_events.is_empty()
     }
@@ -8,4 +8,5 @@ pub mod jump;
 pub mod memory;
 pub mod operations;
 pub mod program;
+pub mod testing;
 pub mod utils;
@@ -0,0 +1,139 @@
+use core::{
+    borrow::{Borrow, BorrowMut},
+    mem::size_of,
+};
+use p3_air::{Air, AirBuilder, BaseAir, PairBuilder};
+use p3_field::{FieldAlgebra, PrimeField32};
+use p3_matrix::{dense::RowMajorMatrix, Matrix};
+
+use bf_core_executor::{ExecutionRecord, Program, LAST_SHARD_PUBLIC_VALUE, MAX_PROGRAM_LEN};
+use bf_derive::AlignedBorrow;
+use bf_stark::air::{BfAirBuilder, MachineAir};
+use bf_stark::{AirLookup, LookupKind};
+
+use crate::utils::{next_power_of_two, zeroed_f_vec};
+
+/// The number of preprocessed memory image columns.
+pub const NUM_MEMORY_IMAGE_PREPROCESSED_COLS: usize = size_of::<MemoryImagePreprocessedCols<u8>>();
+
+/// The number of main memory image columns.
+pub const NUM_MEMORY_IMAGE_COLS: usize = size_of::<MemoryImageCols<u8>>();
+
+/// The preprocessed column layout for the chip, one row per cell of the image.
+#[derive(AlignedBorrow, Clone, Copy, Default)]
+#[repr(C)]
+pub struct MemoryImagePreprocessedCols<T> {
+    /// The address of the cell on the first tape.
+    pub addr: T,
+    /// The initial value of the cell.
+    pub value: T,
+    /// Whether the row is a cell of the image.
+    pub is_real: T,
+}
+
+/// The main column layout for the chip.
+#[derive(AlignedBorrow, Clone, Copy, Default)]
+#[repr(C)]
+pub struct MemoryImageCols<T> {
+    pub multiplicity: T,
+}
+
+/// A chip that commits to the initial contents of the tape, see [`Program::image`].
+#[derive(Default)]
+pub struct MemoryImageChip;
+
+impl<F: PrimeField32> MachineAir<F> for MemoryImageChip {
+    type Record = ExecutionRecord;
+
+    type Program = Program;
+
+    fn name(&self) -> String {
+        "MemoryImage".to_string()
+    }
+
+    fn column_names(&self) -> Vec<String> {
+        MemoryImageCols::<F>::column_names()
+    }
+
+    fn preprocessed_width(&self) -> usize {
+        NUM_MEMORY_IMAGE_PREPROCESSED_COLS
+    }
+
+    fn generate_preprocessed_trace(&self, program: &Self::Program) -> Option<RowMajorMatrix<F>> {
+        assert!(program.image.len() <= MAX_PROGRAM_LEN, "memory image too long");
+        let padded_nb_rows = next_power_of_two(program.image.len());
+        let mut values = zeroed_f_vec(padded_nb_rows * NUM_MEMORY_IMAGE_PREPROCESSED_COLS);
+
+        for (addr, (&value, row)) in program
+            .image
+            .iter()
+            .zip(values.chunks_mut(NUM_MEMORY_IMAGE_PREPROCESSED_COLS))
+            .enumerate()
+        {
+            let cols: &mut MemoryImagePreprocessedCols<F> = row.borrow_mut();
+            cols.addr = F::from_canonical_usize(addr);
+            cols.value = F::from_canonical_u8(value);
+            cols.is_real = F::ONE;
+        }
+
+        Some(RowMajorMatrix::new(values, NUM_MEMORY_IMAGE_PREPROCESSED_COLS))
+    }
+
+    fn generate_trace(
+        &self,
+        input: &ExecutionRecord,
+        _output: &mut ExecutionRecord,
+    ) -> RowMajorMatrix<F> {
+        // The memory tables initialize every cell of the image once, which the last shard
+        // receives.
+        let image_len = input.program.image.len();
+        let padded_nb_rows = next_power_of_two(image_len);
+        let mut values = zeroed_f_vec(padded_nb_rows * NUM_MEMORY_IMAGE_COLS);
+        if input.is_last_shard() {
+            values[..image_len * NUM_MEMORY_IMAGE_COLS].fill(F::ONE);
+        }
+
+        RowMajorMatrix::new(values, NUM_MEMORY_IMAGE_COLS)
+    }
+
+    fn included(&self, _: &Self::Record) -> bool {
+        // The key commits to the image, even if empty, so the chip is in every proof.
+        true
+    }
+
+    fn required(&self) -> bool {
+        true
+    }
+}
+
+impl<F> BaseAir<F> for MemoryImageChip {
+    fn width(&self) -> usize {
+        NUM_MEMORY_IMAGE_COLS
+    }
+}
+
+impl<AB> Air<AB> for MemoryImageChip
+where
+    AB: BfAirBuilder + PairBuilder,
+{
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let preprocessed = builder.preprocessed();
+
+        let prep_local = preprocessed.row_slice(0);
+        let prep_local: &MemoryImagePreprocessedCols<AB::Var> = (*prep_local).borrow();
+        let local = main.row_slice(0);
+        let local: &MemoryImageCols<AB::Var> = (*local).borrow();
+
+        // Each cell of the image initializes exactly one cell of the memory tables. The cells are
+        // initialized in the first shard, but the argument is global, so the image is received
+        // once, in the last one.
+        let last_shard: AB::Expr = builder.public_values()[LAST_SHARD_PUBLIC_VALUE].into();
+        builder.assert_eq(local.multiplicity, prep_local.is_real * last_shard);
+        builder.receive(AirLookup::new(
+            vec![AB::Expr::ZERO, prep_local.addr.into(), prep_local.value.into()],
+            local.multiplicity.into(),
+            LookupKind::MemoryImage,
+        ));
+    }
+}
@@ -4,7 +4,7 @@ use p3_air::{Air, AirBuilder, BaseAir};
 use p3_field::FieldAlgebra;
 use p3_matrix::Matrix;
 
-use bf_core_executor::Opcode;
+use bf_core_executor::{Opcode, ShardBoundary, NEXT_PUBLIC_VALUES, START_PUBLIC_VALUES};
 use bf_stark::air::BfAirBuilder;
 
 use super::{MemoryInstructionsChip, MemoryInstructionsCols, NUM_MEMORY_INSTRUCTIONS_COLS};
@@ -28,10 +28,13 @@ where
         let local: &MemoryInstructionsCols<AB::Var> = (*local).borrow();
         let next: &MemoryInstructionsCols<AB::Var> = (*next).borrow();
 
-        let is_real = local.is_step_forward + local.is_step_backward;
+        let is_real = local.is_step_forward + local.is_step_backward + local.is_switch_tape;
+        let next_is_real = next.is_step_forward + next.is_step_backward + next.is_switch_tape;
         builder.assert_bool(local.is_step_forward);
         builder.assert_bool(local.is_step_backward);
+        builder.assert_bool(local.is_switch_tape);
         builder.assert_bool(is_real.clone());
+        builder.assert_bool(local.tape);
 
         builder.when(local.is_step_forward).assert_eq(
             local.next_mp.reduce::<AB>(),
@@ -43,10 +46,49 @@ where
             local.mp.reduce::<AB>() - AB::F::from_canonical_u32(1),
         );
 
+        // A tape switch moves to the pointer of the other tape, which keeps the current one.
+        builder.when(local.is_switch_tape).assert_eq(local.next_mp.reduce::<AB>(), local.other_mp);
+        let next_tape = local.tape
+            + local.is_switch_tape * (AB::Expr::ONE - local.tape * AB::F::from_canonical_u32(2));
+        let next_other_mp =
+            local.other_mp + local.is_switch_tape * (local.mp.reduce::<AB>() - local.other_mp);
+
+        // Each tape keeps its memory pointer from one memory instruction to the next, from the
+        // start of the shard to its end, where the next shard starts. The first shard starts at
+        // 0 on the first tape.
+        let public_values: Vec<AB::Expr> =
+            builder.public_values().iter().map(|&value| value.into()).collect();
+        let start = ShardBoundary::from_public_values(&public_values, START_PUBLIC_VALUES);
+        let end = ShardBoundary::from_public_values(&public_values, NEXT_PUBLIC_VALUES);
+        let first = [local.tape.into(), local.mp.reduce::<AB>(), local.other_mp.into()];
+        let last = [next_tape.clone(), local.next_mp.reduce::<AB>(), next_other_mp.clone()];
+        let starts = [start.tape, start.mp, start.other_mp];
+        let ends = [end.tape, end.mp, end.other_mp];
+
+        // The real rows come first.
+        builder.when_transition().when_not(is_real.clone()).assert_zero(next_is_real.clone());
+        for ((first, last), (start, end)) in
+            first.into_iter().zip(last).zip(starts.into_iter().zip(ends))
+        {
+            builder.when_first_row().when(is_real.clone()).assert_eq(first, start.clone());
+            builder
+                .when_transition()
+                .when(is_real.clone() - next_is_real.clone())
+                .assert_eq(last.clone(), end.clone());
+            builder.when_last_row().when(is_real.clone()).assert_eq(last, end.clone());
+            // A shard without memory instructions ends where it starts.
+            builder.when_first_row().when_not(is_real.clone()).assert_eq(start, end);
+        }
+
         builder
             .when_transition()
-            .when(next.is_real)
+            .when(next_is_real.clone())
             .assert_eq(local.next_mp.reduce::<AB>(), next.mp.reduce::<AB>());
+        builder
+            .when_transition()
+            .when(next_is_real.clone())
+            .assert_eq(next_tape.clone(), next.tape);
+        builder.when_transition().when(next_is_real).assert_eq(next_other_mp, next.other_mp);
 
         KoalaBearWordRangeChecker::<AB::F>::range_check(
             builder,
@@ -63,13 +105,16 @@ where
         );
 
         let opcode = local.is_step_forward * Opcode::MemStepForward.as_field::<AB::F>()
-            + local.is_step_backward * Opcode::MemStepBackward.as_field::<AB::F>();
+            + local.is_step_backward * Opcode::MemStepBackward.as_field::<AB::F>()
+            + local.is_switch_tape * Opcode::SwitchTape.as_field::<AB::F>();
 
         builder.receive_memory_instr(
             local.clk,
             local.pc,
             opcode,
+            local.tape,
             local.mp.reduce::<AB>(),
+            next_tape,
             local.next_mp.reduce::<AB>(),
             is_real,
         );
@@ -17,6 +17,9 @@ pub struct MemoryInstructionsCols<T> {
     /// The clock cycle number.
     pub clk: T,
 
+    /// The tape the memory pointer points into.
+    pub tape: T,
+
     /// The memory pointer.
     pub mp: Word<T>,
     pub mp_range_checker: KoalaBearWordRangeChecker<T>,
@@ -25,10 +28,15 @@ pub struct MemoryInstructionsCols<T> {
     pub next_mp: Word<T>,
     pub next_mp_range_checker: KoalaBearWordRangeChecker<T>,
 
+    /// The memory pointer of the other tape.
+    pub other_mp: T,
+
     /// Whether this is `>`.
     pub is_step_forward: T,
     /// Whether this is `<`.
     pub is_step_backward: T,
+    /// Whether this is `~`.
+    pub is_switch_tape: T,
 
     /// Selector to label whether this row is a non padded row.
     pub is_real: T,
@@ -9,30 +9,25 @@ pub struct MemoryInstructionsChip;
 
 #[cfg(test)]
 mod tests {
-    use p3_koala_bear::KoalaBear;
-    use p3_matrix::dense::RowMajorMatrix;
-
-    use bf_core_executor::{events::MemInstrEvent, ExecutionRecord, Opcode};
-    use bf_stark::{air::MachineAir, koala_bear_poseidon2::KoalaBearPoseidon2, StarkGenericConfig};
+    use bf_core_executor::{events::MemInstrEvent, Opcode, ShardBoundary};
 
     use super::MemoryInstructionsChip;
-    use crate::utils::{uni_stark_prove as prove, uni_stark_verify as verify};
+    use crate::testing::{prove_chip, RecordBuilder};
 
     #[test]
     fn prove_mem_instrs() {
-        let config = KoalaBearPoseidon2::new();
-        let mut challenger = config.challenger();
-
-        let mut shard = ExecutionRecord::default();
-        shard.memory_instr_events.push(MemInstrEvent::new(1, 1, Opcode::MemStepForward, 1, 2));
-        shard.memory_instr_events.push(MemInstrEvent::new(1, 1, Opcode::MemStepBackward, 2, 1));
-
-        let chip = MemoryInstructionsChip;
-        let trace: RowMajorMatrix<KoalaBear> =
-            chip.generate_trace(&shard, &mut ExecutionRecord::default());
-        let proof = prove::<KoalaBearPoseidon2, _>(&config, &chip, &mut challenger, trace);
-
-        let mut challenger = config.challenger();
-        verify(&config, &chip, &mut challenger, &proof).unwrap();
+        let mut shard = RecordBuilder::new()
+            .event(MemInstrEvent::new(1, 1, Opcode::MemStepForward, 1, 2))
+            .event(MemInstrEvent::new(1, 1, Opcode::MemStepBackward, 2, 1))
+            .event(MemInstrEvent::new(1, 1, Opcode::SwitchTape, 1, 0))
+            .event(MemInstrEvent {
+                tape: 1,
+                other_mp: 1,
+                ..MemInstrEvent::new(1, 1, Opcode::MemStepForward, 0, 1)
+            })
+            .build();
+        shard.start.mp = 1;
+        shard.next = ShardBoundary { tape: 1, mp: 1, other_mp: 1, ..shard.next };
+        prove_chip(&MemoryInstructionsChip, &shard).unwrap();
     }
 }
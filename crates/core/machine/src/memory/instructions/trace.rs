@@ -16,7 +16,7 @@ use super::{
     cols::{MemoryInstructionsCols, NUM_MEMORY_INSTRUCTIONS_COLS},
     MemoryInstructionsChip,
 };
-use crate::utils::{next_power_of_two, zeroed_f_vec};
+use crate::utils::{next_power_of_two, par_chunk_size, zeroed_f_vec};
 
 impl<F: PrimeField32> MachineAir<F> for MemoryInstructionsChip {
     type Record = ExecutionRecord;
@@ -27,6 +27,10 @@ impl<F: PrimeField32> MachineAir<F> for MemoryInstructionsChip {
         "MemoryInstrs".to_string()
     }
 
+    fn column_names(&self) -> Vec<String> {
+        MemoryInstructionsCols::<F>::column_names()
+    }
+
     fn num_rows(&self, input: &Self::Record) -> Option<usize> {
         let nb_rows = next_power_of_two(input.memory_instr_events.len());
         Some(nb_rows)
@@ -37,7 +41,8 @@ impl<F: PrimeField32> MachineAir<F> for MemoryInstructionsChip {
         input: &ExecutionRecord,
         output: &mut ExecutionRecord,
     ) -> RowMajorMatrix<F> {
-        let chunk_size = std::cmp::max((input.memory_instr_events.len()) / num_cpus::get(), 1);
+        let chunk_size =
+            par_chunk_size::<F>(input.memory_instr_events.len(), NUM_MEMORY_INSTRUCTIONS_COLS);
         let padded_nb_rows =
             <MemoryInstructionsChip as MachineAir<F>>::num_rows(self, input).unwrap();
         let mut values = zeroed_f_vec(padded_nb_rows * NUM_MEMORY_INSTRUCTIONS_COLS);
@@ -67,8 +72,19 @@ impl<F: PrimeField32> MachineAir<F> for MemoryInstructionsChip {
         RowMajorMatrix::new(values, NUM_MEMORY_INSTRUCTIONS_COLS)
     }
 
-    fn included(&self, record: &Self::Record) -> bool {
-        !record.memory_instr_events.is_empty()
+    fn generate_trace_and_dependencies(
+        &self,
+        input: &ExecutionRecord,
+        output: &mut ExecutionRecord,
+    ) -> RowMajorMatrix<F> {
+        // The byte lookups are added to `output` while building the rows.
+        self.generate_trace(input, output)
+    }
+
+    fn included(&self, _record: &Self::Record) -> bool {
+        // The chip carries the memory pointers from the start of the shard to its end, so it is
+        // in every shard, even without memory instructions.
+        true
     }
 
     fn local_only(&self) -> bool {
@@ -85,12 +101,15 @@ impl MemoryInstructionsChip {
     ) {
         cols.clk = F::from_canonical_u32(event.clk);
         cols.pc = F::from_canonical_u32(event.pc);
+        cols.tape = F::from_canonical_u32(event.tape);
         cols.mp = event.mp.into();
         cols.mp_range_checker.populate(event.mp);
         cols.next_mp = event.next_mp.into();
         cols.next_mp_range_checker.populate(event.next_mp);
+        cols.other_mp = F::from_wrapped_u32(event.other_mp);
         cols.is_step_forward = F::from_bool(matches!(event.opcode, Opcode::MemStepForward));
         cols.is_step_backward = F::from_bool(matches!(event.opcode, Opcode::MemStepBackward));
+        cols.is_switch_tape = F::from_bool(matches!(event.opcode, Opcode::SwitchTape));
         // Assert that the instruction is not a no-op.
         cols.is_real = F::ONE;
     }
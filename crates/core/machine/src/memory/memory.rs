@@ -3,17 +3,25 @@ use std::{
     mem::size_of,
 };
 
-use p3_air::{Air, BaseAir};
+use hashbrown::HashMap;
+use itertools::Itertools;
+use p3_air::{Air, AirBuilder, BaseAir};
 use p3_field::PrimeField32;
 use p3_matrix::{dense::RowMajorMatrix, Matrix};
 use p3_maybe_rayon::prelude::{ParallelBridge, ParallelIterator};
 
-use bf_core_executor::{ExecutionRecord, Program};
+use bf_core_executor::{
+    events::{ByteLookupEvent, ByteRecord},
+    ExecutionRecord, Program,
+};
 use bf_derive::AlignedBorrow;
-use bf_stark::air::{BfAirBuilder, MachineAir};
-use bf_stark::{AirLookup, LookupKind};
+use bf_stark::air::{BaseAirBuilder, MachineAir};
+use bf_stark::{AirLookup, Byte, LookupKind};
 
-use crate::utils::{next_power_of_two, zeroed_f_vec};
+use crate::{
+    air::BfCoreAirBuilder,
+    utils::{next_power_of_two, par_chunk_size, zeroed_f_vec},
+};
 
 pub const NUM_MEMORY_ENTRIES_PER_ROW: usize = 2;
 
@@ -22,6 +30,9 @@ pub(crate) const NUM_MEMORY_INIT_COLS: usize = size_of::<MemCols<u8>>();
 #[derive(AlignedBorrow, Debug, Clone, Copy)]
 #[repr(C)]
 struct SingleMemoryLocal<T> {
+    /// The tape of the memory access.
+    pub tape: T,
+
     /// The address of the memory access.
     pub addr: T,
 
@@ -32,13 +43,19 @@ struct SingleMemoryLocal<T> {
     pub final_clk: T,
 
     /// The initial value of the memory access.
-    pub initial_value: T,
+    pub initial_value: Byte<T>,
 
     /// The final value of the memory access.
-    pub final_value: T,
+    pub final_value: Byte<T>,
+
+    /// Whether the cell is initialized in this shard, the first one to access it.
+    pub is_initial: T,
 
-    /// Whether the memory access is a real access.
-    pub is_real: T,
+    /// Whether the cell is finalized in this shard, the last one to access it.
+    pub is_final: T,
+
+    /// Whether the cell is in the memory image, which gives its initial value.
+    pub is_image: T,
 }
 
 #[derive(AlignedBorrow, Debug, Clone, Copy)]
@@ -77,23 +94,29 @@ impl<F: PrimeField32> MachineAir<F> for MemoryChip {
         "Memory".to_string()
     }
 
-    fn generate_dependencies(&self, _input: &ExecutionRecord, _output: &mut ExecutionRecord) {
-        // Do nothing since this chip has no dependencies.
+    fn column_names(&self) -> Vec<String> {
+        MemCols::<F>::column_names()
     }
 
     fn generate_trace(
         &self,
         input: &ExecutionRecord,
-        _output: &mut ExecutionRecord,
+        output: &mut ExecutionRecord,
     ) -> RowMajorMatrix<F> {
         // Generate the trace rows for each event.
         let nb_rows = input.cpu_memory_access.len().div_ceil(NUM_MEMORY_ENTRIES_PER_ROW);
         let padded_nb_rows = next_power_of_two(nb_rows);
         let mut values = zeroed_f_vec(padded_nb_rows * NUM_MEMORY_INIT_COLS);
-        let chunk_size = std::cmp::max((nb_rows + 1) / num_cpus::get(), 1);
+        let chunk_size = par_chunk_size::<F>(nb_rows, NUM_MEMORY_INIT_COLS);
+
+        let image_len = input.program.image.len();
 
-        values.chunks_mut(chunk_size * NUM_MEMORY_INIT_COLS).enumerate().par_bridge().for_each(
-            |(i, rows)| {
+        let blu_batches = values
+            .chunks_mut(chunk_size * NUM_MEMORY_INIT_COLS)
+            .enumerate()
+            .par_bridge()
+            .map(|(i, rows)| {
+                let mut blu: HashMap<ByteLookupEvent, usize> = HashMap::new();
                 rows.chunks_mut(NUM_MEMORY_INIT_COLS).enumerate().for_each(|(j, row)| {
                     let idx = (i * chunk_size + j) * NUM_MEMORY_ENTRIES_PER_ROW;
                     let cols: &mut MemCols<F> = row.borrow_mut();
@@ -101,33 +124,64 @@ impl<F: PrimeField32> MachineAir<F> for MemoryChip {
                         let cols = &mut cols.memory_entries[k];
                         if idx + k < input.cpu_memory_access.len() {
                             let event = &input.cpu_memory_access[idx + k];
-                            cols.addr = F::from_canonical_u32(event.addr);
-                            cols.initial_clk =
-                                F::from_canonical_u32(event.initial_mem_access.timestamp);
-                            cols.final_clk =
-                                F::from_canonical_u32(event.final_mem_access.timestamp);
-                            cols.initial_value =
-                                F::from_canonical_u8(event.initial_mem_access.value);
-                            cols.final_value = F::from_canonical_u8(event.final_mem_access.value);
-                            cols.is_real = F::ONE;
+                            let initial = event.initial_mem_access.unwrap_or_default();
+                            let last = event.final_mem_access.unwrap_or_default();
+                            cols.tape = F::from_canonical_u32(event.tape);
+                            cols.addr = F::from_wrapped_u32(event.addr);
+                            cols.initial_clk = F::from_canonical_u32(initial.timestamp);
+                            cols.final_clk = F::from_canonical_u32(last.timestamp);
+                            cols.initial_value = initial.value.into();
+                            cols.final_value = last.value.into();
+                            cols.is_initial = F::from_bool(event.initial_mem_access.is_some());
+                            cols.is_final = F::from_bool(event.final_mem_access.is_some());
+                            cols.is_image = F::from_bool(
+                                event.initial_mem_access.is_some()
+                                    && event.tape == 0
+                                    && (event.addr as usize) < image_len,
+                            );
+                            if event.initial_mem_access.is_some() {
+                                blu.add_u8_range_check(initial.value);
+                            }
+                            if event.final_mem_access.is_some() {
+                                blu.add_u8_range_check(last.value);
+                            }
                         }
                     }
                 });
-            },
-        );
+                blu
+            })
+            .collect::<Vec<_>>();
+
+        output.add_byte_lookup_events_from_maps(blu_batches.iter().collect_vec());
 
         // Convert the trace to a row major matrix.
         RowMajorMatrix::new(values, NUM_MEMORY_INIT_COLS)
     }
 
+    fn generate_trace_and_dependencies(
+        &self,
+        input: &ExecutionRecord,
+        output: &mut ExecutionRecord,
+    ) -> RowMajorMatrix<F> {
+        // The byte lookups are added to `output` while building the rows.
+        self.generate_trace(input, output)
+    }
+
     fn included(&self, shard: &Self::Record) -> bool {
-        !shard.cpu_memory_access.is_empty()
+        // Padding rows send and receive nothing, so a program that never touches the tape, such
+        // as `>`, still has a valid memory table. The last shard always has one, so that the
+        // machine has a memory table even then.
+        !shard.cpu_memory_access.is_empty() || shard.is_last_shard()
+    }
+
+    fn required(&self) -> bool {
+        true
     }
 }
 
 impl<AB> Air<AB> for MemoryChip
 where
-    AB: BfAirBuilder,
+    AB: BfCoreAirBuilder,
 {
     fn eval(&self, builder: &mut AB) {
         let main = builder.main();
@@ -135,12 +189,46 @@ where
         let local: &MemCols<AB::Var> = (*local).borrow();
 
         for local in local.memory_entries.iter() {
-            let values =
-                vec![local.initial_clk.into(), local.addr.into(), local.initial_value.into()];
-            builder.receive(AirLookup::new(values, local.is_real.into(), LookupKind::Memory));
-
-            let values = vec![local.final_clk.into(), local.addr.into(), local.final_value.into()];
-            builder.send(AirLookup::new(values, local.is_real.into(), LookupKind::Memory));
+            // The memory argument is global, so a cell is initialized by the shard that first
+            // accesses it and finalized by the one that last accesses it, the accesses in between
+            // balancing across the shards.
+            builder.assert_bool(local.is_initial);
+            builder.assert_bool(local.is_final);
+            builder.assert_bool(local.is_image);
+            builder.when(local.is_image).assert_one(local.is_initial);
+
+            // The values balancing the memory argument are bytes, so that a cell can't be
+            // written with a field element the instructions would never produce.
+            builder.range_check_u8(local.initial_value.0, local.is_initial);
+            builder.range_check_u8(local.final_value.0, local.is_final);
+
+            // The tape starts at clk 0, with the cells out of the image set to zero.
+            builder.when(local.is_initial).assert_zero(local.initial_clk);
+            builder
+                .when(local.is_initial)
+                .when_not(local.is_image)
+                .assert_zero(local.initial_value.0);
+            builder.send(AirLookup::new(
+                vec![local.tape.into(), local.addr.into(), local.initial_value.reduce::<AB>()],
+                local.is_image.into(),
+                LookupKind::MemoryImage,
+            ));
+
+            let values = vec![
+                local.initial_clk.into(),
+                local.tape.into(),
+                local.addr.into(),
+                local.initial_value.reduce::<AB>(),
+            ];
+            builder.receive(AirLookup::new(values, local.is_initial.into(), LookupKind::Memory));
+
+            let values = vec![
+                local.final_clk.into(),
+                local.tape.into(),
+                local.addr.into(),
+                local.final_value.reduce::<AB>(),
+            ];
+            builder.send(AirLookup::new(values, local.is_final.into(), LookupKind::Memory));
         }
     }
 }
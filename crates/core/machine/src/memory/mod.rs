@@ -1,8 +1,10 @@
 mod consistency;
+mod image;
 mod instructions;
 #[allow(clippy::module_inception)]
 mod memory;
 
 pub use consistency::*;
+pub use image::*;
 pub use instructions::*;
 pub use memory::*;
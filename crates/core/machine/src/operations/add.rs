@@ -1,4 +1,5 @@
 use bf_core_executor::events::ByteRecord;
+use bf_derive::AlignedBorrow;
 use bf_stark::air::BfAirBuilder;
 
 use p3_air::AirBuilder;
@@ -7,7 +8,7 @@ use p3_field::{Field, FieldAlgebra};
 use crate::air::U8AirBuilder;
 
 /// A set of columns needed to compute the add of two words.
-#[derive(Default, Debug, Clone, Copy)]
+#[derive(AlignedBorrow, Default, Debug, Clone, Copy)]
 #[repr(C)]
 pub struct AddOperation<T> {
     /// The result of `a + b`.
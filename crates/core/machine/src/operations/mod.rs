@@ -2,8 +2,10 @@ mod add;
 mod is_zero;
 mod koala_bear_range;
 mod koala_bear_word;
+mod poseidon2;
 
 pub use add::*;
 pub use is_zero::*;
 pub use koala_bear_range::*;
 pub use koala_bear_word::*;
+pub use poseidon2::*;
@@ -0,0 +1,89 @@
+//! An operation to evaluate the Poseidon2 permutation of [`bf_primitives::poseidon2`].
+//!
+//! The state after every external round and the output of the S-box of every internal round are
+//! witnessed, so that each constraint has degree 3: the other elements of the state through the
+//! internal rounds are linear combinations of the witnessed ones.
+use p3_field::Field;
+
+use bf_derive::AlignedBorrow;
+use bf_primitives::poseidon2::{
+    external_linear_layer, external_round, internal_constant, internal_diagonal,
+    internal_linear_layer, sbox, ROUNDS_F, ROUNDS_P, WIDTH,
+};
+use bf_stark::air::BfAirBuilder;
+
+/// A set of columns needed to compute the Poseidon2 permutation of the input.
+#[derive(AlignedBorrow, Default, Debug, Clone, Copy)]
+#[repr(C)]
+pub struct Poseidon2Operation<T> {
+    /// The input of the permutation.
+    pub input: [T; WIDTH],
+
+    /// The state after each external round, the last one being the output.
+    pub external_rounds: [[T; WIDTH]; ROUNDS_F],
+
+    /// The output of the S-box of each internal round.
+    pub internal_sboxes: [T; ROUNDS_P],
+}
+
+impl<F: Field> Poseidon2Operation<F> {
+    pub fn populate(&mut self, input: [F; WIDTH]) -> [F; WIDTH] {
+        self.input = input;
+
+        let mut state = input;
+        external_linear_layer(&mut state);
+        for round in 0..ROUNDS_F / 2 {
+            external_round(&mut state, round);
+            self.external_rounds[round] = state;
+        }
+        let diagonal = internal_diagonal();
+        for round in 0..ROUNDS_P {
+            state[0] = sbox(state[0] + internal_constant::<F>(round));
+            self.internal_sboxes[round] = state[0];
+            internal_linear_layer(&mut state, &diagonal);
+        }
+        for round in ROUNDS_F / 2..ROUNDS_F {
+            external_round(&mut state, round);
+            self.external_rounds[round] = state;
+        }
+        state
+    }
+
+    /// Constrains `cols.output()` to be the permutation of `cols.input`.
+    ///
+    /// The constraints hold on every row, so the padding rows must be filled with a valid
+    /// permutation, e.g. the one of zero.
+    pub fn eval<AB: BfAirBuilder>(builder: &mut AB, cols: &Poseidon2Operation<AB::Var>) {
+        let mut state: [AB::Expr; WIDTH] = cols.input.map(Into::into);
+        external_linear_layer(&mut state);
+
+        let witness = |builder: &mut AB, state: &mut [AB::Expr; WIDTH], round: usize| {
+            for (x, &col) in state.iter().zip(&cols.external_rounds[round]) {
+                builder.assert_eq(x.clone(), col);
+            }
+            *state = cols.external_rounds[round].map(Into::into);
+        };
+
+        for round in 0..ROUNDS_F / 2 {
+            external_round(&mut state, round);
+            witness(builder, &mut state, round);
+        }
+        let diagonal = internal_diagonal();
+        for (round, &col) in cols.internal_sboxes.iter().enumerate() {
+            builder.assert_eq(sbox(state[0].clone() + internal_constant::<AB::Expr>(round)), col);
+            state[0] = col.into();
+            internal_linear_layer(&mut state, &diagonal);
+        }
+        for round in ROUNDS_F / 2..ROUNDS_F {
+            external_round(&mut state, round);
+            witness(builder, &mut state, round);
+        }
+    }
+}
+
+impl<T: Copy> Poseidon2Operation<T> {
+    /// The output of the permutation.
+    pub fn output(&self) -> [T; WIDTH] {
+        self.external_rounds[ROUNDS_F - 1]
+    }
+}
@@ -4,19 +4,19 @@ use core::{
     borrow::{Borrow, BorrowMut},
     mem::size_of,
 };
-use p3_air::{Air, BaseAir, PairBuilder};
-use p3_field::PrimeField32;
+use p3_air::{Air, AirBuilder, BaseAir, PairBuilder};
+use p3_field::{FieldAlgebra, PrimeField32};
 use p3_matrix::{dense::RowMajorMatrix, Matrix};
 use p3_maybe_rayon::prelude::{ParallelBridge, ParallelIterator};
 
-use bf_core_executor::{ExecutionRecord, Program};
+use bf_core_executor::{ExecutionRecord, Program, MAX_PROGRAM_LEN, PROGRAM_LEN_PUBLIC_VALUE};
 use bf_derive::AlignedBorrow;
 use bf_stark::air::{BfAirBuilder, MachineAir};
 
 use crate::cpu::cols::InstructionCols;
 use crate::{
     air::ProgramAirBuilder,
-    utils::{next_power_of_two, pad_rows_fixed, zeroed_f_vec},
+    utils::{next_power_of_two, pad_rows_fixed, par_chunk_size, zeroed_f_vec},
 };
 
 /// The number of preprocessed program columns.
@@ -31,6 +31,8 @@ pub const NUM_PROGRAM_MULT_COLS: usize = size_of::<ProgramMultiplicityCols<u8>>(
 pub struct ProgramPreprocessedCols<T> {
     pub pc: T,
     pub instruction: InstructionCols<T>,
+    /// Whether the row holds the last instruction of the program.
+    pub is_last: T,
 }
 
 /// The column layout for the chip.
@@ -59,17 +61,23 @@ impl<F: PrimeField32> MachineAir<F> for ProgramChip {
         "Program".to_string()
     }
 
+    fn column_names(&self) -> Vec<String> {
+        ProgramMultiplicityCols::<F>::column_names()
+    }
+
     fn preprocessed_width(&self) -> usize {
         NUM_PROGRAM_PREPROCESSED_COLS
     }
 
     fn generate_preprocessed_trace(&self, program: &Self::Program) -> Option<RowMajorMatrix<F>> {
         debug_assert!(!program.instructions.is_empty(), "empty program");
+        // Programs built with `Program::new` skip the length check of `Program::from`.
+        assert!(program.instructions.len() <= MAX_PROGRAM_LEN, "program too long");
         // Generate the trace rows for each event.
         let nb_rows = program.instructions.len();
         let padded_nb_rows = next_power_of_two(nb_rows);
         let mut values = zeroed_f_vec(padded_nb_rows * NUM_PROGRAM_PREPROCESSED_COLS);
-        let chunk_size = std::cmp::max((nb_rows + 1) / num_cpus::get(), 1);
+        let chunk_size = par_chunk_size::<F>(nb_rows, NUM_PROGRAM_PREPROCESSED_COLS);
 
         values
             .chunks_mut(chunk_size * NUM_PROGRAM_PREPROCESSED_COLS)
@@ -85,6 +93,7 @@ impl<F: PrimeField32> MachineAir<F> for ProgramChip {
                         let pc = idx as u32;
                         cols.pc = F::from_canonical_u32(pc);
                         cols.instruction.populate(instruction);
+                        cols.is_last = F::from_bool(idx == nb_rows - 1);
                     }
                 });
             });
@@ -107,8 +116,7 @@ impl<F: PrimeField32> MachineAir<F> for ProgramChip {
         // Collect the number of times each instruction is called from the cpu events.
         // Store it as a map of PC -> count.
         let mut instruction_counts = HashMap::new();
-        input.cpu_events.iter().for_each(|event| {
-            let pc = event.pc;
+        input.cpu_events.pc.iter().for_each(|&pc| {
             instruction_counts.entry(pc).and_modify(|count| *count += 1).or_insert(1);
         });
 
@@ -137,6 +145,10 @@ impl<F: PrimeField32> MachineAir<F> for ProgramChip {
     fn included(&self, _: &Self::Record) -> bool {
         true
     }
+
+    fn required(&self) -> bool {
+        true
+    }
 }
 
 impl<F> BaseAir<F> for ProgramChip {
@@ -160,5 +172,10 @@ where
 
         // Constrain the lookup with CPU table
         builder.receive_program(prep_local.pc, prep_local.instruction, mult_local.multiplicity);
+
+        // Expose the length of the program, so that the verifier can check that the last shard
+        // ends after its last instruction.
+        let program_len = builder.public_values()[PROGRAM_LEN_PUBLIC_VALUE];
+        builder.when(prep_local.is_last).assert_eq(prep_local.pc + AB::Expr::ONE, program_len);
     }
 }
@@ -0,0 +1,215 @@
+//! Helpers to test a chip against the infrastructure the built-in chips are tested with.
+//!
+//! A chip, such as an extension added with [BfAir::machine_with_extensions], is usually tested in
+//! three steps: build an [ExecutionRecord] of the events it consumes with [RecordBuilder], prove
+//! and verify its trace alone with [prove_chip], then check that the lookups of the whole machine
+//! balance with [debug_interactions].
+//!
+//! [BfAir::machine_with_extensions]: crate::brainfuck::BfAir::machine_with_extensions
+
+use std::sync::Arc;
+
+use p3_air::Air;
+use p3_koala_bear::KoalaBear;
+use p3_uni_stark::{PcsError, SymbolicAirBuilder, VerificationError};
+
+use bf_core_executor::{
+    events::{
+        AluEvent, ByteLookupEvent, ByteRecord, CpuEvent, IoEvent, JumpEvent, MemInstrEvent,
+        MemoryEvent,
+    },
+    ExecutionRecord, Opcode, Program,
+};
+use bf_stark::{
+    air::MachineAir, debug_interactions_with_all_chips, koala_bear_poseidon2::KoalaBearPoseidon2,
+    LookupKind, MachineRecord, StarkGenericConfig, StarkMachine, UniConfig,
+};
+
+pub use crate::utils::{uni_stark_prove, uni_stark_verify};
+
+/// The error of [prove_chip] when the proof of the trace does not verify.
+pub type ChipVerificationError = VerificationError<PcsError<UniConfig<KoalaBearPoseidon2>>>;
+
+/// An event stored by an [ExecutionRecord], in the list of its kind.
+pub trait RecordEvent {
+    /// Appends the event to its list in `record`.
+    fn add_to(self, record: &mut ExecutionRecord);
+}
+
+impl RecordEvent for CpuEvent {
+    fn add_to(self, record: &mut ExecutionRecord) {
+        record.cpu_events.push(self);
+    }
+}
+
+/// Set events go to the set list and the others to the add list, as the executor records them.
+impl RecordEvent for AluEvent {
+    fn add_to(self, record: &mut ExecutionRecord) {
+        match self.opcode {
+            Opcode::Set => record.set_events.push(self),
+            _ => record.add_events.push(self),
+        }
+    }
+}
+
+impl RecordEvent for JumpEvent {
+    fn add_to(self, record: &mut ExecutionRecord) {
+        record.jump_events.push(self);
+    }
+}
+
+impl RecordEvent for IoEvent {
+    fn add_to(self, record: &mut ExecutionRecord) {
+        record.io_events.push(self);
+    }
+}
+
+impl RecordEvent for MemInstrEvent {
+    fn add_to(self, record: &mut ExecutionRecord) {
+        record.memory_instr_events.push(self);
+    }
+}
+
+impl RecordEvent for MemoryEvent {
+    fn add_to(self, record: &mut ExecutionRecord) {
+        record.cpu_memory_access.push(self);
+    }
+}
+
+impl RecordEvent for ByteLookupEvent {
+    fn add_to(self, record: &mut ExecutionRecord) {
+        record.add_byte_lookup_event(self);
+    }
+}
+
+/// Builds an [ExecutionRecord] from events, without executing a program.
+#[derive(Default)]
+pub struct RecordBuilder {
+    record: ExecutionRecord,
+}
+
+impl RecordBuilder {
+    /// Creates a builder of an empty record of the empty program.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the program of the record, from which the preprocessed traces are generated.
+    pub fn program(mut self, program: Program) -> Self {
+        self.record.program = Arc::new(program);
+        self
+    }
+
+    /// Appends an event.
+    pub fn event(mut self, event: impl RecordEvent) -> Self {
+        event.add_to(&mut self.record);
+        self
+    }
+
+    /// Appends events in order.
+    pub fn events<E: RecordEvent>(mut self, events: impl IntoIterator<Item = E>) -> Self {
+        events.into_iter().for_each(|event| event.add_to(&mut self.record));
+        self
+    }
+
+    /// Returns the record of the events appended so far.
+    pub fn build(self) -> ExecutionRecord {
+        self.record
+    }
+}
+
+/// Generates the trace of `chip` from `record`, then proves and verifies it alone.
+///
+/// Only the constraints of the chip are checked, not its lookups: see [debug_interactions]. In
+/// debug builds, a violated constraint panics with the row that violates it.
+#[cfg(debug_assertions)]
+pub fn prove_chip<A>(chip: &A, record: &ExecutionRecord) -> Result<(), ChipVerificationError>
+where
+    A: MachineAir<KoalaBear, Record = ExecutionRecord>
+        + Air<SymbolicAirBuilder<KoalaBear>>
+        + for<'a> Air<p3_uni_stark::ProverConstraintFolder<'a, UniConfig<KoalaBearPoseidon2>>>
+        + for<'a> Air<p3_uni_stark::VerifierConstraintFolder<'a, UniConfig<KoalaBearPoseidon2>>>
+        + for<'a> Air<p3_uni_stark::DebugConstraintBuilder<'a, KoalaBear>>,
+{
+    let config = KoalaBearPoseidon2::new();
+    let trace = chip.generate_trace(record, &mut ExecutionRecord::default());
+    let public_values = record.public_values::<KoalaBear>();
+    let proof = uni_stark_prove(&config, chip, &mut config.challenger(), trace, &public_values);
+    uni_stark_verify(&config, chip, &mut config.challenger(), &proof, &public_values)
+}
+
+/// Generates the trace of `chip` from `record`, then proves and verifies it alone.
+///
+/// Only the constraints of the chip are checked, not its lookups: see [debug_interactions].
+#[cfg(not(debug_assertions))]
+pub fn prove_chip<A>(chip: &A, record: &ExecutionRecord) -> Result<(), ChipVerificationError>
+where
+    A: MachineAir<KoalaBear, Record = ExecutionRecord>
+        + Air<SymbolicAirBuilder<KoalaBear>>
+        + for<'a> Air<p3_uni_stark::ProverConstraintFolder<'a, UniConfig<KoalaBearPoseidon2>>>
+        + for<'a> Air<p3_uni_stark::VerifierConstraintFolder<'a, UniConfig<KoalaBearPoseidon2>>>,
+{
+    let config = KoalaBearPoseidon2::new();
+    let trace = chip.generate_trace(record, &mut ExecutionRecord::default());
+    let public_values = record.public_values::<KoalaBear>();
+    let proof = uni_stark_prove(&config, chip, &mut config.challenger(), trace, &public_values);
+    uni_stark_verify(&config, chip, &mut config.challenger(), &proof, &public_values)
+}
+
+/// Runs the interaction debugger on the chips of `machine` included in `record`, as the prover
+/// does when the lookups of a shard do not balance.
+///
+/// The dependencies of the record, such as the byte lookups, are generated first. The lookup keys
+/// whose sends and receives differ are logged with the rows that send or receive them. Returns
+/// whether all the lookups balance.
+pub fn debug_interactions<A>(
+    machine: &StarkMachine<KoalaBearPoseidon2, A>,
+    record: &ExecutionRecord,
+) -> bool
+where
+    A: MachineAir<KoalaBear, Record = ExecutionRecord, Program = Program>,
+{
+    let (pk, _) = machine.setup(&record.program);
+    let mut record = record.clone();
+    machine.generate_dependencies(&mut record, None);
+    debug_interactions_with_all_chips::<KoalaBearPoseidon2, A>(
+        machine,
+        &pk,
+        &record,
+        LookupKind::all_kinds(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use bf_core_executor::Executor;
+    use test_artifacts::FIBO_BF;
+
+    use super::*;
+    use crate::{alu::SetChip, brainfuck::BfAir, utils::setup_logger};
+
+    #[test]
+    fn test_prove_chip() {
+        let record = RecordBuilder::new()
+            .event(AluEvent::new(0, Opcode::Set, 0, 7))
+            .events([AluEvent::new(3, Opcode::Set, 255, 0), AluEvent::new(5, Opcode::Add, 1, 0)])
+            .build();
+        assert_eq!(record.set_events.len(), 2);
+        assert_eq!(record.add_events.len(), 1);
+        prove_chip(&SetChip, &record).unwrap();
+    }
+
+    #[test]
+    fn test_debug_interactions() {
+        setup_logger();
+        let machine = BfAir::machine(KoalaBearPoseidon2::new());
+        let mut runtime = Executor::new(Program::from(FIBO_BF).unwrap(), vec![10]);
+        runtime.run().unwrap();
+        assert!(debug_interactions(&machine, &runtime.record));
+
+        // A jump that the CPU never sends is received without a matching send.
+        let mut record = runtime.record.clone();
+        JumpEvent::new(1, 5, Opcode::LoopStart, 5, 0).add_to(&mut record);
+        assert!(!debug_interactions(&machine, &record));
+    }
+}
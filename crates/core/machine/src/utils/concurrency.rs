@@ -1,4 +1,9 @@
-use std::sync::{Condvar, Mutex};
+use std::{
+    collections::VecDeque,
+    panic,
+    sync::{Condvar, Mutex},
+    thread,
+};
 
 /// A turn-based synchronization primitive.
 pub struct TurnBasedSync {
@@ -32,4 +37,224 @@ impl TurnBasedSync {
         *turn += 1;
         self.cv.notify_all();
     }
+
+    /// Waits for the given turn, runs `f` and advances the turn.
+    ///
+    /// Workers finishing out of order still run `f` in the order of their turns, e.g. to commit
+    /// to the shards in order. The turn is advanced even if `f` panics, so that the later
+    /// turns don't wait forever.
+    pub fn run_in_turn<R>(&self, my_turn: usize, f: impl FnOnce() -> R) -> R {
+        struct Advance<'a>(&'a TurnBasedSync);
+        impl Drop for Advance<'_> {
+            fn drop(&mut self) {
+                self.0.advance_turn();
+            }
+        }
+
+        self.wait_for_turn(my_turn);
+        let _advance = Advance(self);
+        f()
+    }
+}
+
+/// A bounded multi-producer, multi-consumer queue.
+///
+/// Producers block while the queue is full, which bounds the memory held by work waiting to be
+/// processed, such as the records of shards not proven yet.
+pub struct WorkQueue<T> {
+    state: Mutex<(VecDeque<T>, bool)>,
+    not_empty: Condvar,
+    not_full: Condvar,
+    capacity: usize,
+}
+
+impl<T> WorkQueue<T> {
+    /// Creates a queue holding at most `capacity` items.
+    ///
+    /// ### Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "the capacity of a work queue must be positive");
+        Self {
+            state: Mutex::new((VecDeque::with_capacity(capacity), false)),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+            capacity,
+        }
+    }
+
+    /// Pushes an item, waiting for room in the queue.
+    ///
+    /// Gives the item back if the queue is closed.
+    pub fn push(&self, item: T) -> Result<(), T> {
+        let mut state = self.state.lock().unwrap();
+        while state.0.len() == self.capacity && !state.1 {
+            state = self.not_full.wait(state).unwrap();
+        }
+        if state.1 {
+            return Err(item);
+        }
+        state.0.push_back(item);
+        self.not_empty.notify_one();
+        Ok(())
+    }
+
+    /// Pops the oldest item, waiting for one to be pushed.
+    ///
+    /// Returns `None` once the queue is closed and drained.
+    pub fn pop(&self) -> Option<T> {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if let Some(item) = state.0.pop_front() {
+                self.not_full.notify_one();
+                return Some(item);
+            }
+            if state.1 {
+                return None;
+            }
+            state = self.not_empty.wait(state).unwrap();
+        }
+    }
+
+    /// Closes the queue. Pushing fails from now on, and popping drains the remaining items.
+    pub fn close(&self) {
+        self.state.lock().unwrap().1 = true;
+        self.not_empty.notify_all();
+        self.not_full.notify_all();
+    }
+
+    /// The number of items in the queue.
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// A pool of scoped worker threads, which may borrow from the caller.
+#[derive(Debug, Clone, Copy)]
+pub struct WorkerPool {
+    num_workers: usize,
+    queue_capacity: usize,
+}
+
+impl WorkerPool {
+    /// Creates a pool of `num_workers` workers, at least one. The queue holds as many items.
+    pub fn new(num_workers: usize) -> Self {
+        let num_workers = num_workers.max(1);
+        Self { num_workers, queue_capacity: num_workers }
+    }
+
+    /// Sets the number of items waiting for a worker, at least one.
+    pub fn with_queue_capacity(mut self, queue_capacity: usize) -> Self {
+        self.queue_capacity = queue_capacity.max(1);
+        self
+    }
+
+    /// Applies `f` to every item and its index, returning the results in the order of the items.
+    ///
+    /// The items are produced lazily by the calling thread as the workers take them. A panic
+    /// in a worker or in the iterator stops the production and is propagated once the workers
+    /// are done.
+    pub fn map<T, R, I, F>(&self, items: I, f: F) -> Vec<R>
+    where
+        I: IntoIterator<Item = T>,
+        T: Send,
+        R: Send,
+        F: Fn(usize, T) -> R + Sync,
+    {
+        /// Closes the queue on a panic, so that neither the producer nor the workers wait forever.
+        struct CloseOnPanic<'a, T>(&'a WorkQueue<T>);
+        impl<T> Drop for CloseOnPanic<'_, T> {
+            fn drop(&mut self) {
+                if thread::panicking() {
+                    self.0.close();
+                }
+            }
+        }
+
+        let queue = WorkQueue::new(self.queue_capacity);
+        let (queue, f) = (&queue, &f);
+        let mut results = thread::scope(|s| {
+            let workers = (0..self.num_workers)
+                .map(|_| {
+                    s.spawn(move || {
+                        let _close = CloseOnPanic(queue);
+                        let mut results = Vec::new();
+                        while let Some((i, item)) = queue.pop() {
+                            results.push((i, f(i, item)));
+                        }
+                        results
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            let _close = CloseOnPanic(queue);
+            for item in items.into_iter().enumerate() {
+                if queue.push(item).is_err() {
+                    break;
+                }
+            }
+            queue.close();
+
+            let mut results = Vec::new();
+            for worker in workers {
+                match worker.join() {
+                    Ok(worker_results) => results.extend(worker_results),
+                    Err(payload) => panic::resume_unwind(payload),
+                }
+            }
+            results
+        });
+        results.sort_unstable_by_key(|(i, _)| *i);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_work_queue() {
+        let queue = WorkQueue::new(2);
+        queue.push(1).unwrap();
+        queue.push(2).unwrap();
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.pop(), Some(1));
+
+        queue.close();
+        assert_eq!(queue.push(3), Err(3));
+        assert_eq!(queue.pop(), Some(2));
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    fn test_worker_pool_order() {
+        let results = WorkerPool::new(4).with_queue_capacity(2).map(0..100u64, |i, item| {
+            assert_eq!(i as u64, item);
+            item * item
+        });
+        assert_eq!(results, (0..100u64).map(|item| item * item).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_ordered_commitment() {
+        let sync = TurnBasedSync::new();
+        let committed = Mutex::new(Vec::new());
+        WorkerPool::new(4).map((0..16).rev(), |i, shard| {
+            // The shards are done in any order, but committed to in the order of the turns.
+            sync.run_in_turn(i, || committed.lock().unwrap().push(shard));
+        });
+        assert_eq!(committed.into_inner().unwrap(), (0..16).rev().collect::<Vec<_>>());
+    }
+
+    #[test]
+    #[should_panic(expected = "bad item")]
+    fn test_worker_pool_panic() {
+        WorkerPool::new(2).map(0..1000, |_, item| assert!(item != 10, "bad item"));
+    }
 }
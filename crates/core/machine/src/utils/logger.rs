@@ -1,48 +1,211 @@
-use std::sync::Once;
+use std::{
+    fs::File,
+    io,
+    path::PathBuf,
+    str::FromStr,
+    sync::{Mutex, Once},
+};
 
-use tracing_forest::ForestLayer;
+use thiserror::Error;
+use tracing_forest::{ForestLayer, PrettyPrinter};
 use tracing_subscriber::{
-    fmt::format::FmtSpan, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry,
+    filter::ParseError,
+    fmt::{format::FmtSpan, writer::BoxMakeWriter},
+    layer::SubscriberExt,
+    util::{SubscriberInitExt, TryInitError},
+    EnvFilter, Registry,
 };
 
 static INIT: Once = Once::new();
 
+/// The crates whose logs are always filtered out, as they are too verbose to be useful.
+const QUIET_CRATES: [&str; 6] =
+    ["hyper", "p3_keccak_air", "p3_fri", "p3_dft", "p3_challenger", "zkm2_cuda"];
+
+/// The format of the log lines.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// One compact line per event.
+    #[default]
+    Flat,
+    /// The events grouped under their spans as a tree, printed when the root span closes.
+    Forest,
+    /// One JSON object per event, with its span context, for log collectors.
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = LoggerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "flat" => Ok(LogFormat::Flat),
+            "forest" => Ok(LogFormat::Forest),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(LoggerError::InvalidFormat(s.to_string())),
+        }
+    }
+}
+
+/// Where the log lines are written.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum LogTarget {
+    #[default]
+    Stdout,
+    Stderr,
+    /// A file, created or truncated when the logger is set up.
+    File(PathBuf),
+}
+
+/// An error setting up the logger.
+#[derive(Error, Debug)]
+pub enum LoggerError {
+    #[error("invalid logger type: {0}")]
+    InvalidFormat(String),
+    #[error("invalid log filter: {0}")]
+    InvalidFilter(ParseError),
+    #[error("failed to open the log file: {0}")]
+    File(io::Error),
+    #[error("failed to set the global logger: {0}")]
+    Init(TryInitError),
+}
+
+/// The configuration of the global logger, for services that integrate the logs of the prover
+/// with their own.
+///
+/// ```no_run
+/// use bf_core_machine::utils::{LogFormat, LogTarget, LoggerConfig};
+///
+/// LoggerConfig::new()
+///     .format(LogFormat::Json)
+///     .filter("info,bf_core_machine=debug")
+///     .target(LogTarget::Stderr)
+///     .init()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct LoggerConfig {
+    format: LogFormat,
+    filter: Option<String>,
+    target: LogTarget,
+    span_timings: bool,
+}
+
+impl LoggerConfig {
+    /// A flat logger to stdout, filtered by `RUST_LOG` and without span timings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The configuration of [setup_logger]: the format is read from `RUST_LOGGER`, `flat` by
+    /// default, and the span timings are on.
+    pub fn from_env() -> Result<Self, LoggerError> {
+        let format = match std::env::var("RUST_LOGGER") {
+            Ok(format) => format.parse()?,
+            Err(_) => LogFormat::default(),
+        };
+        Ok(Self::new().format(format).span_timings(true))
+    }
+
+    /// Sets the format of the log lines.
+    pub fn format(mut self, format: LogFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Sets the filter directives, such as `info,bf_core_machine=debug`, instead of the ones of
+    /// `RUST_LOG`. Without either, the logs are off.
+    pub fn filter(mut self, filter: impl Into<String>) -> Self {
+        self.filter = Some(filter.into());
+        self
+    }
+
+    /// Sets where the log lines are written.
+    pub fn target(mut self, target: LogTarget) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Sets whether a line is logged with the duration of each span when it closes. The forest
+    /// format always shows the durations.
+    pub fn span_timings(mut self, span_timings: bool) -> Self {
+        self.span_timings = span_timings;
+        self
+    }
+
+    /// Sets up the global logger, which fails if one is already set.
+    pub fn init(self) -> Result<(), LoggerError> {
+        let mut env_filter = match &self.filter {
+            Some(filter) => EnvFilter::try_new(filter).map_err(LoggerError::InvalidFilter)?,
+            None => EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("off")),
+        };
+        for krate in QUIET_CRATES {
+            env_filter = env_filter.add_directive(format!("{krate}=off").parse().unwrap());
+        }
+
+        let (writer, ansi) = match self.target {
+            LogTarget::Stdout => (BoxMakeWriter::new(io::stdout), true),
+            LogTarget::Stderr => (BoxMakeWriter::new(io::stderr), true),
+            LogTarget::File(path) => {
+                let file = File::create(path).map_err(LoggerError::File)?;
+                (BoxMakeWriter::new(Mutex::new(file)), false)
+            }
+        };
+        let span_events = if self.span_timings { FmtSpan::CLOSE } else { FmtSpan::NONE };
+
+        match self.format {
+            LogFormat::Forest => Registry::default()
+                .with(env_filter)
+                .with(ForestLayer::from(PrettyPrinter::new().writer(writer)))
+                .try_init(),
+            LogFormat::Flat => tracing_subscriber::fmt::Subscriber::builder()
+                .compact()
+                .with_file(false)
+                .with_target(false)
+                .with_thread_names(false)
+                .with_ansi(ansi)
+                .with_env_filter(env_filter)
+                .with_span_events(span_events)
+                .with_writer(writer)
+                .finish()
+                .try_init(),
+            LogFormat::Json => tracing_subscriber::fmt::Subscriber::builder()
+                .json()
+                .with_current_span(true)
+                .with_span_list(true)
+                .with_env_filter(env_filter)
+                .with_span_events(span_events)
+                .with_writer(writer)
+                .finish()
+                .try_init(),
+        }
+        .map_err(LoggerError::Init)
+    }
+}
+
 /// A simple logger.
 ///
-/// Set the `RUST_LOG` environment variable to be set to `info` or `debug`.
+/// Set the `RUST_LOG` environment variable to be set to `info` or `debug`, and `RUST_LOGGER` to
+/// `flat`, `forest` or `json`. Use [LoggerConfig] for other configurations.
 pub fn setup_logger() {
     INIT.call_once(|| {
-        let env_filter = EnvFilter::try_from_default_env()
-            .unwrap_or_else(|_| EnvFilter::new("off"))
-            .add_directive("hyper=off".parse().unwrap())
-            .add_directive("p3_keccak_air=off".parse().unwrap())
-            .add_directive("p3_fri=off".parse().unwrap())
-            .add_directive("p3_dft=off".parse().unwrap())
-            .add_directive("p3_challenger=off".parse().unwrap())
-            .add_directive("zkm2_cuda=off".parse().unwrap());
-
-        // if the RUST_LOGGER environment variable is set, use it to determine which logger to
-        // configure (tracing_forest or tracing_subscriber)
-        // otherwise, default to 'forest'
-        let logger_type = std::env::var("RUST_LOGGER").unwrap_or_else(|_| "flat".to_string());
-        match logger_type.as_str() {
-            "forest" => {
-                Registry::default().with(env_filter).with(ForestLayer::default()).init();
-            }
-            "flat" => {
-                tracing_subscriber::fmt::Subscriber::builder()
-                    .compact()
-                    .with_file(false)
-                    .with_target(false)
-                    .with_thread_names(false)
-                    .with_env_filter(env_filter)
-                    .with_span_events(FmtSpan::CLOSE)
-                    .finish()
-                    .init();
-            }
-            _ => {
-                panic!("Invalid logger type: {logger_type}");
-            }
-        }
+        LoggerConfig::from_env()
+            .and_then(LoggerConfig::init)
+            .unwrap_or_else(|e| panic!("failed to set up the logger: {e}"));
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_logger_config() {
+        assert_eq!("json".parse::<LogFormat>().unwrap(), LogFormat::Json);
+        assert!(matches!("tree".parse::<LogFormat>(), Err(LoggerError::InvalidFormat(_))));
+
+        let config =
+            LoggerConfig::new().filter("info,bf_core_machine=loud").target(LogTarget::Stderr);
+        assert!(matches!(config.init(), Err(LoggerError::InvalidFilter(_))));
+    }
+}
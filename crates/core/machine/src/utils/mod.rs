@@ -1,13 +1,17 @@
 pub mod concurrency;
 mod logger;
+mod options;
 mod prove;
 mod span;
+mod stats;
 mod tracer;
 
 pub use logger::*;
+pub use options::*;
 use p3_field::Field;
 pub use prove::*;
 pub use span::*;
+pub use stats::*;
 pub use tracer::*;
 
 use p3_maybe_rayon::prelude::{ParallelBridge, ParallelIterator};
@@ -74,12 +78,9 @@ where
     F: Send,
     P: Fn(usize, &mut [F]) + Send + Sync,
 {
-    // Split the vector into `num_cpus` chunks, but at least `num_cpus` rows per chunk.
     assert!(vec.len() % num_elements_per_event == 0);
     let len = vec.len() / num_elements_per_event;
-    let cpus = num_cpus::get();
-    let ceil_div = len.div_ceil(cpus);
-    let chunk_size = std::cmp::max(ceil_div, cpus);
+    let chunk_size = par_chunk_size::<F>(len, num_elements_per_event);
 
     vec.chunks_mut(chunk_size * num_elements_per_event).enumerate().par_bridge().for_each(
         |(i, chunk)| {
@@ -0,0 +1,78 @@
+use std::mem::size_of;
+
+use rayon::{ThreadPool, ThreadPoolBuildError, ThreadPoolBuilder};
+use serde::{Deserialize, Serialize};
+
+/// The size of a cache line on the targets we prove on.
+const CACHE_LINE_BYTES: usize = 64;
+
+/// The minimum number of trace cells filled by a parallel task, below which scheduling the task
+/// costs more than filling its rows.
+const MIN_CELLS_PER_TASK: usize = 1 << 12;
+
+/// Options of the CPU prover.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CpuProverOpts {
+    /// The number of threads proving runs on. `None` uses the global rayon pool, which has one
+    /// thread per core unless `RAYON_NUM_THREADS` says otherwise.
+    pub num_threads: Option<usize>,
+    /// The number of cycles of each shard. `None` proves the whole execution in one shard.
+    pub shard_size: Option<u64>,
+}
+
+impl CpuProverOpts {
+    /// Builds the thread pool to prove on, if the options ask for a dedicated one.
+    pub fn thread_pool(&self) -> Result<Option<ThreadPool>, ThreadPoolBuildError> {
+        self.num_threads
+            .map(|num_threads| ThreadPoolBuilder::new().num_threads(num_threads).build())
+            .transpose()
+    }
+}
+
+/// The number of rows each parallel task fills in a trace of `num_rows` rows of `width` columns
+/// of `F`, or handles in a list of `num_rows` events of such rows.
+///
+/// The rows are split evenly across the threads of the current pool, with at least
+/// [`MIN_CELLS_PER_TASK`] cells per task, and rounded up so that every task starts on a cache
+/// line and no two tasks write to the same line.
+pub fn par_chunk_size<F>(num_rows: usize, width: usize) -> usize {
+    let width = width.max(1);
+    let even = num_rows.div_ceil(rayon::current_num_threads());
+    let rows = even.max(MIN_CELLS_PER_TASK.div_ceil(width)).max(1);
+
+    let cells_per_line = (CACHE_LINE_BYTES / size_of::<F>().max(1)).max(1);
+    let rows_per_line = cells_per_line / gcd(cells_per_line, width);
+    rows.next_multiple_of(rows_per_line)
+}
+
+const fn gcd(mut a: usize, mut b: usize) -> usize {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_par_chunk_size() {
+        // Small traces are not split below the minimum work per task.
+        assert_eq!(par_chunk_size::<u32>(10, 8), MIN_CELLS_PER_TASK / 8);
+
+        // Every chunk of rows starts on a cache line.
+        for width in [1, 3, 8, 17, 40] {
+            let rows = par_chunk_size::<u32>(1 << 20, width);
+            assert_eq!(rows * width * size_of::<u32>() % CACHE_LINE_BYTES, 0);
+            assert!(rows * rayon::current_num_threads() >= 1 << 20);
+        }
+
+        let pool = CpuProverOpts { num_threads: Some(2), ..Default::default() }
+            .thread_pool()
+            .unwrap()
+            .unwrap();
+        let rows = pool.install(|| par_chunk_size::<u32>(1 << 20, 16));
+        assert_eq!(rows, 1 << 19);
+    }
+}
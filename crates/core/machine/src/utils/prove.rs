@@ -1,16 +1,18 @@
+use std::io;
+
 use p3_field::PrimeField32;
 use p3_koala_bear::KoalaBear;
 use size::Size;
 use thiserror::Error;
 use web_time::Instant;
 
-use bf_core_executor::{ExecutionError, Executor, Program};
+use bf_core_executor::{ExecutionError, ExecutionRecord, Executor, Program};
 use bf_stark::{koala_bear_poseidon2::KoalaBearPoseidon2, MachineVerificationError};
 use bf_stark::{
     Com, MachineProof, MachineProver, OpeningProof, PcsProverData, StarkGenericConfig, UniConfig,
 };
 
-use crate::brainfuck::BfAir;
+use crate::{brainfuck::BfAir, utils::TraceStats};
 
 #[derive(Error, Debug)]
 pub enum BfCoreProverError {
@@ -18,14 +20,33 @@ pub enum BfCoreProverError {
     ExecutionError(ExecutionError),
     #[error("serialization error: {0}")]
     SerializationError(bincode::Error),
+    #[error("key mismatch: {0}")]
+    KeyMismatch(KeyMismatch),
+    #[error("failed to prove: {0}")]
+    ProvingError(String),
+}
+
+/// The part of a proving key that does not belong with the rest.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyMismatch {
+    #[error("the proving key and the verifying key have different preprocessed commitments")]
+    VerifyingKey,
+    #[error("the elf, the program and the preprocessed commitment were not set up together")]
+    Digest,
+    #[error("the execution record is of another program")]
+    Record,
 }
 
+/// Executes and proves `program`, in shards of `shard_size` cycles if any, returning the proof,
+/// the output, the number of cycles and the sizes of the traces.
 pub fn prove<SC: StarkGenericConfig, P: MachineProver<SC, BfAir<SC::Val>>>(
     prover: &P,
     pk: &P::DeviceProvingKey,
     program: Program,
     input: Vec<u8>,
-) -> Result<(MachineProof<SC>, Vec<u8>, u64), BfCoreProverError>
+    private_input: Vec<u8>,
+    shard_size: Option<u64>,
+) -> Result<(MachineProof<SC>, Vec<u8>, u64, TraceStats), BfCoreProverError>
 where
     SC::Val: PrimeField32,
     SC::Challenger: 'static + Clone + Send,
@@ -34,35 +55,128 @@ where
     PcsProverData<SC>: Send + Sync,
 {
     // Setup the runtime.
-    let mut runtime = Executor::new(program, input);
+    let mut runtime = Executor::new_with_private_input(program, input, private_input);
+    if let Some(shard_size) = shard_size {
+        runtime = runtime.with_shard_size(shard_size);
+    }
 
-    // Execute the program to collect trace events.
-    runtime.run().map_err(BfCoreProverError::ExecutionError)?;
+    // Execute the program shard by shard as the shards are proven, so that only the records
+    // being committed to are in memory. An execution error ends the records, and fails the proof.
+    let mut shards = runtime.shards();
+    let mut error = None;
+    let records =
+        std::iter::from_fn(|| match tracing::info_span!("execute").in_scope(|| shards.next())? {
+            Ok(record) => Some(record),
+            Err(e) => {
+                error = Some(e);
+                None
+            }
+        });
+    let result = prove_shards(prover, pk, records);
+    match error {
+        Some(e) => Err(BfCoreProverError::ExecutionError(e)),
+        None => result,
+    }
+}
+
+/// Proves the execution recorded in `record`, without executing the program again, returning
+/// the proof, the output, the number of cycles and the sizes of the traces.
+///
+/// The record can come from another machine or an earlier run, e.g. serialized with bincode
+/// after [Executor::run].
+pub fn prove_record<SC: StarkGenericConfig, P: MachineProver<SC, BfAir<SC::Val>>>(
+    prover: &P,
+    pk: &P::DeviceProvingKey,
+    record: ExecutionRecord,
+) -> Result<(MachineProof<SC>, Vec<u8>, u64, TraceStats), BfCoreProverError>
+where
+    SC::Val: PrimeField32,
+    SC::Challenger: 'static + Clone + Send,
+    OpeningProof<SC>: Send,
+    Com<SC>: Send + Sync,
+    PcsProverData<SC>: Send + Sync,
+{
+    prove_shards(prover, pk, std::iter::once(record))
+}
+
+/// Proves the execution recorded in the records of its shards, in order, as yielded by
+/// [Executor::shards], returning the proof, the output, the number of cycles and the sizes of
+/// the traces.
+///
+/// Each record is dropped once committed to, so the records can be generated as they are proven.
+pub fn prove_shards<SC: StarkGenericConfig, P: MachineProver<SC, BfAir<SC::Val>>>(
+    prover: &P,
+    pk: &P::DeviceProvingKey,
+    records: impl IntoIterator<Item = ExecutionRecord>,
+) -> Result<(MachineProof<SC>, Vec<u8>, u64, TraceStats), BfCoreProverError>
+where
+    SC::Val: PrimeField32,
+    SC::Challenger: 'static + Clone + Send,
+    OpeningProof<SC>: Send,
+    Com<SC>: Send + Sync,
+    PcsProverData<SC>: Send + Sync,
+{
+    // Each shard has the I/O of its cycles, so the output is collected from all of them.
+    let (mut cycles, mut output, mut rows) = (0u64, Vec::new(), Vec::new());
+    #[cfg(feature = "debug")]
+    let mut first_record = None;
+    let records = records.into_iter().inspect(|record| {
+        cycles += record.cpu_events.len() as u64;
+        output.extend(record.output());
+        rows.push(TraceStats::shard_rows(prover.machine(), record));
+        #[cfg(feature = "debug")]
+        first_record.get_or_insert_with(|| record.clone());
+    });
 
     // Prove the program.
     let mut challenger = prover.config().challenger();
     let proving_start = Instant::now();
-    let proof = prover.prove(pk, &mut runtime.record, &mut challenger).unwrap();
+    let proof = prover
+        .prove_shards(pk, records, &mut challenger)
+        .map_err(|e| BfCoreProverError::ProvingError(e.to_string()))?;
     let proving_duration = proving_start.elapsed().as_millis();
-    let nb_bytes = bincode::serialize(&proof).unwrap().len();
+    let stats = TraceStats::from_rows(prover.machine(), &rows, &proof.shard_proofs);
+    let mut nb_bytes = ByteCounter::default();
+    for shard_proof in &proof.shard_proofs {
+        shard_proof.write_to(&mut nb_bytes).map_err(BfCoreProverError::SerializationError)?;
+    }
 
     // Print the summary.
     tracing::info!(
-        "summary: cycles={}, e2e={}, khz={:.2}, proofSize={}",
-        runtime.state.global_clk,
+        "summary: cycles={}, shards={}, e2e={}, khz={:.2}, proofSize={}, cells={}",
+        cycles,
+        proof.shard_proofs.len(),
         proving_duration,
-        (runtime.state.global_clk as f64 / proving_duration as f64),
-        Size::from_bytes(nb_bytes),
+        (cycles as f64 / proving_duration as f64),
+        Size::from_bytes(nb_bytes.0),
+        stats.total_cells,
     );
 
+    // The lookups of a shard only balance with those of the others, so the constraints are
+    // debugged for a single shard.
     #[cfg(feature = "debug")]
-    {
+    if let (Some(record), 1) = (first_record, proof.shard_proofs.len()) {
         let mut challenger = prover.machine().config().challenger();
         let pk_host = prover.pk_to_host(pk);
-        prover.machine().debug_constraints(&pk_host, runtime.record, &mut challenger);
+        prover.machine().debug_constraints(&pk_host, record, &mut challenger);
+    }
+
+    Ok((proof, output, cycles, stats))
+}
+
+/// A writer that only counts the bytes written to it.
+#[derive(Default)]
+struct ByteCounter(u64);
+
+impl io::Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len() as u64;
+        Ok(buf.len())
     }
 
-    Ok((proof, runtime.state.output_stream, runtime.state.global_clk))
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
 }
 
 pub fn run_test<P: MachineProver<KoalaBearPoseidon2, BfAir<KoalaBear>>>(
@@ -82,8 +196,15 @@ pub fn run_test_core<P: MachineProver<KoalaBearPoseidon2, BfAir<KoalaBear>>>(
     let prover = P::new(machine);
 
     let (pk, _) = prover.setup(runtime.program.as_ref());
-    let (proof, output, _) =
-        prove(&prover, &pk, Program::clone(&runtime.program), runtime.state.input_stream).unwrap();
+    let (proof, output, _, _) = prove(
+        &prover,
+        &pk,
+        Program::clone(&runtime.program),
+        runtime.state.input_stream,
+        runtime.state.private_input_stream,
+        runtime.shard_size,
+    )
+    .unwrap();
 
     let config = KoalaBearPoseidon2::new();
     let machine = BfAir::machine(config);
@@ -101,6 +222,7 @@ pub fn uni_stark_prove<SC, A>(
     air: &A,
     challenger: &mut SC::Challenger,
     trace: RowMajorMatrix<SC::Val>,
+    public_values: &[SC::Val],
 ) -> Proof<UniConfig<SC>>
 where
     SC: StarkGenericConfig,
@@ -108,7 +230,8 @@ where
         + for<'a> Air<p3_uni_stark::ProverConstraintFolder<'a, UniConfig<SC>>>
         + for<'a> Air<p3_uni_stark::DebugConstraintBuilder<'a, SC::Val>>,
 {
-    p3_uni_stark::prove(&UniConfig(config.clone()), air, challenger, trace, &vec![])
+    let public_values = public_values.to_vec();
+    p3_uni_stark::prove(&UniConfig(config.clone()), air, challenger, trace, &public_values)
 }
 
 #[cfg(not(debug_assertions))]
@@ -117,13 +240,15 @@ pub fn uni_stark_prove<SC, A>(
     air: &A,
     challenger: &mut SC::Challenger,
     trace: RowMajorMatrix<SC::Val>,
+    public_values: &[SC::Val],
 ) -> Proof<UniConfig<SC>>
 where
     SC: StarkGenericConfig,
     A: Air<p3_uni_stark::SymbolicAirBuilder<SC::Val>>
         + for<'a> Air<p3_uni_stark::ProverConstraintFolder<'a, UniConfig<SC>>>,
 {
-    p3_uni_stark::prove(&UniConfig(config.clone()), air, challenger, trace, &vec![])
+    let public_values = public_values.to_vec();
+    p3_uni_stark::prove(&UniConfig(config.clone()), air, challenger, trace, &public_values)
 }
 
 #[cfg(debug_assertions)]
@@ -133,6 +258,7 @@ pub fn uni_stark_verify<SC, A>(
     air: &A,
     challenger: &mut SC::Challenger,
     proof: &Proof<UniConfig<SC>>,
+    public_values: &[SC::Val],
 ) -> Result<(), p3_uni_stark::VerificationError<p3_uni_stark::PcsError<UniConfig<SC>>>>
 where
     SC: StarkGenericConfig,
@@ -140,7 +266,8 @@ where
         + for<'a> Air<p3_uni_stark::VerifierConstraintFolder<'a, UniConfig<SC>>>
         + for<'a> Air<p3_uni_stark::DebugConstraintBuilder<'a, SC::Val>>,
 {
-    p3_uni_stark::verify(&UniConfig(config.clone()), air, challenger, proof, &vec![])
+    let public_values = public_values.to_vec();
+    p3_uni_stark::verify(&UniConfig(config.clone()), air, challenger, proof, &public_values)
 }
 
 #[cfg(not(debug_assertions))]
@@ -149,13 +276,15 @@ pub fn uni_stark_verify<SC, A>(
     air: &A,
     challenger: &mut SC::Challenger,
     proof: &Proof<UniConfig<SC>>,
+    public_values: &[SC::Val],
 ) -> Result<(), p3_uni_stark::VerificationError<p3_uni_stark::PcsError<UniConfig<SC>>>>
 where
     SC: StarkGenericConfig,
     A: Air<p3_uni_stark::SymbolicAirBuilder<SC::Val>>
         + for<'a> Air<p3_uni_stark::VerifierConstraintFolder<'a, UniConfig<SC>>>,
 {
-    p3_uni_stark::verify(&UniConfig(config.clone()), air, challenger, proof, &vec![])
+    let public_values = public_values.to_vec();
+    p3_uni_stark::verify(&UniConfig(config.clone()), air, challenger, proof, &public_values)
 }
 
 use p3_air::Air;
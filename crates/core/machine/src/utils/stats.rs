@@ -0,0 +1,149 @@
+use std::fmt::{self, Display};
+
+use p3_air::BaseAir;
+use p3_field::PrimeField32;
+use serde::{Deserialize, Serialize};
+
+use bf_core_executor::ExecutionRecord;
+use bf_stark::{air::MachineAir, ShardProof, StarkGenericConfig, StarkMachine};
+
+use crate::{brainfuck::BfAir, memory::NUM_MEMORY_ENTRIES_PER_ROW};
+
+/// The size of the main trace of a chip.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChipTraceStats {
+    /// The name of the chip.
+    pub name: String,
+    /// The number of rows filled from the execution, the others being padding.
+    pub rows: usize,
+    /// The number of rows of the trace, a power of two, summed over the shards of the proof.
+    pub padded_rows: usize,
+    /// The number of columns of the main trace.
+    pub width: usize,
+}
+
+impl ChipTraceStats {
+    /// The number of cells of the main trace.
+    pub const fn cells(&self) -> usize {
+        self.padded_rows * self.width
+    }
+}
+
+/// The sizes of the main traces of a proof, which make up most of the cost of proving.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceStats {
+    /// The chips of the proof, in the order of the machine.
+    pub chips: Vec<ChipTraceStats>,
+    /// The number of cells of all the main traces.
+    pub total_cells: usize,
+}
+
+impl TraceStats {
+    /// Collects the sizes of the traces of `proof`, proving the execution of `record`.
+    pub fn new<SC: StarkGenericConfig>(
+        machine: &StarkMachine<SC, BfAir<SC::Val>>,
+        record: &ExecutionRecord,
+        proof: &ShardProof<SC>,
+    ) -> Self
+    where
+        SC::Val: PrimeField32,
+    {
+        Self::from_shards(machine, std::slice::from_ref(record), std::slice::from_ref(proof))
+    }
+
+    /// Collects the sizes of the traces of the shards of a proof, each proving the execution
+    /// of the record at the same index, with the rows of each chip summed over the shards.
+    pub fn from_shards<SC: StarkGenericConfig>(
+        machine: &StarkMachine<SC, BfAir<SC::Val>>,
+        records: &[ExecutionRecord],
+        proofs: &[ShardProof<SC>],
+    ) -> Self
+    where
+        SC::Val: PrimeField32,
+    {
+        let rows =
+            records.iter().map(|record| Self::shard_rows(machine, record)).collect::<Vec<_>>();
+        Self::from_rows(machine, &rows, proofs)
+    }
+
+    /// The number of rows of the trace of each chip of `machine` filled from `record`, in the
+    /// order of the machine, so that the records can be dropped before the stats are collected
+    /// with [`TraceStats::from_rows`].
+    pub fn shard_rows<SC: StarkGenericConfig>(
+        machine: &StarkMachine<SC, BfAir<SC::Val>>,
+        record: &ExecutionRecord,
+    ) -> Vec<Option<usize>>
+    where
+        SC::Val: PrimeField32,
+    {
+        machine.chips().iter().map(|chip| real_rows(chip.air(), record)).collect()
+    }
+
+    /// Collects the sizes of the traces of the shards of a proof, from the
+    /// [rows](TraceStats::shard_rows) of the record of each shard.
+    pub fn from_rows<SC: StarkGenericConfig>(
+        machine: &StarkMachine<SC, BfAir<SC::Val>>,
+        rows: &[Vec<Option<usize>>],
+        proofs: &[ShardProof<SC>],
+    ) -> Self
+    where
+        SC::Val: PrimeField32,
+    {
+        let chips = machine
+            .chips()
+            .iter()
+            .enumerate()
+            .filter_map(|(i, chip)| {
+                let name = chip.name();
+                let (rows, padded_rows) = rows
+                    .iter()
+                    .zip(proofs)
+                    .filter_map(|(shard_rows, proof)| {
+                        let index = *proof.chip_ordering.get(&name)?;
+                        let padded_rows = 1 << proof.opened_values.chips[index].log_degree;
+                        Some((shard_rows[i].unwrap_or(padded_rows), padded_rows))
+                    })
+                    .reduce(|(rows, padded_rows), shard| (rows + shard.0, padded_rows + shard.1))?;
+                Some(ChipTraceStats { name, rows, padded_rows, width: chip.width() })
+            })
+            .collect::<Vec<_>>();
+        let total_cells = chips.iter().map(ChipTraceStats::cells).sum();
+        Self { chips, total_cells }
+    }
+}
+
+/// The number of rows of the trace of `air` filled from `record`, or `None` if all the rows
+/// are, as in the byte table.
+fn real_rows<F: PrimeField32>(air: &BfAir<F>, record: &ExecutionRecord) -> Option<usize> {
+    let rows = match air {
+        BfAir::Program(_) => record.program.instructions.len(),
+        BfAir::Memory(_) => record.cpu_memory_access.len().div_ceil(NUM_MEMORY_ENTRIES_PER_ROW),
+        BfAir::MemoryImage(_) => record.program.image.len(),
+        BfAir::Cpu(_) => record.cpu_events.len(),
+        BfAir::ByteLookup(_) => return None,
+        BfAir::AddSub(_) => record.add_events.len() + record.sub_events.len(),
+        BfAir::Set(_) => record.set_events.len(),
+        BfAir::Jump(_) => record.jump_events.len(),
+        BfAir::MemoryInstrs(_) => record.memory_instr_events.len(),
+        BfAir::IO(_) => record.io_events.len(),
+        BfAir::Extension(extension) => match *extension {},
+    };
+    Some(rows)
+}
+
+impl Display for TraceStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for chip in &self.chips {
+            writeln!(
+                f,
+                "{:<14} {:>9} / {:>9} rows x {:>3} columns = {:>11} cells",
+                chip.name,
+                chip.rows,
+                chip.padded_rows,
+                chip.width,
+                chip.cells()
+            )?;
+        }
+        write!(f, "total: {} cells", self.total_cells)
+    }
+}
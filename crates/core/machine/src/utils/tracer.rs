@@ -1,6 +1,7 @@
-use std::env;
+use std::{env, path::Path};
 
 use tracing::level_filters::LevelFilter;
+use tracing_chrome::{ChromeLayerBuilder, FlushGuard};
 use tracing_forest::ForestLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
 
@@ -21,3 +22,16 @@ pub fn setup_tracer() {
     }
     Registry::default().with(env_filter).with(ForestLayer::default()).init();
 }
+
+/// A tracer writing the spans of the executor and the prover to `path`, in the JSON format of
+/// `chrome://tracing`, so that they can be inspected in a timeline viewer such as Perfetto.
+///
+/// The `RUST_LOG` environment variable selects the spans, `debug` by default, which includes the
+/// trace generation of every chip. The file is complete once the returned guard is dropped.
+pub fn setup_chrome_tracer(path: impl AsRef<Path>) -> FlushGuard {
+    let env_filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("debug"));
+    let (chrome_layer, guard) =
+        ChromeLayerBuilder::new().file(path.as_ref()).include_args(true).build();
+    Registry::default().with(env_filter).with(chrome_layer).init();
+    guard
+}
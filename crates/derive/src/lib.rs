@@ -25,8 +25,12 @@
 extern crate proc_macro;
 
 use proc_macro::TokenStream;
-use quote::quote;
-use syn::{parse_macro_input, parse_quote, Data, DeriveInput, GenericParam, WherePredicate};
+use proc_macro2::{Delimiter, Span, TokenTree};
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, parse_quote, Data, DeriveInput, Fields, GenericParam, Ident, Lit, LitStr,
+    Type, WherePredicate,
+};
 
 #[proc_macro_derive(AlignedBorrow)]
 pub fn aligned_borrow_derive(input: TokenStream) -> TokenStream {
@@ -85,12 +89,97 @@ pub fn aligned_borrow_derive(input: TokenStream) -> TokenStream {
         }
     };
 
-    TokenStream::from(methods)
+    let column_names = match &ast.data {
+        Data::Struct(data) => {
+            let fields = match &data.fields {
+                Fields::Named(fields) => fields
+                    .named
+                    .iter()
+                    .map(|field| (field.ident.as_ref().unwrap().to_string(), &field.ty))
+                    .collect::<Vec<_>>(),
+                // A newtype, such as `Word`, is named after the field holding it.
+                Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                    vec![(String::new(), &fields.unnamed[0].ty)]
+                }
+                Fields::Unnamed(fields) => {
+                    fields.unnamed.iter().enumerate().map(|(i, f)| (i.to_string(), &f.ty)).collect()
+                }
+                Fields::Unit => vec![],
+            };
+            let pushes = fields.iter().map(|(field_name, ty)| {
+                column_names_of(ty, type_generic, quote! { String::from(#field_name) }, 0)
+            });
+            quote! {
+                #[allow(dead_code)]
+                impl #impl_generics #name #type_generics #where_clause {
+                    /// The names of the columns, in the order of the trace. The fields of nested
+                    /// columns are flattened into paths, e.g. `instruction.op_a[0]`.
+                    pub fn column_names() -> Vec<String> {
+                        let mut names = Vec::new();
+                        #(#pushes)*
+                        names
+                    }
+                }
+            }
+        }
+        _ => quote! {},
+    };
+
+    TokenStream::from(quote! {
+        #methods
+        #column_names
+    })
+}
+
+/// Generates the code pushing the names of the columns of a field of type `ty` to `names`, the
+/// path of the field being `prefix`.
+///
+/// A field is a column if its type is `type_generic`, an array of fields, or a nested columns
+/// struct, which must derive `AlignedBorrow` too.
+fn column_names_of(
+    ty: &Type,
+    type_generic: &Ident,
+    prefix: proc_macro2::TokenStream,
+    depth: usize,
+) -> proc_macro2::TokenStream {
+    match ty {
+        Type::Path(path) if path.qself.is_none() && path.path.is_ident(type_generic) => {
+            quote! { names.push(#prefix); }
+        }
+        Type::Array(array) => {
+            let (elem, len) = (&array.elem, &array.len);
+            let i = format_ident!("i{}", depth);
+            let prefix = quote! { format!("{}[{}]", #prefix, #i) };
+            let inner = column_names_of(elem, type_generic, prefix, depth + 1);
+            quote! {
+                for #i in 0..#len {
+                    #inner
+                }
+            }
+        }
+        _ => quote! {
+            for name in <#ty>::column_names() {
+                let prefix = #prefix;
+                if prefix.is_empty() || name.starts_with('[') {
+                    names.push(format!("{prefix}{name}"));
+                } else {
+                    names.push(format!("{prefix}.{name}"));
+                }
+            }
+        },
+    }
 }
 
 #[proc_macro_derive(
     MachineAir,
-    attributes(bf_core_path, execution_record_path, program_path, builder_path, eval_trait_bound)
+    attributes(
+        bf_core_path,
+        execution_record_path,
+        program_path,
+        builder_path,
+        eval_trait_bound,
+        extension
+    )
 )]
 pub fn machine_air_derive(input: TokenStream) -> TokenStream {
     let ast: syn::DeriveInput = syn::parse(input).unwrap();
@@ -115,14 +204,23 @@ pub fn machine_air_derive(input: TokenStream) -> TokenStream {
                     let mut fields = variant.fields.iter();
                     let field = fields.next().unwrap();
                     assert!(fields.next().is_none(), "Only one field is supported");
-                    (variant_name, field)
+
+                    // A variant gated by a feature is left out of every arm and of the chips.
+                    let cfgs = variant
+                        .attrs
+                        .iter()
+                        .filter(|attr| attr.path.is_ident("cfg"))
+                        .collect::<Vec<_>>();
+                    let is_extension =
+                        variant.attrs.iter().any(|attr| attr.path.is_ident("extension"));
+                    (variant_name, field, cfgs, is_extension)
                 })
                 .collect::<Vec<_>>();
 
-            let width_arms = variants.iter().map(|(variant_name, field)| {
+            let width_arms = variants.iter().map(|(variant_name, field, cfgs, _)| {
                 let field_ty = &field.ty;
                 quote! {
-                    #name::#variant_name(x) => <#field_ty as p3_air::BaseAir<F>>::width(x)
+                    #(#cfgs)* #name::#variant_name(x) => <#field_ty as p3_air::BaseAir<F>>::width(x)
                 }
             });
 
@@ -140,52 +238,80 @@ pub fn machine_air_derive(input: TokenStream) -> TokenStream {
                 }
             };
 
-            let name_arms = variants.iter().map(|(variant_name, field)| {
+            let name_arms = variants.iter().map(|(variant_name, field, cfgs, _)| {
+                let field_ty = &field.ty;
+                quote! {
+                    #(#cfgs)* #name::#variant_name(x) => <#field_ty as bf_stark::air::MachineAir<F>>::name(x)
+                }
+            });
+
+            let preprocessed_width_arms = variants.iter().map(|(variant_name, field, cfgs, _)| {
                 let field_ty = &field.ty;
                 quote! {
-                    #name::#variant_name(x) => <#field_ty as bf_stark::air::MachineAir<F>>::name(x)
+                    #(#cfgs)* #name::#variant_name(x) => <#field_ty as bf_stark::air::MachineAir<F>>::preprocessed_width(x)
                 }
             });
 
-            let preprocessed_width_arms = variants.iter().map(|(variant_name, field)| {
+            let generate_preprocessed_trace_arms = variants.iter().map(|(variant_name, field, cfgs, _)| {
                 let field_ty = &field.ty;
                 quote! {
-                    #name::#variant_name(x) => <#field_ty as bf_stark::air::MachineAir<F>>::preprocessed_width(x)
+                    #(#cfgs)* #name::#variant_name(x) => <#field_ty as bf_stark::air::MachineAir<F>>::generate_preprocessed_trace(x, program)
                 }
             });
 
-            let generate_preprocessed_trace_arms = variants.iter().map(|(variant_name, field)| {
+            let generate_trace_arms = variants.iter().map(|(variant_name, field, cfgs, _)| {
                 let field_ty = &field.ty;
                 quote! {
-                    #name::#variant_name(x) => <#field_ty as bf_stark::air::MachineAir<F>>::generate_preprocessed_trace(x, program)
+                    #(#cfgs)* #name::#variant_name(x) => <#field_ty as bf_stark::air::MachineAir<F>>::generate_trace(x, input, output)
                 }
             });
 
-            let generate_trace_arms = variants.iter().map(|(variant_name, field)| {
+            let generate_dependencies_arms = variants.iter().map(|(variant_name, field, cfgs, _)| {
                 let field_ty = &field.ty;
                 quote! {
-                    #name::#variant_name(x) => <#field_ty as bf_stark::air::MachineAir<F>>::generate_trace(x, input, output)
+                    #(#cfgs)* #name::#variant_name(x) => <#field_ty as bf_stark::air::MachineAir<F>>::generate_dependencies(x, input, output)
                 }
             });
 
-            let generate_dependencies_arms = variants.iter().map(|(variant_name, field)| {
+            let generate_trace_and_dependencies_arms = variants.iter().map(|(variant_name, field, cfgs, _)| {
                 let field_ty = &field.ty;
                 quote! {
-                    #name::#variant_name(x) => <#field_ty as bf_stark::air::MachineAir<F>>::generate_dependencies(x, input, output)
+                    #(#cfgs)* #name::#variant_name(x) => <#field_ty as bf_stark::air::MachineAir<F>>::generate_trace_and_dependencies(x, input, output)
                 }
             });
 
-            let included_arms = variants.iter().map(|(variant_name, field)| {
+            let depends_on_dependencies_arms = variants.iter().map(|(variant_name, field, cfgs, _)| {
                 let field_ty = &field.ty;
                 quote! {
-                    #name::#variant_name(x) => <#field_ty as bf_stark::air::MachineAir<F>>::included(x, shard)
+                    #(#cfgs)* #name::#variant_name(x) => <#field_ty as bf_stark::air::MachineAir<F>>::depends_on_dependencies(x)
                 }
             });
 
-            let local_only_arms = variants.iter().map(|(variant_name, field)| {
+            let included_arms = variants.iter().map(|(variant_name, field, cfgs, _)| {
                 let field_ty = &field.ty;
                 quote! {
-                    #name::#variant_name(x) => <#field_ty as bf_stark::air::MachineAir<F>>::local_only(x)
+                    #(#cfgs)* #name::#variant_name(x) => <#field_ty as bf_stark::air::MachineAir<F>>::included(x, shard)
+                }
+            });
+
+            let required_arms = variants.iter().map(|(variant_name, field, cfgs, _)| {
+                let field_ty = &field.ty;
+                quote! {
+                    #(#cfgs)* #name::#variant_name(x) => <#field_ty as bf_stark::air::MachineAir<F>>::required(x)
+                }
+            });
+
+            let local_only_arms = variants.iter().map(|(variant_name, field, cfgs, _)| {
+                let field_ty = &field.ty;
+                quote! {
+                    #(#cfgs)* #name::#variant_name(x) => <#field_ty as bf_stark::air::MachineAir<F>>::local_only(x)
+                }
+            });
+
+            let column_names_arms = variants.iter().map(|(variant_name, field, cfgs, _)| {
+                let field_ty = &field.ty;
+                quote! {
+                    #(#cfgs)* #name::#variant_name(x) => <#field_ty as bf_stark::air::MachineAir<F>>::column_names(x)
                 }
             });
 
@@ -236,24 +362,83 @@ pub fn machine_air_derive(input: TokenStream) -> TokenStream {
                         }
                     }
 
+                    fn generate_trace_and_dependencies(
+                        &self,
+                        input: &#execution_record_path,
+                        output: &mut #execution_record_path,
+                    ) -> p3_matrix::dense::RowMajorMatrix<F> {
+                        match self {
+                            #(#generate_trace_and_dependencies_arms,)*
+                        }
+                    }
+
+                    fn depends_on_dependencies(&self) -> bool {
+                        match self {
+                            #(#depends_on_dependencies_arms,)*
+                        }
+                    }
+
                     fn included(&self, shard: &Self::Record) -> bool {
                         match self {
                             #(#included_arms,)*
                         }
                     }
 
+                    fn required(&self) -> bool {
+                        match self {
+                            #(#required_arms,)*
+                        }
+                    }
+
                     fn local_only(&self) -> bool {
                         match self {
                             #(#local_only_arms,)*
                         }
                     }
+
+                    fn column_names(&self) -> Vec<String> {
+                        match self {
+                            #(#column_names_arms,)*
+                        }
+                    }
                 }
             };
 
-            let eval_arms = variants.iter().map(|(variant_name, field)| {
+            // The chips of an extension variant are given by the caller instead.
+            let chip_pushes = variants.iter().filter(|(.., is_extension)| !is_extension).map(
+                |(variant_name, field, cfgs, _)| {
+                    let field_ty = &field.ty;
+                    quote! {
+                        #(#cfgs)*
+                        chips.push(bf_stark::Chip::new(#name::#variant_name(
+                            <#field_ty as core::default::Default>::default(),
+                        )));
+                    }
+                },
+            );
+
+            let mut chips_generics = generics.clone();
+            chips_generics.make_where_clause().predicates.push(syn::parse_quote! {
+                Self: p3_air::Air<bf_stark::LookupBuilder<F>>
+                    + p3_air::Air<p3_uni_stark::SymbolicAirBuilder<F>>
+            });
+            let (_, _, chips_where_clause) = chips_generics.split_for_impl();
+
+            let chips = quote! {
+                impl #impl_generics #name #ty_generics #chips_where_clause {
+                    /// Get all the different AIRs, in the order of the variants.
+                    pub fn chips() -> Vec<bf_stark::Chip<F, Self>> {
+                        let mut chips = vec![];
+                        #(#chip_pushes)*
+                        chips
+                    }
+                }
+            };
+
+            let eval_arms = variants.iter().map(|(variant_name, field, cfgs, _)| {
                 let field_ty = &field.ty;
                 quote! {
-                    #name::#variant_name(x) => <#field_ty as p3_air::Air<AB>>::eval(x, builder)
+                    #(#cfgs)* #name::#variant_name(x) => <#field_ty as p3_air::Air<AB>>::eval(x, builder)
                 }
             });
 
@@ -287,6 +472,8 @@ pub fn machine_air_derive(input: TokenStream) -> TokenStream {
                 #machine_air
 
                 #air
+
+                #chips
             }
             .into()
         }
@@ -294,6 +481,150 @@ pub fn machine_air_derive(input: TokenStream) -> TokenStream {
     }
 }
 
+/// Includes a Brainfuck program as a `&'static str`, checked at compile time.
+///
+/// The path is relative to the including crate's `CARGO_MANIFEST_DIR`. Everything but the
+/// commands is stripped, and unbalanced brackets are reported as compile errors pointing at the
+/// offending line and column of the source file.
+///
+/// ```ignore
+/// const FIBO: &str = bf_derive::include_bf!("guests/fibo.bf");
+/// ```
+#[proc_macro]
+pub fn include_bf(input: TokenStream) -> TokenStream {
+    let lit = parse_macro_input!(input as LitStr);
+
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let path = std::path::Path::new(&manifest_dir).join(lit.value());
+    let path_str = path.display().to_string();
+
+    let source = match std::fs::read_to_string(&path) {
+        Ok(source) => source,
+        Err(err) => {
+            let msg = format!("failed to read {path_str}: {err}");
+            return syn::Error::new(lit.span(), msg).to_compile_error().into();
+        }
+    };
+
+    match strip_bf(&source) {
+        Ok(code) => quote! {
+            {
+                // Make cargo rebuild the caller when the file changes.
+                const _: &[u8] = include_bytes!(#path_str);
+                #code
+            }
+        }
+        .into(),
+        Err((line, col, msg)) => {
+            let msg = format!("{path_str}:{line}:{col}: {msg}");
+            syn::Error::new(lit.span(), msg).to_compile_error().into()
+        }
+    }
+}
+
+/// Keeps only the Brainfuck commands of `source`, returning the position of the first unbalanced
+/// bracket on error.
+fn strip_bf(source: &str) -> Result<String, (usize, usize, &'static str)> {
+    let mut code = String::with_capacity(source.len());
+    let mut open = vec![];
+
+    for (line_idx, line) in source.lines().enumerate() {
+        for (col_idx, c) in line.chars().enumerate() {
+            match c {
+                '[' => open.push((line_idx + 1, col_idx + 1)),
+                ']' => {
+                    if open.pop().is_none() {
+                        return Err((line_idx + 1, col_idx + 1, "unmatched ']'"));
+                    }
+                }
+                c if is_bf_command(c) => {}
+                _ => continue,
+            }
+            code.push(c);
+        }
+    }
+
+    match open.pop() {
+        Some((line, col)) => Err((line, col, "unmatched '['")),
+        None => Ok(code),
+    }
+}
+
+/// Writes a Brainfuck program inline as a `&'static str`, checked at compile time.
+///
+/// The commands are read from the Rust tokens, so the program can be laid out and commented
+/// freely: identifiers, numbers and comments are ignored, though doc comments would turn into
+/// attributes. String literals are read as Brainfuck source, for text the Rust lexer would not
+/// accept. Unbalanced brackets are reported as compile
+/// errors pointing at the offending bracket.
+///
+/// ```ignore
+/// const ADD: &str = bf_derive::bf! {
+///     ,>,     // read a and b
+///     [-<+>]  // add b to a
+///     <.      // write a
+/// };
+/// assert_eq!(ADD, ",>,[-<+>]<.");
+/// ```
+#[proc_macro]
+pub fn bf(input: TokenStream) -> TokenStream {
+    let mut commands = vec![];
+    bf_commands(input.into(), &mut commands);
+
+    let mut open = vec![];
+    for &(c, span) in &commands {
+        match c {
+            '[' => open.push(span),
+            ']' if open.pop().is_none() => {
+                return syn::Error::new(span, "unmatched ']'").to_compile_error().into();
+            }
+            _ => {}
+        }
+    }
+    if let Some(span) = open.pop() {
+        return syn::Error::new(span, "unmatched '['").to_compile_error().into();
+    }
+
+    let code = commands.iter().map(|&(c, _)| c).collect::<String>();
+    quote!(#code).into()
+}
+
+/// Collects the Brainfuck commands of `tokens` along with their spans.
+fn bf_commands(tokens: proc_macro2::TokenStream, commands: &mut Vec<(char, Span)>) {
+    for token in tokens {
+        match token {
+            TokenTree::Group(group) => {
+                // Only square brackets are commands, the contents of other groups still count.
+                let is_loop = group.delimiter() == Delimiter::Bracket;
+                if is_loop {
+                    commands.push(('[', group.span_open()));
+                }
+                bf_commands(group.stream(), commands);
+                if is_loop {
+                    commands.push((']', group.span_close()));
+                }
+            }
+            TokenTree::Punct(punct) if is_bf_command(punct.as_char()) => {
+                commands.push((punct.as_char(), punct.span()));
+            }
+            TokenTree::Literal(literal) => {
+                if let Lit::Str(lit) = Lit::new(literal) {
+                    let span = lit.span();
+                    commands.extend(
+                        lit.value().chars().filter(|&c| is_bf_command(c)).map(|c| (c, span)),
+                    );
+                }
+            }
+            TokenTree::Punct(_) | TokenTree::Ident(_) => {}
+        }
+    }
+}
+
+/// Whether `c` is a Brainfuck command, including `;` which reads the private input.
+const fn is_bf_command(c: char) -> bool {
+    matches!(c, '+' | '-' | '>' | '<' | '[' | ']' | ',' | '.' | ';')
+}
+
 fn find_execution_record_path(attrs: &[syn::Attribute]) -> syn::Path {
     for attr in attrs {
         if attr.path.is_ident("execution_record_path") {
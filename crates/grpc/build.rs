@@ -0,0 +1,9 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    const PROTO: &str = "proto/bf/prover/v1/prover.proto";
+    println!("cargo:rerun-if-changed={PROTO}");
+
+    // Compile with protox rather than protoc, so building needs no system dependency.
+    let descriptors = protox::compile([PROTO], ["proto"])?;
+    tonic_build::configure().compile_fds(descriptors)?;
+    Ok(())
+}
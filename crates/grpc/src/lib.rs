@@ -0,0 +1,137 @@
+//! The protobuf messages and the gRPC service of `bf.prover.v1`, generated from
+//! `proto/bf/prover/v1/prover.proto`, with conversions from and to the SDK types.
+//!
+//! [ProverServiceServer] is implemented by provers and [ProverServiceClient] talks to them.
+
+use thiserror::Error;
+
+use bf_sdk::{
+    config, network::RemoteJobStatus, BfProofDecodeError, BfProofWithPublicValues, ProgramDigest,
+};
+
+#[allow(clippy::all)]
+mod generated {
+    tonic::include_proto!("bf.prover.v1");
+}
+
+pub use generated::*;
+pub use prover_service_client::ProverServiceClient;
+pub use prover_service_server::{ProverService, ProverServiceServer};
+
+#[derive(Error, Debug)]
+pub enum ProtoError {
+    #[error("Missing field {0}")]
+    MissingField(&'static str),
+    #[error("Invalid program digest of {0} bytes")]
+    InvalidDigest(usize),
+    #[error("Failed to decode proof: {0}")]
+    Decode(#[from] BfProofDecodeError),
+    #[error("Failed to encode proof: {0}")]
+    Encode(#[from] bincode::Error),
+}
+
+/// Unwraps a message field, which protobuf makes optional.
+pub fn required<T>(field: Option<T>, name: &'static str) -> Result<T, ProtoError> {
+    field.ok_or(ProtoError::MissingField(name))
+}
+
+impl From<ProgramDigest> for ProgramId {
+    fn from(digest: ProgramDigest) -> Self {
+        Self { digest: digest.to_vec() }
+    }
+}
+
+impl TryFrom<ProgramId> for ProgramDigest {
+    type Error = ProtoError;
+
+    fn try_from(id: ProgramId) -> Result<Self, Self::Error> {
+        let len = id.digest.len();
+        id.digest.try_into().map_err(|_| ProtoError::InvalidDigest(len))
+    }
+}
+
+impl TryFrom<&BfProofWithPublicValues> for Proof {
+    type Error = ProtoError;
+
+    fn try_from(proof: &BfProofWithPublicValues) -> Result<Self, Self::Error> {
+        let data = proof.to_bytes()?;
+        Ok(Self { data, output: proof.output.clone(), cycles: proof.metadata.cycles })
+    }
+}
+
+impl TryFrom<&Proof> for BfProofWithPublicValues {
+    type Error = ProtoError;
+
+    /// Decodes the envelope. The `output` and `cycles` of the message are informative only.
+    fn try_from(proof: &Proof) -> Result<Self, Self::Error> {
+        Ok(BfProofWithPublicValues::from_bytes(&proof.data)?)
+    }
+}
+
+impl From<config::FriPreset> for FriPreset {
+    fn from(preset: config::FriPreset) -> Self {
+        match preset {
+            config::FriPreset::Standard => FriPreset::Standard,
+            config::FriPreset::Fast => FriPreset::Fast,
+        }
+    }
+}
+
+impl FriPreset {
+    /// The SDK preset, or `None` if unspecified.
+    pub fn to_sdk(self) -> Option<config::FriPreset> {
+        match self {
+            FriPreset::Unspecified => None,
+            FriPreset::Standard => Some(config::FriPreset::Standard),
+            FriPreset::Fast => Some(config::FriPreset::Fast),
+        }
+    }
+}
+
+impl From<RemoteJobStatus> for JobStatus {
+    fn from(status: RemoteJobStatus) -> Self {
+        match status {
+            RemoteJobStatus::Queued => JobStatus::Queued,
+            RemoteJobStatus::Running => JobStatus::Running,
+            RemoteJobStatus::Succeeded => JobStatus::Succeeded,
+            RemoteJobStatus::Failed => JobStatus::Failed,
+        }
+    }
+}
+
+impl VerificationResult {
+    pub fn valid(output: Vec<u8>) -> Self {
+        Self { valid: true, output, error: String::new() }
+    }
+
+    pub fn invalid(error: impl ToString) -> Self {
+        Self { valid: false, output: Vec::new(), error: error.to_string() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prost::Message;
+
+    use super::*;
+
+    #[test]
+    fn test_program_id() {
+        let digest = bf_sdk::program_digest("++.");
+        let id = ProgramId::from(digest);
+        let decoded = ProgramId::decode(id.encode_to_vec().as_slice()).unwrap();
+        assert_eq!(ProgramDigest::try_from(decoded).unwrap(), digest);
+
+        let short = ProgramId { digest: vec![0; 31] };
+        assert!(matches!(ProgramDigest::try_from(short), Err(ProtoError::InvalidDigest(31))));
+    }
+
+    #[test]
+    fn test_fri_preset() {
+        for preset in [config::FriPreset::Standard, config::FriPreset::Fast] {
+            assert_eq!(FriPreset::from(preset).to_sdk(), Some(preset));
+        }
+        let options = ProofOptions::default();
+        assert_eq!(options.fri_preset().to_sdk(), None);
+    }
+}
@@ -0,0 +1,95 @@
+use crate::parser::{BinOp, Expr, Stmt, StmtKind, Type};
+use crate::LangError;
+
+/// Checks that every variable is declared once before use, and that the program is well typed.
+pub fn check(statements: &[Stmt]) -> Result<(), LangError> {
+    Checker { vars: vec![] }.block(statements)
+}
+
+struct Checker {
+    /// The variables in scope, innermost last.
+    vars: Vec<(String, Type)>,
+}
+
+impl Checker {
+    fn block(&mut self, statements: &[Stmt]) -> Result<(), LangError> {
+        let scope = self.vars.len();
+        for statement in statements {
+            self.statement(statement)?;
+        }
+        self.vars.truncate(scope);
+        Ok(())
+    }
+
+    fn statement(&mut self, statement: &Stmt) -> Result<(), LangError> {
+        let line = statement.line;
+        match &statement.kind {
+            StmtKind::Let(name, ty, value) => {
+                if self.var(name, line).is_ok() {
+                    return Err(LangError::new(line, format!("`{name}` is already declared")));
+                }
+                let value_ty = self.ty(value, line)?;
+                if let Some(ty) = ty {
+                    expect(*ty, value_ty, line)?;
+                }
+                self.vars.push((name.clone(), value_ty));
+            }
+            StmtKind::Assign(name, value) => {
+                let ty = self.var(name, line)?;
+                expect(ty, self.ty(value, line)?, line)?;
+            }
+            StmtKind::If(cond, then, otherwise) => {
+                expect(Type::Bool, self.ty(cond, line)?, line)?;
+                self.block(then)?;
+                self.block(otherwise)?;
+            }
+            StmtKind::While(cond, body) => {
+                expect(Type::Bool, self.ty(cond, line)?, line)?;
+                self.block(body)?;
+            }
+            StmtKind::Write(value) => expect(Type::U8, self.ty(value, line)?, line)?,
+            StmtKind::Print(_) => {}
+        }
+        Ok(())
+    }
+
+    fn var(&self, name: &str, line: usize) -> Result<Type, LangError> {
+        self.vars
+            .iter()
+            .find(|(var, _)| var == name)
+            .map(|(_, ty)| *ty)
+            .ok_or_else(|| LangError::new(line, format!("undeclared variable `{name}`")))
+    }
+
+    fn ty(&self, expr: &Expr, line: usize) -> Result<Type, LangError> {
+        Ok(match expr {
+            Expr::Int(_) | Expr::Read | Expr::ReadPrivate => Type::U8,
+            Expr::Bool(_) => Type::Bool,
+            Expr::Var(name) => self.var(name, line)?,
+            Expr::Not(expr) => {
+                expect(Type::Bool, self.ty(expr, line)?, line)?;
+                Type::Bool
+            }
+            Expr::Binary(op, lhs, rhs) => {
+                let lhs_ty = self.ty(lhs, line)?;
+                let (operand, result) = match op {
+                    BinOp::Add | BinOp::Sub | BinOp::Mul => (Type::U8, Type::U8),
+                    // Booleans compare for equality as well.
+                    BinOp::Eq | BinOp::Ne => (lhs_ty, Type::Bool),
+                    BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge => (Type::U8, Type::Bool),
+                    BinOp::And | BinOp::Or => (Type::Bool, Type::Bool),
+                };
+                expect(operand, lhs_ty, line)?;
+                expect(operand, self.ty(rhs, line)?, line)?;
+                result
+            }
+        })
+    }
+}
+
+fn expect(expected: Type, found: Type, line: usize) -> Result<(), LangError> {
+    match expected == found {
+        true => Ok(()),
+        false => Err(LangError::new(line, format!("expected `{expected}`, found `{found}`"))),
+    }
+}
@@ -0,0 +1,428 @@
+use crate::parser::{BinOp, Expr, Stmt, StmtKind};
+
+/// Generates Brainfuck for checked statements.
+///
+/// Every variable and temporary lives on its own cell, allocated as on a stack. Free cells are
+/// always zero, so a cell is cleared before it is freed, and an expression is evaluated by adding
+/// its value to a cell.
+struct Codegen {
+    code: String,
+    /// The cell the data pointer is at.
+    ptr: usize,
+    /// The first free cell.
+    free: usize,
+    /// The variables in scope and their cells, innermost last.
+    vars: Vec<(String, usize)>,
+}
+
+impl Codegen {
+    fn goto(&mut self, cell: usize) {
+        let (c, n) = match cell >= self.ptr {
+            true => ('>', cell - self.ptr),
+            false => ('<', self.ptr - cell),
+        };
+        self.code.extend(std::iter::repeat_n(c, n));
+        self.ptr = cell;
+    }
+
+    fn emit(&mut self, cell: usize, command: char) {
+        self.goto(cell);
+        self.code.push(command);
+    }
+
+    fn add(&mut self, cell: usize, value: u8) {
+        if value == 0 {
+            return;
+        }
+        self.goto(cell);
+        // Go the short way around the byte.
+        match value <= 128 {
+            true => self.code.extend(std::iter::repeat_n('+', value as usize)),
+            false => self.code.extend(std::iter::repeat_n('-', 256 - value as usize)),
+        }
+    }
+
+    fn clear(&mut self, cell: usize) {
+        self.goto(cell);
+        self.code.push_str("[-]");
+    }
+
+    /// Emits `body` in a loop on `cond`, with the pointer at `cond` at both ends.
+    fn repeat(&mut self, cond: usize, body: impl FnOnce(&mut Self)) {
+        self.emit(cond, '[');
+        body(self);
+        self.emit(cond, ']');
+    }
+
+    /// Emits `body` once if `flag`, which is 0 or 1, is set, clearing `flag`.
+    fn once(&mut self, flag: usize, body: impl FnOnce(&mut Self)) {
+        self.repeat(flag, |cg| {
+            cg.add(flag, 255);
+            body(cg);
+        });
+    }
+
+    /// Adds `src` to every cell of `dsts`, clearing `src`.
+    fn drain(&mut self, src: usize, dsts: &[usize]) {
+        self.repeat(src, |cg| {
+            cg.add(src, 255);
+            for &dst in dsts {
+                cg.add(dst, 1);
+            }
+        });
+    }
+
+    /// Subtracts `src` from `dst`, clearing `src`.
+    fn drain_sub(&mut self, src: usize, dst: usize) {
+        self.repeat(src, |cg| {
+            cg.add(src, 255);
+            cg.add(dst, 255);
+        });
+    }
+
+    /// Adds `src` to every cell of `dsts`.
+    fn copy(&mut self, src: usize, dsts: &[usize]) {
+        let tmp = self.alloc();
+        let mut dsts = dsts.to_vec();
+        dsts.push(tmp);
+        self.drain(src, &dsts);
+        self.drain(tmp, &[src]);
+        self.release(tmp);
+    }
+
+    /// Allocates a cell, which is zero.
+    fn alloc(&mut self) -> usize {
+        self.free += 1;
+        self.free - 1
+    }
+
+    /// Frees the last allocated cell, which must be zero again.
+    fn release(&mut self, cell: usize) {
+        debug_assert_eq!(cell + 1, self.free, "cells are freed in reverse order");
+        self.free -= 1;
+    }
+
+    fn var(&self, name: &str) -> usize {
+        let (_, cell) = self.vars.iter().rev().find(|(var, _)| var == name).expect("checked");
+        *cell
+    }
+
+    /// Sets the zero cell `target` to the value of `expr`.
+    fn eval_fresh(&mut self, expr: &Expr, target: usize) {
+        match expr {
+            Expr::Read => self.emit(target, ','),
+            Expr::ReadPrivate => self.emit(target, ';'),
+            _ => self.eval(expr, target),
+        }
+    }
+
+    /// Adds the value of `expr` to `target`.
+    fn eval(&mut self, expr: &Expr, target: usize) {
+        if let Some(value) = expr.constant() {
+            self.add(target, value);
+            return;
+        }
+
+        match expr {
+            Expr::Int(_) | Expr::Bool(_) => unreachable!("constant"),
+            Expr::Var(name) => {
+                let cell = self.var(name);
+                self.copy(cell, &[target]);
+            }
+            Expr::Read | Expr::ReadPrivate => {
+                let tmp = self.alloc();
+                self.eval_fresh(expr, tmp);
+                self.drain(tmp, &[target]);
+                self.release(tmp);
+            }
+            Expr::Not(expr) => {
+                let flag = self.alloc();
+                self.eval(expr, flag);
+                self.add(target, 1);
+                self.once(flag, |cg| cg.add(target, 255));
+                self.release(flag);
+            }
+            Expr::Binary(op, lhs, rhs) => self.binary(*op, lhs, rhs, target),
+        }
+    }
+
+    /// Subtracts the value of `expr` from `target`.
+    fn eval_sub(&mut self, expr: &Expr, target: usize) {
+        if let Some(value) = expr.constant() {
+            self.add(target, value.wrapping_neg());
+            return;
+        }
+        let tmp = self.alloc();
+        self.eval_fresh(expr, tmp);
+        self.drain_sub(tmp, target);
+        self.release(tmp);
+    }
+
+    fn binary(&mut self, op: BinOp, lhs: &Expr, rhs: &Expr, target: usize) {
+        match op {
+            BinOp::Add => {
+                self.eval(lhs, target);
+                self.eval(rhs, target);
+            }
+            BinOp::Sub => {
+                self.eval(lhs, target);
+                self.eval_sub(rhs, target);
+            }
+            BinOp::Mul => {
+                // Add `rhs` to the target `lhs` times.
+                let (lhs, rhs) = match lhs.constant() {
+                    Some(_) => (rhs, lhs),
+                    None => (lhs, rhs),
+                };
+                let count = self.alloc();
+                self.eval_fresh(lhs, count);
+                match rhs.constant() {
+                    Some(value) => self.repeat(count, |cg| {
+                        cg.add(count, 255);
+                        cg.add(target, value);
+                    }),
+                    None => {
+                        let value = self.alloc();
+                        self.eval_fresh(rhs, value);
+                        self.repeat(count, |cg| {
+                            cg.add(count, 255);
+                            cg.copy(value, &[target]);
+                        });
+                        self.clear(value);
+                        self.release(value);
+                    }
+                }
+                self.release(count);
+            }
+            BinOp::Eq | BinOp::Ne => {
+                // The operands are equal if their difference is zero.
+                let diff = self.alloc();
+                self.eval(lhs, diff);
+                self.eval_sub(rhs, diff);
+                let equal = op == BinOp::Eq;
+                if equal {
+                    self.add(target, 1);
+                }
+                self.repeat(diff, |cg| {
+                    cg.add(target, if equal { 255 } else { 1 });
+                    cg.clear(diff);
+                });
+                self.release(diff);
+            }
+            BinOp::Lt => self.less_than(lhs, rhs, target, false, false),
+            BinOp::Gt => self.less_than(lhs, rhs, target, true, false),
+            BinOp::Ge => self.less_than(lhs, rhs, target, false, true),
+            BinOp::Le => self.less_than(lhs, rhs, target, true, true),
+            BinOp::And => {
+                // `rhs` is only evaluated if `lhs` holds.
+                let flag = self.alloc();
+                self.eval(lhs, flag);
+                self.once(flag, |cg| cg.eval(rhs, target));
+                self.release(flag);
+            }
+            BinOp::Or => {
+                // `rhs` is only evaluated if `lhs` does not hold.
+                let flag = self.alloc();
+                let otherwise = self.alloc();
+                self.add(otherwise, 1);
+                self.eval(lhs, flag);
+                self.once(flag, |cg| {
+                    cg.add(target, 1);
+                    cg.add(otherwise, 255);
+                });
+                self.once(otherwise, |cg| cg.eval(rhs, target));
+                self.release(otherwise);
+                self.release(flag);
+            }
+        }
+    }
+
+    /// Adds `lhs < rhs`, or `rhs < lhs` if `swap`, to `target`. If `negate`, adds its negation
+    /// instead. The operands are evaluated in order either way.
+    fn less_than(&mut self, lhs: &Expr, rhs: &Expr, target: usize, swap: bool, negate: bool) {
+        if negate {
+            self.add(target, 1);
+        }
+        // `x` is followed by the two zero cells of `if_zero`.
+        let cells = [self.alloc(), self.alloc(), self.alloc(), self.alloc()];
+        let (x, y) = if swap { (cells[1], cells[0]) } else { (cells[0], cells[3]) };
+        let (lhs_cell, rhs_cell) = if swap { (y, x) } else { (x, y) };
+        self.eval_fresh(lhs, lhs_cell);
+        self.eval_fresh(rhs, rhs_cell);
+
+        // Count both down: `x < y` if `x` runs out while `y` has not.
+        self.repeat(y, |cg| {
+            cg.if_zero(x, |cg| {
+                cg.add(target, if negate { 255 } else { 1 });
+                // Leave the loop after this round.
+                cg.clear(y);
+                cg.add(y, 1);
+            });
+            cg.add(x, 255);
+            cg.add(y, 255);
+        });
+        self.clear(x);
+        for cell in cells.into_iter().rev() {
+            self.release(cell);
+        }
+    }
+
+    /// Emits `body` once if `x` is zero, in constant time. The two cells after `x` must be zero.
+    fn if_zero(&mut self, x: usize, body: impl FnOnce(&mut Self)) {
+        let (flag, landing) = (x + 1, x + 2);
+        self.add(flag, 1);
+        // If `x` is not zero, clear the flag from `x` and land on the zero cell. Otherwise run
+        // `body` from the flag, which also ends on the zero cell.
+        self.goto(x);
+        self.code.push_str("[>-]>[->");
+        self.ptr = landing;
+        body(self);
+        self.emit(landing, ']');
+    }
+
+    fn statement(&mut self, statement: &Stmt) {
+        match &statement.kind {
+            StmtKind::Let(name, _, value) => {
+                let cell = self.alloc();
+                self.eval_fresh(value, cell);
+                self.vars.push((name.clone(), cell));
+            }
+            StmtKind::Assign(name, value) => {
+                let cell = self.var(name);
+                match value {
+                    // Update the variable in place.
+                    Expr::Binary(BinOp::Add, lhs, rhs)
+                        if **lhs == Expr::Var(name.clone()) && !rhs.uses(name) =>
+                    {
+                        self.eval(rhs, cell)
+                    }
+                    Expr::Binary(BinOp::Sub, lhs, rhs)
+                        if **lhs == Expr::Var(name.clone()) && !rhs.uses(name) =>
+                    {
+                        self.eval_sub(rhs, cell)
+                    }
+                    _ if !value.uses(name) => {
+                        self.clear(cell);
+                        self.eval_fresh(value, cell);
+                    }
+                    _ => {
+                        let tmp = self.alloc();
+                        self.eval_fresh(value, tmp);
+                        self.clear(cell);
+                        self.drain(tmp, &[cell]);
+                        self.release(tmp);
+                    }
+                }
+            }
+            StmtKind::If(cond, then, otherwise) => {
+                let flag = self.alloc();
+                if otherwise.is_empty() {
+                    self.eval(cond, flag);
+                    self.once(flag, |cg| cg.block(then, false));
+                } else {
+                    let other = self.alloc();
+                    self.add(other, 1);
+                    self.eval(cond, flag);
+                    self.once(flag, |cg| {
+                        cg.add(other, 255);
+                        cg.block(then, false);
+                    });
+                    self.once(other, |cg| cg.block(otherwise, false));
+                    self.release(other);
+                }
+                self.release(flag);
+            }
+            StmtKind::While(cond, body) => match loop_var(cond) {
+                // Loop on the variable itself.
+                Some(name) => {
+                    let cell = self.var(name);
+                    self.repeat(cell, |cg| cg.block(body, false));
+                }
+                None => {
+                    let flag = self.alloc();
+                    self.eval(cond, flag);
+                    self.repeat(flag, |cg| {
+                        cg.add(flag, 255);
+                        cg.block(body, false);
+                        cg.eval(cond, flag);
+                    });
+                    self.release(flag);
+                }
+            },
+            StmtKind::Write(Expr::Var(name)) => {
+                let cell = self.var(name);
+                self.emit(cell, '.');
+            }
+            StmtKind::Write(value) => {
+                let tmp = self.alloc();
+                self.eval_fresh(value, tmp);
+                self.emit(tmp, '.');
+                self.clear(tmp);
+                self.release(tmp);
+            }
+            StmtKind::Print(bytes) => {
+                let tmp = self.alloc();
+                let mut current = 0u8;
+                for &byte in bytes {
+                    self.add(tmp, byte.wrapping_sub(current));
+                    self.emit(tmp, '.');
+                    current = byte;
+                }
+                if current != 0 {
+                    self.clear(tmp);
+                }
+                self.release(tmp);
+            }
+        }
+    }
+
+    /// Emits a block, clearing its variables at the end unless it is the whole program.
+    fn block(&mut self, statements: &[Stmt], top_level: bool) {
+        let scope = self.vars.len();
+        for statement in statements {
+            self.statement(statement);
+        }
+        while self.vars.len() > scope {
+            let (_, cell) = self.vars.pop().unwrap();
+            if !top_level {
+                self.clear(cell);
+            }
+            self.release(cell);
+        }
+    }
+}
+
+/// The variable `x` of a loop condition `x != 0`.
+fn loop_var(cond: &Expr) -> Option<&str> {
+    match cond {
+        Expr::Binary(BinOp::Ne, lhs, rhs) if rhs.constant() == Some(0) => match &**lhs {
+            Expr::Var(name) => Some(name),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Compiles checked statements to Brainfuck.
+pub fn compile_statements(statements: &[Stmt]) -> String {
+    let mut cg = Codegen { code: String::new(), ptr: 0, free: 0, vars: vec![] };
+    cg.block(statements, true);
+    optimize(&cg.code)
+}
+
+/// Cancels adjacent commands that undo each other, such as `+-` and `<>`, and drops the moves
+/// and updates after the last loop or I/O command, which cannot change the output.
+fn optimize(code: &str) -> String {
+    let mut optimized = String::with_capacity(code.len());
+    for c in code.chars() {
+        match (optimized.chars().last(), c) {
+            (Some('+'), '-') | (Some('-'), '+') | (Some('>'), '<') | (Some('<'), '>') => {
+                optimized.pop();
+            }
+            _ => optimized.push(c),
+        }
+    }
+    let len = optimized.trim_end_matches(['+', '-', '<', '>']).len();
+    optimized.truncate(len);
+    optimized
+}
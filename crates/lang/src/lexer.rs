@@ -0,0 +1,101 @@
+use crate::LangError;
+
+/// The keywords, which cannot name variables.
+const KEYWORDS: [&str; 8] = ["let", "if", "else", "while", "true", "false", "u8", "bool"];
+
+/// The punctuation, longest first so that `==` is not read as two `=`.
+const SYMBOLS: [&str; 22] = [
+    "+=", "-=", "==", "!=", "<=", ">=", "&&", "||", "(", ")", "{", "}", ";", ":", ",", "=", "+",
+    "-", "*", "<", ">", "!",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+    Ident(String),
+    /// An integer or a character literal.
+    Int(u8),
+    Str(Vec<u8>),
+    /// A keyword or a punctuation.
+    Sym(&'static str),
+}
+
+/// Splits `source` into tokens, each with its 1-based line.
+pub fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, LangError> {
+    let mut tokens = vec![];
+    let mut line = 1;
+    let mut rest = source;
+
+    while let Some(c) = rest.chars().next() {
+        if c == '\n' {
+            line += 1;
+        }
+        if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+        if rest.starts_with("//") {
+            rest = rest.find('\n').map_or("", |i| &rest[i..]);
+            continue;
+        }
+
+        let (token, len) = if c.is_ascii_alphabetic() || c == '_' {
+            let len =
+                rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(rest.len());
+            let word = &rest[..len];
+            match KEYWORDS.iter().find(|keyword| **keyword == word) {
+                Some(keyword) => (Token::Sym(keyword), len),
+                None => (Token::Ident(word.to_string()), len),
+            }
+        } else if c.is_ascii_digit() {
+            let len = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+            let digits = &rest[..len];
+            let value = digits.parse().map_err(|_| {
+                LangError::new(line, format!("integer `{digits}` does not fit in u8"))
+            })?;
+            (Token::Int(value), len)
+        } else if c == '\'' || c == '"' {
+            let (bytes, len) = quoted(rest, line)?;
+            match (c, bytes.as_slice()) {
+                ('\'', [byte]) => (Token::Int(*byte), len),
+                ('\'', _) => return Err(LangError::new(line, "invalid character literal")),
+                _ => (Token::Str(bytes), len),
+            }
+        } else {
+            let symbol = SYMBOLS
+                .iter()
+                .find(|symbol| rest.starts_with(**symbol))
+                .ok_or_else(|| LangError::new(line, format!("unexpected character {c:?}")))?;
+            (Token::Sym(symbol), symbol.len())
+        };
+        tokens.push((line, token));
+        rest = &rest[len..];
+    }
+
+    Ok(tokens)
+}
+
+/// Reads the quoted literal starting `rest`, returning its bytes and its length in the source.
+fn quoted(rest: &str, line: usize) -> Result<(Vec<u8>, usize), LangError> {
+    let quote = rest.chars().next().unwrap();
+    let mut bytes = vec![];
+    let mut chars = rest.char_indices().skip(1);
+
+    while let Some((i, c)) = chars.next() {
+        let byte = match c {
+            _ if c == quote => return Ok((bytes, i + 1)),
+            '\n' => break,
+            '\\' => match chars.next().map(|(_, c)| c) {
+                Some('n') => b'\n',
+                Some('t') => b'\t',
+                Some('0') => 0,
+                Some(c @ ('\\' | '\'' | '"')) => c as u8,
+                _ => return Err(LangError::new(line, "invalid escape")),
+            },
+            _ if c.is_ascii() => c as u8,
+            _ => return Err(LangError::new(line, "non-ASCII character in literal")),
+        };
+        bytes.push(byte);
+    }
+
+    Err(LangError::new(line, "unterminated literal"))
+}
@@ -0,0 +1,184 @@
+//! A tiny typed language that compiles to Brainfuck.
+//!
+//! Unlike `bf-asm`, programs are made of expressions, so realistic guests can be written without
+//! managing cells by hand. `//` starts a comment.
+//!
+//! ```text
+//! let n: u8 = read();           // variables are `u8` or `bool`, the type may be inferred
+//! let secret = read_private();  // reads the private input
+//! let a = 0;
+//! let b = 1;
+//! while n != 0 {                // conditions are `bool`
+//!     let t = a + b;            // arithmetic wraps modulo 256: + - *
+//!     a = b;
+//!     b = t;
+//!     n -= 1;                   // also +=
+//! }
+//! if a >= 'A' && !(a == b) {    // comparisons: == != < <= > >=, logic: && || !
+//!     write(a);                 // writes a byte
+//! } else if secret < 3 {
+//!     print("small\n");         // writes a string
+//! } else {
+//!     write(a * 2);
+//! }
+//! ```
+//!
+//! Variables declared in a block go out of scope at its end, and names cannot be shadowed.
+//! `&&` and `||` short-circuit. Constant expressions are folded, and updates such as `n -= 1`
+//! are compiled in place.
+
+mod check;
+mod codegen;
+mod lexer;
+mod parser;
+
+pub use parser::{parse, BinOp, Expr, Stmt, StmtKind, Type};
+
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("line {line}: {message}")]
+pub struct LangError {
+    /// The 1-based line of the error.
+    pub line: usize,
+    pub message: String,
+}
+
+impl LangError {
+    pub(crate) fn new(line: usize, message: impl Into<String>) -> Self {
+        Self { line, message: message.into() }
+    }
+}
+
+/// Compiles a program to Brainfuck.
+pub fn compile(source: &str) -> Result<String, LangError> {
+    let statements = parse(source)?;
+    check::check(&statements)?;
+    Ok(codegen::compile_statements(&statements))
+}
+
+#[cfg(test)]
+mod tests {
+    use bf_core_executor::{Executor, Program};
+
+    use super::*;
+
+    fn run(source: &str, input: Vec<u8>) -> Vec<u8> {
+        let code = compile(source).unwrap();
+        let mut runtime = Executor::new(Program::from(&code).unwrap(), input);
+        runtime.run().unwrap();
+        runtime.state.output_stream
+    }
+
+    #[test]
+    fn test_fibonacci() {
+        let source = "
+            let n: u8 = read();
+            let a = 0;
+            let b = 1;
+            while n != 0 {
+                let t = a + b;
+                a = b;
+                b = t;
+                n -= 1;
+            }
+            write(a);
+        ";
+        assert_eq!(run(source, vec![10]), vec![55]);
+        assert_eq!(run(source, vec![17]), vec![(1597 % 256) as u8]);
+    }
+
+    #[test]
+    fn test_operators() {
+        let source = "
+            let a = read();
+            let b = read();
+            write(a * b);
+            write(a - b);
+            write(b * 3 + 1);
+            let lt: bool = a < b;
+            if lt { write(1); } else { write(0); }
+            if a <= b { write(1); } else { write(0); }
+            if a > b { write(1); } else { write(0); }
+            if a >= b { write(1); } else { write(0); }
+            if a == b || !(a != b) { write(1); } else { write(0); }
+            if a != 0 && b * 2 == 4 { write(1); } else { write(0); }
+        ";
+        assert_eq!(run(source, vec![3, 2]), [6, 1, 7, 0, 0, 1, 1, 0, 1]);
+        assert_eq!(run(source, vec![2, 3]), [6, 255, 10, 1, 1, 0, 0, 0, 0]);
+        assert_eq!(run(source, vec![3, 3]), [9, 0, 10, 0, 1, 0, 1, 1, 0]);
+        assert_eq!(run(source, vec![0, 2]), [0, 254, 7, 1, 1, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_comparisons() {
+        let source = "
+            let a = read();
+            let b = read();
+            if a < b { write(1); } else { write(0); }
+            if a <= b { write(1); } else { write(0); }
+            if a > b { write(1); } else { write(0); }
+            if a >= b { write(1); } else { write(0); }
+        ";
+        let code = compile(source).unwrap();
+        let values = [0, 1, 2, 10, 127, 128, 254, 255];
+        for a in values {
+            for b in values {
+                let mut runtime = Executor::new(Program::from(&code).unwrap(), vec![a, b]);
+                runtime.run().unwrap();
+                let expected = [a < b, a <= b, a > b, a >= b].map(u8::from);
+                assert_eq!(runtime.state.output_stream, expected, "{a} vs {b}");
+
+                // Each step of the countdown takes a constant number of cycles, so a comparison is
+                // linear in its operands rather than in their product.
+                let bound = 2048 + 512 * (u64::from(a) + u64::from(b));
+                assert!(runtime.state.global_clk < bound, "{a} vs {b}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_io() {
+        let source = r#"
+            let secret = read_private();
+            let guess = read();
+            if guess == secret {
+                print("yes\n");
+            } else if guess < secret {
+                print("higher\n");
+            } else {
+                print("lower\n");
+            }
+        "#;
+        let code = compile(source).unwrap();
+        let run = |guess: u8| {
+            let program = Program::from(&code).unwrap();
+            let mut runtime = Executor::new_with_private_input(program, vec![guess], vec![42]);
+            runtime.run().unwrap();
+            String::from_utf8(runtime.state.output_stream).unwrap()
+        };
+        assert_eq!(run(42), "yes\n");
+        assert_eq!(run(7), "higher\n");
+        assert_eq!(run(200), "lower\n");
+    }
+
+    #[test]
+    fn test_optimized() {
+        // Constants are folded and updates happen in place.
+        assert_eq!(compile("let x = 2 * 3 + 1; x += 1; x -= 2; write(x);").unwrap(), "++++++.");
+        assert_eq!(compile("let x = read(); while x != 0 { x -= 1; }").unwrap(), ",[-]");
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(compile("x = 1;").unwrap_err().line, 1);
+        assert_eq!(compile("let x = 1;\nwhile x { }").unwrap_err().line, 2);
+        assert_eq!(compile("let x = 1;\nif x == 1 {").unwrap_err().line, 2);
+        assert_eq!(compile("let x = 256;").unwrap_err().line, 1);
+        assert_eq!(compile("let x = 1;\n\nlet x = 2;").unwrap_err().line, 3);
+        assert_eq!(compile("let b: bool = 1;").unwrap_err().message, "expected `bool`, found `u8`");
+        assert!(compile("let x = true + 1;").is_err());
+        assert!(compile("let x = 1; { let y = x; }").is_err());
+        assert!(compile("if true { let y = 1; } write(y);").is_err());
+    }
+}
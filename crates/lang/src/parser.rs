@@ -0,0 +1,315 @@
+use std::fmt::{Display, Formatter};
+
+use crate::lexer::{tokenize, Token};
+use crate::LangError;
+
+/// The type of a variable or an expression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Type {
+    /// A byte, whose arithmetic wraps modulo 256.
+    U8,
+    /// A boolean, stored as 0 or 1.
+    Bool,
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::U8 => write!(f, "u8"),
+            Type::Bool => write!(f, "bool"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Int(u8),
+    Bool(bool),
+    Var(String),
+    /// The next byte of stdin.
+    Read,
+    /// The next byte of the private input.
+    ReadPrivate,
+    Not(Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    /// The value of the expression if it does not depend on variables or input, booleans being
+    /// 0 or 1.
+    pub fn constant(&self) -> Option<u8> {
+        match self {
+            Expr::Int(value) => Some(*value),
+            Expr::Bool(value) => Some(*value as u8),
+            Expr::Var(_) | Expr::Read | Expr::ReadPrivate => None,
+            Expr::Not(expr) => Some((expr.constant()? == 0) as u8),
+            Expr::Binary(op, lhs, rhs) => {
+                let (a, b) = (lhs.constant()?, rhs.constant()?);
+                Some(match op {
+                    BinOp::Add => a.wrapping_add(b),
+                    BinOp::Sub => a.wrapping_sub(b),
+                    BinOp::Mul => a.wrapping_mul(b),
+                    BinOp::Eq => (a == b) as u8,
+                    BinOp::Ne => (a != b) as u8,
+                    BinOp::Lt => (a < b) as u8,
+                    BinOp::Le => (a <= b) as u8,
+                    BinOp::Gt => (a > b) as u8,
+                    BinOp::Ge => (a >= b) as u8,
+                    BinOp::And => a & b,
+                    BinOp::Or => a | b,
+                })
+            }
+        }
+    }
+
+    /// Whether the expression reads the variable `name`.
+    pub fn uses(&self, name: &str) -> bool {
+        match self {
+            Expr::Var(var) => var == name,
+            Expr::Int(_) | Expr::Bool(_) | Expr::Read | Expr::ReadPrivate => false,
+            Expr::Not(expr) => expr.uses(name),
+            Expr::Binary(_, lhs, rhs) => lhs.uses(name) || rhs.uses(name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stmt {
+    /// The 1-based line the statement starts on.
+    pub line: usize,
+    pub kind: StmtKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StmtKind {
+    /// Declares a variable, with an optional type annotation.
+    Let(String, Option<Type>, Expr),
+    /// Assigns a variable. `x += e` and `x -= e` are parsed as `x = x + e` and `x = x - e`.
+    Assign(String, Expr),
+    If(Expr, Vec<Stmt>, Vec<Stmt>),
+    While(Expr, Vec<Stmt>),
+    /// Writes a byte to stdout.
+    Write(Expr),
+    /// Writes a string literal to stdout.
+    Print(Vec<u8>),
+}
+
+/// The binary operators by increasing precedence.
+const PRECEDENCE: [&[(&str, BinOp)]; 5] = [
+    &[("||", BinOp::Or)],
+    &[("&&", BinOp::And)],
+    &[
+        ("==", BinOp::Eq),
+        ("!=", BinOp::Ne),
+        ("<=", BinOp::Le),
+        (">=", BinOp::Ge),
+        ("<", BinOp::Lt),
+        (">", BinOp::Gt),
+    ],
+    &[("+", BinOp::Add), ("-", BinOp::Sub)],
+    &[("*", BinOp::Mul)],
+];
+
+/// Parses a program into its statements.
+pub fn parse(source: &str) -> Result<Vec<Stmt>, LangError> {
+    let mut parser = Parser { tokens: tokenize(source)?, pos: 0 };
+    let mut statements = vec![];
+    while parser.peek().is_some() {
+        statements.push(parser.statement()?);
+    }
+    Ok(statements)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+}
+
+impl Parser {
+    /// The line of the next token, or of the last one at the end of the input.
+    fn line(&self) -> usize {
+        self.tokens.get(self.pos).or(self.tokens.last()).map_or(1, |(line, _)| *line)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, token)| token)
+    }
+
+    fn next(&mut self) -> Result<Token, LangError> {
+        let token = self.peek().cloned();
+        self.pos += 1;
+        token.ok_or_else(|| LangError::new(self.line(), "unexpected end of input"))
+    }
+
+    /// Whether the next token is the symbol `sym`.
+    fn is(&self, sym: &str) -> bool {
+        matches!(self.peek(), Some(Token::Sym(s)) if *s == sym)
+    }
+
+    /// Consumes the symbol `sym` if it is next.
+    fn eat(&mut self, sym: &str) -> bool {
+        let is = self.is(sym);
+        self.pos += is as usize;
+        is
+    }
+
+    fn expect(&mut self, sym: &str) -> Result<(), LangError> {
+        match self.eat(sym) {
+            true => Ok(()),
+            false => Err(LangError::new(self.line(), format!("expected `{sym}`"))),
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, LangError> {
+        let line = self.line();
+        match self.next()? {
+            Token::Ident(name) => Ok(name),
+            _ => Err(LangError::new(line, "expected a name")),
+        }
+    }
+
+    fn ty(&mut self) -> Result<Type, LangError> {
+        let line = self.line();
+        match self.next()? {
+            Token::Sym("u8") => Ok(Type::U8),
+            Token::Sym("bool") => Ok(Type::Bool),
+            _ => Err(LangError::new(line, "expected a type")),
+        }
+    }
+
+    fn block(&mut self) -> Result<Vec<Stmt>, LangError> {
+        let line = self.line();
+        self.expect("{")?;
+        let mut statements = vec![];
+        while !self.eat("}") {
+            if self.peek().is_none() {
+                return Err(LangError::new(line, "unclosed block"));
+            }
+            statements.push(self.statement()?);
+        }
+        Ok(statements)
+    }
+
+    fn statement(&mut self) -> Result<Stmt, LangError> {
+        let line = self.line();
+        let kind = if self.eat("let") {
+            let name = self.ident()?;
+            let ty = if self.eat(":") { Some(self.ty()?) } else { None };
+            self.expect("=")?;
+            let value = self.expr()?;
+            self.expect(";")?;
+            StmtKind::Let(name, ty, value)
+        } else if self.eat("if") {
+            self.if_else()?
+        } else if self.eat("while") {
+            let cond = self.expr()?;
+            StmtKind::While(cond, self.block()?)
+        } else {
+            let name = self.ident()?;
+            let kind = if self.eat("(") {
+                let kind = match name.as_str() {
+                    "write" => StmtKind::Write(self.expr()?),
+                    "print" => match self.next()? {
+                        Token::Str(bytes) => StmtKind::Print(bytes),
+                        _ => return Err(LangError::new(line, "`print` expects a string")),
+                    },
+                    _ => return Err(LangError::new(line, format!("unknown statement `{name}`"))),
+                };
+                self.expect(")")?;
+                kind
+            } else if self.eat("=") {
+                StmtKind::Assign(name, self.expr()?)
+            } else if let Some(op) = [("+=", BinOp::Add), ("-=", BinOp::Sub)]
+                .into_iter()
+                .find_map(|(sym, op)| self.eat(sym).then_some(op))
+            {
+                let value =
+                    Expr::Binary(op, Box::new(Expr::Var(name.clone())), self.expr()?.into());
+                StmtKind::Assign(name, value)
+            } else {
+                return Err(LangError::new(line, "expected an assignment"));
+            };
+            self.expect(";")?;
+            kind
+        };
+        Ok(Stmt { line, kind })
+    }
+
+    /// Parses an `if` after its keyword, with `else if` chains nested in the `else` branch.
+    fn if_else(&mut self) -> Result<StmtKind, LangError> {
+        let cond = self.expr()?;
+        let then = self.block()?;
+        let otherwise = match self.eat("else") {
+            true if self.is("if") => {
+                let line = self.line();
+                self.pos += 1;
+                vec![Stmt { line, kind: self.if_else()? }]
+            }
+            true => self.block()?,
+            false => vec![],
+        };
+        Ok(StmtKind::If(cond, then, otherwise))
+    }
+
+    fn expr(&mut self) -> Result<Expr, LangError> {
+        self.binary(0)
+    }
+
+    /// Parses the binary operators of precedence `level` and above, left-associatively.
+    fn binary(&mut self, level: usize) -> Result<Expr, LangError> {
+        if level == PRECEDENCE.len() {
+            return self.unary();
+        }
+        let mut lhs = self.binary(level + 1)?;
+        while let Some(&(_, op)) = PRECEDENCE[level].iter().find(|(sym, _)| self.is(sym)) {
+            self.pos += 1;
+            let rhs = self.binary(level + 1)?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr, LangError> {
+        if self.eat("!") {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+
+        let line = self.line();
+        match self.next()? {
+            Token::Int(value) => Ok(Expr::Int(value)),
+            Token::Sym("true") => Ok(Expr::Bool(true)),
+            Token::Sym("false") => Ok(Expr::Bool(false)),
+            Token::Sym("(") => {
+                let expr = self.expr()?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            Token::Ident(name) if self.eat("(") => {
+                let expr = match name.as_str() {
+                    "read" => Expr::Read,
+                    "read_private" => Expr::ReadPrivate,
+                    _ => return Err(LangError::new(line, format!("unknown function `{name}`"))),
+                };
+                self.expect(")")?;
+                Ok(expr)
+            }
+            Token::Ident(name) => Ok(Expr::Var(name)),
+            _ => Err(LangError::new(line, "expected an expression")),
+        }
+    }
+}
@@ -0,0 +1,36 @@
+import assert from "node:assert/strict";
+import { createRequire } from "node:module";
+import test from "node:test";
+
+const { execute, prove, verify } = createRequire(import.meta.url)("../index.js");
+
+test("execute", async () => {
+  const { output, cycles } = await execute(",[->+<]>.", Buffer.from([3]));
+  assert.deepEqual([...output], [3]);
+  assert.ok(cycles > 0);
+});
+
+test("execute with coverage", async () => {
+  const { coverage } = await execute(",[->+<]>.", Buffer.from([3]), true);
+  assert.ok(coverage.hits.every((hits) => hits > 0));
+  assert.deepEqual(coverage.offsets, [0, 1, 2, 3, 4, 5, 6, 7, 8]);
+  // The loop is entered, so `[` never jumps.
+  assert.equal(coverage.branches, 4);
+  assert.equal(coverage.coveredBranches, 3);
+  assert.match(coverage.lcov, /^TN:\nSF:program.bf\nDA:1,3\n/);
+
+  assert.equal((await execute("+.", Buffer.alloc(0))).coverage, undefined);
+});
+
+test("prove and verify", async () => {
+  const { proof, vk, output } = await prove("++.", Buffer.alloc(0));
+  assert.deepEqual([...output], [2]);
+
+  const report = await verify(proof, vk);
+  assert.equal(report.valid, true, report.error);
+  assert.deepEqual([...report.output], [2]);
+
+  // A proof does not verify against the key of another program.
+  const other = await prove("+++.", Buffer.alloc(0));
+  assert.equal((await verify(proof, other.vk)).valid, false);
+});
@@ -0,0 +1,180 @@
+//! Node.js bindings of the zkVM.
+//!
+//! Every function returns a promise and runs on the libuv thread pool, so proving does not block
+//! the event loop. Build the addon with `npm run build`, which also generates `index.d.ts`:
+//!
+//! ```ts
+//! import { execute, prove, verify } from "@bf-zkvm/node";
+//!
+//! const { output, cycles } = await execute(",[->+<]>.", Buffer.from([3]));
+//! const { coverage } = await execute(",[->+<]>.", Buffer.from([3]), true);
+//! const { proof, vk } = await prove(",[->+<]>.", Buffer.from([3]));
+//! const { valid, error } = await verify(proof, vk);
+//! ```
+
+use napi::{bindgen_prelude::*, Env, Task};
+use napi_derive::napi;
+
+use bf_core_executor::{source_positions, Coverage, Executor, Program};
+use bf_sdk::{BfProofWithPublicValues, BfVerifyingKey, ProverClient};
+
+fn to_napi_error(e: impl ToString) -> Error {
+    Error::from_reason(e.to_string())
+}
+
+/// The result of executing a program.
+#[napi(object)]
+pub struct ExecutionReport {
+    pub output: Buffer,
+    pub cycles: i64,
+    /// The coverage of the execution, if it was requested.
+    pub coverage: Option<CoverageReport>,
+}
+
+/// The instructions and loop branches an execution covered.
+#[napi(object)]
+pub struct CoverageReport {
+    /// The number of times each instruction was executed, indexed by pc.
+    pub hits: Vec<i64>,
+    /// The offset of each instruction in the program source, in characters, indexed by pc.
+    pub offsets: Vec<u32>,
+    /// The number of loop branches, two per `[` or `]`.
+    pub branches: u32,
+    /// The number of loop branches taken at least once.
+    pub covered_branches: u32,
+    /// The coverage in the lcov format, with `program.bf` as the source file.
+    pub lcov: String,
+}
+
+/// A proof and the verifying key to check it with.
+#[napi(object)]
+pub struct ProofReport {
+    /// The proof, encoded with `BfProofWithPublicValues::to_bytes`.
+    pub proof: Buffer,
+    /// The bincode-serialized verifying key.
+    pub vk: Buffer,
+    pub output: Buffer,
+    pub cycles: i64,
+}
+
+/// The outcome of a verification. Invalid proofs resolve with `valid: false` instead of
+/// rejecting, so that callers can tell them apart from malformed inputs.
+#[napi(object)]
+pub struct VerificationReport {
+    pub valid: bool,
+    /// The output committed by the proof, if it is valid.
+    pub output: Option<Buffer>,
+    pub error: Option<String>,
+}
+
+pub struct ExecuteTask {
+    program: String,
+    stdin: Vec<u8>,
+    coverage: bool,
+}
+
+impl Task for ExecuteTask {
+    type Output = (Vec<u8>, u64, Option<Coverage>);
+    type JsValue = ExecutionReport;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        let program = Program::from(&self.program).map_err(to_napi_error)?;
+        let mut runtime = Executor::new(program, self.stdin.clone());
+        runtime.run().map_err(to_napi_error)?;
+        let coverage = self.coverage.then(|| Coverage::from_record(&runtime.record));
+        Ok((runtime.state.output_stream, runtime.state.global_clk, coverage))
+    }
+
+    fn resolve(
+        &mut self,
+        _env: Env,
+        (output, cycles, coverage): Self::Output,
+    ) -> Result<Self::JsValue> {
+        let coverage = coverage.map(|coverage| {
+            let positions = source_positions(&self.program);
+            let (branches, covered_branches) = coverage.branches();
+            CoverageReport {
+                hits: coverage.pcs.iter().map(|pc| pc.hits as i64).collect(),
+                offsets: positions.iter().map(|position| position.offset as u32).collect(),
+                branches: branches as u32,
+                covered_branches: covered_branches as u32,
+                lcov: coverage.to_lcov("program.bf", &positions),
+            }
+        });
+        Ok(ExecutionReport { output: output.into(), cycles: cycles as i64, coverage })
+    }
+}
+
+pub struct ProveTask {
+    program: String,
+    stdin: Vec<u8>,
+}
+
+impl Task for ProveTask {
+    type Output = (BfProofWithPublicValues, Vec<u8>);
+    type JsValue = ProofReport;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        let client = ProverClient::from_env();
+        let (pk, vk) = client.setup(&self.program);
+        let proof = client.prove(&pk, self.stdin.clone()).run().map_err(to_napi_error)?;
+        let vk = bincode::serialize(&vk).map_err(to_napi_error)?;
+        Ok((proof, vk))
+    }
+
+    fn resolve(&mut self, _env: Env, (proof, vk): Self::Output) -> Result<Self::JsValue> {
+        Ok(ProofReport {
+            proof: proof.to_bytes().map_err(to_napi_error)?.into(),
+            vk: vk.into(),
+            output: proof.output.clone().into(),
+            cycles: proof.metadata.cycles as i64,
+        })
+    }
+}
+
+pub struct VerifyTask {
+    proof: Vec<u8>,
+    vk: Vec<u8>,
+}
+
+impl Task for VerifyTask {
+    type Output = std::result::Result<Vec<u8>, String>;
+    type JsValue = VerificationReport;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        let vk: BfVerifyingKey = bincode::deserialize(&self.vk).map_err(to_napi_error)?;
+        let proof = BfProofWithPublicValues::from_bytes(&self.proof).map_err(to_napi_error)?;
+        Ok(bf_sdk::verify_proof(&proof, &vk).map(|()| proof.output).map_err(|e| e.to_string()))
+    }
+
+    fn resolve(&mut self, _env: Env, result: Self::Output) -> Result<Self::JsValue> {
+        Ok(match result {
+            Ok(output) => {
+                VerificationReport { valid: true, output: Some(output.into()), error: None }
+            }
+            Err(error) => VerificationReport { valid: false, output: None, error: Some(error) },
+        })
+    }
+}
+
+/// Executes a program on `stdin` without proving it, and reports the coverage of the execution
+/// if `coverage` is set.
+#[napi(ts_return_type = "Promise<ExecutionReport>")]
+pub fn execute(program: String, stdin: Buffer, coverage: Option<bool>) -> AsyncTask<ExecuteTask> {
+    AsyncTask::new(ExecuteTask { program, stdin: stdin.to_vec(), coverage: coverage == Some(true) })
+}
+
+/// Sets up, executes and proves a program on `stdin`.
+///
+/// The prover is configured from the `BF_*` environment variables, like
+/// `ProverClient::from_env`.
+#[napi(ts_return_type = "Promise<ProofReport>")]
+pub fn prove(program: String, stdin: Buffer) -> AsyncTask<ProveTask> {
+    AsyncTask::new(ProveTask { program, stdin: stdin.to_vec() })
+}
+
+/// Verifies a proof returned by `prove` against its verifying key.
+#[napi(ts_return_type = "Promise<VerificationReport>")]
+pub fn verify(proof: Buffer, vk: Buffer) -> AsyncTask<VerifyTask> {
+    AsyncTask::new(VerifyTask { proof: proof.to_vec(), vk: vk.to_vec() })
+}
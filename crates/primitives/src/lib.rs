@@ -4,6 +4,7 @@ use p3_koala_bear::{KoalaBear, Poseidon2KoalaBear};
 use p3_poseidon2::{ExternalLayerConstants, Poseidon2};
 
 pub mod consts;
+pub mod poseidon2;
 
 lazy_static! {
     // These constants are created by a RNG.
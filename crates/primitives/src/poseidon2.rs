@@ -0,0 +1,155 @@
+//! The rounds of the permutation of [`poseidon2_init`](crate::poseidon2_init), written over any
+//! [`FieldAlgebra`], so that an AIR can evaluate them on its columns as the executor evaluates
+//! them on field elements.
+
+use p3_field::{Field, FieldAlgebra};
+
+use crate::RC_16_30_U32;
+
+/// The number of elements of the state.
+pub const WIDTH: usize = 16;
+
+/// The number of external (full) rounds, half of them before the internal rounds.
+pub const ROUNDS_F: usize = 8;
+
+/// The number of internal (partial) rounds.
+pub const ROUNDS_P: usize = 13;
+
+/// The diagonal `V` of the internal matrix `1 + Diag(V)`, as a signed numerator and the base 2
+/// logarithm of the denominator of each element.
+const INTERNAL_DIAGONAL: [(i32, u32); WIDTH] = [
+    (-2, 0),
+    (1, 0),
+    (2, 0),
+    (1, 1),
+    (3, 0),
+    (4, 0),
+    (-1, 1),
+    (-3, 0),
+    (-4, 0),
+    (1, 8),
+    (1, 3),
+    (1, 24),
+    (-1, 8),
+    (-1, 3),
+    (-1, 4),
+    (-1, 24),
+];
+
+/// The S-box of the permutation, `x^3`.
+pub fn sbox<R: FieldAlgebra>(x: R) -> R {
+    x.cube()
+}
+
+/// The round constants of the external round `round`, the first `ROUNDS_F / 2` of which come
+/// before the internal rounds.
+pub fn external_constants<R: FieldAlgebra>(round: usize) -> [R; WIDTH] {
+    let row = if round < ROUNDS_F / 2 { round } else { round + ROUNDS_P };
+    RC_16_30_U32[row].map(R::from_wrapped_u32)
+}
+
+/// The round constant of the internal round `round`.
+pub fn internal_constant<R: FieldAlgebra>(round: usize) -> R {
+    R::from_wrapped_u32(RC_16_30_U32[ROUNDS_F / 2 + round][0])
+}
+
+/// Multiplies the state by the external matrix: `M_4` on each chunk of four elements, then the
+/// circulant matrix `(2 M_4, M_4, M_4, M_4)` over the chunks.
+pub fn external_linear_layer<R: FieldAlgebra>(state: &mut [R; WIDTH]) {
+    for chunk in state.chunks_exact_mut(4) {
+        let [x0, x1, x2, x3] = [0, 1, 2, 3].map(|i| chunk[i].clone());
+        let t01 = x0.clone() + x1.clone();
+        let t23 = x2.clone() + x3.clone();
+        let t0123 = t01.clone() + t23.clone();
+        let t01123 = t0123.clone() + x1;
+        let t01233 = t0123 + x3;
+        chunk[0] = t01123.clone() + t01;
+        chunk[1] = t01123 + x2.double();
+        chunk[2] = t01233.clone() + t23;
+        chunk[3] = t01233 + x0.double();
+    }
+
+    let sums: [R; 4] =
+        core::array::from_fn(|k| (k..WIDTH).step_by(4).map(|i| state[i].clone()).sum());
+    for (i, x) in state.iter_mut().enumerate() {
+        *x += sums[i % 4].clone();
+    }
+}
+
+/// The diagonal `V` of the internal matrix `1 + Diag(V)`, computed once for the internal rounds
+/// of a permutation.
+pub fn internal_diagonal<F: Field>() -> [F; WIDTH] {
+    let half = F::TWO.inverse();
+    INTERNAL_DIAGONAL.map(|(numerator, log_denominator)| {
+        let v =
+            F::from_canonical_u32(numerator.unsigned_abs()) * half.exp_u64(log_denominator.into());
+        if numerator < 0 {
+            -v
+        } else {
+            v
+        }
+    })
+}
+
+/// Multiplies the state by the internal matrix `1 + Diag(V)`, `V` being `diagonal`.
+pub fn internal_linear_layer<R: FieldAlgebra>(state: &mut [R; WIDTH], diagonal: &[R::F; WIDTH]) {
+    let sum: R = state.iter().cloned().sum();
+    for (x, &v) in state.iter_mut().zip(diagonal) {
+        *x = sum.clone() + x.clone() * R::from_f(v);
+    }
+}
+
+/// Applies the external round `round`: the constants, the S-boxes and the external matrix.
+pub fn external_round<R: FieldAlgebra>(state: &mut [R; WIDTH], round: usize) {
+    for (x, constant) in state.iter_mut().zip(external_constants::<R>(round)) {
+        *x = sbox(x.clone() + constant);
+    }
+    external_linear_layer(state);
+}
+
+/// Applies the internal round `round`: the constant and the S-box on the first element, and the
+/// internal matrix of [`internal_diagonal`].
+pub fn internal_round<R: FieldAlgebra>(
+    state: &mut [R; WIDTH],
+    round: usize,
+    diagonal: &[R::F; WIDTH],
+) {
+    state[0] = sbox(state[0].clone() + internal_constant::<R>(round));
+    internal_linear_layer(state, diagonal);
+}
+
+/// Applies the permutation to `state`.
+pub fn permute<R: FieldAlgebra>(state: &mut [R; WIDTH]) {
+    external_linear_layer(state);
+    for round in 0..ROUNDS_F / 2 {
+        external_round(state, round);
+    }
+    let diagonal = internal_diagonal();
+    for round in 0..ROUNDS_P {
+        internal_round(state, round, &diagonal);
+    }
+    for round in ROUNDS_F / 2..ROUNDS_F {
+        external_round(state, round);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_koala_bear::KoalaBear;
+    use p3_symmetric::Permutation;
+
+    use super::*;
+    use crate::poseidon2_init;
+
+    #[test]
+    fn test_permute() {
+        let perm = poseidon2_init();
+        for seed in [0, 1, 12345] {
+            let input: [KoalaBear; WIDTH] =
+                core::array::from_fn(|i| KoalaBear::from_canonical_u32(seed * 31 + i as u32));
+            let mut state = input;
+            permute(&mut state);
+            assert_eq!(state, perm.permute(input));
+        }
+    }
+}
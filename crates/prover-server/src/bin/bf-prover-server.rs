@@ -0,0 +1,75 @@
+use std::{net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
+
+use anyhow::Result;
+use clap::Parser;
+
+use bf_prover_server::{grpc::GrpcProver, router, ProverServer};
+use bf_sdk::{utils::setup_logger, ProverBackend, ProverClient, ProverClientConfig};
+
+/// Serves proof generation over JSON-RPC.
+#[derive(Parser)]
+#[command(name = "bf-prover-server", author, version, about, long_about = None)]
+struct Args {
+    /// The directory persisting programs, jobs and proofs.
+    #[arg(long, env = "BF_PROVER_JOBS", default_value = "jobs")]
+    jobs_dir: PathBuf,
+
+    /// The address to listen on.
+    #[arg(long, env = "BF_PROVER_ADDR", default_value = "127.0.0.1:3000")]
+    addr: SocketAddr,
+
+    /// The address to serve the gRPC `bf.prover.v1.ProverService` on, if any.
+    #[arg(long, env = "BF_PROVER_GRPC_ADDR")]
+    grpc_addr: Option<SocketAddr>,
+
+    /// The number of proofs generated concurrently.
+    #[arg(long, default_value_t = 1)]
+    workers: usize,
+
+    /// The number of jobs waiting for a worker before `prove` is rejected.
+    #[arg(long, default_value_t = 64)]
+    queue_capacity: usize,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    setup_logger();
+    let args = Args::parse();
+
+    // The server always proves locally, whatever `BF_PROVER` selects for its clients.
+    let config =
+        ProverClientConfig { backend: ProverBackend::Cpu, ..ProverClientConfig::from_env()? };
+    let client = Arc::new(ProverClient::from_config(config)?);
+    let server =
+        Arc::new(ProverServer::open(client, &args.jobs_dir, args.workers, args.queue_capacity)?);
+
+    // Persist the results of finished jobs even if nobody asks for them.
+    let reaper = server.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(1));
+        loop {
+            interval.tick().await;
+            reaper.reap();
+        }
+    });
+
+    if let Some(addr) = args.grpc_addr {
+        tracing::info!("serving gRPC on {addr}");
+        let service = GrpcProver::new(server.clone()).into_service();
+        let grpc = tonic::transport::Server::builder().add_service(service).serve(addr);
+        tokio::spawn(async move {
+            if let Err(e) = grpc.await {
+                tracing::error!("gRPC server failed: {e}");
+            }
+        });
+    }
+
+    tracing::info!("serving proofs on {}", args.addr);
+    let listener = tokio::net::TcpListener::bind(args.addr).await?;
+    axum::serve(listener, router(server))
+        .with_graceful_shutdown(async {
+            tokio::signal::ctrl_c().await.ok();
+        })
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,197 @@
+//! The `bf.prover.v1.ProverService` gRPC service, backed by the same [ProverServer] as the
+//! JSON-RPC routes.
+
+use std::sync::Arc;
+
+use tonic::{Code, Request, Response, Status};
+
+use bf_grpc::{
+    required, GetProofRequest, GetProofResponse, GetStatusRequest, GetStatusResponse, JobStatus,
+    Proof, ProtoError, ProveRequest, ProveResponse, ProverService, ProverServiceServer,
+    SetupRequest, SetupResponse, VerificationResult, VerifyRequest, VerifyResponse,
+};
+use bf_sdk::{network::error_code, network::RpcError, BfProofWithPublicValues, ProgramDigest};
+
+use crate::ProverServer;
+
+pub struct GrpcProver {
+    server: Arc<ProverServer>,
+}
+
+impl GrpcProver {
+    pub fn new(server: Arc<ProverServer>) -> Self {
+        Self { server }
+    }
+
+    pub fn into_service(self) -> ProverServiceServer<Self> {
+        ProverServiceServer::new(self)
+    }
+
+    /// Runs `f` on the blocking pool, since setup and verification are CPU bound.
+    async fn blocking<T, F>(&self, f: F) -> Result<T, Status>
+    where
+        T: Send + 'static,
+        F: FnOnce(&ProverServer) -> Result<T, RpcError> + Send + 'static,
+    {
+        let server = self.server.clone();
+        tokio::task::spawn_blocking(move || f(&server))
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?
+            .map_err(to_status)
+    }
+}
+
+fn to_status(error: RpcError) -> Status {
+    let code = match error.code {
+        error_code::INVALID_PARAMS => Code::InvalidArgument,
+        error_code::UNKNOWN_PROGRAM | error_code::UNKNOWN_JOB => Code::NotFound,
+        error_code::PROOF_UNAVAILABLE => Code::FailedPrecondition,
+        error_code::QUEUE_FULL => Code::ResourceExhausted,
+        _ => Code::Internal,
+    };
+    Status::new(code, error.message)
+}
+
+fn invalid_argument(error: ProtoError) -> Status {
+    Status::invalid_argument(error.to_string())
+}
+
+fn parse_job_id(job_id: &str) -> Result<u64, Status> {
+    job_id.parse().map_err(|_| Status::invalid_argument("invalid job id"))
+}
+
+#[tonic::async_trait]
+impl ProverService for GrpcProver {
+    async fn setup(
+        &self,
+        request: Request<SetupRequest>,
+    ) -> Result<Response<SetupResponse>, Status> {
+        let program =
+            required(request.into_inner().program, "program").map_err(invalid_argument)?;
+        let (digest, vk) = self.blocking(move |server| server.register(&program.source)).await?;
+        let vk = bincode::serialize(&vk).map_err(|e| Status::internal(e.to_string()))?;
+        Ok(Response::new(SetupResponse { program_id: Some(digest.into()), verifying_key: vk }))
+    }
+
+    async fn prove(
+        &self,
+        request: Request<ProveRequest>,
+    ) -> Result<Response<ProveResponse>, Status> {
+        let request = request.into_inner();
+        let program_id = required(request.program_id, "program_id").map_err(invalid_argument)?;
+        let digest = ProgramDigest::try_from(program_id).map_err(invalid_argument)?;
+        let stdin = request.stdin.map(|stdin| stdin.data).unwrap_or_default();
+
+        // The pool proves every job with the configuration of the server.
+        let config = &self.server.client().config;
+        if let Some(options) = request.options {
+            let preset = options.fri_preset().to_sdk();
+            if preset.is_some_and(|preset| preset != config.fri_preset) {
+                return Err(Status::unimplemented("the server uses another FRI preset"));
+            }
+            let shard_size = options.shard_size.map(|size| size as usize);
+            if shard_size.is_some() && shard_size != config.shard_size {
+                return Err(Status::unimplemented("the server uses another shard size"));
+            }
+        }
+
+        let id = self.blocking(move |server| server.queue(&digest, &stdin)).await?;
+        Ok(Response::new(ProveResponse { job_id: id.to_string() }))
+    }
+
+    async fn get_status(
+        &self,
+        request: Request<GetStatusRequest>,
+    ) -> Result<Response<GetStatusResponse>, Status> {
+        let id = parse_job_id(&request.into_inner().job_id)?;
+        let status = self.server.job_status(id).map_err(to_status)?;
+        Ok(Response::new(GetStatusResponse {
+            status: JobStatus::from(status.status).into(),
+            error: status.error.unwrap_or_default(),
+        }))
+    }
+
+    async fn get_proof(
+        &self,
+        request: Request<GetProofRequest>,
+    ) -> Result<Response<GetProofResponse>, Status> {
+        let id = parse_job_id(&request.into_inner().job_id)?;
+        let bytes = self.server.proof_bytes(id).map_err(to_status)?;
+        let proof = BfProofWithPublicValues::from_bytes(&bytes)
+            .map_err(|e| Status::internal(e.to_string()))?;
+        let proof = Proof::try_from(&proof).map_err(|e| Status::internal(e.to_string()))?;
+        Ok(Response::new(GetProofResponse { proof: Some(proof) }))
+    }
+
+    async fn verify(
+        &self,
+        request: Request<VerifyRequest>,
+    ) -> Result<Response<VerifyResponse>, Status> {
+        let request = request.into_inner();
+        let program_id = required(request.program_id, "program_id").map_err(invalid_argument)?;
+        let digest = ProgramDigest::try_from(program_id).map_err(invalid_argument)?;
+        let proof = required(request.proof, "proof").map_err(invalid_argument)?;
+
+        let result = self
+            .blocking(move |server| {
+                let vk = server.verifying_key(&digest)?;
+                let result = match BfProofWithPublicValues::try_from(&proof) {
+                    Ok(proof) => match server.client().verify(&proof, &vk) {
+                        Ok(()) => VerificationResult::valid(proof.output),
+                        Err(e) => VerificationResult::invalid(e),
+                    },
+                    Err(e) => VerificationResult::invalid(e),
+                };
+                Ok(result)
+            })
+            .await?;
+        Ok(Response::new(VerifyResponse { result: Some(result) }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bf_grpc::{Program, ProgramId, Stdin};
+
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_grpc_prover() {
+        let dir = tempfile::tempdir().unwrap();
+        let client = Arc::new(bf_sdk::ProverClient::new());
+        let server = ProverServer::open(client, dir.path(), 1, 4).unwrap();
+        let prover = GrpcProver::new(Arc::new(server));
+
+        let setup = SetupRequest { program: Some(Program { source: ",+.".into() }) };
+        let setup = prover.setup(Request::new(setup)).await.unwrap().into_inner();
+        let program_id = setup.program_id.unwrap();
+
+        let prove = ProveRequest {
+            program_id: Some(program_id.clone()),
+            stdin: Some(Stdin { data: vec![4] }),
+            options: None,
+        };
+        let job_id = prover.prove(Request::new(prove)).await.unwrap().into_inner().job_id;
+        loop {
+            let request = Request::new(GetStatusRequest { job_id: job_id.clone() });
+            let status = prover.get_status(request).await.unwrap().into_inner();
+            match status.status() {
+                JobStatus::Succeeded => break,
+                JobStatus::Failed => panic!("proving failed: {}", status.error),
+                _ => tokio::time::sleep(std::time::Duration::from_millis(100)).await,
+            }
+        }
+        let request = Request::new(GetProofRequest { job_id });
+        let proof = prover.get_proof(request).await.unwrap().into_inner().proof.unwrap();
+        assert_eq!(proof.output, vec![5]);
+
+        let verify = VerifyRequest { program_id: Some(program_id), proof: Some(proof.clone()) };
+        let result = prover.verify(Request::new(verify)).await.unwrap().into_inner();
+        assert!(result.result.unwrap().valid);
+
+        let other = ProgramId::from(bf_sdk::program_digest("+."));
+        let verify = VerifyRequest { program_id: Some(other), proof: Some(proof) };
+        let status = prover.verify(Request::new(verify)).await.unwrap_err();
+        assert_eq!(status.code(), Code::NotFound);
+    }
+}
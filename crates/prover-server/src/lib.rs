@@ -0,0 +1,505 @@
+//! A JSON-RPC 2.0 service generating proofs, the server side of the SDK `NetworkProver`.
+//!
+//! Requests are posted to `/` and follow the protocol of [bf_sdk::network]: `setup` registers
+//! a program, `prove` queues a job on a [ProvingPool], and clients poll `status` and fetch the
+//! result with `getProof`. `GET /health` answers `ok`.
+//!
+//! Programs, jobs and proofs are persisted in a [JobStore], so finished proofs survive a restart
+//! and unfinished jobs are queued again.
+//!
+//! The same jobs are also served over gRPC by [grpc::GrpcProver].
+
+pub mod grpc;
+
+use std::{
+    collections::HashMap,
+    fs,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use anyhow::{Context, Result};
+use axum::{
+    body::Bytes,
+    extract::State,
+    routing::{get, post},
+    Json, Router,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+use bf_core_executor::Program;
+use bf_sdk::network::{
+    error_code, GetProofResult, JobParams, ProveParams, ProveResult, RemoteJobStatus, RpcError,
+    RpcRequest, RpcResponse, SetupParams, SetupResult, StatusResult, GET_PROOF_METHOD,
+    PROVE_METHOD, SETUP_METHOD, STATUS_METHOD,
+};
+use bf_sdk::{
+    program_digest, BfProofWithPublicValues, BfVerifyingKey, ProgramDigest, ProgramRegistry,
+    ProofHandle, ProverClient, ProvingPool,
+};
+
+/// The number of proving keys kept in memory.
+const PROVING_KEY_CACHE_SIZE: usize = 16;
+
+/// The persisted state of a job.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobRecord {
+    /// The hex-encoded program digest.
+    pub program_id: String,
+    /// The hex-encoded input.
+    pub stdin: String,
+    pub status: RemoteJobStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The directory persisting the state of a [ProverServer]:
+///
+/// - `programs/<digest>.bf`, the programs that were set up.
+/// - `jobs/<id>.json`, the [JobRecord] of every job.
+/// - `jobs/<id>.proof`, the proofs of the jobs that succeeded.
+pub struct JobStore {
+    dir: PathBuf,
+}
+
+impl JobStore {
+    pub fn open(dir: &Path) -> Result<Self> {
+        for sub in ["programs", "jobs"] {
+            let path = dir.join(sub);
+            fs::create_dir_all(&path)
+                .with_context(|| format!("failed to create {}", path.display()))?;
+        }
+        Ok(Self { dir: dir.to_owned() })
+    }
+
+    fn program_path(&self, digest: &ProgramDigest) -> PathBuf {
+        self.dir.join("programs").join(format!("{}.bf", hex::encode(digest)))
+    }
+
+    fn record_path(&self, id: u64) -> PathBuf {
+        self.dir.join("jobs").join(format!("{id}.json"))
+    }
+
+    fn proof_path(&self, id: u64) -> PathBuf {
+        self.dir.join("jobs").join(format!("{id}.proof"))
+    }
+
+    pub fn save_program(&self, elf: &str) -> Result<()> {
+        write_atomic(&self.program_path(&program_digest(elf)), elf.as_bytes())
+    }
+
+    /// The programs that were set up.
+    pub fn programs(&self) -> Result<Vec<String>> {
+        let mut programs = Vec::new();
+        for entry in fs::read_dir(self.dir.join("programs"))? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "bf") {
+                programs.push(fs::read_to_string(&path)?);
+            }
+        }
+        Ok(programs)
+    }
+
+    pub fn save_record(&self, id: u64, record: &JobRecord) -> Result<()> {
+        write_atomic(&self.record_path(id), &serde_json::to_vec(record)?)
+    }
+
+    /// The records of every job, by id.
+    pub fn records(&self) -> Result<Vec<(u64, JobRecord)>> {
+        let mut records = Vec::new();
+        for entry in fs::read_dir(self.dir.join("jobs"))? {
+            let path = entry?.path();
+            if !path.extension().is_some_and(|ext| ext == "json") {
+                continue;
+            }
+            let Some(id) = path.file_stem().and_then(|stem| stem.to_str()?.parse().ok()) else {
+                continue;
+            };
+            let record = serde_json::from_slice(&fs::read(&path)?)
+                .with_context(|| format!("invalid job record {}", path.display()))?;
+            records.push((id, record));
+        }
+        records.sort_by_key(|(id, _)| *id);
+        Ok(records)
+    }
+
+    pub fn save_proof(&self, id: u64, proof: &BfProofWithPublicValues) -> Result<()> {
+        write_atomic(&self.proof_path(id), &proof.to_bytes()?)
+    }
+
+    pub fn proof(&self, id: u64) -> Result<Vec<u8>> {
+        Ok(fs::read(self.proof_path(id))?)
+    }
+}
+
+/// Writes through a temporary file, so a crash never leaves a truncated file behind.
+fn write_atomic(path: &Path, bytes: &[u8]) -> Result<()> {
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, bytes)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+struct Job {
+    record: JobRecord,
+    /// The handle of a job still in the pool.
+    handle: Option<ProofHandle>,
+}
+
+/// The state of the service.
+pub struct ProverServer {
+    client: Arc<ProverClient>,
+    registry: ProgramRegistry,
+    pool: ProvingPool,
+    store: JobStore,
+    jobs: Mutex<HashMap<u64, Job>>,
+    next_id: AtomicU64,
+}
+
+impl ProverServer {
+    /// Opens the store in `dir`, registering its programs and queueing its unfinished jobs again.
+    pub fn open(
+        client: Arc<ProverClient>,
+        dir: &Path,
+        num_workers: usize,
+        queue_capacity: usize,
+    ) -> Result<Self> {
+        let store = JobStore::open(dir)?;
+        let registry = ProgramRegistry::new(NonZeroUsize::new(PROVING_KEY_CACHE_SIZE).unwrap());
+        for elf in store.programs()? {
+            registry.register(&elf);
+        }
+        let records = store.records()?;
+        let next_id = records.last().map_or(0, |(id, _)| id + 1);
+
+        let pool = ProvingPool::new(client.clone(), num_workers, queue_capacity);
+        let server = Self {
+            client,
+            registry,
+            pool,
+            store,
+            jobs: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(next_id),
+        };
+
+        let mut jobs = HashMap::new();
+        for (id, mut record) in records {
+            let mut handle = None;
+            if !record.status.is_finished() {
+                tracing::info!("queueing job {id} again");
+                match server.submit(&record, true) {
+                    Ok(h) => {
+                        record.status = RemoteJobStatus::Queued;
+                        handle = Some(h);
+                    }
+                    Err(e) => {
+                        record.status = RemoteJobStatus::Failed;
+                        record.error = Some(e.message);
+                    }
+                }
+                server.store.save_record(id, &record)?;
+            }
+            jobs.insert(id, Job { record, handle });
+        }
+        *server.jobs.lock().unwrap() = jobs;
+        Ok(server)
+    }
+
+    pub fn client(&self) -> &ProverClient {
+        &self.client
+    }
+
+    /// Registers a program and sets it up.
+    pub fn register(&self, program: &str) -> Result<(ProgramDigest, BfVerifyingKey), RpcError> {
+        Program::from(program).map_err(|e| RpcError::new(error_code::INVALID_PARAMS, e))?;
+        let digest = self.registry.register(program);
+        self.store.save_program(program).map_err(internal_error)?;
+        let vk = self.registry.verifying_key(&self.client, &digest).expect("just registered");
+        Ok((digest, vk))
+    }
+
+    /// The verifying key of a program that was set up.
+    pub fn verifying_key(&self, digest: &ProgramDigest) -> Result<BfVerifyingKey, RpcError> {
+        self.registry.verifying_key(&self.client, digest).ok_or_else(|| unknown_program(digest))
+    }
+
+    /// Queues a proof of a program that was set up, returning the id of the job.
+    pub fn queue(&self, digest: &ProgramDigest, stdin: &[u8]) -> Result<u64, RpcError> {
+        let record = JobRecord {
+            program_id: hex::encode(digest),
+            stdin: hex::encode(stdin),
+            status: RemoteJobStatus::Queued,
+            error: None,
+        };
+        let handle = self.submit(&record, false)?;
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.store.save_record(id, &record).map_err(internal_error)?;
+        self.jobs.lock().unwrap().insert(id, Job { record, handle: Some(handle) });
+        Ok(id)
+    }
+
+    pub fn job_status(&self, id: u64) -> Result<StatusResult, RpcError> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let job = jobs.get_mut(&id).ok_or_else(|| unknown_job(id))?;
+        self.poll(id, job);
+        let status = match &job.handle {
+            Some(handle) => handle.status().into(),
+            None => job.record.status,
+        };
+        Ok(StatusResult { status, error: job.record.error.clone() })
+    }
+
+    /// The proof of a job that succeeded, as written by [BfProofWithPublicValues::to_bytes].
+    pub fn proof_bytes(&self, id: u64) -> Result<Vec<u8>, RpcError> {
+        {
+            let mut jobs = self.jobs.lock().unwrap();
+            let job = jobs.get_mut(&id).ok_or_else(|| unknown_job(id))?;
+            self.poll(id, job);
+            if job.record.status != RemoteJobStatus::Succeeded {
+                return Err(RpcError::new(
+                    error_code::PROOF_UNAVAILABLE,
+                    format!("job {id} has not succeeded"),
+                ));
+            }
+        }
+        self.store.proof(id).map_err(internal_error)
+    }
+
+    /// The `setup` method.
+    pub fn setup(&self, params: SetupParams) -> Result<SetupResult, RpcError> {
+        let (digest, vk) = self.register(&params.program)?;
+        Ok(SetupResult {
+            program_id: hex::encode(digest),
+            vk: hex::encode(bincode::serialize(&vk).map_err(internal_error)?),
+        })
+    }
+
+    /// The `prove` method.
+    pub fn prove(&self, params: ProveParams) -> Result<ProveResult, RpcError> {
+        let digest = hex::decode(&params.program_id)
+            .ok()
+            .and_then(|bytes| ProgramDigest::try_from(bytes).ok())
+            .ok_or_else(|| RpcError::new(error_code::INVALID_PARAMS, "invalid program id"))?;
+        let stdin = hex::decode(&params.stdin)
+            .map_err(|_| RpcError::new(error_code::INVALID_PARAMS, "invalid stdin encoding"))?;
+        let id = self.queue(&digest, &stdin)?;
+        Ok(ProveResult { job_id: id.to_string() })
+    }
+
+    /// The `status` method.
+    pub fn status(&self, params: JobParams) -> Result<StatusResult, RpcError> {
+        self.job_status(parse_job_id(&params.job_id)?)
+    }
+
+    /// The `getProof` method.
+    pub fn get_proof(&self, params: JobParams) -> Result<GetProofResult, RpcError> {
+        let proof = self.proof_bytes(parse_job_id(&params.job_id)?)?;
+        Ok(GetProofResult { proof: hex::encode(proof) })
+    }
+
+    /// Persists the result of every finished job.
+    pub fn reap(&self) {
+        for (id, job) in self.jobs.lock().unwrap().iter_mut() {
+            self.poll(*id, job);
+        }
+    }
+
+    /// Dispatches a JSON-RPC request.
+    pub fn handle(&self, request: RpcRequest) -> Result<Value, RpcError> {
+        if request.jsonrpc != "2.0" {
+            return Err(RpcError::new(error_code::INVALID_REQUEST, "expected JSON-RPC 2.0"));
+        }
+        match request.method.as_str() {
+            SETUP_METHOD => to_value(self.setup(from_value(request.params)?)?),
+            PROVE_METHOD => to_value(self.prove(from_value(request.params)?)?),
+            STATUS_METHOD => to_value(self.status(from_value(request.params)?)?),
+            GET_PROOF_METHOD => to_value(self.get_proof(from_value(request.params)?)?),
+            method => {
+                Err(RpcError::new(error_code::METHOD_NOT_FOUND, format!("unknown method {method}")))
+            }
+        }
+    }
+
+    fn submit(&self, record: &JobRecord, block: bool) -> Result<ProofHandle, RpcError> {
+        let digest = hex::decode(&record.program_id)
+            .ok()
+            .and_then(|bytes| ProgramDigest::try_from(bytes).ok())
+            .ok_or_else(|| internal_error("corrupted job record"))?;
+        let stdin = hex::decode(&record.stdin).map_err(internal_error)?;
+        let pk = self
+            .registry
+            .proving_key(&self.client, &digest)
+            .ok_or_else(|| unknown_program(&digest))?;
+        if block {
+            return Ok(self.pool.submit(pk, stdin));
+        }
+        self.pool.try_submit(pk, stdin).map_err(|e| RpcError::new(error_code::QUEUE_FULL, e))
+    }
+
+    /// Persists the result of the job if it has finished.
+    fn poll(&self, id: u64, job: &mut Job) {
+        if !job.handle.as_ref().is_some_and(|handle| handle.is_finished()) {
+            return;
+        }
+        let result = job.handle.take().unwrap().wait();
+        let result = result.and_then(|proof| self.store.save_proof(id, &proof));
+        match result {
+            Ok(()) => job.record.status = RemoteJobStatus::Succeeded,
+            Err(e) => {
+                tracing::warn!("job {id} failed: {e:#}");
+                job.record.status = RemoteJobStatus::Failed;
+                job.record.error = Some(format!("{e:#}"));
+            }
+        }
+        if let Err(e) = self.store.save_record(id, &job.record) {
+            tracing::error!("failed to persist job {id}: {e:#}");
+        }
+    }
+}
+
+fn parse_job_id(job_id: &str) -> Result<u64, RpcError> {
+    job_id.parse().map_err(|_| RpcError::new(error_code::INVALID_PARAMS, "invalid job id"))
+}
+
+fn unknown_program(digest: &ProgramDigest) -> RpcError {
+    RpcError::new(error_code::UNKNOWN_PROGRAM, format!("unknown program {}", hex::encode(digest)))
+}
+
+fn unknown_job(id: u64) -> RpcError {
+    RpcError::new(error_code::UNKNOWN_JOB, format!("unknown job {id}"))
+}
+
+fn internal_error(e: impl std::fmt::Display) -> RpcError {
+    RpcError::new(error_code::INTERNAL_ERROR, e)
+}
+
+fn from_value<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    serde_json::from_value(params).map_err(|e| RpcError::new(error_code::INVALID_PARAMS, e))
+}
+
+fn to_value<T: Serialize>(result: T) -> Result<Value, RpcError> {
+    serde_json::to_value(result).map_err(internal_error)
+}
+
+/// Builds the routes of the service.
+pub fn router(server: Arc<ProverServer>) -> Router {
+    Router::new().route("/", post(rpc)).route("/health", get(|| async { "ok" })).with_state(server)
+}
+
+async fn rpc(State(server): State<Arc<ProverServer>>, body: Bytes) -> Json<RpcResponse> {
+    let request: RpcRequest = match serde_json::from_slice(&body) {
+        Ok(request) => request,
+        Err(e) => {
+            let error = RpcError::new(error_code::PARSE_ERROR, e);
+            return Json(RpcResponse::failure(Value::Null, error));
+        }
+    };
+    let id = request.id.clone();
+
+    // Setup is CPU bound, so keep it off the async workers.
+    let result = tokio::task::spawn_blocking(move || server.handle(request)).await;
+    Json(match result {
+        Ok(Ok(result)) => RpcResponse::success(id, result),
+        Ok(Err(error)) => RpcResponse::failure(id, error),
+        Err(e) => RpcResponse::failure(id, internal_error(e)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{thread, time::Duration};
+
+    use axum::{body::Body, http::Request};
+    use tower::ServiceExt;
+
+    use bf_sdk::{ProverBackend, ProverClientConfig};
+
+    use super::*;
+
+    async fn call<T: DeserializeOwned>(
+        router: Router,
+        method: &str,
+        params: impl Serialize,
+    ) -> Result<T, RpcError> {
+        let request = RpcRequest::new(1, method, params).unwrap();
+        let request = Request::post("/")
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::to_vec(&request).unwrap()))
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let response: RpcResponse = serde_json::from_slice(&body).unwrap();
+        match response.error {
+            Some(error) => Err(error),
+            None => Ok(serde_json::from_value(response.result.unwrap()).unwrap()),
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_prove_and_restart() {
+        let client = Arc::new(ProverClient::new());
+        let dir = tempfile::tempdir().unwrap();
+        let server = Arc::new(ProverServer::open(client.clone(), dir.path(), 1, 4).unwrap());
+        let app = router(server.clone());
+
+        let setup: SetupResult =
+            call(app.clone(), SETUP_METHOD, SetupParams { program: ",+.".into() }).await.unwrap();
+        let vk: BfVerifyingKey = bincode::deserialize(&hex::decode(&setup.vk).unwrap()).unwrap();
+        let params = ProveParams { program_id: setup.program_id, stdin: "02".into() };
+        let prove: ProveResult = call(app.clone(), PROVE_METHOD, params).await.unwrap();
+        let job = JobParams { job_id: prove.job_id };
+
+        loop {
+            let status: StatusResult = call(app.clone(), STATUS_METHOD, job.clone()).await.unwrap();
+            if status.status.is_finished() {
+                assert_eq!(status.status, RemoteJobStatus::Succeeded, "{:?}", status.error);
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        let result: GetProofResult =
+            call(app.clone(), GET_PROOF_METHOD, job.clone()).await.unwrap();
+        let bytes = hex::decode(result.proof).unwrap();
+        let proof = BfProofWithPublicValues::from_bytes(&bytes).unwrap();
+        assert_eq!(proof.output, vec![3]);
+        client.verify(&proof, &vk).unwrap();
+
+        let error = call::<StatusResult>(app, STATUS_METHOD, JobParams { job_id: "42".into() })
+            .await
+            .unwrap_err();
+        assert_eq!(error.code, error_code::UNKNOWN_JOB);
+
+        // The proof survives a restart.
+        drop(server);
+        let server = Arc::new(ProverServer::open(client, dir.path(), 1, 4).unwrap());
+        let result: GetProofResult = call(router(server), GET_PROOF_METHOD, job).await.unwrap();
+        assert!(!result.proof.is_empty());
+    }
+
+    #[test]
+    fn test_network_prover() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let server = ProverServer::open(Arc::new(ProverClient::new()), dir.path(), 1, 4).unwrap();
+        let listener = runtime.block_on(tokio::net::TcpListener::bind("127.0.0.1:0")).unwrap();
+        let addr = listener.local_addr().unwrap();
+        runtime.spawn(async move { axum::serve(listener, router(Arc::new(server))).await });
+        thread::sleep(Duration::from_millis(100));
+
+        let client = ProverClient::from_config(ProverClientConfig {
+            backend: ProverBackend::Network,
+            rpc_url: Some(format!("http://{addr}")),
+            ..Default::default()
+        })
+        .unwrap();
+        let (pk, vk) = client.setup("++.");
+        let proof = client.prove(&pk, vec![]).run().unwrap();
+        assert_eq!(proof.output, vec![2]);
+        client.verify(&proof, &vk).unwrap();
+    }
+}
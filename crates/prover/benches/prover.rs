@@ -0,0 +1,250 @@
+//! Benchmarks of the executor and of every proving stage on the test artifacts.
+//!
+//! Run them with `cargo bench -p bf-prover`. Besides the criterion reports, a summary of all the
+//! results is written to `target/criterion/summary.json`, or to `BF_BENCH_JSON` if set.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use criterion::{criterion_group, BatchSize, BenchmarkId, Criterion, Throughput};
+use p3_commit::{Pcs, PolynomialSpace};
+use p3_field::FieldAlgebra;
+use p3_matrix::Matrix;
+use p3_util::log2_strict_usize;
+use serde_json::{json, Value};
+
+use bf_core_executor::{ExecutionRecord, Executor, Program};
+use bf_prover::{BfProver, CoreSC};
+use bf_stark::{
+    air::MachineAir, quotient_values, MachineProver, PackedChallenge, StarkGenericConfig,
+};
+
+type Challenge = <CoreSC as StarkGenericConfig>::Challenge;
+
+/// The standard artifacts and their inputs.
+const ARTIFACTS: [(&str, &str, &[u8]); 5] = [
+    ("fibo", test_artifacts::FIBO_BF, &[17]),
+    ("hello", test_artifacts::HELLO_BF, &[]),
+    ("loop", test_artifacts::LOOP_BF, &[]),
+    ("move", test_artifacts::MOVE_BF, &[]),
+    ("printa", test_artifacts::PRINTA_BF, &[]),
+];
+
+/// Executes a program and generates the dependencies of its record, as done before proving.
+fn execution_record(prover: &BfProver, elf: &str, stdin: &[u8]) -> ExecutionRecord {
+    let mut runtime = Executor::new(Program::from(elf).unwrap(), stdin.to_vec());
+    runtime.run().unwrap();
+    let mut record = runtime.record;
+    prover.core_prover.machine().generate_dependencies(&mut record, None);
+    record
+}
+
+fn bench_execute(c: &mut Criterion) {
+    let mut group = c.benchmark_group("execute");
+    for (name, elf, stdin) in ARTIFACTS {
+        let program = Program::from(elf).unwrap();
+        let mut runtime = Executor::new(program.clone(), stdin.to_vec());
+        runtime.run().unwrap();
+
+        group.throughput(Throughput::Elements(runtime.state.global_clk));
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || Executor::new(program.clone(), stdin.to_vec()),
+                |mut runtime| runtime.run().unwrap(),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn bench_trace(c: &mut Criterion) {
+    let prover: BfProver = BfProver::new();
+    let mut group = c.benchmark_group("trace");
+    for (name, elf, stdin) in ARTIFACTS {
+        let record = execution_record(&prover, elf, stdin);
+        for chip in prover.core_prover.get_chips(&record) {
+            group.bench_with_input(BenchmarkId::new(chip.name(), name), &record, |b, record| {
+                b.iter(|| chip.generate_trace(record, &mut ExecutionRecord::default()))
+            });
+        }
+    }
+    group.finish();
+}
+
+fn bench_commit(c: &mut Criterion) {
+    let prover: BfProver = BfProver::new();
+    let mut group = c.benchmark_group("commit");
+    for (name, elf, stdin) in ARTIFACTS {
+        let record = execution_record(&prover, elf, stdin);
+        let traces = prover.core_prover.generate_traces(&record);
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || traces.clone(),
+                |traces| prover.core_prover.commit(traces),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+/// Benchmarks the quotient computation of all the chips, with fixed challenges.
+fn bench_quotient(c: &mut Criterion) {
+    let prover: BfProver = BfProver::new();
+    let machine = prover.core_prover.machine();
+    let pcs = machine.config().pcs();
+    let challenges = vec![Challenge::from_canonical_u32(7), Challenge::from_canonical_u32(11)];
+    let packed_challenges =
+        challenges.iter().map(|c| PackedChallenge::<CoreSC>::from_f(*c)).collect::<Vec<_>>();
+    let alpha = Challenge::from_canonical_u32(13);
+
+    let mut group = c.benchmark_group("quotient");
+    for (name, elf, stdin) in ARTIFACTS {
+        let (pk, _) = prover.core_prover.setup(&Program::from(elf).unwrap());
+        let record = execution_record(&prover, elf, stdin);
+        let data = prover.core_prover.commit(prover.core_prover.generate_traces(&record));
+        let chips = machine.shard_chips_ordered(&data.chip_ordering).collect::<Vec<_>>();
+
+        let trace_domains = data
+            .traces
+            .iter()
+            .map(|trace| pcs.natural_domain_for_degree(trace.height()))
+            .collect::<Vec<_>>();
+        let (permutation_traces, cumulative_sums): (Vec<_>, Vec<_>) = chips
+            .iter()
+            .zip(data.traces.iter())
+            .map(|(chip, main_trace)| {
+                let preprocessed_trace =
+                    pk.chip_ordering.get(&chip.name()).map(|&index| &pk.traces[index]);
+                chip.generate_permutation_trace(preprocessed_trace, main_trace, &challenges)
+            })
+            .unzip();
+        let (_, permutation_data) = pcs.commit(
+            permutation_traces
+                .into_iter()
+                .zip(trace_domains.iter())
+                .map(|(trace, domain)| (*domain, trace.flatten_to_base()))
+                .collect(),
+        );
+        let quotient_domains = trace_domains
+            .iter()
+            .zip(chips.iter())
+            .map(|(domain, chip)| {
+                let log_degree = log2_strict_usize(domain.size());
+                domain.create_disjoint_domain(1 << (log_degree + chip.log_quotient_degree()))
+            })
+            .collect::<Vec<_>>();
+
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                for (i, quotient_domain) in quotient_domains.iter().enumerate() {
+                    let preprocessed = pk.chip_ordering.get(&chips[i].name()).map(|&index| {
+                        pcs.get_evaluations_on_domain(&*pk.data, index, *quotient_domain)
+                            .to_row_major_matrix()
+                    });
+                    let main = pcs
+                        .get_evaluations_on_domain(&data.main_data, i, *quotient_domain)
+                        .to_row_major_matrix();
+                    let permutation = pcs
+                        .get_evaluations_on_domain(&permutation_data, i, *quotient_domain)
+                        .to_row_major_matrix();
+                    quotient_values::<CoreSC, _, _>(
+                        chips[i],
+                        &cumulative_sums[i],
+                        trace_domains[i],
+                        *quotient_domain,
+                        preprocessed,
+                        main,
+                        permutation,
+                        &packed_challenges,
+                        alpha,
+                    );
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_prove(c: &mut Criterion) {
+    let prover: BfProver = BfProver::new();
+    let mut group = c.benchmark_group("prove");
+    group.sample_size(10).measurement_time(Duration::from_secs(30));
+    for (name, elf, stdin) in ARTIFACTS {
+        let (pk, _) = prover.setup(elf);
+        let cycles = prover.prove(&pk, stdin).unwrap().cycles;
+        group.throughput(Throughput::Elements(cycles));
+        group.bench_function(name, |b| b.iter(|| prover.prove(&pk, stdin).unwrap()));
+    }
+    group.finish();
+}
+
+/// The directory criterion writes its results to.
+fn criterion_dir() -> PathBuf {
+    let target = env::var_os("CARGO_TARGET_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(env!("CARGO_MANIFEST_DIR")).join("../../target"));
+    target.join("criterion")
+}
+
+/// Collects the results of every benchmark below `dir`.
+fn collect_results(dir: &Path, results: &mut Vec<Value>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_dir() {
+            continue;
+        }
+        if path.file_name().is_some_and(|name| name == "new") {
+            let read = |file: &str| -> Option<Value> {
+                serde_json::from_slice(&fs::read(path.join(file)).ok()?).ok()
+            };
+            let (Some(benchmark), Some(estimates)) =
+                (read("benchmark.json"), read("estimates.json"))
+            else {
+                continue;
+            };
+            let mean_ns = estimates["mean"]["point_estimate"].as_f64().unwrap_or_default();
+            let elements_per_sec = benchmark["throughput"]["Elements"]
+                .as_f64()
+                .filter(|_| mean_ns > 0.0)
+                .map(|elements| elements * 1e9 / mean_ns);
+            results.push(json!({
+                "id": benchmark["full_id"],
+                "mean_ns": mean_ns,
+                "median_ns": estimates["median"]["point_estimate"],
+                "std_dev_ns": estimates["std_dev"]["point_estimate"],
+                "cycles_per_sec": elements_per_sec,
+            }));
+        } else {
+            collect_results(&path, results);
+        }
+    }
+}
+
+/// Writes a summary of all the results as JSON.
+fn export_json() {
+    let dir = criterion_dir();
+    let mut results = vec![];
+    collect_results(&dir, &mut results);
+    results.sort_by(|a, b| a["id"].as_str().cmp(&b["id"].as_str()));
+
+    let path = env::var_os("BF_BENCH_JSON").map_or_else(|| dir.join("summary.json"), PathBuf::from);
+    let summary = json!({ "version": env!("CARGO_PKG_VERSION"), "results": results });
+    fs::write(&path, serde_json::to_vec_pretty(&summary).unwrap()).unwrap();
+    println!("wrote benchmark summary to {}", path.display());
+}
+
+criterion_group!(benches, bench_execute, bench_trace, bench_commit, bench_quotient, bench_prove);
+
+fn main() {
+    benches();
+    Criterion::default().configure_from_args().final_summary();
+    export_json();
+}
@@ -0,0 +1,118 @@
+//! Golden proof vectors: known-good proofs generated under pinned parameters.
+//!
+//! The fixtures live in `fixtures/golden`. Every vector is a bincode-serialized
+//! [GoldenVector]. Verifying them on every change catches accidental changes to the proof
+//! format or the transcript, since old proofs stop deserializing or verifying.
+//!
+//! To regenerate the fixtures after an intended change, run
+//!
+//! ```shell
+//! cargo test -r -p bf-prover regenerate_golden_vectors -- --ignored
+//! ```
+
+use std::{fs, io, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use bf_stark::koala_bear_poseidon2::FriParameters;
+
+use crate::{components::BfProverComponents, BfCoreProofData, BfProver, CoreSC};
+
+/// The FRI parameters of the golden vectors. They do not depend on `FRI_QUERIES`.
+pub const GOLDEN_FRI_PARAMETERS: FriParameters =
+    FriParameters { log_blowup: 1, num_queries: 84, proof_of_work_bits: 16 };
+
+/// A proof of a program on a given input.
+#[derive(Serialize, Deserialize)]
+pub struct GoldenVector {
+    pub name: String,
+    pub elf: String,
+    pub stdin: Vec<u8>,
+    pub output: Vec<u8>,
+    pub proof: BfCoreProofData,
+}
+
+/// The directory holding the fixtures.
+pub fn golden_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures").join("golden")
+}
+
+/// A prover using the pinned parameters.
+pub fn golden_prover<C: BfProverComponents>() -> BfProver<C> {
+    BfProver::from_config(CoreSC::with_fri_parameters(GOLDEN_FRI_PARAMETERS))
+}
+
+/// Proves `elf` on `stdin` and writes the vector to the fixtures directory.
+pub fn generate_golden_vector<C: BfProverComponents>(
+    prover: &BfProver<C>,
+    name: &str,
+    elf: &str,
+    stdin: &[u8],
+) -> io::Result<()> {
+    let (pk, _) = prover.setup(elf);
+    let proof = prover.prove(&pk, stdin).map_err(io::Error::other)?;
+    let vector = GoldenVector {
+        name: name.to_string(),
+        elf: elf.to_string(),
+        stdin: stdin.to_vec(),
+        output: proof.public_values,
+        proof: proof.proof,
+    };
+
+    fs::create_dir_all(golden_dir())?;
+    let bytes = bincode::serialize(&vector).map_err(io::Error::other)?;
+    fs::write(golden_dir().join(format!("{name}.bin")), bytes)
+}
+
+/// Loads the vector `name` from the fixtures directory.
+pub fn load_golden_vector(name: &str) -> io::Result<GoldenVector> {
+    let path = golden_dir().join(format!("{name}.bin"));
+    let bytes = fs::read(&path)
+        .map_err(|e| io::Error::new(e.kind(), format!("{}: {e}", path.display())))?;
+    bincode::deserialize(&bytes)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {e}", path.display())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::DefaultProverComponents;
+
+    fn golden_programs() -> Vec<(&'static str, &'static str, Vec<u8>)> {
+        vec![
+            ("fibo", test_artifacts::FIBO_BF, vec![17]),
+            ("hello", test_artifacts::HELLO_BF, vec![]),
+            ("loop", test_artifacts::LOOP_BF, vec![]),
+            ("move", test_artifacts::MOVE_BF, vec![]),
+            ("printa", test_artifacts::PRINTA_BF, vec![]),
+        ]
+    }
+
+    #[test]
+    #[ignore]
+    fn regenerate_golden_vectors() {
+        let prover = golden_prover::<DefaultProverComponents>();
+        for (name, elf, stdin) in golden_programs() {
+            generate_golden_vector(&prover, name, elf, &stdin).unwrap();
+        }
+    }
+
+    /// Every program has its vector: a missing one fails rather than checking nothing.
+    #[test]
+    fn test_golden_vectors() {
+        let prover = golden_prover::<DefaultProverComponents>();
+        for (name, elf, stdin) in golden_programs() {
+            let vector = load_golden_vector(name).unwrap_or_else(|e| {
+                panic!("missing golden vector {name}, run regenerate_golden_vectors: {e}")
+            });
+            assert_eq!((vector.name.as_str(), vector.elf.as_str()), (name, elf));
+            assert_eq!(vector.stdin, stdin);
+
+            let (_, vk) = prover.setup(&vector.elf);
+            prover
+                .verify(&vector.proof, &vk)
+                .unwrap_or_else(|e| panic!("golden vector {} failed: {e:?}", vector.name));
+            assert_eq!(prover.execute(&vector.elf, vector.stdin).unwrap(), vector.output);
+        }
+    }
+}
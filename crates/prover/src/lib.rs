@@ -5,16 +5,27 @@
 #![allow(clippy::collapsible_else_if)]
 
 pub mod components;
+pub mod golden;
+#[cfg(any(test, feature = "export-tests"))]
+pub mod soundness;
+pub mod shards;
 pub mod types;
 pub mod verify;
+pub mod version;
 
+use rayon::{ThreadPool, ThreadPoolBuildError};
 use tracing::instrument;
 
-use bf_core_executor::{ExecutionError, Executor, Program};
-use bf_core_machine::{brainfuck::BfAir, utils::BfCoreProverError};
+use bf_core_executor::{ExecutionError, ExecutionRecord, Executor, Program};
+use bf_core_machine::{
+    brainfuck::BfAir,
+    utils::{BfCoreProverError, CpuProverOpts, KeyMismatch},
+};
 use bf_stark::{koala_bear_poseidon2::KoalaBearPoseidon2, MachineProver};
 
 pub use types::*;
+pub use verify::VerifierLimits;
+pub use version::{ProofSystemInfo, ProofSystemVersion, CORE_CONFIG_ID};
 
 use components::{BfProverComponents, DefaultProverComponents};
 
@@ -28,66 +39,215 @@ pub type InnerSC = KoalaBearPoseidon2;
 pub struct BfProver<C: BfProverComponents = DefaultProverComponents> {
     /// The machine used for proving the core step.
     pub core_prover: C::CoreProver,
+    opts: CpuProverOpts,
+    /// The thread pool of `opts`, if it asks for a dedicated one.
+    pool: Option<ThreadPool>,
+    /// The bounds on the proofs that [BfProver::verify] accepts.
+    limits: VerifierLimits,
 }
 
 impl<C: BfProverComponents> BfProver<C> {
     /// Initializes a new [BfProver].
-    #[instrument(name = "initialize prover", level = "debug", skip_all)]
     pub fn new() -> Self {
+        Self::from_config(CoreSC::default())
+    }
+
+    /// Initializes a new [BfProver] with the given core config.
+    ///
+    /// Proofs are only verifiable by a prover built with the same config.
+    #[instrument(name = "initialize prover", level = "debug", skip_all)]
+    pub fn from_config(config: CoreSC) -> Self {
         // Initialize the provers.
-        let core_machine = BfAir::machine(CoreSC::default());
+        let core_machine = BfAir::machine(config);
         let core_prover = C::CoreProver::new(core_machine);
 
-        Self { core_prover }
+        Self {
+            core_prover,
+            opts: CpuProverOpts::default(),
+            pool: None,
+            limits: VerifierLimits::default(),
+        }
+    }
+
+    /// Sets the options of the prover, building its thread pool.
+    pub fn with_opts(mut self, opts: CpuProverOpts) -> Result<Self, ThreadPoolBuildError> {
+        self.pool = opts.thread_pool()?;
+        self.opts = opts;
+        Ok(self)
+    }
+
+    /// Sets the number of cycles of each shard, keeping the other options.
+    pub fn with_shard_size(mut self, shard_size: Option<u64>) -> Self {
+        self.opts.shard_size = shard_size;
+        self
+    }
+
+    /// The options of the prover.
+    pub fn opts(&self) -> CpuProverOpts {
+        self.opts
+    }
+
+    /// Sets the bounds on the proofs that [BfProver::verify] accepts.
+    pub fn with_limits(mut self, limits: VerifierLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// The bounds on the proofs that [BfProver::verify] accepts.
+    pub fn limits(&self) -> VerifierLimits {
+        self.limits
+    }
+
+    /// Runs `f` on the thread pool of the prover.
+    fn install<R: Send>(&self, f: impl FnOnce() -> R + Send) -> R {
+        match &self.pool {
+            Some(pool) => pool.install(f),
+            None => f(),
+        }
+    }
+
+    /// The version and parameters of the proofs of this prover.
+    pub fn system_info(&self) -> ProofSystemInfo {
+        ProofSystemInfo::new(self.core_prover.machine().config())
     }
 
     /// Creates a proving key and a verifying key for a given MIPS ELF.
-    #[instrument(name = "setup", level = "debug", skip_all)]
     pub fn setup(&self, elf: &str) -> (BfProvingKey, BfVerifyingKey) {
-        let program = Program::from(elf).unwrap();
-        let (pk, vk) = self.core_prover.setup(&program);
-        let vk = BfVerifyingKey { vk };
-        let pk = BfProvingKey {
+        self.setup_with_image(elf, &[])
+    }
+
+    /// Creates the keys of a program whose first tape starts with `image`, see
+    /// [`Program::image`].
+    ///
+    /// The image is in the preprocessed traces, so the verifying key pins both the code and the
+    /// initial memory, and the memory chip takes the initial values of the image cells from it.
+    #[instrument(name = "setup", level = "debug", skip_all)]
+    pub fn setup_with_image(&self, elf: &str, image: &[u8]) -> (BfProvingKey, BfVerifyingKey) {
+        let program = Program { image: image.to_vec(), ..Program::from(elf).unwrap() };
+        let (pk, vk) = self.install(|| self.core_prover.setup(&program));
+        let vk = BfVerifyingKey { vk, system: self.system_info() };
+        let mut pk = BfProvingKey {
             pk: self.core_prover.pk_to_host(&pk),
             elf: elf.to_owned(),
+            program,
             vk: vk.clone(),
+            digest: [0; 32],
         };
+        pk.digest = pk.compute_digest();
         (pk, vk)
     }
 
     /// Generate a proof of a program with the specified inputs.
-    #[instrument(name = "execute", level = "info", skip_all)]
     pub fn execute<'a>(&'a self, elf: &str, input: Vec<u8>) -> Result<Vec<u8>, ExecutionError> {
+        self.execute_with_private_input(elf, input, vec![])
+    }
+
+    /// Executes a program as [BfProver::execute] does, with a private input stream for the
+    /// private input instructions.
+    #[instrument(name = "execute", level = "info", skip_all)]
+    pub fn execute_with_private_input(
+        &self,
+        elf: &str,
+        input: Vec<u8>,
+        private_input: Vec<u8>,
+    ) -> Result<Vec<u8>, ExecutionError> {
         let program = Program::from(elf).unwrap();
-        let mut runtime = Executor::new(program, input);
+        let mut runtime = Executor::new_with_private_input(program, input, private_input);
         runtime.run()?;
         Ok(runtime.state.output_stream)
     }
 
     /// Generate shard proofs which split up and prove the valid execution of a MIPS program with
     /// the core prover. Uses the provided context.
-    #[instrument(name = "prove", level = "info", skip_all)]
     pub fn prove<'a>(
         &'a self,
         pk: &BfProvingKey,
         stdin: &[u8],
     ) -> Result<BfCoreProof, BfCoreProverError> {
-        let program = Program::from(&pk.elf).unwrap();
-        let pk = self.core_prover.pk_to_device(&pk.pk);
-        let (proof, public_values_stream, cycles) =
+        self.prove_with_private_input(pk, stdin, &[])
+    }
+
+    /// Proves a program as [BfProver::prove] does, with a private input stream for the private
+    /// input instructions.
+    ///
+    /// The proof commits to the bytes read from `stdin` only, and `private_input` is left out
+    /// of the returned proof.
+    #[instrument(name = "prove", level = "info", skip_all)]
+    pub fn prove_with_private_input(
+        &self,
+        pk: &BfProvingKey,
+        stdin: &[u8],
+        private_input: &[u8],
+    ) -> Result<BfCoreProof, BfCoreProverError> {
+        self.check_key(pk).map_err(BfCoreProverError::KeyMismatch)?;
+        let device_pk = self.core_prover.pk_to_device(&pk.pk);
+        let (proof, public_values_stream, cycles, trace_stats) = self.install(|| {
             bf_core_machine::utils::prove::<_, C::CoreProver>(
                 &self.core_prover,
-                &pk,
-                program,
+                &device_pk,
+                pk.program.clone(),
                 stdin.to_owned(),
-            )?;
+                private_input.to_owned(),
+                self.opts.shard_size,
+            )
+        })?;
         Ok(BfCoreProof {
-            proof: BfCoreProofData(proof.shard_proof),
+            proof: BfCoreProofData(proof.shard_proofs),
             stdin: stdin.to_owned(),
             public_values: public_values_stream,
             cycles,
+            trace_stats,
+        })
+    }
+
+    /// Proves the execution recorded in `record` without executing the program again, so that
+    /// the execution and the proving can run on different machines or at different times.
+    ///
+    /// The record is the one of [Executor::run] on the program of `pk`, e.g. sent over in its
+    /// bincode serialization. The stdin of the proof is made of the bytes the program read.
+    #[instrument(name = "prove_from_record", level = "info", skip_all)]
+    pub fn prove_from_record(
+        &self,
+        pk: &BfProvingKey,
+        record: ExecutionRecord,
+    ) -> Result<BfCoreProof, BfCoreProverError> {
+        self.check_key(pk).map_err(BfCoreProverError::KeyMismatch)?;
+        if *record.program != pk.program {
+            return Err(BfCoreProverError::KeyMismatch(KeyMismatch::Record));
+        }
+        let stdin = record.input();
+        let device_pk = self.core_prover.pk_to_device(&pk.pk);
+        let (proof, public_values_stream, cycles, trace_stats) = self.install(|| {
+            bf_core_machine::utils::prove_record::<_, C::CoreProver>(
+                &self.core_prover,
+                &device_pk,
+                record,
+            )
+        })?;
+        Ok(BfCoreProof {
+            proof: BfCoreProofData(proof.shard_proofs),
+            stdin,
+            public_values: public_values_stream,
+            cycles,
+            trace_stats,
         })
     }
+
+    /// Checks that the elf, the program, the verifying key and the preprocessed commitment of
+    /// `pk` belong together, so that a proof is never generated against another program.
+    ///
+    /// The parts are hashed and compared with the digest taken at setup, which guards against
+    /// mixing up keys, not against a forged key: neither the program is parsed nor its
+    /// preprocessed traces committed again.
+    pub fn check_key(&self, pk: &BfProvingKey) -> Result<(), KeyMismatch> {
+        if pk.pk.commit != pk.vk.vk.commit || pk.pk.chip_ordering != pk.vk.vk.chip_ordering {
+            return Err(KeyMismatch::VerifyingKey);
+        }
+        if pk.compute_digest() != pk.digest {
+            return Err(KeyMismatch::Digest);
+        }
+        Ok(())
+    }
 }
 
 #[cfg(any(test, feature = "export-tests"))]
@@ -95,6 +255,14 @@ pub mod tests {
     use super::*;
 
     use anyhow::Result;
+    use bf_core_executor::LAST_SHARD_PUBLIC_VALUE;
+    use bf_core_machine::cpu::MAX_CPU_LOG_DEGREE;
+    use bf_stark::{
+        MachineVerificationError, OpeningShapeError, ShardProof, Val, VerificationError,
+    };
+    use p3_field::FieldAlgebra;
+
+    use crate::verify::verify_core_proof_with_limits;
 
     #[cfg(test)]
     use bf_core_machine::utils::setup_logger;
@@ -113,6 +281,399 @@ pub mod tests {
         test_e2e_prover::<DefaultProverComponents>(&prover, elf, vec![17], true)
     }
 
+    #[test]
+    fn test_dedicated_thread_pool() -> Result<()> {
+        let opts = CpuProverOpts { num_threads: Some(2), ..Default::default() };
+        let prover = BfProver::<DefaultProverComponents>::new().with_opts(opts)?;
+        assert_eq!(prover.opts(), opts);
+        test_e2e_prover::<DefaultProverComponents>(&prover, test_artifacts::HELLO_BF, vec![], true)
+    }
+
+    #[test]
+    #[serial]
+    fn test_sharded_e2e() -> Result<()> {
+        let opts = CpuProverOpts { shard_size: Some(100), ..Default::default() };
+        let prover = BfProver::<DefaultProverComponents>::new().with_opts(opts)?;
+        let (pk, vk) = prover.setup(test_artifacts::FIBO_BF);
+        let proof = prover.prove(&pk, &[17])?;
+        assert!(proof.proof.0.len() > 1);
+        assert!(proof.check_cycles());
+        prover.verify(&proof.proof, &vk)?;
+
+        // Each shard commits to its own part of the I/O, which must add up to the whole streams.
+        assert!(proof.proof.0[0].chip_ordering.contains_key("IO"));
+        assert!(proof.check_io());
+        assert_eq!(proof.proof.input_count(), Some(1));
+        let mut tampered = proof.clone();
+        tampered.public_values.pop();
+        assert!(!tampered.check_io());
+
+        // The shards can neither be dropped nor reordered.
+        let mut tampered = proof.proof.clone();
+        tampered.0.remove(0);
+        assert!(matches!(
+            prover.verify(&tampered, &vk),
+            Err(MachineVerificationError::InvalidPublicValues("shard start"))
+        ));
+        let mut tampered = proof.proof.clone();
+        tampered.0.swap(0, 1);
+        assert!(matches!(
+            prover.verify(&tampered, &vk),
+            Err(MachineVerificationError::InvalidPublicValues("shard start"))
+        ));
+        let mut tampered = proof.proof.clone();
+        tampered.0.pop();
+        assert!(matches!(
+            prover.verify(&tampered, &vk),
+            Err(MachineVerificationError::InvalidPublicValues("last shard flag"))
+        ));
+        // Flagging the new last shard does not pass the truncated execution off as a full one.
+        let last = tampered.0.last_mut().unwrap();
+        last.public_values[LAST_SHARD_PUBLIC_VALUE] = Val::<CoreSC>::ONE;
+        assert!(matches!(
+            prover.verify(&tampered, &vk),
+            Err(MachineVerificationError::InvalidPublicValues("program end"))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_pk_to_device_shares_preprocessed_data() {
+        let prover = BfProver::<DefaultProverComponents>::new();
+        let (pk, _) = prover.setup(test_artifacts::HELLO_BF);
+        let device_pk = prover.core_prover.pk_to_device(&pk.pk);
+        assert!(std::sync::Arc::ptr_eq(&device_pk.traces, &pk.pk.traces));
+        assert!(std::sync::Arc::ptr_eq(&device_pk.data, &pk.pk.data));
+    }
+
+    #[test]
+    fn test_shard_proof_streaming() -> Result<()> {
+        let prover = BfProver::<DefaultProverComponents>::new();
+        let (pk, vk) = prover.setup(test_artifacts::HELLO_BF);
+        let proof = prover.prove(&pk, &[])?;
+
+        let mut bytes = Vec::new();
+        proof.proof.0[0].write_to(&mut bytes)?;
+        assert_eq!(bytes, bincode::serialize(&proof.proof.0[0])?);
+
+        let mut decoded = proof.clone();
+        decoded.proof.0[0] = ShardProof::read_from(bytes.as_slice())?;
+        prover.verify(&decoded.proof, &vk)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_key_mismatch() {
+        let prover = BfProver::<DefaultProverComponents>::new();
+        let (pk, _) = prover.setup(test_artifacts::HELLO_BF);
+        let (other, _) = prover.setup("++.");
+        assert_eq!(prover.check_key(&pk), Ok(()));
+
+        let mut wrong_elf = pk.clone();
+        wrong_elf.elf = other.elf.clone();
+        assert_eq!(prover.check_key(&wrong_elf), Err(KeyMismatch::Digest));
+
+        let mut wrong_vk = pk.clone();
+        wrong_vk.vk = other.vk.clone();
+        assert_eq!(prover.check_key(&wrong_vk), Err(KeyMismatch::VerifyingKey));
+
+        let mut wrong_program = other.clone();
+        wrong_program.elf = pk.elf.clone();
+        wrong_program.program = pk.program.clone();
+        assert_eq!(prover.check_key(&wrong_program), Err(KeyMismatch::Digest));
+        assert!(matches!(
+            prover.prove(&wrong_program, &[]),
+            Err(BfCoreProverError::KeyMismatch(KeyMismatch::Digest))
+        ));
+    }
+
+    #[test]
+    fn test_prove_from_record() -> Result<()> {
+        let prover = BfProver::<DefaultProverComponents>::new();
+        let (pk, vk) = prover.setup(test_artifacts::FIBO_BF);
+
+        // The record goes through its serialization, as between two machines.
+        let mut runtime = Executor::new(pk.program.clone(), vec![17, 1]);
+        runtime.run()?;
+        let record: ExecutionRecord = bincode::deserialize(&bincode::serialize(&runtime.record)?)?;
+
+        let proof = prover.prove_from_record(&pk, record)?;
+        let expected = prover.prove(&pk, &[17])?;
+        assert_eq!(proof.stdin, [17]);
+        assert_eq!(proof.public_values, expected.public_values);
+        assert_eq!(proof.cycles, expected.cycles);
+        assert!(proof.check_cycles());
+        prover.verify(&proof.proof, &vk)?;
+
+        let (other, _) = prover.setup(test_artifacts::HELLO_BF);
+        let mut runtime = Executor::new(other.program.clone(), vec![]);
+        runtime.run()?;
+        assert!(matches!(
+            prover.prove_from_record(&pk, runtime.record),
+            Err(BfCoreProverError::KeyMismatch(KeyMismatch::Record))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_trace_stats() -> Result<()> {
+        let prover = BfProver::<DefaultProverComponents>::new();
+        let (pk, _) = prover.setup(test_artifacts::HELLO_BF);
+        let proof = prover.prove(&pk, &[])?;
+        let stats = &proof.trace_stats;
+
+        let cpu = stats.chips.iter().find(|chip| chip.name == "Cpu").unwrap();
+        assert_eq!(cpu.rows as u64, proof.cycles);
+        assert!(cpu.padded_rows.is_power_of_two());
+        assert_eq!(stats.chips.len(), proof.proof.0[0].chip_ordering.len());
+        assert!(stats.chips.iter().all(|chip| chip.rows <= chip.padded_rows));
+        assert_eq!(stats.total_cells, stats.chips.iter().map(|chip| chip.cells()).sum());
+        Ok(())
+    }
+
+    #[test]
+    fn test_cycles_in_public_values() -> Result<()> {
+        let prover = BfProver::<DefaultProverComponents>::new();
+        let (pk, vk) = prover.setup(test_artifacts::FIBO_BF);
+        let mut proof = prover.prove(&pk, &[17])?;
+        assert_eq!(proof.proof.cycles(), Some(proof.cycles));
+        assert!(proof.check_cycles());
+        prover.verify(&proof.proof, &vk)?;
+
+        proof.cycles += 1;
+        assert!(!proof.check_cycles());
+        Ok(())
+    }
+
+    #[test]
+    fn test_io_in_public_values() -> Result<()> {
+        let prover = BfProver::<DefaultProverComponents>::new();
+        let (pk, _) = prover.setup(test_artifacts::FIBO_BF);
+        // The program reads one byte, so the extra input is not committed to but still matches.
+        let proof = prover.prove(&pk, &[17, 3])?;
+        assert!(proof.check_io());
+        assert_eq!(proof.proof.input_count(), Some(1));
+        // Without stdin, only the output is checked.
+        assert!(verify::check_io(&proof.proof.0, None, &proof.public_values));
+        assert!(!verify::check_io(&proof.proof.0, None, &[]));
+
+        let mut tampered = proof.clone();
+        tampered.stdin = vec![18];
+        assert!(!tampered.check_io());
+        let mut tampered = proof.clone();
+        tampered.stdin.clear();
+        assert!(!tampered.check_io());
+        let mut tampered = proof;
+        tampered.public_values[0] ^= 1;
+        assert!(!tampered.check_io());
+        Ok(())
+    }
+
+    #[test]
+    fn test_image_in_key() -> Result<()> {
+        let prover = BfProver::<DefaultProverComponents>::new();
+        let (pk, vk) = prover.setup_with_image(".>.", &[3, 4]);
+        assert_eq!(prover.check_key(&pk), Ok(()));
+        let proof = prover.prove(&pk, &[])?;
+        assert_eq!(proof.public_values, [3, 4]);
+        prover.verify(&proof.proof, &vk)?;
+
+        // The same code with another image has another key, which rejects the proof.
+        let (other_pk, other_vk) = prover.setup_with_image(".>.", &[3, 5]);
+        assert_ne!(other_vk.vk.commit, vk.vk.commit);
+        assert!(prover.verify(&proof.proof, &other_vk).is_err());
+
+        let mut wrong_image = other_pk;
+        wrong_image.program = pk.program.clone();
+        assert_eq!(prover.check_key(&wrong_image), Err(KeyMismatch::Digest));
+        Ok(())
+    }
+
+    #[test]
+    fn test_tampered_chip_ordering() -> Result<()> {
+        let prover = BfProver::<DefaultProverComponents>::new();
+        let (pk, vk) = prover.setup(test_artifacts::HELLO_BF);
+        let proof = prover.prove(&pk, &[])?;
+
+        let mut renamed = proof.proof.clone();
+        let index = renamed.0[0].chip_ordering.remove("IO").unwrap();
+        renamed.0[0].chip_ordering.insert("Unknown".to_string(), index);
+        assert!(matches!(
+            prover.verify(&renamed, &vk),
+            Err(MachineVerificationError::InvalidShardProof(
+                VerificationError::InvalidChipOrdering
+            ))
+        ));
+
+        let mut dropped = proof.proof.clone();
+        dropped.0[0].chip_ordering.remove("Program");
+        assert!(prover.verify(&dropped, &vk).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_required_chip() -> Result<()> {
+        let prover = BfProver::<DefaultProverComponents>::new();
+        let (pk, vk) = prover.setup(">");
+        let proof = prover.prove(&pk, &[])?;
+        prover.verify(&proof.proof, &vk)?;
+
+        for name in ["Program", "Memory", "MemoryImage", "Byte", "IO"] {
+            let mut dropped = proof.proof.clone();
+            dropped.0[0].chip_ordering.remove(name);
+            match prover.verify(&dropped, &vk) {
+                Err(MachineVerificationError::InvalidChipOccurrence(chip)) => {
+                    assert_eq!(chip, name);
+                }
+                result => panic!("unexpected result {result:?}"),
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_cpu_log_degree_too_large() -> Result<()> {
+        let prover = BfProver::<DefaultProverComponents>::new();
+        let (pk, vk) = prover.setup(test_artifacts::HELLO_BF);
+        let proof = prover.prove(&pk, &[])?;
+
+        let mut tampered = proof.proof.clone();
+        let cpu = tampered.0[0].chip_ordering["Cpu"];
+        tampered.0[0].opened_values.chips[cpu].log_degree = MAX_CPU_LOG_DEGREE + 1;
+        assert!(matches!(
+            prover.verify(&tampered, &vk),
+            Err(MachineVerificationError::CpuLogDegreeTooLarge(log_degree))
+                if log_degree == MAX_CPU_LOG_DEGREE + 1
+        ));
+
+        // An out of range index is rejected rather than panicking.
+        let mut tampered = proof.proof.clone();
+        tampered.0[0].chip_ordering.insert("Cpu".to_string(), usize::MAX);
+        assert!(matches!(
+            prover.verify(&tampered, &vk),
+            Err(MachineVerificationError::InvalidShardProof(
+                VerificationError::InvalidChipOrdering
+            ))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_verifier_limits() -> Result<()> {
+        let prover = BfProver::<DefaultProverComponents>::new();
+        let (pk, vk) = prover.setup(test_artifacts::HELLO_BF);
+        let proof = prover.prove(&pk, &[])?.proof;
+        let max_log_degree =
+            proof.0[0].opened_values.chips.iter().map(|values| values.log_degree).max().unwrap();
+
+        // The default limits accept every proof, and the tightest ones accept this proof.
+        assert_eq!(prover.limits(), VerifierLimits::default());
+        let limits = VerifierLimits { max_shards: 1, max_log_degree };
+        let prover = prover.with_limits(limits);
+        prover.verify(&proof, &vk)?;
+
+        let machine = prover.core_prover.machine();
+        let limits = VerifierLimits { max_log_degree: max_log_degree - 1, ..limits };
+        assert!(matches!(
+            verify_core_proof_with_limits(machine, &proof, &vk, limits),
+            Err(MachineVerificationError::LogDegreeTooLarge(_, log_degree))
+                if log_degree == max_log_degree
+        ));
+        let limits = VerifierLimits { max_shards: 0, ..limits };
+        assert!(matches!(
+            verify_core_proof_with_limits(machine, &proof, &vk, limits),
+            Err(MachineVerificationError::TooManyShards(1))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_malformed_openings() -> Result<()> {
+        let prover = BfProver::<DefaultProverComponents>::new();
+        let (pk, vk) = prover.setup(test_artifacts::HELLO_BF);
+        let proof = prover.prove(&pk, &[])?.proof;
+        let io = proof.0[0].chip_ordering["IO"];
+        let byte = proof.0[0].chip_ordering["Byte"];
+
+        // Openings of the wrong shape are rejected rather than indexed out of bounds.
+        let mut tampered = proof.clone();
+        tampered.0[0].opened_values.chips[io].main.local.pop();
+        assert!(matches!(
+            prover.verify(&tampered, &vk),
+            Err(MachineVerificationError::InvalidShardProof(VerificationError::OpeningShapeError(
+                chip,
+                OpeningShapeError::MainWidthMismatch(..),
+            ))) if chip == "IO"
+        ));
+        let mut tampered = proof.clone();
+        tampered.0[0].opened_values.chips[byte].quotient.pop();
+        assert!(matches!(
+            prover.verify(&tampered, &vk),
+            Err(MachineVerificationError::InvalidShardProof(VerificationError::OpeningShapeError(
+                chip,
+                OpeningShapeError::QuotientWidthMismatch(..),
+            ))) if chip == "Byte"
+        ));
+
+        // Without limits, a log degree too large for the field is still rejected.
+        let limits = VerifierLimits { max_log_degree: usize::MAX, ..VerifierLimits::default() };
+        let mut tampered = proof.clone();
+        tampered.0[0].opened_values.chips[byte].log_degree = 64;
+        assert!(matches!(
+            verify_core_proof_with_limits(prover.core_prover.machine(), &tampered, &vk, limits),
+            Err(MachineVerificationError::InvalidShardProof(VerificationError::LogDegreeTooLarge(
+                chip,
+                64,
+            ))) if chip == "Byte"
+        ));
+        Ok(())
+    }
+
+    /// Proves and verifies the large generated artifacts, whose runs take between one and two
+    /// million cycles, close to the largest trace a shard supports, then a run of five million
+    /// cycles split into shards.
+    ///
+    /// A Mandelbrot render is still left out, as a follow-up: compiled from `bf-lang`, even a
+    /// 12x9 render with 8 iterations per point runs 7.3 million cycles, too close to the
+    /// [MAX_CLK](bf_core_executor::MAX_CLK) bound on the whole execution.
+    ///
+    /// The runs take minutes, so the test only builds with the `export-tests` feature.
+    #[test]
+    #[cfg(feature = "export-tests")]
+    #[serial]
+    fn test_e2e_long_run() -> Result<()> {
+        setup_logger();
+        let prover = BfProver::<DefaultProverComponents>::new();
+
+        let input = vec![200, 3, 255, 0, 17, 17, 99, 1, 254, 42];
+        let sorted = vec![0, 1, 3, 17, 17, 42, 99, 200, 254, 255];
+        let mut blocks = vec![8];
+        blocks.extend((0..512u32).map(|i| (i * i % 251) as u8));
+
+        for (elf, stdin, output) in [
+            (test_artifacts::SORT_BF, input, sorted),
+            (test_artifacts::SORT_BF, vec![255; 10], vec![255; 10]),
+            (test_artifacts::FLETCHER_BF, blocks, vec![129, 45]),
+        ] {
+            assert_eq!(prover.execute(elf, stdin.clone())?, output);
+            test_e2e_prover::<DefaultProverComponents>(&prover, elf, stdin, true)?;
+        }
+
+        // 24 blocks take more cycles than the CPU trace of a single shard can hold.
+        let opts = CpuProverOpts { shard_size: Some(1 << 20), ..Default::default() };
+        let prover = prover.with_opts(opts)?;
+        let mut blocks = vec![24];
+        blocks.extend((0..24 * 64u32).map(|i| (i * i % 251) as u8));
+        assert_eq!(prover.execute(test_artifacts::FLETCHER_BF, blocks.clone())?, vec![10, 157]);
+        let (pk, vk) = prover.setup(test_artifacts::FLETCHER_BF);
+        let proof = prover.prove(&pk, &blocks)?;
+        assert!(proof.cycles > 1 << MAX_CPU_LOG_DEGREE);
+        assert_eq!(proof.proof.0.len() as u64, proof.cycles.div_ceil(1 << 20));
+        assert!(proof.check_io() && proof.check_cycles());
+        prover.verify(&proof.proof, &vk)?;
+        Ok(())
+    }
+
     pub fn test_e2e_prover<C: BfProverComponents>(
         prover: &BfProver<C>,
         elf: &str,
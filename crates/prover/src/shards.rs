@@ -0,0 +1,323 @@
+//! Proving an execution shard by shard through a directory, so that a crashed or preempted
+//! prover resumes where it stopped instead of starting over.
+//!
+//! A [ShardStore] holds, for each shard, a [Checkpoint] with its record, written as soon as the
+//! shard is executed, and the proof of the shard once it is opened:
+//!
+//! | file              | content                                                         |
+//! |-------------------|-----------------------------------------------------------------|
+//! | `manifest`        | the digest of the proving key and the input the shards are of   |
+//! | `shard-{i}.ckpt`  | the framed checkpoint after shard `i`, with the record of it    |
+//! | `shards`          | the number of shards, once they are all executed                |
+//! | `shard-{i}.proof` | the proof of shard `i`, see [ShardProof::write_to]              |
+//!
+//! The challenges of every shard depend on the main commitments of all of them, so the shards
+//! are opened once they are all committed to. The commitments are deterministic: on a retry, the
+//! shards with a proof take their commitment from it, and only the others are committed to again
+//! from their records, which leaves the challenges, and so the stored proofs, unchanged.
+//!
+//! For the same reason, the proofs of an execution are not reused for another input, even when
+//! only the last shards read the bytes that changed: the commitments of these shards change, and
+//! with them the challenges that every shard is opened at. A store of another input is cleared,
+//! and the execution is proven from the start.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use bf_core_executor::{Checkpoint, CheckpointError, Executor};
+use bf_core_machine::{
+    brainfuck::BfAir,
+    utils::{BfCoreProverError, TraceStats},
+};
+use bf_stark::{MachineProver, MachineProvingKey, MachineRecord, ShardProof, Val};
+
+use crate::{
+    components::BfProverComponents, BfCoreProof, BfCoreProofData, BfProver, BfProvingKey, CoreSC,
+};
+
+/// The proving key of the core prover of `C` on its device.
+type DeviceProvingKey<C> = <<C as BfProverComponents>::CoreProver as MachineProver<
+    CoreSC,
+    BfAir<Val<CoreSC>>,
+>>::DeviceProvingKey;
+
+/// Errors of proving through a [ShardStore].
+#[derive(Error, Debug)]
+pub enum ShardStoreError {
+    #[error("failed to prove: {0}")]
+    Prover(#[from] BfCoreProverError),
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    #[error("invalid checkpoint: {0}")]
+    Checkpoint(#[from] CheckpointError),
+    #[error("serialization error: {0}")]
+    Serialization(#[from] bincode::Error),
+    /// The store counts more shards than it has checkpoints.
+    #[error("missing the checkpoint of shard {0}")]
+    MissingCheckpoint(usize),
+}
+
+/// The checkpoints and proofs of the shards of an execution, in a directory.
+#[derive(Debug, Clone)]
+pub struct ShardStore {
+    dir: PathBuf,
+}
+
+impl ShardStore {
+    /// Opens the store in `dir`, creating the directory if needed.
+    pub fn open(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    /// The directory of the store.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Makes the store hold the shards of the execution with the given manifest, removing the
+    /// files of another execution. Returns whether the store already held them.
+    pub fn set_manifest(&self, manifest: [u8; 32]) -> io::Result<bool> {
+        if read_optional(&self.dir.join("manifest"))?.is_some_and(|bytes| bytes == manifest) {
+            return Ok(true);
+        }
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.file_name().is_some_and(|name| name.to_string_lossy().starts_with("shard")) {
+                fs::remove_file(path)?;
+            }
+        }
+        write_atomic(&self.dir.join("manifest"), &manifest)?;
+        Ok(false)
+    }
+
+    /// The number of shards, if they are all executed.
+    pub fn num_shards(&self) -> io::Result<Option<usize>> {
+        let Some(bytes) = read_optional(&self.dir.join("shards"))? else {
+            return Ok(None);
+        };
+        let bytes = bytes.try_into().map_err(|_| io::Error::from(io::ErrorKind::InvalidData))?;
+        Ok(Some(u64::from_le_bytes(bytes) as usize))
+    }
+
+    /// Records that the execution has `num_shards` shards, all checkpointed.
+    pub fn set_num_shards(&self, num_shards: usize) -> io::Result<()> {
+        write_atomic(&self.dir.join("shards"), &(num_shards as u64).to_le_bytes())
+    }
+
+    /// The checkpoint after shard `index`, if it was written.
+    pub fn checkpoint(&self, index: usize) -> Result<Option<Checkpoint>, ShardStoreError> {
+        let bytes = read_optional(&self.path(index, "ckpt"))?;
+        Ok(bytes.map(|bytes| Checkpoint::from_bytes(&bytes)).transpose()?)
+    }
+
+    /// Writes the checkpoint after shard `index`.
+    pub fn write_checkpoint(
+        &self,
+        index: usize,
+        checkpoint: &Checkpoint,
+    ) -> Result<(), ShardStoreError> {
+        Ok(write_atomic(&self.path(index, "ckpt"), &checkpoint.to_bytes()?)?)
+    }
+
+    /// The proof of shard `index`, if it was written.
+    pub fn proof(&self, index: usize) -> Result<Option<ShardProof<CoreSC>>, ShardStoreError> {
+        let bytes = read_optional(&self.path(index, "proof"))?;
+        Ok(bytes.map(|bytes| ShardProof::read_from(bytes.as_slice())).transpose()?)
+    }
+
+    /// Writes the proof of shard `index`.
+    pub fn write_proof(
+        &self,
+        index: usize,
+        proof: &ShardProof<CoreSC>,
+    ) -> Result<(), ShardStoreError> {
+        let mut bytes = Vec::new();
+        proof.write_to(&mut bytes)?;
+        Ok(write_atomic(&self.path(index, "proof"), &bytes)?)
+    }
+
+    fn path(&self, index: usize, extension: &str) -> PathBuf {
+        self.dir.join(format!("shard-{index}.{extension}"))
+    }
+}
+
+/// Reads the file at `path`, or `None` if there is none.
+fn read_optional(path: &Path) -> io::Result<Option<Vec<u8>>> {
+    match fs::read(path) {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Writes the file at `path` through a temporary file, so that an interrupted write leaves
+/// either the previous file or none.
+fn write_atomic(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, bytes)?;
+    fs::rename(tmp, path)
+}
+
+impl<C: BfProverComponents> BfProver<C> {
+    /// Proves a program as [BfProver::prove] does, through the files of `store`, so that the
+    /// proof resumes where a previous call on the same key and input stopped.
+    ///
+    /// The shards that were executed are not executed again, and the shards that were proven
+    /// are neither committed to nor opened again; a store of another key or input is cleared
+    /// first. See the [module documentation](self).
+    pub fn prove_resumable(
+        &self,
+        pk: &BfProvingKey,
+        stdin: &[u8],
+        store: &ShardStore,
+    ) -> Result<BfCoreProof, ShardStoreError> {
+        self.check_key(pk).map_err(BfCoreProverError::KeyMismatch)?;
+        let manifest = Sha256::digest(bincode::serialize(&(&pk.digest, stdin))?).into();
+        store.set_manifest(manifest)?;
+        let num_shards = match store.num_shards()? {
+            Some(num_shards) => num_shards,
+            None => self.execute_into(pk, stdin, store)?,
+        };
+
+        let device_pk = self.core_prover.pk_to_device(&pk.pk);
+        let (proofs, output, cycles, trace_stats) =
+            self.install(|| self.prove_stored(&device_pk, store, num_shards))?;
+        Ok(BfCoreProof {
+            proof: BfCoreProofData(proofs),
+            stdin: stdin.to_owned(),
+            public_values: output,
+            cycles,
+            trace_stats,
+        })
+    }
+
+    /// Executes the program shard by shard, checkpointing each shard, and returns the number of
+    /// shards.
+    fn execute_into(
+        &self,
+        pk: &BfProvingKey,
+        stdin: &[u8],
+        store: &ShardStore,
+    ) -> Result<usize, ShardStoreError> {
+        let mut runtime = Executor::new(pk.program.clone(), stdin.to_owned());
+        if let Some(shard_size) = self.opts.shard_size {
+            runtime = runtime.with_shard_size(shard_size);
+        }
+        let mut shards = runtime.shards();
+        let mut num_shards = 0;
+        while let Some(record) = shards.next() {
+            let record = record.map_err(BfCoreProverError::ExecutionError)?;
+            let checkpoint = Checkpoint::new(shards.state().clone(), record);
+            store.write_checkpoint(num_shards, &checkpoint)?;
+            num_shards += 1;
+        }
+        store.set_num_shards(num_shards)?;
+        Ok(num_shards)
+    }
+
+    /// Proves the checkpointed shards that have no proof yet, returning the proofs of all the
+    /// shards, the output, the number of cycles and the sizes of the traces.
+    fn prove_stored(
+        &self,
+        pk: &DeviceProvingKey<C>,
+        store: &ShardStore,
+        num_shards: usize,
+    ) -> Result<(Vec<ShardProof<CoreSC>>, Vec<u8>, u64, TraceStats), ShardStoreError> {
+        let prover = &self.core_prover;
+        let mut challenger = prover.config().challenger();
+        pk.observe_into(&mut challenger);
+
+        let (mut cycles, mut output, mut rows) = (0u64, Vec::new(), Vec::new());
+        let mut proofs = Vec::with_capacity(num_shards);
+        let mut pending = Vec::new();
+        for index in 0..num_shards {
+            let checkpoint = store.checkpoint(index)?;
+            let mut record = checkpoint.ok_or(ShardStoreError::MissingCheckpoint(index))?.record;
+            cycles += record.cpu_events.len() as u64;
+            output.extend(record.output());
+            rows.push(TraceStats::shard_rows(prover.machine(), &record));
+
+            let public_values = record.public_values::<Val<CoreSC>>();
+            let proof = store.proof(index)?.filter(|proof| proof.public_values == public_values);
+            if let Some(proof) = &proof {
+                prover.observe(
+                    &mut challenger,
+                    proof.commitment.main_commit.clone(),
+                    &proof.public_values,
+                );
+            } else {
+                if prover.get_chips(&record).next().is_none() {
+                    return Err(BfCoreProverError::ProvingError("empty shard".to_string()).into());
+                }
+                let data = tracing::info_span!("commit_shard", index)
+                    .in_scope(|| prover.generate_and_commit(&mut record));
+                prover.observe(&mut challenger, data.main_commit.clone(), &public_values);
+                pending.push((index, data, public_values));
+            }
+            proofs.push(proof);
+        }
+
+        // Each proof is stored as soon as it is opened, so that a retry skips it.
+        for (index, data, public_values) in pending {
+            let proof = tracing::info_span!("prove_shard", index)
+                .in_scope(|| prover.open(pk, data, &public_values, &mut challenger.clone()))
+                .map_err(|e| BfCoreProverError::ProvingError(e.to_string()))?;
+            store.write_proof(index, &proof)?;
+            proofs[index] = Some(proof);
+        }
+
+        let proofs = proofs.into_iter().map(|proof| proof.expect("every shard is proven"));
+        let proofs = proofs.collect::<Vec<_>>();
+        let stats = TraceStats::from_rows(prover.machine(), &rows, &proofs);
+        Ok((proofs, output, cycles, stats))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use bf_core_machine::utils::CpuProverOpts;
+
+    use super::*;
+    use crate::components::DefaultProverComponents;
+
+    #[test]
+    fn test_prove_resumable() -> Result<()> {
+        let opts = CpuProverOpts { shard_size: Some(100), ..Default::default() };
+        let prover = BfProver::<DefaultProverComponents>::new().with_opts(opts)?;
+        let (pk, vk) = prover.setup(test_artifacts::FIBO_BF);
+        let dir = tempfile::tempdir()?;
+        let store = ShardStore::open(dir.path())?;
+
+        let proof = prover.prove_resumable(&pk, &[17], &store)?;
+        assert!(proof.proof.0.len() > 1);
+        assert!(proof.check_io() && proof.check_cycles());
+        prover.verify(&proof.proof, &vk)?;
+        let expected = prover.prove(&pk, &[17])?;
+        assert_eq!(bincode::serialize(&proof.proof)?, bincode::serialize(&expected.proof)?);
+
+        // A shard whose proof was lost is proven again, leaving the other proofs as they are.
+        let modified = |index| fs::metadata(store.path(index, "proof")).and_then(|m| m.modified());
+        let first = modified(0)?;
+        fs::remove_file(store.path(1, "proof"))?;
+        let resumed = prover.prove_resumable(&pk, &[17], &store)?;
+        assert_eq!(bincode::serialize(&resumed.proof)?, bincode::serialize(&proof.proof)?);
+        assert_eq!(modified(0)?, first);
+        assert_eq!(resumed.public_values, proof.public_values);
+        assert_eq!(resumed.cycles, proof.cycles);
+
+        // The store of another input is cleared rather than mixed with the new shards.
+        let other = prover.prove_resumable(&pk, &[5], &store)?;
+        assert_eq!(other.public_values, prover.execute(test_artifacts::FIBO_BF, vec![5])?);
+        prover.verify(&other.proof, &vk)?;
+        Ok(())
+    }
+}
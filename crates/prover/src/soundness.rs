@@ -0,0 +1,138 @@
+//! A harness that proves tampered executions, to check that the verifier rejects them.
+//!
+//! The honest prover never produces such proofs, so the harness runs the proving steps itself
+//! and lets the caller mutate the traces, or the public values, before committing to them.
+
+use p3_field::FieldAlgebra;
+use p3_matrix::dense::RowMajorMatrix;
+
+use bf_core_executor::Executor;
+use bf_stark::{MachineProver, MachineProvingKey, MachineRecord, Val};
+
+use crate::{components::BfProverComponents, BfCoreProofData, BfProver, BfProvingKey, CoreSC};
+
+/// The main traces of a shard, by chip name.
+pub type NamedTraces = Vec<(String, RowMajorMatrix<Val<CoreSC>>)>;
+
+impl<C: BfProverComponents> BfProver<C> {
+    /// Proves the execution of `pk`'s program on `stdin` as [BfProver::prove] does, except that
+    /// `tamper` may change the main traces and the public values before they are committed to.
+    ///
+    /// ### Panics
+    ///
+    /// Panics if the program does not execute.
+    pub fn prove_tampered(
+        &self,
+        pk: &BfProvingKey,
+        stdin: &[u8],
+        tamper: impl FnOnce(&mut NamedTraces, &mut Vec<Val<CoreSC>>),
+    ) -> BfCoreProofData {
+        let mut runtime = Executor::new(pk.program.clone(), stdin.to_owned());
+        runtime.run().expect("failed to execute the program");
+        let record = &mut runtime.record;
+
+        let prover = &self.core_prover;
+        prover.machine().generate_dependencies(record, None);
+        let mut traces = prover.generate_traces(record);
+        let mut public_values = record.public_values::<Val<CoreSC>>();
+        tamper(&mut traces, &mut public_values);
+
+        let device_pk = prover.pk_to_device(&pk.pk);
+        let mut challenger = prover.config().challenger();
+        device_pk.observe_into(&mut challenger);
+        let data = prover.commit(traces);
+        prover.observe(&mut challenger, data.main_commit.clone(), &public_values);
+        let proof = prover
+            .open(&device_pk, data, &public_values, &mut challenger)
+            .expect("failed to open the tampered traces");
+        BfCoreProofData(vec![proof])
+    }
+}
+
+/// Adds one to the cell of the trace of `chip` at `row` and `col`.
+///
+/// ### Panics
+///
+/// Panics if the shard has no trace for `chip`.
+pub fn bump_cell(traces: &mut NamedTraces, chip: &str, row: usize, col: usize) {
+    let (_, trace) = traces.iter_mut().find(|(name, _)| name == chip).expect("unknown chip");
+    trace.values[row * trace.width + col] += Val::<CoreSC>::ONE;
+}
+
+#[cfg(test)]
+mod tests {
+    use bf_core_executor::NUM_PUBLIC_VALUES;
+    use bf_stark::{Challenge, ChipOpenedValues};
+
+    use super::*;
+    use crate::components::DefaultProverComponents;
+
+    #[test]
+    fn test_untampered_proof_verifies() {
+        let prover = BfProver::<DefaultProverComponents>::new();
+        let (pk, vk) = prover.setup(test_artifacts::HELLO_BF);
+        let proof = prover.prove_tampered(&pk, &[], |_, _| {});
+        prover.verify(&proof, &vk).unwrap();
+    }
+
+    #[test]
+    fn test_tampered_trace_cells() {
+        let prover = BfProver::<DefaultProverComponents>::new();
+        let (pk, vk) = prover.setup(test_artifacts::HELLO_BF);
+        let chips = prover.prove(&pk, &[]).unwrap().proof.0[0].chip_ordering;
+
+        // The first row of each chip is real, so its cells are all constrained.
+        for chip in chips.keys() {
+            let proof = prover.prove_tampered(&pk, &[], |traces, _| bump_cell(traces, chip, 0, 0));
+            assert!(prover.verify(&proof, &vk).is_err(), "tampering with {chip} was not detected");
+        }
+    }
+
+    #[test]
+    fn test_tampered_lookup_multiplicities() {
+        let prover = BfProver::<DefaultProverComponents>::new();
+        let (pk, vk) = prover.setup(test_artifacts::HELLO_BF);
+
+        // The main traces of the Byte and Program chips are their lookup multiplicities.
+        for (chip, row) in [("Byte", 7), ("Program", 3)] {
+            let proof =
+                prover.prove_tampered(&pk, &[], |traces, _| bump_cell(traces, chip, row, 0));
+            assert!(prover.verify(&proof, &vk).is_err(), "{chip} multiplicity was not checked");
+        }
+    }
+
+    #[test]
+    fn test_tampered_public_values() {
+        let prover = BfProver::<DefaultProverComponents>::new();
+        let (pk, vk) = prover.setup(test_artifacts::FIBO_BF);
+
+        for i in 0..NUM_PUBLIC_VALUES {
+            let proof = prover.prove_tampered(&pk, &[17], |_, public_values| {
+                public_values[i] += Val::<CoreSC>::ONE;
+            });
+            assert!(prover.verify(&proof, &vk).is_err(), "public value {i} was not checked");
+        }
+    }
+
+    #[test]
+    fn test_tampered_openings() {
+        let prover = BfProver::<DefaultProverComponents>::new();
+        let (pk, vk) = prover.setup(test_artifacts::HELLO_BF);
+        let proof = prover.prove(&pk, &[]).unwrap().proof;
+        let cpu = proof.0[0].chip_ordering["Cpu"];
+
+        let one = Challenge::<CoreSC>::ONE;
+        let tampers: [fn(&mut ChipOpenedValues<Challenge<CoreSC>>, Challenge<CoreSC>); 5] = [
+            |values, one| values.main.local[0] += one,
+            |values, one| values.main.next[0] += one,
+            |values, one| values.permutation.local[0] += one,
+            |values, one| values.quotient[0][0] += one,
+            |values, one| values.cumulative_sum += one,
+        ];
+        for (i, tamper) in tampers.into_iter().enumerate() {
+            let mut tampered = proof.clone();
+            tamper(&mut tampered.0[0].opened_values.chips[cpu], one);
+            assert!(prover.verify(&tampered, &vk).is_err(), "opening {i} was not checked");
+        }
+    }
+}
@@ -1,22 +1,45 @@
+use p3_field::PrimeField64;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
-use bf_stark::{ShardProof, StarkProvingKey, StarkVerifyingKey};
+use bf_core_executor::{Program, CYCLES_PUBLIC_VALUE, INPUT_COUNT_PUBLIC_VALUE};
+use bf_core_machine::utils::TraceStats;
+use bf_stark::{ShardProof, StarkProvingKey, StarkVerifyingKey, Val};
 
-use crate::CoreSC;
+use crate::{verify::check_io, CoreSC, ProofSystemInfo};
 
 /// The information necessary to generate a proof for a given program.
 #[derive(Clone, Serialize, Deserialize)]
 pub struct BfProvingKey {
     pub pk: StarkProvingKey<CoreSC>,
     pub elf: String,
+    /// The parsed `elf`, so that proving does not parse it again.
+    pub program: Program,
     /// Verifying key is also included as we need it for recursion
     pub vk: BfVerifyingKey,
+    /// The digest of the elf, the program and the preprocessed commitment, taken at setup, see
+    /// [BfProvingKey::compute_digest].
+    pub digest: [u8; 32],
+}
+
+impl BfProvingKey {
+    /// Hashes the elf, the program and the preprocessed commitment of the key.
+    ///
+    /// Setup stores the digest in the key, so that proving checks that the parts of the key
+    /// still belong together by hashing them instead of setting the program up again.
+    pub fn compute_digest(&self) -> [u8; 32] {
+        let parts = (&self.elf, &self.program, &self.pk.commit);
+        let bytes = bincode::serialize(&parts).expect("failed to serialize the proving key");
+        Sha256::digest(bytes).into()
+    }
 }
 
 /// The information necessary to verify a proof for a given program.
 #[derive(Clone, Serialize, Deserialize)]
 pub struct BfVerifyingKey {
     pub vk: StarkVerifyingKey<CoreSC>,
+    /// The proving system the key was set up with.
+    pub system: ProofSystemInfo,
 }
 
 /// A proof of a ELF execution with given inputs and outputs.
@@ -28,10 +51,46 @@ pub struct BfProofWithMetadata<P: Clone> {
     pub stdin: Vec<u8>,
     pub public_values: Vec<u8>,
     pub cycles: u64,
+    /// The sizes of the traces proven, to compare the cost of proofs.
+    #[serde(default)]
+    pub trace_stats: TraceStats,
 }
 
 /// A proof of a program without any wrapping.
 pub type BfCoreProof = BfProofWithMetadata<BfCoreProofData>;
 
+impl BfCoreProof {
+    /// Whether `cycles` is the number of cycles the proof commits to.
+    pub fn check_cycles(&self) -> bool {
+        self.proof.cycles() == Some(self.cycles)
+    }
+
+    /// Whether `stdin` and `public_values` are the input and output streams the proof commits
+    /// to, see [check_io](crate::verify::check_io).
+    pub fn check_io(&self) -> bool {
+        check_io(&self.proof.0, Some(self.stdin.as_slice()), &self.public_values)
+    }
+}
+
+/// The shard proofs of an execution, in the order of execution.
 #[derive(Serialize, Deserialize, Clone)]
-pub struct BfCoreProofData(pub ShardProof<CoreSC>);
+pub struct BfCoreProofData(pub Vec<ShardProof<CoreSC>>);
+
+impl BfCoreProofData {
+    /// The number of cycles in the public values of the shards, which the CPU constrains.
+    pub fn cycles(&self) -> Option<u64> {
+        self.0.iter().try_fold(0, |cycles, shard| {
+            let shard_cycles = shard.public_values.get(CYCLES_PUBLIC_VALUE)?;
+            cycles.checked_add(shard_cycles.as_canonical_u64())
+        })
+    }
+
+    /// The number of bytes the program read from the input stream, the sum of the counts in the
+    /// public values of the shards, which the IO chip constrains.
+    pub fn input_count(&self) -> Option<usize> {
+        self.0.iter().try_fold(0usize, |count, shard| {
+            let shard_count = shard.public_values.get(INPUT_COUNT_PUBLIC_VALUE)?;
+            count.checked_add(shard_count.as_canonical_u64().try_into().ok()?)
+        })
+    }
+}
@@ -1,37 +1,227 @@
 use anyhow::Result;
+use p3_field::{Field, FieldAlgebra, PrimeField64, TwoAdicField};
 
-use bf_core_machine::cpu::MAX_CPU_LOG_DEGREE;
-use bf_stark::{MachineProof, MachineProver, MachineVerificationError, StarkGenericConfig};
+use bf_core_executor::{
+    io_digest, ShardBoundary, CYCLES_PUBLIC_VALUE, INPUT_COUNT_PUBLIC_VALUE, IO_DIGEST_LEN,
+    LAST_SHARD_PUBLIC_VALUE, MAX_CLK, NEXT_PUBLIC_VALUES, NUM_PUBLIC_VALUES,
+    OUTPUT_COUNT_PUBLIC_VALUE, PROGRAM_LEN_PUBLIC_VALUE, START_PUBLIC_VALUES,
+};
+use bf_core_machine::{brainfuck::BfAir, cpu::MAX_CPU_LOG_DEGREE, io::IoChip};
+use bf_stark::{
+    air::MachineAir, MachineProof, MachineProver, MachineVerificationError, ShardProof,
+    StarkGenericConfig, StarkMachine, Val, VerificationError,
+};
 
 use crate::{components::BfProverComponents, BfCoreProofData, BfProver, BfVerifyingKey, CoreSC};
 
+/// The machine that core proofs are generated and verified with.
+pub type CoreMachine = StarkMachine<CoreSC, BfAir<<CoreSC as StarkGenericConfig>::Val>>;
+
+/// The maximum number of shards of a core proof. Every shard runs at least one cycle, and the
+/// clk of the last one stays below [`MAX_CLK`].
+pub const MAX_CORE_PROOF_SHARDS: usize = (MAX_CLK / 2) as usize;
+
+/// Bounds on the size of the proofs a verifier accepts, and so on its worst-case work.
+///
+/// The verification time grows with the number of shards and with the height of their traces, so
+/// a service verifying untrusted proofs can reject large ones before checking them. The default
+/// limits accept every proof the prover generates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VerifierLimits {
+    /// The maximum number of shards of a proof.
+    pub max_shards: usize,
+    /// The maximum log degree of the trace of each chip of a shard, preprocessed or not.
+    pub max_log_degree: usize,
+}
+
+impl Default for VerifierLimits {
+    fn default() -> Self {
+        Self {
+            max_shards: MAX_CORE_PROOF_SHARDS,
+            max_log_degree: <Val<CoreSC> as TwoAdicField>::TWO_ADICITY,
+        }
+    }
+}
+
+impl VerifierLimits {
+    /// Checks the number of shards of `proof` and the log degrees of their traces.
+    pub fn check(&self, proof: &BfCoreProofData) -> Result<(), MachineVerificationError<CoreSC>> {
+        if proof.0.len() > self.max_shards {
+            return Err(MachineVerificationError::TooManyShards(proof.0.len()));
+        }
+
+        for shard in &proof.0 {
+            let mut chips = shard.chip_ordering.iter().collect::<Vec<_>>();
+            chips.sort_by_key(|(_, &index)| index);
+            for (name, &index) in chips {
+                // An index out of range fails the verification of the chip ordering.
+                let Some(values) = shard.opened_values.chips.get(index) else { continue };
+                if values.log_degree > self.max_log_degree {
+                    return Err(MachineVerificationError::LogDegreeTooLarge(
+                        name.clone(),
+                        values.log_degree,
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
 impl<C: BfProverComponents> BfProver<C> {
-    /// Verify a core proof by verifying the shard, verifying lookup bus.
+    /// Verify a core proof by verifying the shards, verifying lookup bus.
+    ///
+    /// The proof must be within the [limits](BfProver::limits) of the prover.
     pub fn verify(
         &self,
         proof: &BfCoreProofData,
         vk: &BfVerifyingKey,
     ) -> Result<(), MachineVerificationError<CoreSC>> {
-        let shard = &proof.0;
+        verify_core_proof_with_limits(self.core_prover.machine(), proof, vk, self.limits())
+    }
+}
+
+/// Verify a core proof with the given machine.
+///
+/// Unlike [BfProver::verify], this only needs the machine's chips and config, so callers that
+/// never prove can skip constructing a prover.
+pub fn verify_core_proof(
+    machine: &CoreMachine,
+    proof: &BfCoreProofData,
+    vk: &BfVerifyingKey,
+) -> Result<(), MachineVerificationError<CoreSC>> {
+    verify_core_proof_with_limits(machine, proof, vk, VerifierLimits::default())
+}
+
+/// Verify a core proof with the given machine, rejecting it if it exceeds `limits`.
+///
+/// The limits are checked before the proof, so a proof exceeding them costs little to reject.
+pub fn verify_core_proof_with_limits(
+    machine: &CoreMachine,
+    proof: &BfCoreProofData,
+    vk: &BfVerifyingKey,
+    limits: VerifierLimits,
+) -> Result<(), MachineVerificationError<CoreSC>> {
+    limits.check(proof)?;
+    if proof.0.is_empty() {
+        return Err(MachineVerificationError::EmptyProof);
+    }
+
+    let io_chip = MachineAir::<Val<CoreSC>>::name(&IoChip);
+    let mut previous_next = ShardBoundary::<Val<CoreSC>>::default();
+    for (i, shard) in proof.0.iter().enumerate() {
+        // Every shard runs at least one cycle, and carries the tapes on to the next one.
         if !shard.contains_cpu() {
-            return Err(MachineVerificationError::MissingCpuInFirstShard);
+            return Err(match i {
+                0 => MachineVerificationError::MissingCpuInFirstShard,
+                _ => MachineVerificationError::InvalidChipOccurrence("Cpu".to_string()),
+            });
+        }
+        if !shard.chip_ordering.contains_key("MemoryInstrs") {
+            return Err(MachineVerificationError::InvalidChipOccurrence(
+                "MemoryInstrs".to_string(),
+            ));
         }
 
         // CPU log degree bound constraints.
         //
-        // Assert that the CPU log degree does not exceed `MAX_CPU_LOG_DEGREE`. This is to ensure
-        // that the lookup argument's multiplicities do not overflow.
-        let shard_proof = &proof.0;
-        let log_degree_cpu = shard_proof.log_degree_cpu();
+        // Assert that the CPU log degree does not exceed `MAX_CPU_LOG_DEGREE`. This is to
+        // ensure that the lookup argument's multiplicities do not overflow, and that the clk
+        // stays within the 24 bits the memory argument range checks.
+        let log_degree_cpu = shard.log_degree_cpu().ok_or(
+            MachineVerificationError::InvalidShardProof(VerificationError::InvalidChipOrdering),
+        )?;
         if log_degree_cpu > MAX_CPU_LOG_DEGREE {
             return Err(MachineVerificationError::CpuLogDegreeTooLarge(log_degree_cpu));
         }
 
-        // Verify the shard proof.
-        let mut challenger = self.core_prover.config().challenger();
-        let machine_proof = MachineProof { shard_proof: proof.0.clone() };
-        self.core_prover.machine().verify(&vk.vk, &machine_proof, &mut challenger)?;
+        // The shards must continue each other, from the initial state of the program.
+        let public_values = &shard.public_values;
+        if public_values.len() != NUM_PUBLIC_VALUES {
+            return Err(MachineVerificationError::InvalidPublicValues("wrong length"));
+        }
+        let start = ShardBoundary::from_public_values(public_values, START_PUBLIC_VALUES);
+        if start != previous_next {
+            return Err(MachineVerificationError::InvalidPublicValues("shard start"));
+        }
+        previous_next = ShardBoundary::from_public_values(public_values, NEXT_PUBLIC_VALUES);
 
-        Ok(())
+        let last_shard = i == proof.0.len() - 1;
+        if public_values[LAST_SHARD_PUBLIC_VALUE] != Val::<CoreSC>::from_bool(last_shard) {
+            return Err(MachineVerificationError::InvalidPublicValues("last shard flag"));
+        }
+        // The IO chip constrains the digests and the counts of a shard, so a shard without it
+        // has no I/O and leaves them at zero.
+        if !shard.chip_ordering.contains_key(&io_chip)
+            && public_values[..CYCLES_PUBLIC_VALUE].iter().any(|v| !v.is_zero())
+        {
+            return Err(MachineVerificationError::InvalidPublicValues("shard digests"));
+        }
+        // The program chip binds the length of the program, so the last shard must end after the
+        // last instruction: a proof of a prefix of the execution is not a proof of it.
+        if last_shard && previous_next.pc != public_values[PROGRAM_LEN_PUBLIC_VALUE] {
+            return Err(MachineVerificationError::InvalidPublicValues("program end"));
+        }
     }
+
+    // Verify the shard proofs.
+    let mut challenger = machine.config().challenger();
+    let machine_proof = MachineProof { shard_proofs: proof.0.clone() };
+    machine.verify(&vk.vk, &machine_proof, &mut challenger)?;
+
+    Ok(())
+}
+
+/// Whether the I/O streams `shards` commit to are `stdin` and `output`.
+///
+/// Each shard commits to the bytes it read and wrote: their numbers, and their digests, the
+/// Poseidon2 sponges the IO chip computes, see [IoDigest](bf_core_executor::IoDigest). The
+/// output of the shards must be the whole of `output`, and their input the first
+/// [input count](crate::BfCoreProofData::input_count) bytes of `stdin`, the bytes the program
+/// read. Without `stdin`, e.g. when it was stripped from a published proof, only the output is
+/// checked.
+///
+/// This only reads the public values: verify the shards to trust them.
+pub fn check_io(shards: &[ShardProof<CoreSC>], stdin: Option<&[u8]>, output: &[u8]) -> bool {
+    let Some(last_shard) = shards.last() else { return false };
+    // The IO chip produces the digests, so a proof without it does not bind the I/O.
+    if !last_shard.chip_ordering.contains_key(&MachineAir::<Val<CoreSC>>::name(&IoChip)) {
+        return false;
+    }
+
+    // The shards digest consecutive parts of the streams, so each one consumes its counts.
+    let (mut read, mut written) = (0usize, 0usize);
+    for shard in shards {
+        let public_values = &shard.public_values;
+        if public_values.len() != NUM_PUBLIC_VALUES {
+            return false;
+        }
+        let count = |index: usize| usize::try_from(public_values[index].as_canonical_u64()).ok();
+        let (Some(input_count), Some(output_count)) =
+            (count(INPUT_COUNT_PUBLIC_VALUE), count(OUTPUT_COUNT_PUBLIC_VALUE))
+        else {
+            return false;
+        };
+        let (input_digest, rest) = public_values.split_at(IO_DIGEST_LEN);
+
+        let Some(shard_output) = next_bytes(output, &mut written, output_count) else {
+            return false;
+        };
+        if rest[..IO_DIGEST_LEN] != io_digest::<Val<CoreSC>>(shard_output) {
+            return false;
+        }
+        let Some(stdin) = stdin else { continue };
+        let Some(shard_input) = next_bytes(stdin, &mut read, input_count) else { return false };
+        if input_digest != io_digest::<Val<CoreSC>>(shard_input) {
+            return false;
+        }
+    }
+    written == output.len()
+}
+
+/// The `count` bytes of `stream` from `offset`, moving `offset` past them.
+fn next_bytes<'a>(stream: &'a [u8], offset: &mut usize, count: usize) -> Option<&'a [u8]> {
+    let bytes = stream.get(*offset..offset.checked_add(count)?)?;
+    *offset += count;
+    Some(bytes)
 }
@@ -0,0 +1,132 @@
+//! The version of the proving system, embedded in verifying keys and proofs.
+//!
+//! A verifier only accepts keys and proofs whose [ProofSystemInfo] is compatible with its own,
+//! so a mismatch is reported as such instead of as an opaque transcript failure:
+//!
+//! | Verifier  | Accepts proofs of                    |
+//! |-----------|--------------------------------------|
+//! | `0.y.z`   | `0.y.*`                              |
+//! | `x.y.z`   | `x.0.*` to `x.y.*`, for `x >= 1`     |
+//!
+//! and in every case, the same parameter hash.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::CoreSC;
+
+/// The identifier of [CoreSC], part of the parameter hash.
+pub const CORE_CONFIG_ID: &str = "koala-bear-poseidon2";
+
+/// The semantic version of the proving system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofSystemVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl ProofSystemVersion {
+    /// The version of this crate.
+    pub const CURRENT: Self = Self {
+        major: parse_u32(env!("CARGO_PKG_VERSION_MAJOR")),
+        minor: parse_u32(env!("CARGO_PKG_VERSION_MINOR")),
+        patch: parse_u32(env!("CARGO_PKG_VERSION_PATCH")),
+    };
+
+    /// Whether a verifier of this version accepts proofs of the given version.
+    pub const fn accepts(&self, proof: &Self) -> bool {
+        if self.major == 0 {
+            proof.major == 0 && proof.minor == self.minor
+        } else {
+            proof.major == self.major && proof.minor <= self.minor
+        }
+    }
+}
+
+impl fmt::Display for ProofSystemVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+const fn parse_u32(s: &str) -> u32 {
+    let bytes = s.as_bytes();
+    let mut value = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        value = value * 10 + (bytes[i] - b'0') as u32;
+        i += 1;
+    }
+    value
+}
+
+/// The version and the parameters a verifying key or a proof was generated with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofSystemInfo {
+    pub version: ProofSystemVersion,
+    /// The hash of the config, see [params_hash].
+    pub params_hash: [u8; 32],
+}
+
+impl ProofSystemInfo {
+    /// The info of this crate proving with the given config.
+    pub fn new(config: &CoreSC) -> Self {
+        Self { version: ProofSystemVersion::CURRENT, params_hash: params_hash(config) }
+    }
+
+    /// Whether a verifier with this info accepts keys and proofs with the given info.
+    pub fn accepts(&self, other: &Self) -> bool {
+        self.version.accepts(&other.version) && self.params_hash == other.params_hash
+    }
+}
+
+impl fmt::Display for ProofSystemInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "v{} with parameters ", self.version)?;
+        for byte in &self.params_hash[..4] {
+            write!(f, "{byte:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Hashes the identifier of the config and its FRI parameters.
+pub fn params_hash(config: &CoreSC) -> [u8; 32] {
+    let params = bincode::serialize(&(CORE_CONFIG_ID, config.fri_parameters()))
+        .expect("failed to serialize parameters");
+    Sha256::digest(params).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use bf_stark::koala_bear_poseidon2::FriParameters;
+
+    use super::*;
+
+    fn version(major: u32, minor: u32, patch: u32) -> ProofSystemVersion {
+        ProofSystemVersion { major, minor, patch }
+    }
+
+    #[test]
+    fn test_compatibility_table() {
+        assert!(version(0, 3, 0).accepts(&version(0, 3, 7)));
+        assert!(!version(0, 3, 0).accepts(&version(0, 2, 0)));
+        assert!(!version(0, 3, 0).accepts(&version(1, 3, 0)));
+        assert!(version(2, 4, 0).accepts(&version(2, 0, 9)));
+        assert!(!version(2, 4, 0).accepts(&version(2, 5, 0)));
+        assert!(!version(2, 4, 0).accepts(&version(1, 4, 0)));
+        assert!(ProofSystemVersion::CURRENT.accepts(&ProofSystemVersion::CURRENT));
+    }
+
+    #[test]
+    fn test_params_hash() {
+        let info = |params| ProofSystemInfo::new(&CoreSC::with_fri_parameters(params));
+        let standard = info(FriParameters::standard());
+        let fast = info(FriParameters::fast());
+        assert!(standard.accepts(&standard));
+        assert!(!standard.accepts(&fast));
+    }
+}
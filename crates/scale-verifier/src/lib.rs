@@ -0,0 +1,154 @@
+//! SCALE-encoded proofs and verifying keys, for Substrate pallets and ink! contracts accepting
+//! Brainfuck zkVM proofs.
+//!
+//! The types are `no_std` and can be stored and passed around on chain. The STARK verifier itself
+//! needs `std`, so runtimes verify through [verify] on the native side, typically behind a
+//! runtime interface:
+//!
+//! ```ignore
+//! #[sp_runtime_interface::runtime_interface]
+//! pub trait BfVerifier {
+//!     fn verify(proof: &Proof, vk: &VerifyingKey) -> Result<(), VerifyError> {
+//!         bf_scale_verifier::verify(proof, vk)
+//!     }
+//! }
+//! ```
+//!
+//! Verifying inside the runtime, e.g. in an ink! contract or in a pallet without a runtime
+//! interface, is out of scope: there is no `no_std` verifier.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use codec::{Decode, Encode};
+use scale_info::TypeInfo;
+
+/// The largest proof accepted by [verify], in bytes. Pallets can use it to bound storage.
+///
+/// A proof grows with its number of shards, so the limit also bounds the length of the
+/// executions accepted, to a few shards of the largest traces.
+pub const MAX_PROOF_SIZE: u32 = 4 << 20;
+
+/// The largest verifying key accepted by [verify], in bytes.
+pub const MAX_VERIFYING_KEY_SIZE: u32 = 64 << 10;
+
+/// A verifying key, bincode-serialized, with its `bf_sdk::vk_digest`.
+#[derive(Clone, PartialEq, Eq, Debug, Encode, Decode, TypeInfo)]
+pub struct VerifyingKey {
+    /// Identifies the program on chain. [verify] checks it against the key.
+    pub digest: [u8; 32],
+    pub bytes: Vec<u8>,
+}
+
+/// A proof, as written by `BfProofWithPublicValues::to_bytes`, with its output.
+#[derive(Clone, PartialEq, Eq, Debug, Encode, Decode, TypeInfo)]
+pub struct Proof {
+    /// The output of the program, readable without decoding the proof. [verify] checks it
+    /// against the proof.
+    pub output: Vec<u8>,
+    pub bytes: Vec<u8>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Encode, Decode, TypeInfo)]
+pub enum VerifyError {
+    /// The proof or the key exceeds its maximum size.
+    TooLarge,
+    /// The verifying key does not decode.
+    InvalidVerifyingKey,
+    /// The digest does not match the verifying key.
+    DigestMismatch,
+    /// The proof does not decode.
+    InvalidProof,
+    /// The output does not match the proof.
+    OutputMismatch,
+    /// The proof does not verify against the key.
+    Rejected,
+}
+
+#[cfg(feature = "std")]
+impl VerifyingKey {
+    pub fn new(vk: &bf_sdk::BfVerifyingKey) -> Self {
+        Self {
+            digest: bf_sdk::vk_digest(vk),
+            bytes: bincode::serialize(vk).expect("failed to serialize verifying key"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Proof {
+    pub fn new(proof: &bf_sdk::BfProofWithPublicValues) -> Result<Self, bincode::Error> {
+        Ok(Self { output: proof.output.clone(), bytes: proof.to_bytes()? })
+    }
+}
+
+/// Verifies a proof.
+#[cfg(feature = "std")]
+pub fn verify(proof: &Proof, vk: &VerifyingKey) -> Result<(), VerifyError> {
+    if proof.bytes.len() > MAX_PROOF_SIZE as usize
+        || vk.bytes.len() > MAX_VERIFYING_KEY_SIZE as usize
+    {
+        return Err(VerifyError::TooLarge);
+    }
+
+    let key: bf_sdk::BfVerifyingKey =
+        bincode::deserialize(&vk.bytes).map_err(|_| VerifyError::InvalidVerifyingKey)?;
+    if bf_sdk::vk_digest(&key) != vk.digest {
+        return Err(VerifyError::DigestMismatch);
+    }
+    let decoded = bf_sdk::BfProofWithPublicValues::from_bytes(&proof.bytes)
+        .map_err(|_| VerifyError::InvalidProof)?;
+    if decoded.output != proof.output {
+        return Err(VerifyError::OutputMismatch);
+    }
+    bf_sdk::verify_proof(&decoded, &key).map_err(|_| VerifyError::Rejected)
+}
+
+#[cfg(test)]
+mod tests {
+    use bf_sdk::{ProverClient, ProverClientConfig};
+
+    use super::*;
+
+    #[test]
+    fn test_verify() {
+        let client = ProverClient::new();
+        let (pk, vk) = client.setup("++.");
+        let proof = Proof::new(&client.prove(&pk, vec![]).run().unwrap()).unwrap();
+        let vk = VerifyingKey::new(&vk);
+
+        let proof = Proof::decode(&mut proof.encode().as_slice()).unwrap();
+        let vk = VerifyingKey::decode(&mut vk.encode().as_slice()).unwrap();
+        assert_eq!(verify(&proof, &vk), Ok(()));
+
+        let mut wrong_output = proof.clone();
+        wrong_output.output = vec![3];
+        assert_eq!(verify(&wrong_output, &vk), Err(VerifyError::OutputMismatch));
+
+        let mut wrong_digest = vk.clone();
+        wrong_digest.digest[0] ^= 1;
+        assert_eq!(verify(&proof, &wrong_digest), Err(VerifyError::DigestMismatch));
+
+        let (_, other_vk) = client.setup("+++.");
+        assert_eq!(verify(&proof, &VerifyingKey::new(&other_vk)), Err(VerifyError::Rejected));
+    }
+
+    #[test]
+    fn test_verify_sharded() {
+        let config = ProverClientConfig { shard_size: Some(100), ..Default::default() };
+        let client = ProverClient::from_config(config).unwrap();
+        let (pk, vk) = client.setup(test_artifacts::FIBO_BF);
+        let proof = client.prove(&pk, vec![17]).run().unwrap();
+        assert!(proof.proof.len() > 1);
+        let proof = Proof::new(&proof).unwrap();
+        assert!(proof.bytes.len() < MAX_PROOF_SIZE as usize);
+        assert_eq!(verify(&proof, &VerifyingKey::new(&vk)), Ok(()));
+
+        let mut too_large = proof.clone();
+        too_large.bytes.resize(MAX_PROOF_SIZE as usize + 1, 0);
+        assert_eq!(verify(&too_large, &VerifyingKey::new(&vk)), Err(VerifyError::TooLarge));
+    }
+}
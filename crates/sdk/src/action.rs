@@ -1,9 +1,17 @@
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Instant,
+};
+
 use anyhow::{Ok, Result};
+use rayon::{prelude::*, ThreadPoolBuilder};
 
 use bf_prover::components::DefaultProverComponents;
-use bf_prover::types::BfProvingKey;
+use bf_prover::types::{BfProvingKey, BfVerifyingKey};
 
-use crate::{BfProofWithPublicValues, Prover};
+use crate::cache::{ProofCacheKey, ProofStore};
+use crate::metrics::{self, Phase};
+use crate::{BfProofWithPublicValues, BfVerificationError, Prover};
 
 /// Builder to prepare and configure execution of a program on an input.
 /// May be run with [Self::run].
@@ -11,6 +19,7 @@ pub struct Execute<'a> {
     prover: &'a dyn Prover<DefaultProverComponents>,
     elf: &'a str,
     stdin: Vec<u8>,
+    private_input: Vec<u8>,
 }
 
 impl<'a> Execute<'a> {
@@ -23,13 +32,22 @@ impl<'a> Execute<'a> {
         elf: &'a str,
         stdin: Vec<u8>,
     ) -> Self {
-        Self { prover, elf, stdin }
+        Self { prover, elf, stdin, private_input: vec![] }
+    }
+
+    /// Set the private input, read by the private input instructions.
+    pub fn private_input(mut self, private_input: Vec<u8>) -> Self {
+        self.private_input = private_input;
+        self
     }
 
     /// Execute the program on the input, consuming the built action `self`.
     pub fn run(self) -> Result<Vec<u8>> {
-        let Self { prover, elf, stdin } = self;
-        Ok(prover.prover().execute(elf, stdin)?)
+        let Self { prover, elf, stdin, private_input } = self;
+        let start = Instant::now();
+        let output = prover.prover().execute_with_private_input(elf, stdin, private_input)?;
+        metrics::record_phase(Phase::Execute, start.elapsed());
+        Ok(output)
     }
 }
 
@@ -39,6 +57,9 @@ pub struct Prove<'a> {
     prover: &'a dyn Prover<DefaultProverComponents>,
     pk: &'a BfProvingKey,
     stdin: Vec<u8>,
+    private_input: Vec<u8>,
+    program_name: Option<String>,
+    cache: Option<&'a dyn ProofStore>,
 }
 
 impl<'a> Prove<'a> {
@@ -51,12 +72,125 @@ impl<'a> Prove<'a> {
         pk: &'a BfProvingKey,
         stdin: Vec<u8>,
     ) -> Self {
-        Self { prover, pk, stdin }
+        Self { prover, pk, stdin, private_input: vec![], program_name: None, cache: None }
+    }
+
+    /// Set the private input, read by the private input instructions. Unlike the input, the
+    /// proof does not reveal it.
+    ///
+    /// A proof with a private input is not cached, since the cache is keyed by the input.
+    pub fn private_input(mut self, private_input: Vec<u8>) -> Self {
+        self.private_input = private_input;
+        self
+    }
+
+    /// Record the name of the program in the proof metadata.
+    pub fn program_name(mut self, name: impl Into<String>) -> Self {
+        self.program_name = Some(name.into());
+        self
+    }
+
+    /// Consult `cache` before proving, and store the generated proof in it.
+    ///
+    /// A cached proof is only returned if it verifies against the proving key's verifying key.
+    pub fn cache(mut self, cache: &'a dyn ProofStore) -> Self {
+        self.cache = Some(cache);
+        self
     }
 
     /// Prove the execution of the program on the input, consuming the built action `self`.
     pub fn run(self) -> Result<BfProofWithPublicValues> {
-        let Self { prover, pk, stdin } = self;
-        prover.prove(pk, stdin)
+        let Self { prover, pk, stdin, private_input, program_name, cache } = self;
+
+        let Some(cache) = cache.filter(|_| private_input.is_empty()) else {
+            let mut proof = prover.prove_with_private_input(pk, stdin, private_input)?;
+            proof.metadata.program_name = program_name;
+            return Ok(proof);
+        };
+
+        let key = ProofCacheKey::new(prover, pk, &stdin);
+        let cached = cache
+            .get(&key)
+            .and_then(|bytes| BfProofWithPublicValues::from_bytes(&bytes).ok())
+            .filter(|proof| {
+                proof.stdin.try_bytes() == Ok(&stdin[..]) && prover.verify(proof, &pk.vk).is_ok()
+            });
+        if let Some(mut proof) = cached {
+            tracing::info!("using cached proof {}", key.to_hex());
+            proof.metadata.program_name = program_name;
+            return Ok(proof);
+        }
+
+        let mut proof = prover.prove(pk, stdin)?;
+        proof.metadata.program_name = program_name;
+        match proof.to_bytes() {
+            Ok(bytes) => cache.put(&key, &bytes),
+            Err(err) => tracing::warn!("failed to cache proof {}: {}", key.to_hex(), err),
+        }
+        Ok(proof)
+    }
+}
+
+/// Builder to prepare and configure verifying a batch of proofs in parallel.
+/// May be run with [Self::run].
+pub struct VerifyBatch<'a> {
+    prover: &'a dyn Prover<DefaultProverComponents>,
+    items: &'a [(BfProofWithPublicValues, BfVerifyingKey)],
+    fail_fast: bool,
+    num_threads: Option<usize>,
+}
+
+impl<'a> VerifyBatch<'a> {
+    /// Prepare to verify each proof against its verifying key.
+    ///
+    /// Prefer using [ProverClient::verify_batch](super::ProverClient::verify_batch).
+    /// See there for more documentation.
+    pub fn new(
+        prover: &'a dyn Prover<DefaultProverComponents>,
+        items: &'a [(BfProofWithPublicValues, BfVerifyingKey)],
+    ) -> Self {
+        Self { prover, items, fail_fast: false, num_threads: None }
+    }
+
+    /// Skip the proofs not yet started once a proof is rejected.
+    pub fn fail_fast(mut self) -> Self {
+        self.fail_fast = true;
+        self
+    }
+
+    /// Verify on a pool of `num_threads` threads instead of the global rayon pool.
+    pub fn num_threads(mut self, num_threads: usize) -> Self {
+        self.num_threads = Some(num_threads);
+        self
+    }
+
+    /// Verify the proofs, consuming the built action `self`.
+    ///
+    /// Returns the result of every proof, in the order of the batch. The proofs skipped by
+    /// [Self::fail_fast] have no result.
+    pub fn run(self) -> Result<Vec<Option<Result<(), BfVerificationError>>>> {
+        let Self { prover, items, fail_fast, num_threads } = self;
+        let rejected = AtomicBool::new(false);
+        let verify = || {
+            items
+                .par_iter()
+                .map(|(proof, vk)| {
+                    if fail_fast && rejected.load(Ordering::Relaxed) {
+                        return None;
+                    }
+                    let result = prover.verify(proof, vk);
+                    if result.is_err() {
+                        rejected.store(true, Ordering::Relaxed);
+                    }
+                    Some(result)
+                })
+                .collect::<Vec<_>>()
+        };
+        match num_threads {
+            Some(num_threads) => {
+                Ok(ThreadPoolBuilder::new().num_threads(num_threads).build()?.install(verify))
+            }
+            None => Ok(verify()),
+        }
     }
 }
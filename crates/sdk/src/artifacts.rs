@@ -0,0 +1,196 @@
+//! Management of checksum-pinned artifacts, such as the trusted setups and circuits that
+//! wrapping proofs into Groth16 or PLONK would need. No wrapping mode is implemented, so the SDK
+//! pins no artifacts of its own.
+//!
+//! Artifacts are pinned by their SHA-256 checksum. On first use they are downloaded into the
+//! artifacts directory (requires the `network` feature) and every later use re-checks the
+//! checksum. In offline mode, the artifacts must already be present in the directory.
+
+use std::{
+    env, fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+/// The environment variable pointing at a local artifacts directory. When set, the manager runs
+/// in offline mode.
+pub const ARTIFACTS_DIR_ENV: &str = "BF_ARTIFACTS_DIR";
+
+/// A checksum-pinned artifact.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Artifact {
+    /// The file name inside the artifacts directory.
+    pub name: &'static str,
+    /// Where to download the artifact from.
+    pub url: &'static str,
+    /// The hex-encoded SHA-256 checksum of the artifact.
+    pub sha256: &'static str,
+}
+
+#[derive(Error, Debug)]
+pub enum ArtifactError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Artifact {0} is missing and the manager is offline")]
+    Missing(String),
+    #[error("Checksum mismatch for {name}: expected {expected}, got {actual}")]
+    ChecksumMismatch { name: String, expected: String, actual: String },
+    #[error("Failed to download {name}: {reason}")]
+    Download { name: String, reason: String },
+}
+
+/// Downloads and verifies artifacts.
+#[derive(Debug, Clone)]
+pub struct ArtifactManager {
+    dir: PathBuf,
+    offline: bool,
+    timeout: Option<Duration>,
+}
+
+impl ArtifactManager {
+    /// Creates a manager that downloads into `~/.bf/circuits`.
+    pub fn new() -> Self {
+        Self::with_dir(dirs::home_dir().unwrap_or_else(env::temp_dir).join(".bf").join("circuits"))
+    }
+
+    /// Creates a manager that downloads into `dir`.
+    pub fn with_dir(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into(), offline: false, timeout: None }
+    }
+
+    /// Creates a manager that only reads artifacts from `dir`.
+    pub fn offline(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into(), offline: true, timeout: None }
+    }
+
+    /// Sets the timeout of downloads.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Creates an offline manager if [ARTIFACTS_DIR_ENV] is set, and a downloading one otherwise.
+    pub fn from_env() -> Self {
+        match env::var(ARTIFACTS_DIR_ENV) {
+            Ok(dir) => Self::offline(dir),
+            Err(_) => Self::new(),
+        }
+    }
+
+    /// The directory holding the artifacts.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Makes sure the artifact is present and matches its checksum, returning its path.
+    pub fn ensure(&self, artifact: &Artifact) -> Result<PathBuf, ArtifactError> {
+        let path = self.dir.join(artifact.name);
+        if !path.exists() {
+            if self.offline {
+                return Err(ArtifactError::Missing(path.display().to_string()));
+            }
+            fs::create_dir_all(&self.dir)?;
+            download(artifact, &self.dir, self.timeout)?;
+        }
+        verify_checksum(&path, artifact)?;
+        Ok(path)
+    }
+}
+
+impl Default for ArtifactManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Checks the SHA-256 checksum of the file at `path`.
+pub fn verify_checksum(path: &Path, artifact: &Artifact) -> Result<(), ArtifactError> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 1 << 16];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+
+    let actual = hex::encode(hasher.finalize());
+    if !actual.eq_ignore_ascii_case(artifact.sha256) {
+        return Err(ArtifactError::ChecksumMismatch {
+            name: artifact.name.to_string(),
+            expected: artifact.sha256.to_string(),
+            actual,
+        });
+    }
+    Ok(())
+}
+
+/// Downloads the artifact into a temporary file of `dir` and moves it in place once complete.
+#[cfg(feature = "network")]
+fn download(
+    artifact: &Artifact,
+    dir: &Path,
+    timeout: Option<Duration>,
+) -> Result<(), ArtifactError> {
+    let download_err =
+        |reason: String| ArtifactError::Download { name: artifact.name.to_string(), reason };
+
+    tracing::info!("downloading {} from {}", artifact.name, artifact.url);
+    let mut builder = reqwest::blocking::Client::builder();
+    if let Some(timeout) = timeout {
+        builder = builder.timeout(timeout);
+    }
+    let mut response = builder
+        .build()
+        .and_then(|client| client.get(artifact.url).send())
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| download_err(e.to_string()))?;
+
+    let mut file = tempfile::NamedTempFile::new_in(dir)?;
+    response.copy_to(&mut file).map_err(|e| download_err(e.to_string()))?;
+    file.persist(dir.join(artifact.name)).map_err(|e| ArtifactError::Io(e.error))?;
+    Ok(())
+}
+
+#[cfg(not(feature = "network"))]
+fn download(
+    artifact: &Artifact,
+    _dir: &Path,
+    _timeout: Option<Duration>,
+) -> Result<(), ArtifactError> {
+    Err(ArtifactError::Download {
+        name: artifact.name.to_string(),
+        reason: "the `network` feature is disabled".to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offline_artifacts() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = ArtifactManager::offline(dir.path());
+
+        fs::write(dir.path().join("abc"), b"abc").unwrap();
+        let artifact = Artifact {
+            name: "abc",
+            url: "",
+            sha256: "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+        };
+        assert_eq!(manager.ensure(&artifact).unwrap(), dir.path().join("abc"));
+
+        let tampered = Artifact { sha256: &artifact.sha256[1..], ..artifact };
+        assert!(matches!(manager.ensure(&tampered), Err(ArtifactError::ChecksumMismatch { .. })));
+
+        let missing = Artifact { name: "missing", ..artifact };
+        assert!(matches!(manager.ensure(&missing), Err(ArtifactError::Missing(_))));
+    }
+}
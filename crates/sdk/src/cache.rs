@@ -0,0 +1,109 @@
+//! Caching of proofs keyed by the program, the input and the prover options.
+
+use std::{fs, io, path::PathBuf, sync::Mutex};
+
+use hashbrown::HashMap;
+use sha2::{Digest, Sha256};
+
+use bf_prover::components::DefaultProverComponents;
+use bf_stark::MachineProver;
+
+use crate::{program_digest, BfProvingKey, Prover};
+
+/// The key of a cached proof: the digest of the program, the stdin and the prover options.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ProofCacheKey(pub [u8; 32]);
+
+impl ProofCacheKey {
+    /// Computes the key of proving `stdin` with `pk` on `prover`.
+    ///
+    /// The options cover the SDK version, the FRI parameters and the shard size, since proofs
+    /// are not interchangeable across any of them.
+    pub fn new(
+        prover: &dyn Prover<DefaultProverComponents>,
+        pk: &BfProvingKey,
+        stdin: &[u8],
+    ) -> Self {
+        let fri_parameters = prover.prover().core_prover.config().fri_parameters();
+        let shard_size = prover.prover().opts().shard_size;
+        let options =
+            bincode::serialize(&(env!("CARGO_PKG_VERSION"), fri_parameters, shard_size)).unwrap();
+
+        let mut hasher = Sha256::new();
+        hasher.update(program_digest(&pk.elf));
+        hasher.update(Sha256::digest(stdin));
+        hasher.update(Sha256::digest(options));
+        Self(hasher.finalize().into())
+    }
+
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.0)
+    }
+}
+
+/// A store of serialized proofs.
+pub trait ProofStore: Send + Sync {
+    /// Returns the proof stored under `key`, if any.
+    fn get(&self, key: &ProofCacheKey) -> Option<Vec<u8>>;
+
+    /// Stores a proof under `key`. Failing to store only costs a re-prove later.
+    fn put(&self, key: &ProofCacheKey, proof: &[u8]);
+}
+
+/// A [ProofStore] keeping proofs in memory.
+#[derive(Default)]
+pub struct MemoryProofStore {
+    proofs: Mutex<HashMap<ProofCacheKey, Vec<u8>>>,
+}
+
+impl MemoryProofStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ProofStore for MemoryProofStore {
+    fn get(&self, key: &ProofCacheKey) -> Option<Vec<u8>> {
+        self.proofs.lock().unwrap().get(key).cloned()
+    }
+
+    fn put(&self, key: &ProofCacheKey, proof: &[u8]) {
+        self.proofs.lock().unwrap().insert(*key, proof.to_vec());
+    }
+}
+
+/// A [ProofStore] keeping one file per proof in a directory.
+pub struct DiskProofStore {
+    dir: PathBuf,
+}
+
+impl DiskProofStore {
+    /// Creates a store in `dir`, creating the directory if needed.
+    pub fn new(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    fn path(&self, key: &ProofCacheKey) -> PathBuf {
+        self.dir.join(format!("{}.proof", key.to_hex()))
+    }
+}
+
+impl ProofStore for DiskProofStore {
+    fn get(&self, key: &ProofCacheKey) -> Option<Vec<u8>> {
+        fs::read(self.path(key)).ok()
+    }
+
+    fn put(&self, key: &ProofCacheKey, proof: &[u8]) {
+        // Write to a temporary file first so readers never see a partial proof.
+        let result = tempfile::NamedTempFile::new_in(&self.dir).and_then(|mut file| {
+            io::Write::write_all(&mut file, proof)?;
+            file.persist(self.path(key)).map_err(|e| e.error)?;
+            Ok(())
+        });
+        if let Err(err) = result {
+            tracing::warn!("failed to cache proof {}: {}", key.to_hex(), err);
+        }
+    }
+}
@@ -0,0 +1,186 @@
+//! Configuration of the [ProverClient](crate::ProverClient), optionally read from the
+//! environment.
+
+use std::{env, path::PathBuf, str::FromStr, time::Duration};
+
+use thiserror::Error;
+
+use bf_prover::{BfProver, CoreSC, VerifierLimits};
+use bf_stark::koala_bear_poseidon2::FriParameters;
+
+use crate::artifacts::{ArtifactManager, ARTIFACTS_DIR_ENV};
+
+/// Selects the prover backend: `cpu`, or `network` with the `network` feature.
+pub const PROVER_ENV: &str = "BF_PROVER";
+/// Selects the FRI preset: `standard` or `fast`.
+pub const FRI_PRESET_ENV: &str = "BF_FRI_PRESET";
+/// The number of cycles per shard.
+pub const SHARD_SIZE_ENV: &str = "BF_SHARD_SIZE";
+/// The directory for cached artifacts.
+pub const CACHE_DIR_ENV: &str = "BF_CACHE_DIR";
+/// The timeout of network requests, in seconds.
+pub const TIMEOUT_ENV: &str = "BF_TIMEOUT_SECS";
+/// The endpoint of a remote prover, such as `http://127.0.0.1:3000`.
+pub const RPC_URL_ENV: &str = "BF_RPC_URL";
+/// The maximum number of shards of a verified proof.
+pub const MAX_SHARDS_ENV: &str = "BF_MAX_SHARDS";
+/// The maximum log degree of the traces of a verified proof.
+pub const MAX_LOG_DEGREE_ENV: &str = "BF_MAX_LOG_DEGREE";
+
+/// The backend that generates proofs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProverBackend {
+    #[default]
+    Cpu,
+    /// A remote `bf-prover-server` at [ProverClientConfig::rpc_url].
+    #[cfg(feature = "network")]
+    Network,
+}
+
+impl FromStr for ProverBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "cpu" | "local" => Ok(ProverBackend::Cpu),
+            #[cfg(feature = "network")]
+            "network" | "remote" => Ok(ProverBackend::Network),
+            #[cfg(feature = "network")]
+            _ => Err("expected `cpu` or `network`".to_string()),
+            #[cfg(not(feature = "network"))]
+            _ => Err("expected `cpu`".to_string()),
+        }
+    }
+}
+
+/// A named set of FRI parameters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FriPreset {
+    /// About 100 bits of security.
+    #[default]
+    Standard,
+    /// Few queries and low security, for development only.
+    Fast,
+}
+
+impl FriPreset {
+    pub fn parameters(&self) -> FriParameters {
+        match self {
+            FriPreset::Standard => FriParameters::standard(),
+            FriPreset::Fast => FriParameters::fast(),
+        }
+    }
+}
+
+impl FromStr for FriPreset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "standard" | "default" => Ok(FriPreset::Standard),
+            "fast" => Ok(FriPreset::Fast),
+            _ => Err("expected `standard` or `fast`".to_string()),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("Invalid value {value:?} for {var}: {reason}")]
+    InvalidValue { var: &'static str, value: String, reason: String },
+    #[cfg(feature = "network")]
+    #[error("The network backend needs an RPC URL, set BF_RPC_URL")]
+    MissingRpcUrl,
+}
+
+/// The configuration of a [ProverClient](crate::ProverClient).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProverClientConfig {
+    pub backend: ProverBackend,
+    pub fri_preset: FriPreset,
+    /// The number of cycles per shard. `None` proves the whole execution in one shard.
+    pub shard_size: Option<usize>,
+    /// The directory for cached artifacts. Defaults to `~/.bf`.
+    pub cache_dir: Option<PathBuf>,
+    /// The timeout of network requests.
+    pub timeout: Option<Duration>,
+    /// The endpoint of a remote prover.
+    pub rpc_url: Option<String>,
+    /// The bounds on the proofs the client verifies, which bound the work of verifying
+    /// untrusted proofs.
+    pub verifier_limits: VerifierLimits,
+}
+
+impl ProverClientConfig {
+    /// Reads the configuration from the environment, falling back to the defaults for unset
+    /// variables.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let limits = VerifierLimits::default();
+        Ok(Self {
+            backend: parse_env(PROVER_ENV)?.unwrap_or_default(),
+            fri_preset: parse_env(FRI_PRESET_ENV)?.unwrap_or_default(),
+            shard_size: parse_shard_size()?,
+            cache_dir: env::var_os(CACHE_DIR_ENV).map(PathBuf::from),
+            timeout: parse_env(TIMEOUT_ENV)?.map(Duration::from_secs),
+            rpc_url: env::var(RPC_URL_ENV).ok(),
+            verifier_limits: VerifierLimits {
+                max_shards: parse_env(MAX_SHARDS_ENV)?.unwrap_or(limits.max_shards),
+                max_log_degree: parse_env(MAX_LOG_DEGREE_ENV)?.unwrap_or(limits.max_log_degree),
+            },
+        })
+    }
+
+    /// The core config matching the FRI preset.
+    pub fn core_config(&self) -> CoreSC {
+        CoreSC::with_fri_parameters(self.fri_preset.parameters())
+    }
+
+    /// A prover of the core config, proving in shards of the shard size and verifying proofs
+    /// within the verifier limits.
+    pub fn core_prover(&self) -> BfProver {
+        BfProver::from_config(self.core_config())
+            .with_shard_size(self.shard_size.map(|size| size as u64))
+            .with_limits(self.verifier_limits)
+    }
+
+    /// The artifact manager matching the cache directory and timeout.
+    ///
+    /// [ARTIFACTS_DIR_ENV] takes precedence and selects offline mode.
+    pub fn artifact_manager(&self) -> ArtifactManager {
+        let manager = match (env::var_os(ARTIFACTS_DIR_ENV), &self.cache_dir) {
+            (Some(_), _) | (None, None) => ArtifactManager::from_env(),
+            (None, Some(dir)) => ArtifactManager::with_dir(dir.join("circuits")),
+        };
+        match self.timeout {
+            Some(timeout) => manager.with_timeout(timeout),
+            None => manager,
+        }
+    }
+}
+
+/// Reads [SHARD_SIZE_ENV], which must be positive as every shard runs at least one cycle.
+fn parse_shard_size() -> Result<Option<usize>, ConfigError> {
+    match parse_env(SHARD_SIZE_ENV)? {
+        Some(0) => Err(ConfigError::InvalidValue {
+            var: SHARD_SIZE_ENV,
+            value: "0".to_string(),
+            reason: "shards must have at least one cycle".to_string(),
+        }),
+        shard_size => Ok(shard_size),
+    }
+}
+
+fn parse_env<T>(var: &'static str) -> Result<Option<T>, ConfigError>
+where
+    T: FromStr,
+    T::Err: ToString,
+{
+    match env::var(var) {
+        Ok(value) => value.parse().map(Some).map_err(|e: T::Err| ConfigError::InvalidValue {
+            var,
+            value,
+            reason: e.to_string(),
+        }),
+        Err(_) => Ok(None),
+    }
+}
@@ -0,0 +1,116 @@
+//! Hex and base64 encodings of proofs, verifying keys and public values, for pasting them into
+//! configs, command lines and JSON documents.
+//!
+//! Proofs are encoded as [BfProofWithPublicValues::to_bytes], verifying keys with bincode and
+//! public values as is.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use thiserror::Error;
+
+use crate::{BfProofDecodeError, BfProofWithPublicValues, BfVerifyingKey};
+
+#[derive(Error, Debug)]
+pub enum EncodingError {
+    #[error("Invalid hex: {0}")]
+    Hex(#[from] hex::FromHexError),
+    #[error("Invalid base64: {0}")]
+    Base64(#[from] base64::DecodeError),
+    #[error("Failed to decode proof: {0}")]
+    Proof(#[from] BfProofDecodeError),
+    #[error("Failed to decode verifying key: {0}")]
+    VerifyingKey(#[from] bincode::Error),
+}
+
+/// An artifact with a canonical byte encoding, and hence text encodings.
+pub trait ArtifactEncoding: Sized {
+    fn to_artifact_bytes(&self) -> Vec<u8>;
+
+    fn from_artifact_bytes(bytes: &[u8]) -> Result<Self, EncodingError>;
+
+    /// Encodes as lowercase hex, without a `0x` prefix.
+    fn to_hex(&self) -> String {
+        hex::encode(self.to_artifact_bytes())
+    }
+
+    /// Decodes hex, ignoring surrounding whitespace and an optional `0x` prefix.
+    fn from_hex(s: &str) -> Result<Self, EncodingError> {
+        let s = s.trim();
+        let s = s.strip_prefix("0x").unwrap_or(s);
+        Self::from_artifact_bytes(&hex::decode(s)?)
+    }
+
+    /// Encodes as standard, padded base64.
+    fn to_base64(&self) -> String {
+        STANDARD.encode(self.to_artifact_bytes())
+    }
+
+    /// Decodes standard, padded base64, ignoring surrounding whitespace.
+    fn from_base64(s: &str) -> Result<Self, EncodingError> {
+        Self::from_artifact_bytes(&STANDARD.decode(s.trim())?)
+    }
+}
+
+impl ArtifactEncoding for BfProofWithPublicValues {
+    fn to_artifact_bytes(&self) -> Vec<u8> {
+        self.to_bytes().expect("failed to serialize proof")
+    }
+
+    fn from_artifact_bytes(bytes: &[u8]) -> Result<Self, EncodingError> {
+        Ok(Self::from_bytes(bytes)?)
+    }
+}
+
+impl ArtifactEncoding for BfVerifyingKey {
+    fn to_artifact_bytes(&self) -> Vec<u8> {
+        bincode::serialize(self).expect("failed to serialize verifying key")
+    }
+
+    fn from_artifact_bytes(bytes: &[u8]) -> Result<Self, EncodingError> {
+        Ok(bincode::deserialize(bytes)?)
+    }
+}
+
+/// Public values, such as [BfProofWithPublicValues::output].
+impl ArtifactEncoding for Vec<u8> {
+    fn to_artifact_bytes(&self) -> Vec<u8> {
+        self.clone()
+    }
+
+    fn from_artifact_bytes(bytes: &[u8]) -> Result<Self, EncodingError> {
+        Ok(bytes.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ProverClient;
+
+    #[test]
+    fn test_round_trip() {
+        let client = ProverClient::new();
+        let (pk, vk) = client.setup("++.");
+        let proof = client.prove(&pk, vec![]).run().unwrap();
+
+        let decoded = BfProofWithPublicValues::from_hex(&proof.to_hex()).unwrap();
+        client.verify(&decoded, &vk).unwrap();
+        let decoded = BfProofWithPublicValues::from_base64(&proof.to_base64()).unwrap();
+        client.verify(&decoded, &vk).unwrap();
+
+        let vk_hex = format!(" 0x{}\n", vk.to_hex());
+        client.verify(&proof, &BfVerifyingKey::from_hex(&vk_hex).unwrap()).unwrap();
+        client.verify(&proof, &BfVerifyingKey::from_base64(&vk.to_base64()).unwrap()).unwrap();
+
+        assert_eq!(proof.output.to_hex(), "02");
+        assert_eq!(proof.output.to_base64(), "Ag==");
+        assert_eq!(Vec::<u8>::from_base64("Ag==").unwrap(), vec![2]);
+    }
+
+    #[test]
+    fn test_invalid_encodings() {
+        assert!(matches!(Vec::<u8>::from_hex("0xabc"), Err(EncodingError::Hex(_))));
+        assert!(matches!(Vec::<u8>::from_base64("A"), Err(EncodingError::Base64(_))));
+        assert!(matches!(BfVerifyingKey::from_hex("00"), Err(EncodingError::VerifyingKey(_))));
+        assert!(matches!(BfProofWithPublicValues::from_hex("00"), Err(EncodingError::Proof(_))));
+    }
+}
@@ -0,0 +1,250 @@
+//! Encodes proofs as calldata of `IBfVerifier.verifyProof` (see `contracts/src/IBfVerifier.sol`).
+//!
+//! The calldata is the function selector followed by the standard ABI encoding of
+//! `(bytes32 programVKey, bytes publicValues, bytes proofBytes)`: the static head of three
+//! 32-byte words, then each dynamic argument as its length and its bytes, right-padded with
+//! zeros to a multiple of 32 bytes.
+//!
+//! The public values are the ones of the machine, those of every shard in order, each element
+//! as a big-endian 32-bit word, see [encode_public_values].
+
+use p3_field::PrimeField32;
+use thiserror::Error;
+
+use bf_prover::CoreSC;
+use bf_stark::ShardProof;
+
+use crate::{vk_digest, BfProofWithPublicValues, BfVerifyingKey, VerifyingKeyDigest};
+
+/// The selector of `verifyProof(bytes32,bytes,bytes)`.
+pub const VERIFY_PROOF_SELECTOR: [u8; 4] = [0x41, 0x49, 0x3c, 0x60];
+
+const WORD: usize = 32;
+
+/// The head of the arguments: `programVKey` and the offsets of the dynamic arguments.
+const HEAD_LEN: usize = 3 * WORD;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum EvmDecodeError {
+    #[error("Calldata too short")]
+    TooShort,
+    #[error("Unexpected function selector {0:02x?}")]
+    Selector([u8; 4]),
+    #[error("Invalid offset of argument {0}")]
+    Offset(usize),
+    #[error("Invalid length of argument {0}")]
+    Length(usize),
+    #[error("Non-zero padding")]
+    Padding,
+}
+
+/// The arguments of `verifyProof`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvmProof {
+    pub program_vkey: VerifyingKeyDigest,
+    pub public_values: Vec<u8>,
+    /// The bincode-serialized shard proofs.
+    pub proof: Vec<u8>,
+}
+
+impl EvmProof {
+    pub fn new(vk: &BfVerifyingKey, public_values: Vec<u8>, proof: Vec<u8>) -> Self {
+        Self { program_vkey: vk_digest(vk), public_values, proof }
+    }
+
+    /// The arguments of `verifyProof` for a proof of the program of `vk`.
+    pub fn from_proof(
+        vk: &BfVerifyingKey,
+        proof: &BfProofWithPublicValues,
+    ) -> Result<Self, bincode::Error> {
+        let public_values = encode_public_values(&proof.proof);
+        Ok(Self::new(vk, public_values, bincode::serialize(&proof.proof)?))
+    }
+
+    /// The calldata of `verifyProof`.
+    pub fn to_calldata(&self) -> Vec<u8> {
+        let public_values_offset = HEAD_LEN;
+        let proof_offset = public_values_offset + WORD + padded_len(self.public_values.len());
+
+        let mut calldata = Vec::with_capacity(
+            VERIFY_PROOF_SELECTOR.len() + proof_offset + WORD + padded_len(self.proof.len()),
+        );
+        calldata.extend_from_slice(&VERIFY_PROOF_SELECTOR);
+        calldata.extend_from_slice(&self.program_vkey);
+        calldata.extend_from_slice(&encode_word(public_values_offset));
+        calldata.extend_from_slice(&encode_word(proof_offset));
+        encode_bytes(&mut calldata, &self.public_values);
+        encode_bytes(&mut calldata, &self.proof);
+        calldata
+    }
+
+    /// Decodes calldata of `verifyProof`, rejecting any non-canonical encoding.
+    pub fn from_calldata(calldata: &[u8]) -> Result<Self, EvmDecodeError> {
+        let selector = calldata.get(..4).ok_or(EvmDecodeError::TooShort)?;
+        if selector != VERIFY_PROOF_SELECTOR {
+            return Err(EvmDecodeError::Selector(selector.try_into().unwrap()));
+        }
+        let args = &calldata[4..];
+        if args.len() < HEAD_LEN {
+            return Err(EvmDecodeError::TooShort);
+        }
+
+        let program_vkey = args[..WORD].try_into().unwrap();
+        let (public_values, end) = decode_bytes(args, 1, HEAD_LEN)?;
+        let (proof, end) = decode_bytes(args, 2, end)?;
+        if end != args.len() {
+            return Err(EvmDecodeError::Length(2));
+        }
+        Ok(Self { program_vkey, public_values, proof })
+    }
+}
+
+/// The public values of `shards`, in order, each element as a big-endian 32-bit word.
+pub fn encode_public_values(shards: &[ShardProof<CoreSC>]) -> Vec<u8> {
+    let values = shards.iter().flat_map(|shard| &shard.public_values);
+    values.flat_map(|value| value.as_canonical_u32().to_be_bytes()).collect()
+}
+
+fn padded_len(len: usize) -> usize {
+    len.div_ceil(WORD) * WORD
+}
+
+/// A big-endian `uint256`.
+fn encode_word(value: usize) -> [u8; WORD] {
+    let mut word = [0; WORD];
+    word[WORD - 8..].copy_from_slice(&(value as u64).to_be_bytes());
+    word
+}
+
+fn decode_word(word: &[u8]) -> Option<usize> {
+    if word[..WORD - 8].iter().any(|&b| b != 0) {
+        return None;
+    }
+    usize::try_from(u64::from_be_bytes(word[WORD - 8..].try_into().unwrap())).ok()
+}
+
+fn encode_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    out.extend_from_slice(&encode_word(bytes.len()));
+    out.extend_from_slice(bytes);
+    out.resize(out.len() + padded_len(bytes.len()) - bytes.len(), 0);
+}
+
+/// Decodes the dynamic argument `index`, which must start at `expected_offset`, returning it
+/// and the offset following it.
+fn decode_bytes(
+    args: &[u8],
+    index: usize,
+    expected_offset: usize,
+) -> Result<(Vec<u8>, usize), EvmDecodeError> {
+    let offset = decode_word(&args[index * WORD..(index + 1) * WORD]);
+    if offset != Some(expected_offset) {
+        return Err(EvmDecodeError::Offset(index));
+    }
+    let len_word =
+        args.get(expected_offset..expected_offset + WORD).ok_or(EvmDecodeError::TooShort)?;
+    let len = decode_word(len_word).ok_or(EvmDecodeError::Length(index))?;
+
+    let start = expected_offset + WORD;
+    let end = start.checked_add(padded_len(len)).ok_or(EvmDecodeError::Length(index))?;
+    let data = args.get(start..end).ok_or(EvmDecodeError::TooShort)?;
+    if data[len..].iter().any(|&b| b != 0) {
+        return Err(EvmDecodeError::Padding);
+    }
+    Ok((data[..len].to_vec(), end))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_sol_types::{sol, SolCall};
+    use bf_core_executor::{CYCLES_PUBLIC_VALUE, NUM_PUBLIC_VALUES};
+
+    use super::*;
+    use crate::{ProverClient, ProverClientConfig};
+
+    sol! {
+        function verifyProof(
+            bytes32 programVKey,
+            bytes calldata publicValues,
+            bytes calldata proofBytes
+        ) external view;
+    }
+
+    fn evm_proof(public_values_len: usize, proof_len: usize) -> EvmProof {
+        EvmProof {
+            program_vkey: [7; 32],
+            public_values: (0..public_values_len).map(|i| i as u8).collect(),
+            proof: (0..proof_len).map(|i| (i * 3) as u8).collect(),
+        }
+    }
+
+    #[test]
+    fn test_matches_abi() {
+        assert_eq!(VERIFY_PROOF_SELECTOR, verifyProofCall::SELECTOR);
+
+        for (public_values_len, proof_len) in [(0, 0), (1, 256), (32, 260), (33, 31)] {
+            let proof = evm_proof(public_values_len, proof_len);
+            let call = verifyProofCall {
+                programVKey: proof.program_vkey.into(),
+                publicValues: proof.public_values.clone().into(),
+                proofBytes: proof.proof.clone().into(),
+            };
+            let calldata = proof.to_calldata();
+            assert_eq!(calldata, call.abi_encode());
+            assert_eq!(calldata.len() % WORD, 4);
+
+            let decoded = verifyProofCall::abi_decode(&calldata, true).unwrap();
+            assert_eq!(decoded.publicValues.as_ref(), proof.public_values.as_slice());
+            assert_eq!(EvmProof::from_calldata(&calldata).unwrap(), proof);
+        }
+    }
+
+    #[test]
+    fn test_rejects_malformed_calldata() {
+        let calldata = evm_proof(3, 5).to_calldata();
+
+        let mut wrong_selector = calldata.clone();
+        wrong_selector[0] ^= 1;
+        assert!(matches!(
+            EvmProof::from_calldata(&wrong_selector),
+            Err(EvmDecodeError::Selector(_))
+        ));
+
+        let mut dirty_padding = calldata.clone();
+        *dirty_padding.last_mut().unwrap() = 1;
+        assert_eq!(EvmProof::from_calldata(&dirty_padding), Err(EvmDecodeError::Padding));
+
+        let mut wrong_offset = calldata.clone();
+        wrong_offset[4 + 2 * WORD - 1] += WORD as u8;
+        assert_eq!(EvmProof::from_calldata(&wrong_offset), Err(EvmDecodeError::Offset(1)));
+
+        assert_eq!(
+            EvmProof::from_calldata(&calldata[..calldata.len() - 1]),
+            Err(EvmDecodeError::TooShort)
+        );
+    }
+
+    #[test]
+    fn test_from_proof() {
+        let config = ProverClientConfig { shard_size: Some(100), ..Default::default() };
+        let client = ProverClient::from_config(config).unwrap();
+        let (pk, vk) = client.setup(test_artifacts::FIBO_BF);
+        let proof = client.prove(&pk, vec![17]).run().unwrap();
+        assert!(proof.proof.len() > 1);
+
+        let evm_proof = EvmProof::from_proof(&vk, &proof).unwrap();
+        assert_eq!(evm_proof.program_vkey, vk_digest(&vk));
+        let shards: Vec<ShardProof<CoreSC>> = bincode::deserialize(&evm_proof.proof).unwrap();
+        assert_eq!(shards.len(), proof.proof.len());
+
+        // Each shard has its own words, the number of cycles of the shard among them.
+        let words = evm_proof.public_values.chunks_exact(4);
+        let words = words.map(|word| u32::from_be_bytes(word.try_into().unwrap()));
+        let words = words.collect::<Vec<_>>();
+        assert_eq!(words.len(), proof.proof.len() * NUM_PUBLIC_VALUES);
+        let cycles = words.chunks(NUM_PUBLIC_VALUES).map(|shard| shard[CYCLES_PUBLIC_VALUE]);
+        assert_eq!(cycles.map(u64::from).sum::<u64>(), proof.metadata.cycles);
+
+        let calldata = evm_proof.to_calldata();
+        assert_eq!(EvmProof::from_calldata(&calldata).unwrap(), evm_proof);
+    }
+}
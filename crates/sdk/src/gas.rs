@@ -0,0 +1,227 @@
+//! Estimates the cost of verifying a proof on an EVM chain.
+//!
+//! The estimate assumes a Solidity FRI verifier of the shard proofs implementing
+//! `IBfVerifier`, as proofs cannot be wrapped into Groth16 or PLONK. It follows the layout of the
+//! proof and the number of public values: the calldata of [`verifyProof`](crate::evm::EvmProof),
+//! the hashes the verifier computes and the extension field arithmetic. It is meant to compare
+//! proofs, not to predict the gas of a transaction to the unit.
+
+use serde::{Deserialize, Serialize};
+
+use bf_stark::koala_bear_poseidon2::FriParameters;
+
+use crate::{
+    evm::{encode_public_values, EvmProof},
+    BfProofWithPublicValues,
+};
+
+/// The base field elements hashed by one Poseidon2 permutation.
+const HASH_RATE: usize = 8;
+
+/// The coefficients of an extension field element.
+const EXTENSION_DEGREE: usize = 4;
+
+/// The commitments opened by every FRI query: preprocessed, main, permutation and quotient.
+const NUM_INPUT_COMMITMENTS: usize = 4;
+
+/// The gas costs of a chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GasModel {
+    /// The base cost of a transaction.
+    pub transaction: u64,
+    /// The cost of a zero byte of calldata.
+    pub calldata_zero_byte: u64,
+    /// The cost of a non-zero byte of calldata.
+    pub calldata_nonzero_byte: u64,
+    /// The cost of a Poseidon2 permutation over KoalaBear in Solidity. There is no Solidity
+    /// verifier to measure it on yet, see [GasModel::ethereum].
+    pub poseidon2_permutation: u64,
+    /// The cost of an extension field operation in Solidity, unmeasured as well.
+    pub extension_op: u64,
+}
+
+impl GasModel {
+    /// The costs of Ethereum mainnet.
+    ///
+    /// The transaction and calldata costs are the ones of the Ethereum yellow paper, with the
+    /// calldata repricing of EIP-2028. The costs of a permutation and of an extension field
+    /// operation are placeholders, measured on no verifier since none is written yet: the
+    /// execution gas only compares proofs until they are replaced by measured costs.
+    pub const fn ethereum() -> Self {
+        Self {
+            transaction: 21_000,
+            calldata_zero_byte: 4,
+            calldata_nonzero_byte: 16,
+            // Placeholders, see above.
+            poseidon2_permutation: 25_000,
+            extension_op: 60,
+        }
+    }
+
+    /// The cost of calldata.
+    pub fn calldata(&self, calldata: &[u8]) -> u64 {
+        calldata
+            .iter()
+            .map(|&b| if b == 0 { self.calldata_zero_byte } else { self.calldata_nonzero_byte })
+            .sum()
+    }
+}
+
+impl Default for GasModel {
+    fn default() -> Self {
+        Self::ethereum()
+    }
+}
+
+/// The parts of a proof that the cost of its verification depends on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProofLayout {
+    /// The public values passed to `verifyProof`, those of every shard, see
+    /// [encode_public_values].
+    pub public_values: Vec<u8>,
+    /// The serialized shard proofs, passed to a FRI verifier.
+    pub shard_proof: Vec<u8>,
+    /// The number of shards, each verified on its own.
+    pub num_shards: usize,
+    /// The number of FRI queries.
+    pub num_queries: usize,
+    /// The number of FRI folding rounds.
+    pub num_fri_rounds: usize,
+    /// The depth of the Merkle trees of the traces of the tallest shard.
+    pub log_height: usize,
+    /// The base field elements opened by every query, summed over the chips and the shards.
+    pub opened_width: usize,
+    /// The extension field elements opened at the out-of-domain point, summed over the shards.
+    pub num_opened_values: usize,
+}
+
+impl ProofLayout {
+    /// The layout of a proof generated with the given FRI parameters.
+    pub fn from_proof(proof: &BfProofWithPublicValues, fri_parameters: &FriParameters) -> Self {
+        let shards = &proof.proof;
+        let chips = || shards.iter().flat_map(|shard| &shard.opened_values.chips);
+        let max_log_degree = chips().map(|chip| chip.log_degree).max().unwrap_or(0);
+
+        let opened_width = chips()
+            .map(|chip| {
+                let quotient = chip.quotient.iter().map(Vec::len).sum::<usize>();
+                chip.preprocessed.local.len()
+                    + chip.main.local.len()
+                    + (chip.permutation.local.len() + quotient) * EXTENSION_DEGREE
+            })
+            .sum();
+        let num_opened_values = chips()
+            .map(|chip| {
+                let quotient = chip.quotient.iter().map(Vec::len).sum::<usize>();
+                2 * (chip.preprocessed.local.len()
+                    + chip.main.local.len()
+                    + chip.permutation.local.len())
+                    + quotient
+            })
+            .sum();
+
+        Self {
+            public_values: encode_public_values(shards),
+            shard_proof: bincode::serialize(shards).expect("failed to serialize proof"),
+            num_shards: shards.len(),
+            num_queries: shards.first().map_or(0, |shard| shard.opening_proof.query_proofs.len()),
+            num_fri_rounds: shards
+                .iter()
+                .map(|shard| shard.opening_proof.commit_phase_commits.len())
+                .max()
+                .unwrap_or(0),
+            log_height: max_log_degree + fri_parameters.log_blowup,
+            opened_width,
+            num_opened_values,
+        }
+    }
+
+    /// The Poseidon2 permutations of a FRI verifier.
+    pub fn num_permutations(&self) -> usize {
+        // Each query hashes the opened rows and walks the Merkle paths of the traces, then of
+        // the folded polynomials, whose leaves are a pair of extension field elements. Every
+        // shard is bounded by the tallest one.
+        let paths = NUM_INPUT_COMMITMENTS * self.log_height;
+        let folding = (0..self.num_fri_rounds)
+            .map(|round| 1 + self.log_height.saturating_sub(round + 1))
+            .sum::<usize>();
+        let input = self.opened_width.div_ceil(HASH_RATE);
+        self.num_queries * (input + self.num_shards * (paths + folding))
+    }
+}
+
+/// The estimated cost of verifying a proof.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerificationCost {
+    /// The length of the calldata of `verifyProof`.
+    pub calldata_len: usize,
+    /// The gas of the calldata and of the transaction.
+    pub calldata_gas: u64,
+    /// The gas of the verification itself.
+    pub execution_gas: u64,
+}
+
+impl VerificationCost {
+    /// The gas of the transaction.
+    pub const fn total_gas(&self) -> u64 {
+        self.calldata_gas + self.execution_gas
+    }
+}
+
+/// Estimates the cost of verifying a proof of the given layout with a FRI verifier.
+pub fn estimate_verification_cost(layout: &ProofLayout, model: &GasModel) -> VerificationCost {
+    let calldata = EvmProof {
+        program_vkey: [0xff; 32],
+        public_values: layout.public_values.clone(),
+        proof: layout.shard_proof.clone(),
+    }
+    .to_calldata();
+
+    // The public values, a word each, are observed by the challenger, and the opened values are
+    // combined by the constraints and by every query.
+    let public_values = (layout.public_values.len() / 4).div_ceil(HASH_RATE);
+    let permutations = (layout.num_permutations() + public_values) as u64;
+    let extension_ops =
+        (layout.num_opened_values * (1 + layout.num_queries)) as u64 * EXTENSION_DEGREE as u64;
+    let execution_gas =
+        permutations * model.poseidon2_permutation + extension_ops * model.extension_op;
+
+    VerificationCost {
+        calldata_len: calldata.len(),
+        calldata_gas: model.transaction + model.calldata(&calldata),
+        execution_gas,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(public_values_len: usize) -> ProofLayout {
+        ProofLayout {
+            public_values: vec![1; public_values_len],
+            shard_proof: vec![1; 100_000],
+            num_shards: 1,
+            num_queries: 84,
+            num_fri_rounds: 12,
+            log_height: 13,
+            opened_width: 120,
+            num_opened_values: 300,
+        }
+    }
+
+    #[test]
+    fn test_estimate_verification_cost() {
+        let model = GasModel::ethereum();
+        let cost = estimate_verification_cost(&layout(10), &model);
+        assert_eq!(cost.calldata_len, 4 + 3 * 32 + 32 + 32 + 32 + 100_000);
+        // The length words are mostly zero bytes, as is the padding of the public values.
+        assert_eq!(cost.calldata_gas, model.transaction + 16 * 100_052 + 4 * 144);
+        assert!(cost.execution_gas > 0);
+
+        // More public values cost more calldata and more hashing.
+        let more = estimate_verification_cost(&layout(1000), &model);
+        assert!(more.calldata_gas > cost.calldata_gas);
+        assert!(more.execution_gas > cost.execution_gas);
+    }
+}
@@ -0,0 +1,115 @@
+//! Typed encoding of the stdin and stdout byte streams.
+//!
+//! Values are framed with bincode's default encoding:
+//!
+//! - integers are fixed-width and little-endian, so a `u8` is a single byte and a `u16` is two
+//!   bytes, low byte first;
+//! - `bool` is one byte, `0` or `1`;
+//! - sequences and strings are prefixed with their length as a little-endian `u64`;
+//! - structs and tuples are their fields in order, with no padding.
+//!
+//! On the guest side, every byte is one `,` or `.`. A guest reading a `u8` followed by a `u16`
+//! executes `,` three times, and a guest writing a `u16` prints its low byte first.
+
+use serde::{de::DeserializeOwned, Serialize};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum CodecError {
+    #[error("Codec error: {0}")]
+    Codec(#[from] bincode::Error),
+    #[error("{0} trailing bytes after the decoded value")]
+    TrailingBytes(usize),
+}
+
+/// Encodes a value as a guest input.
+pub fn encode_input<T: Serialize>(value: &T) -> Result<Vec<u8>, CodecError> {
+    Ok(bincode::serialize(value)?)
+}
+
+/// Decodes a guest output holding exactly one value.
+pub fn decode_output<T: DeserializeOwned>(output: &[u8]) -> Result<T, CodecError> {
+    let mut reader = OutputReader::new(output);
+    let value = reader.read()?;
+    match reader.remaining() {
+        0 => Ok(value),
+        n => Err(CodecError::TrailingBytes(n)),
+    }
+}
+
+/// Builds a guest input out of several values.
+#[derive(Debug, Clone, Default)]
+pub struct InputWriter {
+    buffer: Vec<u8>,
+}
+
+impl InputWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a value.
+    pub fn write<T: Serialize>(&mut self, value: &T) -> Result<&mut Self, CodecError> {
+        bincode::serialize_into(&mut self.buffer, value)?;
+        Ok(self)
+    }
+
+    /// Appends raw bytes, without a length prefix.
+    pub fn write_slice(&mut self, bytes: &[u8]) -> &mut Self {
+        self.buffer.extend_from_slice(bytes);
+        self
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buffer
+    }
+}
+
+/// Reads values one after another from a guest output.
+#[derive(Debug, Clone)]
+pub struct OutputReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> OutputReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    /// Reads the next value.
+    pub fn read<T: DeserializeOwned>(&mut self) -> Result<T, CodecError> {
+        Ok(bincode::deserialize_from(&mut self.bytes)?)
+    }
+
+    /// The number of bytes left.
+    pub fn remaining(&self) -> usize {
+        self.bytes.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ProverClient;
+
+    #[test]
+    fn test_codec() {
+        // A single byte is encoded as itself, so the existing guests keep working.
+        let stdin = encode_input(&17u8).unwrap();
+        assert_eq!(stdin, vec![17]);
+        let output = ProverClient::new().execute(test_artifacts::FIBO_BF, stdin).run().unwrap();
+        assert_eq!(decode_output::<u8>(&output).unwrap(), 85);
+
+        let mut writer = InputWriter::new();
+        writer.write(&0x0102u16).unwrap().write(&(true, String::from("bf"))).unwrap();
+        let bytes = writer.into_bytes();
+        assert_eq!(&bytes[..3], &[0x02, 0x01, 1]);
+
+        let mut reader = OutputReader::new(&bytes);
+        assert_eq!(reader.read::<u16>().unwrap(), 0x0102);
+        assert_eq!(reader.read::<(bool, String)>().unwrap(), (true, "bf".to_string()));
+        assert_eq!(reader.remaining(), 0);
+
+        assert!(matches!(decode_output::<u8>(&[1, 2]), Err(CodecError::TrailingBytes(1))));
+    }
+}
@@ -1,25 +1,51 @@
 //! A library for interacting with the zkVM.
 
 pub mod action;
+pub mod artifacts;
+pub mod cache;
+pub mod config;
+pub mod encoding;
+pub mod evm;
+pub mod gas;
+pub mod io;
 
+pub mod metrics;
+pub mod network;
+pub mod pool;
 pub mod proof;
 pub mod provers;
+pub mod receipt;
+pub mod registry;
+pub mod stdin;
+mod verify;
 
 use bf_prover::components::DefaultProverComponents;
+pub use config::{ConfigError, ProverBackend, ProverClientConfig};
+pub use encoding::{ArtifactEncoding, EncodingError};
+pub use pool::{ProofHandle, ProvingPool};
 pub use proof::*;
 pub use provers::BfVerificationError;
+pub use receipt::{Receipt, ReceiptClaim};
+pub use registry::{
+    program_digest, vk_digest, ProgramDigest, ProgramRegistry, VerifyingKeyDigest, VkCheckError,
+};
 
 pub use provers::{CpuProver, Prover};
+pub use stdin::CompressedStdin;
+pub use verify::{verify, verify_proof, EmbeddedVerifyingKey, VkAllowlist};
 
 // Re-export the utilities.
 pub use bf_core_machine::utils;
 pub use bf_core_machine::utils::setup_logger;
-pub use bf_prover::{BfProver, BfProvingKey, BfVerifyingKey, CoreSC, InnerSC};
+pub use bf_derive::bf;
+pub use bf_prover::{BfProver, BfProvingKey, BfVerifyingKey, CoreSC, InnerSC, VerifierLimits};
 
 /// A client for interacting with zkMIPS.
 pub struct ProverClient {
     /// The underlying prover implementation.
     pub prover: Box<dyn Prover<DefaultProverComponents>>,
+    /// The configuration the client was built with.
+    pub config: ProverClientConfig,
 }
 
 impl ProverClient {
@@ -33,7 +59,61 @@ impl ProverClient {
     /// let client = ProverClient::new();
     /// ```
     pub fn new() -> Self {
-        Self { prover: Box::new(CpuProver::new()) }
+        Self { prover: Box::new(CpuProver::new()), config: ProverClientConfig::default() }
+    }
+
+    /// Creates a new [ProverClient] configured from the environment.
+    ///
+    /// | Variable            | Description                            | Default                 |
+    /// |---------------------|----------------------------------------|-------------------------|
+    /// | `BF_PROVER`         | The prover backend (`cpu`, `network`)  | `cpu`                   |
+    /// | `BF_FRI_PRESET`     | The FRI preset (`standard` or `fast`)  | `standard`              |
+    /// | `BF_SHARD_SIZE`     | The number of cycles per shard         | unsharded               |
+    /// | `BF_CACHE_DIR`      | The directory for cached artifacts     | `~/.bf`                 |
+    /// | `BF_TIMEOUT_SECS`   | The timeout of network requests        | none                    |
+    /// | `BF_RPC_URL`        | The endpoint of a remote prover        | none                    |
+    /// | `BF_MAX_SHARDS`     | The maximum shards of a verified proof | `MAX_CORE_PROOF_SHARDS` |
+    /// | `BF_MAX_LOG_DEGREE` | The maximum log degree of its traces   | 24                      |
+    ///
+    /// `MAX_CORE_PROOF_SHARDS` is [bf_prover::verify::MAX_CORE_PROOF_SHARDS], the most shards
+    /// an execution can have.
+    ///
+    /// `FRI_QUERIES` still overrides the number of queries of the `standard` preset.
+    ///
+    /// ### Panics
+    ///
+    /// Panics if a variable holds an invalid value, or if `BF_PROVER` selects the network
+    /// backend without `BF_RPC_URL`.
+    ///
+    /// ### Examples
+    ///
+    /// ```no_run
+    /// use bf_sdk::ProverClient;
+    ///
+    /// std::env::set_var("BF_FRI_PRESET", "fast");
+    /// let client = ProverClient::from_env();
+    /// ```
+    pub fn from_env() -> Self {
+        ProverClientConfig::from_env()
+            .and_then(Self::from_config)
+            .expect("invalid prover configuration")
+    }
+
+    /// Creates a new [ProverClient] from the given configuration.
+    ///
+    /// The network backend needs [ProverClientConfig::rpc_url], and fails with
+    /// [ConfigError::MissingRpcUrl] without it.
+    pub fn from_config(config: ProverClientConfig) -> Result<Self, ConfigError> {
+        let prover = match config.backend {
+            ProverBackend::Cpu => Box::new(CpuProver::from_prover(config.core_prover()))
+                as Box<dyn Prover<DefaultProverComponents>>,
+            #[cfg(feature = "network")]
+            ProverBackend::Network => {
+                let url = config.rpc_url.as_deref().ok_or(ConfigError::MissingRpcUrl)?;
+                Box::new(network::NetworkProver::new(url, &config))
+            }
+        };
+        Ok(Self { prover, config })
     }
 
     /// Returns a [ProverClientBuilder] to easily create a [ProverClient].
@@ -115,6 +195,83 @@ impl ProverClient {
         self.prover.verify(proof, vk)
     }
 
+    /// Prepare to verify a batch of proofs, each against its verifying key, in parallel.
+    ///
+    /// To verify, call [action::VerifyBatch::run], which returns the result of every proof.
+    ///
+    /// ### Examples
+    /// ```no_run
+    /// use bf_sdk::ProverClient;
+    ///
+    /// let elf = test_artifacts::FIBO_BF;
+    /// let client = ProverClient::new();
+    /// let (pk, vk) = client.setup(elf);
+    /// let proof = client.prove(&pk, vec![17]).run().unwrap();
+    /// let batch = vec![(proof.clone(), vk.clone()), (proof, vk)];
+    /// let results = client.verify_batch(&batch).fail_fast().run().unwrap();
+    /// assert!(results.iter().all(|result| matches!(result, Some(Ok(())))));
+    /// ```
+    pub fn verify_batch<'a>(
+        &'a self,
+        items: &'a [(BfProofWithPublicValues, BfVerifyingKey)],
+    ) -> action::VerifyBatch<'a> {
+        action::VerifyBatch::new(self.prover.as_ref(), items)
+    }
+
+    /// Verifies a serialized proof received over the wire.
+    ///
+    /// The envelope version and proof kind are detected by [BfProofWithPublicValues::from_bytes],
+    /// so the caller does not need to know them in advance.
+    ///
+    /// ### Examples
+    /// ```no_run
+    /// use bf_sdk::ProverClient;
+    ///
+    /// let elf = test_artifacts::FIBO_BF;
+    /// let client = ProverClient::new();
+    /// let (pk, vk) = client.setup(elf);
+    /// let proof = client.prove(&pk, vec![17]).run().unwrap();
+    /// client.verify_bytes(&proof.to_bytes().unwrap(), &vk).unwrap();
+    /// ```
+    pub fn verify_bytes(
+        &self,
+        bytes: &[u8],
+        vk: &BfVerifyingKey,
+    ) -> Result<(), BfVerificationError> {
+        let proof = BfProofWithPublicValues::from_bytes(bytes)?;
+        self.verify(&proof, vk)
+    }
+
+    /// Verifies a proof against a pinned verifying key digest, without the verifying key.
+    ///
+    /// The proof embeds the verifying key of its program. It is accepted only if its
+    /// [vk_digest] is `vk_digest`, the way an on-chain verifier references a program by its key
+    /// hash. The digest binds the whole key, including the commitment the proof opens.
+    ///
+    /// ### Examples
+    /// ```no_run
+    /// use bf_sdk::{vk_digest, ProverClient};
+    ///
+    /// let elf = test_artifacts::FIBO_BF;
+    /// let client = ProverClient::new();
+    /// let (pk, vk) = client.setup(elf);
+    /// let pinned = vk_digest(&vk);
+    /// let proof = client.prove(&pk, vec![17]).run().unwrap();
+    /// client.verify_with_vkey_hash(&proof, pinned).unwrap();
+    /// ```
+    pub fn verify_with_vkey_hash(
+        &self,
+        proof: &BfProofWithPublicValues,
+        vk_digest: VerifyingKeyDigest,
+    ) -> Result<(), BfVerificationError> {
+        let vk = proof.embedded_vk();
+        let got = registry::vk_digest(&vk);
+        if got != vk_digest {
+            return Err(BfVerificationError::VkDigestMismatch { expected: vk_digest, got });
+        }
+        self.verify(proof, &vk)
+    }
+
     /// Setup a program to be proven and verified by the zkVM by computing the proving
     /// and verifying keys.
     ///
@@ -133,6 +290,54 @@ impl ProverClient {
     pub fn setup(&self, elf: &str) -> (BfProvingKey, BfVerifyingKey) {
         self.prover.setup(elf)
     }
+
+    /// Setup a program whose tape starts with `image` instead of zeros.
+    ///
+    /// The verifying key commits to the image, so it only accepts proofs of executions starting
+    /// from that image.
+    pub fn setup_with_image(&self, elf: &str, image: &[u8]) -> (BfProvingKey, BfVerifyingKey) {
+        self.prover.prover().setup_with_image(elf, image)
+    }
+
+    /// Checks that `vk` is the verifying key of `elf`, by setting the program up again and
+    /// comparing the digests of the keys, see [vk_digest].
+    ///
+    /// This validates a published key against the published source before trusting the proofs
+    /// it accepts. Keys committing to a memory image are checked with
+    /// [Self::check_vk_with_image].
+    ///
+    /// ### Examples
+    /// ```no_run
+    /// use bf_sdk::ProverClient;
+    ///
+    /// let elf = test_artifacts::FIBO_BF;
+    /// let client = ProverClient::new();
+    /// let (_, vk) = client.setup(elf);
+    /// client.check_vk(elf, &vk).unwrap();
+    /// ```
+    pub fn check_vk(&self, elf: &str, vk: &BfVerifyingKey) -> Result<(), VkCheckError> {
+        self.check_vk_with_image(elf, &[], vk)
+    }
+
+    /// Checks that `vk` is the verifying key of `elf` with the memory image `image`, as
+    /// [Self::check_vk] does.
+    pub fn check_vk_with_image(
+        &self,
+        elf: &str,
+        image: &[u8],
+        vk: &BfVerifyingKey,
+    ) -> Result<(), VkCheckError> {
+        // Setting up panics on an invalid program, so parse it first.
+        bf_core_executor::Program::from(elf)
+            .map_err(|e| VkCheckError::InvalidProgram(e.to_string()))?;
+        let (_, expected) = self.setup_with_image(elf, image);
+        let (expected, got) = (vk_digest(&expected), vk_digest(vk));
+        if expected == got {
+            Ok(())
+        } else {
+            Err(VkCheckError::DigestMismatch { expected, got })
+        }
+    }
 }
 
 impl Default for ProverClient {
@@ -169,8 +374,14 @@ impl ProverClientBuilder {
 
 #[cfg(test)]
 mod tests {
+    use bf_stark::MachineVerificationError;
+
     use super::setup_logger;
-    use crate::ProverClient;
+    use crate::cache::MemoryProofStore;
+    use crate::config::FriPreset;
+    use crate::{vk_digest, BfVerificationError, ProverClient, ProverClientConfig, VkCheckError};
+    #[cfg(feature = "network")]
+    use crate::{ConfigError, ProverBackend};
 
     #[test]
     fn test_execute() {
@@ -182,6 +393,45 @@ mod tests {
         assert_eq!(85, output[0]);
     }
 
+    #[test]
+    fn test_e2e_fast_preset() {
+        setup_logger();
+        let config = ProverClientConfig { fri_preset: FriPreset::Fast, ..Default::default() };
+        let client = ProverClient::from_config(config).unwrap();
+        let (pk, vk) = client.setup(test_artifacts::FIBO_BF);
+        let proof = client.prove(&pk, vec![17]).run().unwrap();
+        client.verify(&proof, &vk).unwrap();
+    }
+
+    #[cfg(feature = "network")]
+    #[test]
+    fn test_network_without_rpc_url() {
+        let config = ProverClientConfig { backend: ProverBackend::Network, ..Default::default() };
+        assert!(matches!(ProverClient::from_config(config), Err(ConfigError::MissingRpcUrl)));
+    }
+
+    #[test]
+    fn test_e2e_sharded() {
+        setup_logger();
+        let config = ProverClientConfig {
+            fri_preset: FriPreset::Fast,
+            shard_size: Some(100),
+            ..Default::default()
+        };
+        let client = ProverClient::from_config(config).unwrap();
+        let (pk, vk) = client.setup(test_artifacts::FIBO_BF);
+        let proof = client.prove(&pk, vec![17]).run().unwrap();
+        assert!(proof.proof.len() > 1);
+        assert_eq!(proof.metadata.shard_count, proof.proof.len());
+        client.verify(&proof, &vk).unwrap();
+        client.verify_bytes(&proof.to_bytes().unwrap(), &vk).unwrap();
+
+        // Dropping a shard breaks the chain of shards.
+        let mut truncated = proof;
+        truncated.proof.remove(1);
+        assert!(client.verify(&truncated, &vk).is_err());
+    }
+
     #[test]
     fn test_e2e_core() {
         setup_logger();
@@ -191,7 +441,102 @@ mod tests {
         let stdin = vec![17];
 
         // Generate proof & verify.
-        let proof = client.prove(&pk, stdin).run().unwrap();
+        let proof = client.prove(&pk, stdin).program_name("fibo").run().unwrap();
         client.verify(&proof, &vk).unwrap();
+        assert_eq!(proof.metadata.program_name.as_deref(), Some("fibo"));
+        assert_eq!(proof.metadata.shard_count, 1);
+
+        // A cached proof is returned instead of re-proving.
+        let cache = MemoryProofStore::new();
+        let first = client.prove(&pk, vec![17]).cache(&cache).run().unwrap();
+        let second = client.prove(&pk, vec![17]).cache(&cache).run().unwrap();
+        assert_eq!(first.metadata.created_at, second.metadata.created_at);
+        assert_eq!(first.to_bytes().unwrap(), second.to_bytes().unwrap());
+
+        // Enveloped and bare encodings are both accepted.
+        client.verify_bytes(&proof.to_bytes().unwrap(), &vk).unwrap();
+        client.verify_bytes(&bincode::serialize(&proof).unwrap(), &vk).unwrap();
+    }
+
+    #[test]
+    fn test_bf_macro() {
+        const ADD: &str = crate::bf! {
+            ,>,     // read a and b
+            [-<+>]  // add b to a
+            <.      // write a
+        };
+        assert_eq!(ADD, ",>,[-<+>]<.");
+        // String literals hold text the Rust lexer would reject.
+        assert_eq!(crate::bf!("don't [" - "] read;"), "[-];");
+
+        let output = ProverClient::new().execute(ADD, vec![2, 3]).run().unwrap();
+        assert_eq!(output, [5]);
+    }
+
+    #[test]
+    fn test_check_vk() {
+        let client = ProverClient::new();
+        let (_, vk) = client.setup(test_artifacts::FIBO_BF);
+        assert_eq!(client.check_vk(test_artifacts::FIBO_BF, &vk), Ok(()));
+        assert!(matches!(
+            client.check_vk(test_artifacts::HELLO_BF, &vk),
+            Err(VkCheckError::DigestMismatch { .. })
+        ));
+        assert!(matches!(client.check_vk("[", &vk), Err(VkCheckError::InvalidProgram(_))));
+
+        let (_, vk) = client.setup_with_image(".", &[1]);
+        assert!(client.check_vk(".", &vk).is_err());
+        assert_eq!(client.check_vk_with_image(".", &[1], &vk), Ok(()));
+    }
+
+    #[test]
+    fn test_verify_with_vkey_hash() {
+        setup_logger();
+        let client = ProverClient::new();
+        let (pk, vk) = client.setup(test_artifacts::FIBO_BF);
+        let proof = client.prove(&pk, vec![17]).run().unwrap();
+        client.verify_with_vkey_hash(&proof, vk_digest(&vk)).unwrap();
+
+        let (_, other_vk) = client.setup(test_artifacts::HELLO_BF);
+        assert!(matches!(
+            client.verify_with_vkey_hash(&proof, vk_digest(&other_vk)),
+            Err(BfVerificationError::VkDigestMismatch { .. })
+        ));
+
+        // Swapping the embedded key changes its digest.
+        let mut swapped = proof.clone();
+        swapped.vk = other_vk.vk;
+        assert!(client.verify_with_vkey_hash(&swapped, vk_digest(&vk)).is_err());
+
+        // So does tampering with its chip information, which the verifier also checks against
+        // the machine.
+        let mut tampered = proof.clone();
+        tampered.vk.chip_information[0].2.width += 1;
+        assert!(matches!(
+            client.verify_with_vkey_hash(&tampered, vk_digest(&vk)),
+            Err(BfVerificationError::VkDigestMismatch { .. })
+        ));
+        assert!(matches!(
+            client.verify(&tampered, &tampered.embedded_vk()),
+            Err(BfVerificationError::Core(MachineVerificationError::InvalidVerificationKey))
+        ));
+    }
+
+    #[test]
+    fn test_verify_batch() {
+        setup_logger();
+        let client = ProverClient::new();
+        let (pk, vk) = client.setup(test_artifacts::FIBO_BF);
+        let proof = client.prove(&pk, vec![17]).run().unwrap();
+        let mut tampered = proof.clone();
+        tampered.output[0] += 1;
+        let batch = vec![(proof.clone(), vk.clone()), (tampered, vk.clone()), (proof, vk.clone())];
+
+        let results = client.verify_batch(&batch).run().unwrap();
+        assert!(matches!(results[..], [Some(Ok(())), Some(Err(_)), Some(Ok(()))]));
+
+        // On a single thread the proofs are verified in order, so the last one is skipped.
+        let results = client.verify_batch(&batch).fail_fast().num_threads(1).run().unwrap();
+        assert!(matches!(results[..], [Some(Ok(())), Some(Err(_)), None]));
     }
 }
@@ -0,0 +1,79 @@
+//! Prometheus-friendly metrics for proving services.
+//!
+//! When the `metrics` feature is enabled, the SDK reports counters and histograms through the
+//! [`metrics`](https://docs.rs/metrics) facade. Install any compatible recorder (for example
+//! `metrics-exporter-prometheus`) to collect them. Without the feature every hook is a no-op.
+
+use std::time::Duration;
+
+/// Total number of proofs generated.
+pub const PROOFS_GENERATED: &str = "bf_sdk_proofs_generated_total";
+/// Total number of cycles proved.
+pub const CYCLES_PROVED: &str = "bf_sdk_cycles_proved_total";
+/// Total number of failed verifications.
+pub const VERIFICATION_FAILURES: &str = "bf_sdk_verification_failures_total";
+/// Duration of each phase (`setup`, `execute`, `prove`, `verify`), in seconds.
+pub const PHASE_DURATION: &str = "bf_sdk_phase_duration_seconds";
+/// Size of the generated proofs, in bytes.
+pub const PROOF_SIZE: &str = "bf_sdk_proof_size_bytes";
+
+/// A proving phase whose duration is reported under [`PHASE_DURATION`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Setup,
+    Execute,
+    Prove,
+    Verify,
+}
+
+impl Phase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Phase::Setup => "setup",
+            Phase::Execute => "execute",
+            Phase::Prove => "prove",
+            Phase::Verify => "verify",
+        }
+    }
+}
+
+/// Registers the descriptions of all SDK metrics with the installed recorder.
+///
+/// Calling this is optional, but it gives exporters a help text and a unit for each metric.
+pub fn describe_metrics() {
+    #[cfg(feature = "metrics")]
+    {
+        use ::metrics::{describe_counter, describe_histogram, Unit};
+
+        describe_counter!(PROOFS_GENERATED, Unit::Count, "Number of proofs generated");
+        describe_counter!(CYCLES_PROVED, Unit::Count, "Number of cycles proved");
+        describe_counter!(VERIFICATION_FAILURES, Unit::Count, "Number of failed verifications");
+        describe_histogram!(PHASE_DURATION, Unit::Seconds, "Duration of each proving phase");
+        describe_histogram!(PROOF_SIZE, Unit::Bytes, "Size of the generated proofs");
+    }
+}
+
+pub(crate) fn record_phase(phase: Phase, elapsed: Duration) {
+    #[cfg(feature = "metrics")]
+    ::metrics::histogram!(PHASE_DURATION, "phase" => phase.as_str()).record(elapsed.as_secs_f64());
+    #[cfg(not(feature = "metrics"))]
+    let _ = (phase, elapsed);
+}
+
+/// Records a generated proof. Its size is only computed with the feature, as it takes a pass
+/// over the whole proof.
+pub(crate) fn record_proof(cycles: u64, proof_size: impl FnOnce() -> u64) {
+    #[cfg(feature = "metrics")]
+    {
+        ::metrics::counter!(PROOFS_GENERATED).increment(1);
+        ::metrics::counter!(CYCLES_PROVED).increment(cycles);
+        ::metrics::histogram!(PROOF_SIZE).record(proof_size() as f64);
+    }
+    #[cfg(not(feature = "metrics"))]
+    let _ = (cycles, proof_size);
+}
+
+pub(crate) fn record_verification_failure() {
+    #[cfg(feature = "metrics")]
+    ::metrics::counter!(VERIFICATION_FAILURES).increment(1);
+}
@@ -0,0 +1,296 @@
+//! Proving on a remote `bf-prover-server` over JSON-RPC 2.0.
+//!
+//! Requests are JSON-RPC 2.0 objects posted to the root of the server. Binary data is
+//! hex-encoded and programs are addressed by their [program_digest](crate::program_digest).
+//!
+//! | Method     | Params          | Result           |
+//! |------------|-----------------|------------------|
+//! | `setup`    | [SetupParams]   | [SetupResult]    |
+//! | `prove`    | [ProveParams]   | [ProveResult]    |
+//! | `status`   | [JobParams]     | [StatusResult]   |
+//! | `getProof` | [JobParams]     | [GetProofResult] |
+//!
+//! `prove` only queues a job. Clients poll `status` until the job has finished and then fetch
+//! the proof with `getProof`.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub const SETUP_METHOD: &str = "setup";
+pub const PROVE_METHOD: &str = "prove";
+pub const STATUS_METHOD: &str = "status";
+pub const GET_PROOF_METHOD: &str = "getProof";
+
+/// The standard JSON-RPC error codes.
+pub mod error_code {
+    pub const PARSE_ERROR: i64 = -32700;
+    pub const INVALID_REQUEST: i64 = -32600;
+    pub const METHOD_NOT_FOUND: i64 = -32601;
+    pub const INVALID_PARAMS: i64 = -32602;
+    pub const INTERNAL_ERROR: i64 = -32603;
+    /// No program with the given digest was set up.
+    pub const UNKNOWN_PROGRAM: i64 = -32001;
+    /// No job with the given id exists.
+    pub const UNKNOWN_JOB: i64 = -32002;
+    /// The job has not succeeded (yet).
+    pub const PROOF_UNAVAILABLE: i64 = -32003;
+    /// The proving queue is full.
+    pub const QUEUE_FULL: i64 = -32004;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcRequest {
+    pub jsonrpc: String,
+    pub id: Value,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+impl RpcRequest {
+    pub fn new(id: u64, method: &str, params: impl Serialize) -> serde_json::Result<Self> {
+        Ok(Self {
+            jsonrpc: "2.0".to_string(),
+            id: id.into(),
+            method: method.to_string(),
+            params: serde_json::to_value(params)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcResponse {
+    pub jsonrpc: String,
+    pub id: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+}
+
+impl RpcResponse {
+    pub fn success(id: Value, result: Value) -> Self {
+        Self { jsonrpc: "2.0".to_string(), id, result: Some(result), error: None }
+    }
+
+    pub fn failure(id: Value, error: RpcError) -> Self {
+        Self { jsonrpc: "2.0".to_string(), id, result: None, error: Some(error) }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, thiserror::Error)]
+#[error("JSON-RPC error {code}: {message}")]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    pub fn new(code: i64, message: impl ToString) -> Self {
+        Self { code, message: message.to_string() }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetupParams {
+    /// The source of the program.
+    pub program: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetupResult {
+    /// The hex-encoded program digest.
+    pub program_id: String,
+    /// The hex-encoded, bincode-serialized verifying key.
+    pub vk: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProveParams {
+    /// The hex-encoded program digest returned by `setup`.
+    pub program_id: String,
+    /// The hex-encoded input.
+    pub stdin: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProveResult {
+    pub job_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobParams {
+    pub job_id: String,
+}
+
+/// The status of a remote proving job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RemoteJobStatus {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+}
+
+impl RemoteJobStatus {
+    pub fn is_finished(&self) -> bool {
+        matches!(self, RemoteJobStatus::Succeeded | RemoteJobStatus::Failed)
+    }
+}
+
+impl From<crate::pool::JobStatus> for RemoteJobStatus {
+    fn from(status: crate::pool::JobStatus) -> Self {
+        use crate::pool::JobStatus;
+        match status {
+            JobStatus::Queued => RemoteJobStatus::Queued,
+            JobStatus::Running => RemoteJobStatus::Running,
+            JobStatus::Succeeded => RemoteJobStatus::Succeeded,
+            JobStatus::Failed => RemoteJobStatus::Failed,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusResult {
+    pub status: RemoteJobStatus,
+    /// Why the job failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetProofResult {
+    /// The hex-encoded proof, as written by
+    /// [BfProofWithPublicValues::to_bytes](crate::BfProofWithPublicValues).
+    pub proof: String,
+}
+
+#[cfg(feature = "network")]
+pub use client::NetworkProver;
+
+#[cfg(feature = "network")]
+mod client {
+    use std::{
+        sync::atomic::{AtomicU64, Ordering},
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use anyhow::{bail, Context, Result};
+    use serde::{de::DeserializeOwned, Serialize};
+
+    use bf_prover::{components::DefaultProverComponents, BfProver};
+
+    use super::*;
+    use crate::{
+        program_digest, BfProofWithPublicValues, BfProvingKey, BfVerifyingKey, Prover,
+        ProverClientConfig,
+    };
+
+    const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+    /// An implementation of [crate::ProverClient] that generates proofs on a remote
+    /// `bf-prover-server`.
+    ///
+    /// Setup and verification run locally, so the keys never need to be trusted from the server.
+    pub struct NetworkProver {
+        prover: BfProver<DefaultProverComponents>,
+        url: String,
+        http: reqwest::blocking::Client,
+        timeout: Option<Duration>,
+        next_id: AtomicU64,
+    }
+
+    impl NetworkProver {
+        /// Creates a [NetworkProver] targeting the server at `url`.
+        pub fn new(url: impl Into<String>, config: &ProverClientConfig) -> Self {
+            Self {
+                prover: config.core_prover(),
+                url: url.into(),
+                http: reqwest::blocking::Client::new(),
+                timeout: config.timeout,
+                next_id: AtomicU64::new(0),
+            }
+        }
+
+        fn call<P: Serialize, R: DeserializeOwned>(&self, method: &str, params: P) -> Result<R> {
+            let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+            let request = RpcRequest::new(id, method, params)?;
+            let body = self
+                .http
+                .post(&self.url)
+                .json(&request)
+                .send()
+                .and_then(|response| response.error_for_status())
+                .with_context(|| format!("failed to call {method} on {}", self.url))?
+                .bytes()?;
+            let response: RpcResponse = serde_json::from_slice(&body)?;
+            match (response.result, response.error) {
+                (_, Some(error)) => Err(error.into()),
+                (Some(result), None) => Ok(serde_json::from_value(result)?),
+                (None, None) => bail!("the response to {method} has neither a result nor an error"),
+            }
+        }
+
+        /// Queues a proof of `program` on the server, returning the job id.
+        pub fn submit(&self, program: &str, stdin: &[u8]) -> Result<String> {
+            let setup: SetupResult =
+                self.call(SETUP_METHOD, SetupParams { program: program.to_string() })?;
+            if setup.program_id != hex::encode(program_digest(program)) {
+                bail!("the server returned the digest of another program");
+            }
+            let params = ProveParams { program_id: setup.program_id, stdin: hex::encode(stdin) };
+            let prove: ProveResult = self.call(PROVE_METHOD, params)?;
+            Ok(prove.job_id)
+        }
+
+        pub fn status(&self, job_id: &str) -> Result<StatusResult> {
+            self.call(STATUS_METHOD, JobParams { job_id: job_id.to_string() })
+        }
+
+        /// Polls the job until it has finished and fetches its proof.
+        pub fn wait_proof(&self, job_id: &str) -> Result<BfProofWithPublicValues> {
+            let start = Instant::now();
+            loop {
+                let status = self.status(job_id)?;
+                match status.status {
+                    RemoteJobStatus::Succeeded => break,
+                    RemoteJobStatus::Failed => {
+                        let error = status.error.unwrap_or_else(|| "unknown error".to_string());
+                        bail!("job {job_id} failed: {error}");
+                    }
+                    RemoteJobStatus::Queued | RemoteJobStatus::Running => {}
+                }
+                if self.timeout.is_some_and(|timeout| start.elapsed() > timeout) {
+                    bail!("timed out waiting for job {job_id}");
+                }
+                thread::sleep(POLL_INTERVAL);
+            }
+
+            let result: GetProofResult =
+                self.call(GET_PROOF_METHOD, JobParams { job_id: job_id.to_string() })?;
+            let bytes = hex::decode(result.proof).context("invalid proof encoding")?;
+            Ok(BfProofWithPublicValues::from_bytes(&bytes)?)
+        }
+    }
+
+    impl Prover<DefaultProverComponents> for NetworkProver {
+        fn prover(&self) -> &BfProver<DefaultProverComponents> {
+            &self.prover
+        }
+
+        fn setup(&self, elf: &str) -> (BfProvingKey, BfVerifyingKey) {
+            self.prover.setup(elf)
+        }
+
+        fn prove(&self, pk: &BfProvingKey, stdin: Vec<u8>) -> Result<BfProofWithPublicValues> {
+            // The server sets the programs up from their elf alone.
+            if !pk.program.image.is_empty() {
+                bail!("the network prover does not support memory images");
+            }
+            let job_id = self.submit(&pk.elf, &stdin)?;
+            self.wait_proof(&job_id)
+        }
+    }
+}
@@ -0,0 +1,200 @@
+//! A pool of workers generating proofs concurrently.
+
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, Receiver, SyncSender, TrySendError},
+        Arc, Condvar, Mutex,
+    },
+    thread::{self, JoinHandle},
+};
+
+use anyhow::{anyhow, Result};
+
+use crate::{BfProofWithPublicValues, BfProvingKey, ProverClient};
+
+/// The status of a proving job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+}
+
+struct JobState {
+    status: Mutex<(JobStatus, Option<Result<BfProofWithPublicValues>>)>,
+    done: Condvar,
+}
+
+impl JobState {
+    fn set_status(&self, status: JobStatus) {
+        self.status.lock().unwrap().0 = status;
+    }
+
+    fn finish(&self, result: Result<BfProofWithPublicValues>) {
+        let status = if result.is_ok() { JobStatus::Succeeded } else { JobStatus::Failed };
+        *self.status.lock().unwrap() = (status, Some(result));
+        self.done.notify_all();
+    }
+}
+
+struct Job {
+    pk: Arc<BfProvingKey>,
+    stdin: Vec<u8>,
+    state: Arc<JobState>,
+}
+
+/// A handle to a job submitted to a [ProvingPool].
+pub struct ProofHandle {
+    id: u64,
+    state: Arc<JobState>,
+}
+
+impl ProofHandle {
+    /// The id of the job, unique within its pool.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// The current status of the job.
+    pub fn status(&self) -> JobStatus {
+        self.state.status.lock().unwrap().0
+    }
+
+    /// Whether the job has finished, successfully or not.
+    pub fn is_finished(&self) -> bool {
+        matches!(self.status(), JobStatus::Succeeded | JobStatus::Failed)
+    }
+
+    /// Blocks until the job finishes and returns its result.
+    pub fn wait(self) -> Result<BfProofWithPublicValues> {
+        let mut guard = self.state.status.lock().unwrap();
+        loop {
+            if let Some(result) = guard.1.take() {
+                return result;
+            }
+            guard = self.state.done.wait(guard).unwrap();
+        }
+    }
+}
+
+/// The error returned by [ProvingPool::try_submit] when the queue is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolFull;
+
+impl std::fmt::Display for PoolFull {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "the proving queue is full")
+    }
+}
+
+impl std::error::Error for PoolFull {}
+
+/// Runs a bounded number of proofs concurrently.
+///
+/// Jobs are started in submission order. At most `queue_capacity` jobs wait for a worker, after
+/// which [Self::submit] blocks and [Self::try_submit] fails, giving callers backpressure.
+pub struct ProvingPool {
+    sender: Option<SyncSender<Job>>,
+    workers: Vec<JoinHandle<()>>,
+    next_id: AtomicU64,
+}
+
+impl ProvingPool {
+    /// Creates a pool running at most `num_workers` proofs at a time.
+    pub fn new(client: Arc<ProverClient>, num_workers: usize, queue_capacity: usize) -> Self {
+        assert!(num_workers > 0, "a proving pool needs at least one worker");
+
+        let (sender, receiver) = mpsc::sync_channel::<Job>(queue_capacity);
+        let receiver = Arc::new(Mutex::new(receiver));
+        let workers = (0..num_workers)
+            .map(|i| {
+                let client = client.clone();
+                let receiver = receiver.clone();
+                thread::Builder::new()
+                    .name(format!("bf-prover-{i}"))
+                    .spawn(move || worker(&client, &receiver))
+                    .expect("failed to spawn proving worker")
+            })
+            .collect();
+
+        Self { sender: Some(sender), workers, next_id: AtomicU64::new(0) }
+    }
+
+    /// Submits a job, blocking while the queue is full.
+    pub fn submit(&self, pk: Arc<BfProvingKey>, stdin: Vec<u8>) -> ProofHandle {
+        let (job, handle) = self.job(pk, stdin);
+        self.sender.as_ref().unwrap().send(job).expect("proving workers have stopped");
+        handle
+    }
+
+    /// Submits a job if the queue has room.
+    pub fn try_submit(
+        &self,
+        pk: Arc<BfProvingKey>,
+        stdin: Vec<u8>,
+    ) -> Result<ProofHandle, PoolFull> {
+        let (job, handle) = self.job(pk, stdin);
+        match self.sender.as_ref().unwrap().try_send(job) {
+            Ok(()) => Ok(handle),
+            Err(TrySendError::Full(_)) => Err(PoolFull),
+            Err(TrySendError::Disconnected(_)) => panic!("proving workers have stopped"),
+        }
+    }
+
+    fn job(&self, pk: Arc<BfProvingKey>, stdin: Vec<u8>) -> (Job, ProofHandle) {
+        let state = Arc::new(JobState {
+            status: Mutex::new((JobStatus::Queued, None)),
+            done: Condvar::new(),
+        });
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        (Job { pk, stdin, state: state.clone() }, ProofHandle { id, state })
+    }
+}
+
+impl Drop for ProvingPool {
+    /// Waits for the queued jobs to finish.
+    fn drop(&mut self) {
+        self.sender.take();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn worker(client: &ProverClient, receiver: &Mutex<Receiver<Job>>) {
+    loop {
+        // Only hold the lock while waiting for the next job.
+        let job = match receiver.lock().unwrap().recv() {
+            Ok(job) => job,
+            Err(_) => return,
+        };
+
+        job.state.set_status(JobStatus::Running);
+        let prove = AssertUnwindSafe(|| client.prove(&job.pk, job.stdin).run());
+        let result =
+            panic::catch_unwind(prove).unwrap_or_else(|_| Err(anyhow!("the prover panicked")));
+        job.state.finish(result);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proving_pool() {
+        let client = Arc::new(ProverClient::new());
+        let (pk, vk) = client.setup(test_artifacts::FIBO_BF);
+        let pk = Arc::new(pk);
+
+        let pool = ProvingPool::new(client.clone(), 2, 4);
+        let handles = (0..3).map(|i| pool.submit(pk.clone(), vec![10 + i])).collect::<Vec<_>>();
+        for handle in handles {
+            let proof = handle.wait().unwrap();
+            client.verify(&proof, &vk).unwrap();
+        }
+    }
+}
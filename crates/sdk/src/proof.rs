@@ -1,15 +1,166 @@
-use std::fmt::Debug;
+use std::{
+    fmt::Debug,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
-use bf_prover::CoreSC;
-use bf_stark::{MachineVerificationError, ShardProof};
+use bf_prover::{BfVerifyingKey, CoreSC, ProofSystemInfo};
+use bf_stark::{MachineVerificationError, ShardProof, StarkVerifyingKey};
+
+use crate::CompressedStdin;
 
 /// A proof generated with Bf, bundled together with stdin, public values, and the zkMIPS version.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BfProofWithPublicValues {
-    pub proof: ShardProof<CoreSC>,
-    pub stdin: Vec<u8>,
+    /// The proofs of the shards of the execution, in the order of execution.
+    pub proof: Vec<ShardProof<CoreSC>>,
+    /// The bytes given to the program, compressed.
+    pub stdin: CompressedStdin,
+    /// The bytes written to stdout by the program.
+    pub output: Vec<u8>,
+    /// The proving system the proof was generated with, checked before verifying.
+    pub system: ProofSystemInfo,
+    /// The verifying key of the program, which lets the proof be verified against a pinned
+    /// [vk_digest](crate::vk_digest), see [ProverClient::verify_with_vkey_hash](
+    /// crate::ProverClient::verify_with_vkey_hash).
+    pub vk: StarkVerifyingKey<CoreSC>,
+    pub metadata: BfProofMetadata,
+}
+
+/// The identifier of [CoreSC] recorded in the proof metadata.
+pub use bf_prover::CORE_CONFIG_ID;
+
+/// Information about how and when a proof was generated.
+///
+/// The metadata is serialized with the proof and can be read without verifying it. Apart from
+/// the number of cycles, which verification checks against the public values, it is not bound
+/// by the proof, so it must not be trusted for anything but audit trails and debugging.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BfProofMetadata {
+    /// The version of the SDK that generated the proof.
+    pub version: String,
+    /// The identifier of the stark config.
+    pub config: String,
+    /// The number of cycles proved, committed to by the proof.
+    pub cycles: u64,
+    /// The number of shards.
+    pub shard_count: usize,
+    /// The creation time, in seconds since the Unix epoch.
+    pub created_at: u64,
+    /// The name of the proven program, if any.
+    pub program_name: Option<String>,
+}
+
+impl BfProofMetadata {
+    /// Creates the metadata of a proof generated now.
+    pub fn new(cycles: u64, shard_count: usize) -> Self {
+        let created_at =
+            SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            config: CORE_CONFIG_ID.to_string(),
+            cycles,
+            shard_count,
+            created_at,
+            program_name: None,
+        }
+    }
 }
 
 pub type BfCoreProofVerificationError = MachineVerificationError<CoreSC>;
+
+/// The magic bytes that prefix an enveloped proof.
+pub const PROOF_MAGIC: [u8; 4] = *b"BFPF";
+
+/// The current version of the proof envelope.
+pub const PROOF_ENVELOPE_VERSION: u8 = 6;
+
+/// The length of the envelope header: magic, version and kind.
+const PROOF_HEADER_LEN: usize = PROOF_MAGIC.len() + 2;
+
+/// The kind of proof carried by an envelope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum BfProofKind {
+    Core = 0,
+}
+
+impl BfProofKind {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(BfProofKind::Core),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum BfProofDecodeError {
+    #[error("Unsupported proof envelope version: {0}")]
+    UnsupportedVersion(u8),
+    #[error("Unknown proof kind: {0}")]
+    UnknownKind(u8),
+    #[error("Failed to deserialize proof: {0}")]
+    Deserialization(#[from] bincode::Error),
+}
+
+impl BfProofWithPublicValues {
+    /// The kind of this proof.
+    pub const fn kind(&self) -> BfProofKind {
+        BfProofKind::Core
+    }
+
+    /// Strips stdin and its digest from the proof, so that the proof can be published without
+    /// revealing the input. The stripped proof still verifies, and its output is still checked.
+    ///
+    /// The input is then only known through the public values: the number of bytes the program
+    /// read and their Poseidon2 digests, against which a holder of the input can check it with
+    /// [check_io](bf_prover::verify::check_io).
+    pub fn into_public(mut self) -> Self {
+        self.stdin.strip();
+        self
+    }
+
+    /// The verifying key embedded in the proof.
+    ///
+    /// It is not trusted on its own: verify against it only after matching its
+    /// [vk_digest](crate::vk_digest) with a pinned one.
+    pub fn embedded_vk(&self) -> BfVerifyingKey {
+        BfVerifyingKey { vk: self.vk.clone(), system: self.system }
+    }
+
+    /// Serializes the proof into a versioned envelope.
+    ///
+    /// The envelope is [PROOF_MAGIC], the envelope version, the [BfProofKind] and then the
+    /// bincode-serialized proof.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, bincode::Error> {
+        let mut bytes = Vec::with_capacity(PROOF_HEADER_LEN);
+        bytes.extend_from_slice(&PROOF_MAGIC);
+        bytes.push(PROOF_ENVELOPE_VERSION);
+        bytes.push(self.kind() as u8);
+        bincode::serialize_into(&mut bytes, self)?;
+        Ok(bytes)
+    }
+
+    /// Deserializes a proof, detecting the envelope version and the proof kind.
+    ///
+    /// Bytes without the envelope header are decoded as a bare bincode-serialized core proof.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BfProofDecodeError> {
+        if bytes.len() < PROOF_HEADER_LEN || bytes[..PROOF_MAGIC.len()] != PROOF_MAGIC {
+            return Ok(bincode::deserialize(bytes)?);
+        }
+
+        let version = bytes[PROOF_MAGIC.len()];
+        if version != PROOF_ENVELOPE_VERSION {
+            return Err(BfProofDecodeError::UnsupportedVersion(version));
+        }
+
+        let kind = bytes[PROOF_MAGIC.len() + 1];
+        match BfProofKind::from_u8(kind) {
+            Some(BfProofKind::Core) => Ok(bincode::deserialize(&bytes[PROOF_HEADER_LEN..])?),
+            None => Err(BfProofDecodeError::UnknownKind(kind)),
+        }
+    }
+}
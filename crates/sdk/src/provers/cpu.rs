@@ -1,8 +1,11 @@
+use std::time::Instant;
+
 use anyhow::Result;
 
 use bf_prover::{components::DefaultProverComponents, BfProver};
 
-use crate::{BfProofWithPublicValues, BfProvingKey, BfVerifyingKey, Prover};
+use crate::metrics::{self, Phase};
+use crate::{BfProofMetadata, BfProofWithPublicValues, BfProvingKey, BfVerifyingKey, Prover};
 
 /// An implementation of [crate::ProverClient] that can generate end-to-end proofs locally.
 pub struct CpuProver {
@@ -24,7 +27,10 @@ impl CpuProver {
 
 impl Prover<DefaultProverComponents> for CpuProver {
     fn setup(&self, elf: &str) -> (BfProvingKey, BfVerifyingKey) {
-        self.prover.setup(elf)
+        let start = Instant::now();
+        let keys = self.prover.setup(elf);
+        metrics::record_phase(Phase::Setup, start.elapsed());
+        keys
     }
 
     fn prover(&self) -> &BfProver<DefaultProverComponents> {
@@ -32,9 +38,29 @@ impl Prover<DefaultProverComponents> for CpuProver {
     }
 
     fn prove(&self, pk: &BfProvingKey, stdin: Vec<u8>) -> Result<BfProofWithPublicValues> {
+        self.prove_with_private_input(pk, stdin, vec![])
+    }
+
+    fn prove_with_private_input(
+        &self,
+        pk: &BfProvingKey,
+        stdin: Vec<u8>,
+        private_input: Vec<u8>,
+    ) -> Result<BfProofWithPublicValues> {
+        let start = Instant::now();
         let proof: bf_prover::BfProofWithMetadata<bf_prover::BfCoreProofData> =
-            self.prover.prove(pk, &stdin)?;
-        Ok(BfProofWithPublicValues { proof: proof.proof.0, stdin: proof.stdin })
+            self.prover.prove_with_private_input(pk, &stdin, &private_input)?;
+        metrics::record_phase(Phase::Prove, start.elapsed());
+        metrics::record_proof(proof.cycles, || bf_stark::serialized_size(&proof.proof));
+        let shard_count = proof.proof.0.len();
+        Ok(BfProofWithPublicValues {
+            proof: proof.proof.0,
+            stdin: proof.stdin.into(),
+            output: proof.public_values,
+            system: self.prover.system_info(),
+            vk: pk.vk.vk.clone(),
+            metadata: BfProofMetadata::new(proof.cycles, shard_count),
+        })
     }
 }
 
@@ -2,20 +2,43 @@ mod cpu;
 
 pub use cpu::CpuProver;
 
-use anyhow::Result;
+use std::time::Instant;
+
+use anyhow::{bail, Result};
+use p3_field::PrimeField64;
 use thiserror::Error;
 
+use bf_core_executor::CYCLES_PUBLIC_VALUE;
 use bf_prover::{
-    components::BfProverComponents, BfCoreProofData, BfProver, BfProvingKey, BfVerifyingKey, CoreSC,
+    components::BfProverComponents, BfCoreProofData, BfProver, BfProvingKey, BfVerifyingKey,
+    CoreSC, ProofSystemInfo,
 };
 use bf_stark::MachineVerificationError;
 
-use crate::BfProofWithPublicValues;
+use crate::metrics::{self, Phase};
+use crate::{stdin::StdinError, BfProofDecodeError, BfProofWithPublicValues, VerifyingKeyDigest};
 
 #[derive(Error, Debug)]
 pub enum BfVerificationError {
     #[error("Invalid public values")]
     InvalidPublicValues,
+    /// The proof or the key was generated by a proving system the verifier does not accept, see
+    /// [bf_prover::version].
+    #[error("Incompatible proof system: got {proof}, but the verifier is {verifier}")]
+    IncompatibleVersion { proof: ProofSystemInfo, verifier: ProofSystemInfo },
+    #[error("Failed to deserialize: {0}")]
+    Deserialization(bincode::Error),
+    /// The verifying key embedded in the proof is not the pinned one.
+    #[error(
+        "Verifying key digest {} does not match the pinned {}",
+        hex::encode(.got),
+        hex::encode(.expected)
+    )]
+    VkDigestMismatch { expected: VerifyingKeyDigest, got: VerifyingKeyDigest },
+    #[error("Invalid stdin: {0}")]
+    Stdin(#[from] StdinError),
+    #[error("Failed to decode proof: {0}")]
+    Decode(#[from] BfProofDecodeError),
     #[error("Core machine verification error: {0}")]
     Core(MachineVerificationError<CoreSC>),
 }
@@ -29,14 +52,87 @@ pub trait Prover<C: BfProverComponents>: Send + Sync {
     /// Prove the execution of a ELF with the given inputs.
     fn prove(&self, pk: &BfProvingKey, stdin: Vec<u8>) -> Result<BfProofWithPublicValues>;
 
+    /// Prove the execution of a ELF with the given inputs and private inputs, which the proof
+    /// does not reveal.
+    ///
+    /// Provers that would have to send the private inputs elsewhere do not support them.
+    fn prove_with_private_input(
+        &self,
+        pk: &BfProvingKey,
+        stdin: Vec<u8>,
+        private_input: Vec<u8>,
+    ) -> Result<BfProofWithPublicValues> {
+        if !private_input.is_empty() {
+            bail!("this prover does not support private inputs");
+        }
+        self.prove(pk, stdin)
+    }
+
     /// Verify that a proof is valid given its vkey and metadata.
     fn verify(
         &self,
         bundle: &BfProofWithPublicValues,
         vkey: &BfVerifyingKey,
     ) -> Result<(), BfVerificationError> {
-        self.prover()
-            .verify(&BfCoreProofData(bundle.proof.clone()), vkey)
-            .map_err(BfVerificationError::Core)
+        let start = Instant::now();
+        let result = check_compatibility(&self.prover().system_info(), bundle, vkey)
+            .and_then(|()| check_io(bundle))
+            .and_then(|()| check_cycles(bundle))
+            .and_then(|()| {
+                self.prover()
+                    .verify(&BfCoreProofData(bundle.proof.clone()), vkey)
+                    .map_err(BfVerificationError::Core)
+            });
+        metrics::record_phase(Phase::Verify, start.elapsed());
+        if result.is_err() {
+            metrics::record_verification_failure();
+        }
+        result
+    }
+}
+
+/// Checks that the verifier accepts the proving systems of the proof and of the key.
+pub(crate) fn check_compatibility(
+    verifier: &ProofSystemInfo,
+    proof: &BfProofWithPublicValues,
+    vk: &BfVerifyingKey,
+) -> Result<(), BfVerificationError> {
+    for system in [&proof.system, &vk.system] {
+        if !verifier.accepts(system) {
+            return Err(BfVerificationError::IncompatibleVersion {
+                proof: *system,
+                verifier: *verifier,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the I/O digests the proof commits to are the digests of the bytes of stdin the
+/// program read and of the output of the proof, see [bf_prover::verify::check_io]. Stdin is
+/// decompressed and checked against its digest on the way.
+///
+/// A proof whose stdin was stripped with [BfProofWithPublicValues::into_public] only has its
+/// output checked: the verification says nothing about its input.
+pub(crate) fn check_io(proof: &BfProofWithPublicValues) -> Result<(), BfVerificationError> {
+    let stdin = if proof.stdin.is_stripped() { None } else { Some(proof.stdin.try_bytes()?) };
+    if bf_prover::verify::check_io(&proof.proof, stdin, &proof.output) {
+        Ok(())
+    } else {
+        Err(BfVerificationError::InvalidPublicValues)
+    }
+}
+
+/// Checks that the number of cycles of the metadata is the one the proof commits to, summed over
+/// the shards.
+pub(crate) fn check_cycles(proof: &BfProofWithPublicValues) -> Result<(), BfVerificationError> {
+    let cycles = proof.proof.iter().try_fold(0u64, |cycles, shard| {
+        let shard_cycles = shard.public_values.get(CYCLES_PUBLIC_VALUE)?;
+        cycles.checked_add(shard_cycles.as_canonical_u64())
+    });
+    if cycles == Some(proof.metadata.cycles) {
+        Ok(())
+    } else {
+        Err(BfVerificationError::InvalidPublicValues)
     }
 }
@@ -0,0 +1,83 @@
+//! Receipts: a proof together with the claim it attests to.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    program_digest, verify_proof, BfProofWithPublicValues, BfVerificationError, BfVerifyingKey,
+    ProgramDigest,
+};
+
+/// What a [Receipt] claims: running the program on the input produced the output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReceiptClaim {
+    /// The digest of the program, see [program_digest].
+    pub program_digest: ProgramDigest,
+    /// The SHA-256 digest of stdin, or `None` if it was stripped from the proof, in which case
+    /// the receipt claims nothing about the input.
+    pub input_digest: Option<[u8; 32]>,
+    /// The SHA-256 digest of stdout.
+    pub output_digest: [u8; 32],
+}
+
+/// A proof bundled with its claim.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Receipt {
+    pub claim: ReceiptClaim,
+    pub proof: BfProofWithPublicValues,
+}
+
+impl Receipt {
+    /// Creates the receipt of a proof of `elf`.
+    pub fn new(elf: &str, proof: BfProofWithPublicValues) -> Self {
+        let claim = ReceiptClaim {
+            program_digest: program_digest(elf),
+            input_digest: proof.stdin.digest().copied(),
+            output_digest: Sha256::digest(&proof.output).into(),
+        };
+        Self { claim, proof }
+    }
+
+    /// Verifies the proof against `vk` and checks that the claim matches the proof.
+    ///
+    /// The verifying key must belong to the program of [ReceiptClaim::program_digest]; the
+    /// caller is responsible for pairing them, e.g. through a
+    /// [ProgramRegistry](crate::ProgramRegistry). The input and output digests are checked
+    /// against the bytes bundled with the proof.
+    pub fn verify(&self, vk: &BfVerifyingKey) -> Result<(), BfVerificationError> {
+        let input_digest = self.proof.stdin.digest().copied();
+        let output_digest: [u8; 32] = Sha256::digest(&self.proof.output).into();
+        if input_digest != self.claim.input_digest || output_digest != self.claim.output_digest {
+            return Err(BfVerificationError::InvalidPublicValues);
+        }
+        verify_proof(&self.proof, vk)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ProverClient;
+
+    #[test]
+    fn test_receipt() {
+        let client = ProverClient::new();
+        let (pk, vk) = client.setup(test_artifacts::FIBO_BF);
+        let proof = client.prove(&pk, vec![17]).run().unwrap();
+
+        let receipt = Receipt::new(test_artifacts::FIBO_BF, proof);
+        receipt.verify(&vk).unwrap();
+
+        let bytes = bincode::serialize(&receipt).unwrap();
+        let mut receipt: Receipt = bincode::deserialize(&bytes).unwrap();
+        receipt.verify(&vk).unwrap();
+
+        receipt.claim.output_digest = Sha256::digest([86]).into();
+        assert!(matches!(receipt.verify(&vk), Err(BfVerificationError::InvalidPublicValues)));
+
+        // The receipt of a public proof claims nothing about the input.
+        let public = Receipt::new(test_artifacts::FIBO_BF, receipt.proof.into_public());
+        assert_eq!(public.claim.input_digest, None);
+        public.verify(&vk).unwrap();
+    }
+}
@@ -0,0 +1,187 @@
+//! A registry of the programs a service can prove, keyed by their digest.
+
+use std::{
+    num::NonZeroUsize,
+    sync::{Arc, Mutex},
+};
+
+use hashbrown::HashMap;
+use itertools::Itertools;
+use lru::LruCache;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::{BfProvingKey, BfVerifyingKey, ProverClient};
+
+/// The SHA-256 digest of a program's commands.
+pub type ProgramDigest = [u8; 32];
+
+/// Computes the digest of a program.
+///
+/// Only the commands are hashed, so formatting and comments do not change the digest.
+pub fn program_digest(elf: &str) -> ProgramDigest {
+    let mut hasher = Sha256::new();
+    let is_command = |c: &char| matches!(c, '+' | '-' | '>' | '<' | '[' | ']' | ',' | '.' | ';');
+    for c in elf.chars().filter(is_command) {
+        hasher.update([c as u8]);
+    }
+    hasher.finalize().into()
+}
+
+/// The SHA-256 digest of a verifying key.
+pub type VerifyingKeyDigest = [u8; 32];
+
+/// Computes the digest of a verifying key.
+///
+/// The whole key is hashed: the commitment to the preprocessed traces, which binds the program
+/// and its memory image, and the chip information the verifier reads the traces with. The chip
+/// ordering is a map, so it is hashed sorted by name to serialize deterministically.
+pub fn vk_digest(vk: &BfVerifyingKey) -> VerifyingKeyDigest {
+    let chip_ordering = vk.vk.chip_ordering.iter().sorted().collect::<Vec<_>>();
+    let key = (&vk.vk.commit, &vk.vk.chip_information, chip_ordering);
+    let bytes = bincode::serialize(&key).expect("failed to serialize the verifying key");
+    Sha256::digest(bytes).into()
+}
+
+/// Why a verifying key does not belong to a program, see [ProverClient::check_vk].
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum VkCheckError {
+    #[error("Invalid program: {0}")]
+    InvalidProgram(String),
+    #[error(
+        "Verifying key digest {} does not match {} derived from the program",
+        hex::encode(.got),
+        hex::encode(.expected)
+    )]
+    DigestMismatch { expected: VerifyingKeyDigest, got: VerifyingKeyDigest },
+}
+
+struct ProgramEntry {
+    elf: String,
+    vk: Option<BfVerifyingKey>,
+}
+
+/// Holds many programs and their keys.
+///
+/// Keys are set up lazily on first use. Verifying keys are small and kept for every program,
+/// while proving keys are kept in an LRU cache of bounded capacity.
+pub struct ProgramRegistry {
+    programs: Mutex<HashMap<ProgramDigest, ProgramEntry>>,
+    proving_keys: Mutex<LruCache<ProgramDigest, Arc<BfProvingKey>>>,
+}
+
+impl ProgramRegistry {
+    /// Creates a registry keeping at most `capacity` proving keys.
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        Self {
+            programs: Mutex::new(HashMap::new()),
+            proving_keys: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    /// Registers a program without setting it up, returning its digest.
+    pub fn register(&self, elf: &str) -> ProgramDigest {
+        let digest = program_digest(elf);
+        self.programs
+            .lock()
+            .unwrap()
+            .entry(digest)
+            .or_insert_with(|| ProgramEntry { elf: elf.to_owned(), vk: None });
+        digest
+    }
+
+    /// Whether a program with the given digest is registered.
+    pub fn contains(&self, digest: &ProgramDigest) -> bool {
+        self.programs.lock().unwrap().contains_key(digest)
+    }
+
+    /// The number of registered programs.
+    pub fn len(&self) -> usize {
+        self.programs.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The program with the given digest.
+    pub fn program(&self, digest: &ProgramDigest) -> Option<String> {
+        self.programs.lock().unwrap().get(digest).map(|entry| entry.elf.clone())
+    }
+
+    /// The proving key of the program with the given digest, set up on a cache miss.
+    pub fn proving_key(
+        &self,
+        client: &ProverClient,
+        digest: &ProgramDigest,
+    ) -> Option<Arc<BfProvingKey>> {
+        if let Some(pk) = self.proving_keys.lock().unwrap().get(digest) {
+            return Some(pk.clone());
+        }
+
+        // Set up without holding the locks, since it is expensive.
+        let elf = self.program(digest)?;
+        let (pk, vk) = client.setup(&elf);
+        let pk = Arc::new(pk);
+
+        if let Some(entry) = self.programs.lock().unwrap().get_mut(digest) {
+            entry.vk.get_or_insert(vk);
+        }
+        self.proving_keys.lock().unwrap().put(*digest, pk.clone());
+        Some(pk)
+    }
+
+    /// The verifying key of the program with the given digest, set up if needed.
+    pub fn verifying_key(
+        &self,
+        client: &ProverClient,
+        digest: &ProgramDigest,
+    ) -> Option<BfVerifyingKey> {
+        if let Some(vk) = self.programs.lock().unwrap().get(digest)?.vk.clone() {
+            return Some(vk);
+        }
+        Some(self.proving_key(client, digest)?.vk.clone())
+    }
+
+    /// Drops the cached proving key of the given program. The program stays registered.
+    pub fn evict(&self, digest: &ProgramDigest) {
+        self.proving_keys.lock().unwrap().pop(digest);
+    }
+
+    /// Unregisters a program and drops its keys.
+    pub fn remove(&self, digest: &ProgramDigest) -> Option<String> {
+        self.evict(digest);
+        self.programs.lock().unwrap().remove(digest).map(|entry| entry.elf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry() {
+        let client = ProverClient::new();
+        let registry = ProgramRegistry::new(NonZeroUsize::new(1).unwrap());
+
+        let fibo = registry.register(test_artifacts::FIBO_BF);
+        let hello = registry.register(test_artifacts::HELLO_BF);
+        assert_eq!(registry.len(), 2);
+        assert_eq!(fibo, program_digest(&format!("{} # comment", test_artifacts::FIBO_BF)));
+        assert_ne!(fibo, program_digest(&format!("{};", test_artifacts::FIBO_BF)));
+
+        let fibo_pk = registry.proving_key(&client, &fibo).unwrap();
+        let hello_pk = registry.proving_key(&client, &hello).unwrap();
+        assert_eq!(hello_pk.elf, test_artifacts::HELLO_BF);
+
+        // The fibo proving key was evicted, but its verifying key is kept.
+        assert!(registry.proving_keys.lock().unwrap().peek(&fibo).is_none());
+        assert_eq!(
+            bincode::serialize(&registry.verifying_key(&client, &fibo).unwrap()).unwrap(),
+            bincode::serialize(&fibo_pk.vk).unwrap()
+        );
+
+        assert!(registry.remove(&fibo).is_some());
+        assert!(registry.proving_key(&client, &fibo).is_none());
+    }
+}
@@ -0,0 +1,146 @@
+//! The stdin bundled with a proof, stored zstd-compressed or stripped to its digest.
+
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+/// The zstd compression level of stdin.
+const COMPRESSION_LEVEL: i32 = 3;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum StdinError {
+    #[error("Failed to decompress stdin: {0}")]
+    Decompression(String),
+    #[error("Stdin does not match its digest")]
+    DigestMismatch,
+    #[error("Stdin was stripped from the proof")]
+    Stripped,
+}
+
+/// The stdin of a proof, zstd-compressed along with the SHA-256 digest of the raw bytes.
+///
+/// The bytes are decompressed on first access and kept, so a large input only takes its
+/// compressed size in a serialized proof. The digest is checked when decompressing, which lets
+/// the verifier rely on [CompressedStdin::digest] without decompressing twice.
+///
+/// A [stripped](CompressedStdin::strip) stdin keeps neither the bytes nor their digest, so that
+/// a proof can be published without revealing its input.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompressedStdin {
+    /// The compressed bytes, or `None` if stripped.
+    compressed: Option<Vec<u8>>,
+    /// The digest of the raw bytes, or `None` if stripped.
+    digest: Option<[u8; 32]>,
+    #[serde(skip)]
+    raw: OnceLock<Vec<u8>>,
+}
+
+impl CompressedStdin {
+    /// Compresses `raw`.
+    pub fn new(raw: Vec<u8>) -> Self {
+        let compressed =
+            zstd::encode_all(raw.as_slice(), COMPRESSION_LEVEL).expect("failed to compress stdin");
+        let digest = Some(Sha256::digest(&raw).into());
+        Self { compressed: Some(compressed), digest, raw: OnceLock::from(raw) }
+    }
+
+    /// The SHA-256 digest of the raw bytes, or `None` if they were stripped.
+    ///
+    /// The proof does not commit to this digest, only to the bytes the program read, see
+    /// [check_io](bf_prover::verify::check_io).
+    pub const fn digest(&self) -> Option<&[u8; 32]> {
+        self.digest.as_ref()
+    }
+
+    /// The length of the compressed bytes, zero if stripped.
+    pub fn compressed_len(&self) -> usize {
+        self.compressed.as_ref().map_or(0, Vec::len)
+    }
+
+    /// Drops the bytes and their digest.
+    ///
+    /// The digest goes too: the proof does not bind it, and it would let anyone who can guess
+    /// the input confirm the guess.
+    pub fn strip(&mut self) {
+        self.compressed = None;
+        self.digest = None;
+        self.raw = OnceLock::new();
+    }
+
+    /// Whether the bytes were stripped.
+    pub const fn is_stripped(&self) -> bool {
+        self.compressed.is_none()
+    }
+
+    /// The raw bytes, decompressed and checked against the digest on first access.
+    pub fn try_bytes(&self) -> Result<&[u8], StdinError> {
+        if let Some(raw) = self.raw.get() {
+            return Ok(raw);
+        }
+        let compressed = self.compressed.as_ref().ok_or(StdinError::Stripped)?;
+        let raw = zstd::decode_all(compressed.as_slice())
+            .map_err(|e| StdinError::Decompression(e.to_string()))?;
+        if Some(<[u8; 32]>::from(Sha256::digest(&raw))) != self.digest {
+            return Err(StdinError::DigestMismatch);
+        }
+        Ok(self.raw.get_or_init(|| raw))
+    }
+
+    /// The raw bytes.
+    ///
+    /// ### Panics
+    ///
+    /// Panics if the bytes were stripped or are corrupted, see [CompressedStdin::try_bytes].
+    pub fn bytes(&self) -> &[u8] {
+        self.try_bytes().expect("stripped or corrupted stdin")
+    }
+}
+
+impl From<Vec<u8>> for CompressedStdin {
+    fn from(raw: Vec<u8>) -> Self {
+        Self::new(raw)
+    }
+}
+
+impl PartialEq for CompressedStdin {
+    fn eq(&self, other: &Self) -> bool {
+        self.digest == other.digest
+    }
+}
+
+impl Eq for CompressedStdin {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compressed_stdin() {
+        let raw = [17u8; 10_000].to_vec();
+        let stdin = CompressedStdin::new(raw.clone());
+        assert!(stdin.compressed_len() < 100);
+        assert_eq!(stdin.digest(), Some(&<[u8; 32]>::from(Sha256::digest(&raw))));
+
+        // A deserialized stdin is decompressed on access.
+        let decoded: CompressedStdin =
+            bincode::deserialize(&bincode::serialize(&stdin).unwrap()).unwrap();
+        assert!(decoded.raw.get().is_none());
+        assert_eq!(decoded.bytes(), raw.as_slice());
+        assert_eq!(decoded, stdin);
+
+        let mut tampered = CompressedStdin::new(vec![1, 2, 3]);
+        tampered.raw = OnceLock::new();
+        tampered.digest.as_mut().unwrap()[0] ^= 1;
+        assert_eq!(tampered.try_bytes(), Err(StdinError::DigestMismatch));
+        tampered.compressed = Some(vec![0xff; 4]);
+        assert!(matches!(tampered.try_bytes(), Err(StdinError::Decompression(_))));
+
+        let mut stripped = stdin.clone();
+        stripped.strip();
+        assert!(stripped.is_stripped());
+        assert_eq!(stripped.try_bytes(), Err(StdinError::Stripped));
+        assert_eq!(stripped.digest(), None);
+    }
+}
@@ -0,0 +1,318 @@
+//! Verification of proofs without a [ProverClient](crate::ProverClient).
+//!
+//! Verifying only needs the machine's chips and config, so these helpers never build the
+//! prover.
+
+use std::time::Instant;
+
+use hashbrown::HashSet;
+
+use bf_core_machine::brainfuck::BfAir;
+use bf_prover::{
+    verify::{verify_core_proof, CoreMachine},
+    BfCoreProofData, BfVerifyingKey, CoreSC, ProofSystemInfo,
+};
+use bf_stark::MachineVerificationError;
+
+use crate::metrics::{self, Phase};
+use crate::provers::{check_compatibility, check_cycles, check_io};
+use crate::{vk_digest, BfProofWithPublicValues, BfVerificationError, VerifyingKeyDigest};
+
+/// Verifies a serialized [BfProofWithPublicValues] against the given verifying key.
+///
+/// The bytes are decoded with [BfProofWithPublicValues::from_bytes].
+///
+/// ### Examples
+/// ```no_run
+/// use bf_sdk::ProverClient;
+///
+/// let client = ProverClient::new();
+/// let (pk, vk) = client.setup(test_artifacts::FIBO_BF);
+/// let proof = client.prove(&pk, vec![17]).run().unwrap();
+/// let bytes = proof.to_bytes().unwrap();
+///
+/// bf_sdk::verify(&bytes, &vk).unwrap();
+/// ```
+pub fn verify(proof_bytes: &[u8], vk: &BfVerifyingKey) -> Result<(), BfVerificationError> {
+    let proof = BfProofWithPublicValues::from_bytes(proof_bytes)?;
+    verify_proof(&proof, vk)
+}
+
+/// Verifies a [BfProofWithPublicValues] against the given verifying key.
+///
+/// Only proofs generated with the default config are accepted.
+pub fn verify_proof(
+    proof: &BfProofWithPublicValues,
+    vk: &BfVerifyingKey,
+) -> Result<(), BfVerificationError> {
+    let start = Instant::now();
+    let config = CoreSC::default();
+    let result = check_compatibility(&ProofSystemInfo::new(&config), proof, vk)
+        .and_then(|()| check_io(proof))
+        .and_then(|()| check_cycles(proof))
+        .and_then(|()| {
+            let machine: CoreMachine = BfAir::machine(config);
+            verify_core_proof(&machine, &BfCoreProofData(proof.proof.clone()), vk)
+                .map_err(BfVerificationError::Core)
+        });
+    metrics::record_phase(Phase::Verify, start.elapsed());
+    if result.is_err() {
+        metrics::record_verification_failure();
+    }
+    result
+}
+
+/// The verifying keys, by their [vk_digest], whose proofs a deployment accepts.
+///
+/// ### Examples
+/// ```no_run
+/// use bf_sdk::{ProverClient, VkAllowlist};
+///
+/// let client = ProverClient::new();
+/// let (pk, vk) = client.setup(test_artifacts::FIBO_BF);
+/// let allowlist = VkAllowlist::from_keys([&vk]);
+///
+/// let proof = client.prove(&pk, vec![17]).run().unwrap();
+/// allowlist.verify_proof(&proof, &vk).unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct VkAllowlist {
+    digests: HashSet<VerifyingKeyDigest>,
+}
+
+impl VkAllowlist {
+    /// Creates an empty allowlist, which rejects every key.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an allowlist of the given keys.
+    pub fn from_keys<'a>(vks: impl IntoIterator<Item = &'a BfVerifyingKey>) -> Self {
+        vks.into_iter().map(vk_digest).collect()
+    }
+
+    /// Allows the key with the given digest. Returns whether it was not allowed yet.
+    pub fn insert(&mut self, digest: VerifyingKeyDigest) -> bool {
+        self.digests.insert(digest)
+    }
+
+    /// Disallows the key with the given digest. Returns whether it was allowed.
+    pub fn remove(&mut self, digest: &VerifyingKeyDigest) -> bool {
+        self.digests.remove(digest)
+    }
+
+    /// Whether the given key is allowed.
+    pub fn contains(&self, vk: &BfVerifyingKey) -> bool {
+        self.digests.contains(&vk_digest(vk))
+    }
+
+    /// Checks that the given key is allowed.
+    pub fn check(&self, vk: &BfVerifyingKey) -> Result<(), BfVerificationError> {
+        if self.contains(vk) {
+            Ok(())
+        } else {
+            Err(BfVerificationError::Core(MachineVerificationError::InvalidVerificationKey))
+        }
+    }
+
+    /// Verifies a [BfProofWithPublicValues], as [verify_proof] does, if its key is allowed.
+    pub fn verify_proof(
+        &self,
+        proof: &BfProofWithPublicValues,
+        vk: &BfVerifyingKey,
+    ) -> Result<(), BfVerificationError> {
+        self.check(vk)?;
+        verify_proof(proof, vk)
+    }
+
+    /// Verifies a serialized [BfProofWithPublicValues], as [verify] does, if its key is allowed.
+    pub fn verify(
+        &self,
+        proof_bytes: &[u8],
+        vk: &BfVerifyingKey,
+    ) -> Result<(), BfVerificationError> {
+        self.check(vk)?;
+        verify(proof_bytes, vk)
+    }
+}
+
+impl FromIterator<VerifyingKeyDigest> for VkAllowlist {
+    fn from_iter<I: IntoIterator<Item = VerifyingKeyDigest>>(digests: I) -> Self {
+        Self { digests: digests.into_iter().collect() }
+    }
+}
+
+/// A bincode-serialized [BfVerifyingKey] embedded in the binary.
+///
+/// The constructor is a `const fn`, so a verifier can pin its key at compile time:
+///
+/// ```ignore
+/// const VK: bf_sdk::EmbeddedVerifyingKey =
+///     bf_sdk::EmbeddedVerifyingKey::new(include_bytes!("../fibo.vk"));
+///
+/// VK.verify(&proof_bytes)?;
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct EmbeddedVerifyingKey {
+    bytes: &'static [u8],
+}
+
+impl EmbeddedVerifyingKey {
+    /// Wraps the serialized verifying key.
+    pub const fn new(bytes: &'static [u8]) -> Self {
+        Self { bytes }
+    }
+
+    /// Returns the serialized verifying key.
+    pub const fn as_bytes(&self) -> &'static [u8] {
+        self.bytes
+    }
+
+    /// Deserializes the verifying key.
+    pub fn load(&self) -> Result<BfVerifyingKey, BfVerificationError> {
+        bincode::deserialize(self.bytes).map_err(BfVerificationError::Deserialization)
+    }
+
+    /// Verifies a serialized [BfProofWithPublicValues] against the embedded key.
+    pub fn verify(&self, proof_bytes: &[u8]) -> Result<(), BfVerificationError> {
+        verify(proof_bytes, &self.load()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::FriPreset, utils::setup_logger, ProverClient, ProverClientConfig};
+
+    #[test]
+    fn test_verify_without_client() {
+        setup_logger();
+        let client = ProverClient::new();
+        let (pk, vk) = client.setup(test_artifacts::FIBO_BF);
+        let proof = client.prove(&pk, vec![17]).run().unwrap();
+
+        let proof_bytes = proof.to_bytes().unwrap();
+        verify(&proof_bytes, &vk).unwrap();
+
+        let vk_bytes = bincode::serialize(&vk).unwrap().leak();
+        EmbeddedVerifyingKey::new(vk_bytes).verify(&proof_bytes).unwrap();
+
+        assert!(matches!(verify(&proof_bytes[..16], &vk), Err(BfVerificationError::Decode(_))));
+    }
+
+    #[test]
+    fn test_vk_allowlist() {
+        let client = ProverClient::new();
+        let (pk, vk) = client.setup(test_artifacts::FIBO_BF);
+        let (_, other_vk) = client.setup(test_artifacts::HELLO_BF);
+        let proof = client.prove(&pk, vec![17]).run().unwrap();
+
+        let mut allowlist = VkAllowlist::from_keys([&other_vk]);
+        assert!(matches!(
+            allowlist.verify(&proof.to_bytes().unwrap(), &vk),
+            Err(BfVerificationError::Core(MachineVerificationError::InvalidVerificationKey))
+        ));
+
+        assert!(allowlist.insert(vk_digest(&vk)));
+        allowlist.verify_proof(&proof, &vk).unwrap();
+
+        assert!(allowlist.remove(&vk_digest(&vk)));
+        assert!(!allowlist.contains(&vk));
+    }
+
+    #[test]
+    fn test_incompatible_version() {
+        let client = ProverClient::new();
+        let (pk, vk) = client.setup("++.");
+        let mut proof = client.prove(&pk, vec![]).run().unwrap();
+
+        proof.system.version.minor += 1;
+        match verify_proof(&proof, &vk) {
+            Err(BfVerificationError::IncompatibleVersion { proof: system, .. }) => {
+                assert_eq!(system, proof.system)
+            }
+            result => panic!("unexpected result {result:?}"),
+        }
+
+        // A proof of another FRI preset names the mismatch instead of failing the transcript.
+        let config = ProverClientConfig { fri_preset: FriPreset::Fast, ..Default::default() };
+        let fast = ProverClient::from_config(config).unwrap();
+        let (pk, vk) = fast.setup("++.");
+        let proof = fast.prove(&pk, vec![]).run().unwrap();
+        assert!(matches!(
+            verify_proof(&proof, &vk),
+            Err(BfVerificationError::IncompatibleVersion { .. })
+        ));
+        fast.verify(&proof, &vk).unwrap();
+    }
+
+    #[test]
+    fn test_tampered_stdin() {
+        let client = ProverClient::new();
+        let (pk, vk) = client.setup(test_artifacts::FIBO_BF);
+        let mut proof = client.prove(&pk, vec![17]).run().unwrap();
+
+        proof.stdin = vec![18].into();
+        assert!(matches!(verify_proof(&proof, &vk), Err(BfVerificationError::InvalidPublicValues)));
+        let result = client.verify(&proof, &vk);
+        assert!(matches!(result, Err(BfVerificationError::InvalidPublicValues)));
+
+        // Bytes the program did not read are not bound.
+        proof.stdin = vec![17, 1].into();
+        verify_proof(&proof, &vk).unwrap();
+    }
+
+    #[test]
+    fn test_public_proof() {
+        let client = ProverClient::new();
+        let (pk, vk) = client.setup(test_artifacts::FIBO_BF);
+        let proof = client.prove(&pk, vec![17]).run().unwrap();
+        let stdin = proof.stdin.bytes().to_vec();
+
+        // Nothing is left of the input but the public values.
+        let public = proof.into_public();
+        assert!(public.stdin.is_stripped());
+        assert_eq!(public.stdin.digest(), None);
+        assert!(bf_prover::verify::check_io(&public.proof, Some(&stdin), &public.output));
+        assert!(!bf_prover::verify::check_io(&public.proof, Some(&[18]), &public.output));
+
+        let bytes = public.to_bytes().unwrap();
+        verify(&bytes, &vk).unwrap();
+        let decoded = BfProofWithPublicValues::from_bytes(&bytes).unwrap();
+        assert!(decoded.stdin.try_bytes().is_err());
+
+        // The output is still bound.
+        let mut tampered = public.clone();
+        tampered.output[0] += 1;
+        assert!(matches!(
+            verify_proof(&tampered, &vk),
+            Err(BfVerificationError::InvalidPublicValues)
+        ));
+    }
+
+    #[test]
+    fn test_tampered_output() {
+        let client = ProverClient::new();
+        let (pk, vk) = client.setup(test_artifacts::FIBO_BF);
+        let mut proof = client.prove(&pk, vec![17]).run().unwrap();
+
+        proof.output[0] += 1;
+        assert!(matches!(verify_proof(&proof, &vk), Err(BfVerificationError::InvalidPublicValues)));
+
+        // Unlike the stdin, the whole output is bound.
+        proof.output[0] -= 1;
+        proof.output.push(0);
+        let result = client.verify(&proof, &vk);
+        assert!(matches!(result, Err(BfVerificationError::InvalidPublicValues)));
+    }
+
+    #[test]
+    fn test_tampered_cycles() {
+        let client = ProverClient::new();
+        let (pk, vk) = client.setup(test_artifacts::FIBO_BF);
+        let mut proof = client.prove(&pk, vec![17]).run().unwrap();
+
+        proof.metadata.cycles -= 1;
+        assert!(matches!(verify_proof(&proof, &vk), Err(BfVerificationError::InvalidPublicValues)));
+    }
+}
@@ -87,12 +87,14 @@ pub trait InstructionAirBuilder: BaseAirBuilder {
         opcode: impl Into<Self::Expr>,
         next_mv: impl Into<Self::Expr>,
         mv: impl Into<Self::Expr>,
+        op_a: impl Into<Self::Expr>,
         multiplicity: impl Into<Self::Expr>,
     ) {
         let values = once(pc.into())
             .chain(once(opcode.into()))
             .chain(once(next_mv.into()))
             .chain(once(mv.into()))
+            .chain(once(op_a.into()))
             .collect();
 
         self.send(AirLookup::new(values, multiplicity.into(), LookupKind::Alu));
@@ -105,12 +107,14 @@ pub trait InstructionAirBuilder: BaseAirBuilder {
         opcode: impl Into<Self::Expr>,
         next_mv: impl Into<Self::Expr>,
         mv: impl Into<Self::Expr>,
+        op_a: impl Into<Self::Expr>,
         multiplicity: impl Into<Self::Expr>,
     ) {
         let values = once(pc.into())
             .chain(once(opcode.into()))
             .chain(once(next_mv.into()))
             .chain(once(mv.into()))
+            .chain(once(op_a.into()))
             .collect();
 
         self.receive(AirLookup::new(values, multiplicity.into(), LookupKind::Alu));
@@ -153,19 +157,24 @@ pub trait InstructionAirBuilder: BaseAirBuilder {
     }
 
     /// Sends a memory pointer operation to be processed.
+    #[allow(clippy::too_many_arguments)]
     fn send_memory_instr(
         &mut self,
         clk: impl Into<Self::Expr>,
         pc: impl Into<Self::Expr>,
         opcode: impl Into<Self::Expr>,
+        tape: impl Into<Self::Expr>,
         mp: impl Into<Self::Expr>,
+        next_tape: impl Into<Self::Expr>,
         next_mp: impl Into<Self::Expr>,
         multiplicity: impl Into<Self::Expr>,
     ) {
         let values = once(clk.into())
             .chain(once(pc.into()))
             .chain(once(opcode.into()))
+            .chain(once(tape.into()))
             .chain(once(mp.into()))
+            .chain(once(next_tape.into()))
             .chain(once(next_mp.into()))
             .collect();
 
@@ -173,35 +182,42 @@ pub trait InstructionAirBuilder: BaseAirBuilder {
     }
 
     /// Receives an ALU operation to be processed.
+    #[allow(clippy::too_many_arguments)]
     fn receive_memory_instr(
         &mut self,
         clk: impl Into<Self::Expr>,
         pc: impl Into<Self::Expr>,
         opcode: impl Into<Self::Expr>,
+        tape: impl Into<Self::Expr>,
         mp: impl Into<Self::Expr>,
+        next_tape: impl Into<Self::Expr>,
         next_mp: impl Into<Self::Expr>,
         multiplicity: impl Into<Self::Expr>,
     ) {
         let values = once(clk.into())
             .chain(once(pc.into()))
             .chain(once(opcode.into()))
+            .chain(once(tape.into()))
             .chain(once(mp.into()))
+            .chain(once(next_tape.into()))
             .chain(once(next_mp.into()))
             .collect();
 
         self.receive(AirLookup::new(values, multiplicity.into(), LookupKind::MemInstr));
     }
 
-    /// Sends an ALU operation to be processed.
+    /// Sends an I/O operation to be processed.
     fn send_io(
         &mut self,
+        clk: impl Into<Self::Expr>,
         pc: impl Into<Self::Expr>,
         opcode: impl Into<Self::Expr>,
         mp: impl Into<Self::Expr>,
         mv: impl Into<Self::Expr>,
         multiplicity: impl Into<Self::Expr>,
     ) {
-        let values = once(pc.into())
+        let values = once(clk.into())
+            .chain(once(pc.into()))
             .chain(once(opcode.into()))
             .chain(once(mp.into()))
             .chain(once(mv.into()))
@@ -210,16 +226,18 @@ pub trait InstructionAirBuilder: BaseAirBuilder {
         self.send(AirLookup::new(values, multiplicity.into(), LookupKind::IO));
     }
 
-    /// Receives an ALU operation to be processed.
+    /// Receives an I/O operation to be processed.
     fn receive_io(
         &mut self,
+        clk: impl Into<Self::Expr>,
         pc: impl Into<Self::Expr>,
         opcode: impl Into<Self::Expr>,
         mp: impl Into<Self::Expr>,
         mv: impl Into<Self::Expr>,
         multiplicity: impl Into<Self::Expr>,
     ) {
-        let values = once(pc.into())
+        let values = once(clk.into())
+            .chain(once(pc.into()))
             .chain(once(opcode.into()))
             .chain(once(mp.into()))
             .chain(once(mv.into()))
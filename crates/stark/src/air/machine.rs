@@ -34,9 +34,33 @@ pub trait MachineAir<F: Field>: BaseAir<F> + 'static + Send + Sync {
         self.generate_trace(input, output);
     }
 
+    /// Generate the trace and the dependencies for a given execution record.
+    ///
+    /// Airs whose dependencies come out of building their rows override this to do both in a
+    /// single pass over the events.
+    fn generate_trace_and_dependencies(
+        &self,
+        input: &Self::Record,
+        output: &mut Self::Record,
+    ) -> RowMajorMatrix<F> {
+        self.generate_dependencies(input, output);
+        self.generate_trace(input, &mut Self::Record::default())
+    }
+
+    /// Whether the trace depends on the dependencies generated by the other airs, such as the
+    /// multiplicities of byte lookups.
+    fn depends_on_dependencies(&self) -> bool {
+        false
+    }
+
     /// Whether this execution record contains events for this air.
     fn included(&self, shard: &Self::Record) -> bool;
 
+    /// Whether every shard must include this air. The verifier rejects proofs without it.
+    fn required(&self) -> bool {
+        false
+    }
+
     /// The width of the preprocessed trace.
     fn preprocessed_width(&self) -> usize {
         0
@@ -51,6 +75,14 @@ pub trait MachineAir<F: Field>: BaseAir<F> + 'static + Send + Sync {
     fn local_only(&self) -> bool {
         false
     }
+
+    /// The names of the main columns, in the order of the trace, for debugging.
+    ///
+    /// Airs whose columns struct derives `AlignedBorrow` return its `column_names`. The others
+    /// name the columns by their index.
+    fn column_names(&self) -> Vec<String> {
+        (0..self.width()).map(|i| format!("main[{i}]")).collect()
+    }
 }
 
 /// A program that defines the control flow of a machine through a program counter.
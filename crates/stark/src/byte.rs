@@ -0,0 +1,72 @@
+use std::iter::{once, Once};
+
+use p3_air::AirBuilder;
+use p3_field::{Field, FieldAlgebra};
+use serde::{Deserialize, Serialize};
+
+use bf_derive::AlignedBorrow;
+
+use crate::{air::BfAirBuilder, Word};
+
+/// A byte, such as the value of a memory cell.
+///
+/// Like [Word], the generic type `T` ranges from a `u8` to a `AB::Var` or `AB::Expr`. The column
+/// is named after the field holding it, so a `Byte<T>` column takes the place of a `T` column
+/// without changing the trace.
+#[derive(
+    AlignedBorrow, Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize,
+)]
+#[repr(C)]
+pub struct Byte<T>(pub T);
+
+impl<T> Byte<T> {
+    /// Applies `f` to the byte.
+    pub fn map<F, S>(self, f: F) -> Byte<S>
+    where
+        F: FnOnce(T) -> S,
+    {
+        Byte(f(self.0))
+    }
+}
+
+impl<T: FieldAlgebra> Byte<T> {
+    /// Returns a byte with a zero expression.
+    #[must_use]
+    pub fn zero<AB: BfAirBuilder<Expr = T>>() -> Byte<T> {
+        Byte(AB::Expr::ZERO)
+    }
+}
+
+impl<F: Field> Byte<F> {
+    /// Converts a byte to a u8.
+    pub fn to_u8(&self) -> u8 {
+        self.0.to_string().parse::<u8>().unwrap()
+    }
+}
+
+impl<V: Copy> Byte<V> {
+    /// Reduces a byte to a single expression.
+    pub fn reduce<AB: AirBuilder<Var = V>>(&self) -> AB::Expr {
+        self.0.into()
+    }
+
+    /// Extends a byte to a word, with the byte as the least significant limb.
+    pub fn extend<AB: AirBuilder<Var = V>>(&self) -> Word<AB::Expr> {
+        Word([self.reduce::<AB>(), AB::Expr::ZERO, AB::Expr::ZERO, AB::Expr::ZERO])
+    }
+}
+
+impl<F: FieldAlgebra> From<u8> for Byte<F> {
+    fn from(value: u8) -> Self {
+        Byte(F::from_canonical_u8(value))
+    }
+}
+
+impl<T> IntoIterator for Byte<T> {
+    type Item = T;
+    type IntoIter = Once<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        once(self.0)
+    }
+}
@@ -43,6 +43,11 @@ impl<F: Field, A> Chip<F, A> {
         self.log_quotient_degree
     }
 
+    /// The underlying air of the chip.
+    pub const fn air(&self) -> &A {
+        &self.air
+    }
+
     /// Consumes the chip and returns the underlying air.
     pub fn into_inner(self) -> A {
         self.air
@@ -203,13 +208,33 @@ where
         self.air.generate_dependencies(input, output);
     }
 
+    fn generate_trace_and_dependencies(
+        &self,
+        input: &A::Record,
+        output: &mut A::Record,
+    ) -> RowMajorMatrix<F> {
+        self.air.generate_trace_and_dependencies(input, output)
+    }
+
+    fn depends_on_dependencies(&self) -> bool {
+        self.air.depends_on_dependencies()
+    }
+
     fn included(&self, shard: &Self::Record) -> bool {
         self.air.included(shard)
     }
 
+    fn required(&self) -> bool {
+        self.air.required()
+    }
+
     fn local_only(&self) -> bool {
         self.air.local_only()
     }
+
+    fn column_names(&self) -> Vec<String> {
+        self.air.column_names()
+    }
 }
 
 // Implement AIR directly on Chip, evaluating both execution and permutation constraints.
@@ -1,6 +1,6 @@
 use p3_challenger::{CanObserve, CanSample, FieldChallenger};
 use p3_commit::{Pcs, PolynomialSpace};
-use p3_field::{ExtensionField, Field, PrimeField};
+use p3_field::{ExtensionField, Field, PrimeField, TwoAdicField};
 use serde::{de::DeserializeOwned, Serialize};
 
 pub type Val<SC> = <Domain<SC> as PolynomialSpace>::Val;
@@ -44,7 +44,7 @@ pub type PackedChallenge<SC> =
     <<SC as StarkGenericConfig>::Challenge as ExtensionField<Val<SC>>>::ExtensionPacking;
 
 pub trait StarkGenericConfig: 'static + Send + Sync + Serialize + DeserializeOwned + Clone {
-    type Val: PrimeField;
+    type Val: PrimeField + TwoAdicField;
     type Domain: PolynomialSpace<Val = Self::Val> + Sync;
 
     /// The PCS used to commit to trace polynomials.
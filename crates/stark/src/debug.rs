@@ -1,10 +1,14 @@
 use std::{
     borrow::Borrow,
+    fmt::Display,
     panic::{self, AssertUnwindSafe},
     process::exit,
 };
 
-use p3_air::{Air, AirBuilder, ExtensionBuilder, PairBuilder, PermutationAirBuilder};
+use p3_air::{
+    Air, AirBuilder, AirBuilderWithPublicValues, ExtensionBuilder, PairBuilder,
+    PermutationAirBuilder,
+};
 use p3_field::{ExtensionField, Field, FieldAlgebra, PrimeField32};
 use p3_matrix::{
     dense::{RowMajorMatrix, RowMajorMatrixView},
@@ -28,6 +32,7 @@ pub fn debug_constraints<SC, A>(
     perm: &RowMajorMatrix<SC::Challenge>,
     perm_challenges: &[SC::Challenge],
     cumulative_sum: &SC::Challenge,
+    public_values: &[Val<SC>],
 ) where
     SC: StarkGenericConfig,
     Val<SC>: PrimeField32,
@@ -81,6 +86,7 @@ pub fn debug_constraints<SC, A>(
             ),
             perm_challenges,
             cumulative_sum,
+            public_values,
             is_first_row: Val::<SC>::ZERO,
             is_last_row: Val::<SC>::ZERO,
             is_transition: Val::<SC>::ONE,
@@ -96,14 +102,27 @@ pub fn debug_constraints<SC, A>(
             chip.eval(&mut builder);
         }));
         if result.is_err() {
-            eprintln!("local: {main_local:?}");
-            eprintln!("next:  {main_next:?}");
+            let names = chip.column_names();
+            eprintln!("local: {}", named_row(&names, main_local));
+            eprintln!("next:  {}", named_row(&names, main_next));
             eprintln!("failed at row {} of chip {}", i, chip.name());
             exit(1);
         }
     });
 }
 
+/// Formats the values of a row as `name = value` pairs, naming the columns by `names`.
+pub fn named_row<T: Display>(names: &[String], row: &[T]) -> String {
+    row.iter()
+        .enumerate()
+        .map(|(i, value)| match names.get(i) {
+            Some(name) => format!("{name} = {value}"),
+            None => format!("main[{i}] = {value}"),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 fn catch_unwind_silent<F: FnOnce() -> R + panic::UnwindSafe, R>(f: F) -> std::thread::Result<R> {
     let prev_hook = panic::take_hook();
     panic::set_hook(Box::new(|_| {}));
@@ -130,7 +149,7 @@ pub struct DebugConstraintBuilder<'a, F: Field, EF: ExtensionField<F>> {
     pub(crate) is_first_row: F,
     pub(crate) is_last_row: F,
     pub(crate) is_transition: F,
-    // pub(crate) public_values: &'a [F],
+    pub(crate) public_values: &'a [F],
 }
 
 impl<F, EF> ExtensionBuilder for DebugConstraintBuilder<'_, F, EF>
@@ -247,6 +266,18 @@ where
     }
 }
 
+impl<F, EF> AirBuilderWithPublicValues for DebugConstraintBuilder<'_, F, EF>
+where
+    F: Field,
+    EF: ExtensionField<F>,
+{
+    type PublicVar = F;
+
+    fn public_values(&self) -> &[Self::PublicVar] {
+        self.public_values
+    }
+}
+
 impl<'a, F, EF> MultiTableAirBuilder<'a> for DebugConstraintBuilder<'a, F, EF>
 where
     F: Field,
@@ -0,0 +1,48 @@
+use p3_field::Field;
+use p3_matrix::dense::RowMajorMatrix;
+
+/// The hardware a [`MachineProver`](crate::MachineProver) commits its traces on.
+///
+/// The CPU backend hands the traces to the PCS where they already are. Backends for
+/// accelerators move them to their own memory instead, so that the prover does not depend on
+/// where the traces live.
+pub trait ProverDevice<F: Field>: Send + Sync {
+    /// A short name of the device, for logs.
+    fn name(&self) -> &'static str;
+
+    /// Moves a trace from the host to the device, ahead of committing to it.
+    fn copy_to_device(&self, trace: RowMajorMatrix<F>) -> RowMajorMatrix<F>;
+}
+
+/// The CPU, running the operations on the host memory.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CpuDevice;
+
+impl<F: Field> ProverDevice<F> for CpuDevice {
+    fn name(&self) -> &'static str {
+        "cpu"
+    }
+
+    fn copy_to_device(&self, trace: RowMajorMatrix<F>) -> RowMajorMatrix<F> {
+        trace
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_field::FieldAlgebra;
+    use p3_koala_bear::KoalaBear;
+
+    use super::*;
+
+    #[test]
+    fn test_cpu_device() {
+        let device = CpuDevice;
+        let values = (0..32).map(KoalaBear::from_canonical_u32).collect::<Vec<_>>();
+        let mat = RowMajorMatrix::new(values.clone(), 2);
+
+        assert_eq!(ProverDevice::<KoalaBear>::name(&device), "cpu");
+        let moved = device.copy_to_device(mat);
+        assert_eq!((moved.width, moved.values), (2, values));
+    }
+}
@@ -0,0 +1,313 @@
+use p3_air::{Air, BaseAir};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_uni_stark::SymbolicAirBuilder;
+
+use crate::{
+    air::{MachineAir, MachineProgram},
+    lookup::LookupBuilder,
+    record::MachineRecord,
+    Chip, DebugConstraintBuilder, ProverConstraintFolder, StarkGenericConfig, StarkMachine, Val,
+    VerifierConstraintFolder,
+};
+
+/// An object-safe [MachineAir], evaluated with the builders of the prover and the verifier of
+/// `SC`.
+///
+/// It is implemented by every [MachineAir] that the prover and the verifier accept, so a new AIR
+/// can be boxed and proven with [StarkMachine::from_dyn_chips] without being added to an enum
+/// deriving [MachineAir]. The dynamic dispatch makes it slower, so it is meant for prototyping.
+pub trait DynMachineAir<SC: StarkGenericConfig>: Send + Sync + 'static {
+    /// The execution record containing events for producing the air trace.
+    type Record: MachineRecord;
+
+    /// The program that defines the control flow of the machine.
+    type Program: MachineProgram<Val<SC>>;
+
+    /// See [BaseAir::width].
+    fn width(&self) -> usize;
+
+    /// See [MachineAir::name].
+    fn name(&self) -> String;
+
+    /// See [MachineAir::num_rows].
+    fn num_rows(&self, input: &Self::Record) -> Option<usize>;
+
+    /// See [MachineAir::generate_trace].
+    fn generate_trace(
+        &self,
+        input: &Self::Record,
+        output: &mut Self::Record,
+    ) -> RowMajorMatrix<Val<SC>>;
+
+    /// See [MachineAir::generate_dependencies].
+    fn generate_dependencies(&self, input: &Self::Record, output: &mut Self::Record);
+
+    /// See [MachineAir::generate_trace_and_dependencies].
+    fn generate_trace_and_dependencies(
+        &self,
+        input: &Self::Record,
+        output: &mut Self::Record,
+    ) -> RowMajorMatrix<Val<SC>>;
+
+    /// See [MachineAir::depends_on_dependencies].
+    fn depends_on_dependencies(&self) -> bool;
+
+    /// See [MachineAir::included].
+    fn included(&self, shard: &Self::Record) -> bool;
+
+    /// See [MachineAir::required].
+    fn required(&self) -> bool;
+
+    /// See [MachineAir::preprocessed_width].
+    fn preprocessed_width(&self) -> usize;
+
+    /// See [MachineAir::generate_preprocessed_trace].
+    fn generate_preprocessed_trace(
+        &self,
+        program: &Self::Program,
+    ) -> Option<RowMajorMatrix<Val<SC>>>;
+
+    /// See [MachineAir::local_only].
+    fn local_only(&self) -> bool;
+
+    /// See [MachineAir::column_names].
+    fn column_names(&self) -> Vec<String>;
+
+    /// Evaluates the constraints symbolically, to get their degree.
+    fn eval_symbolic(&self, builder: &mut SymbolicAirBuilder<Val<SC>>);
+
+    /// Evaluates the constraints to collect the lookups.
+    fn eval_lookups(&self, builder: &mut LookupBuilder<Val<SC>>);
+
+    /// Evaluates the constraints on the quotient domain of the prover.
+    fn eval_prover(&self, builder: &mut ProverConstraintFolder<'_, SC>);
+
+    /// Evaluates the constraints at the point opened by the verifier.
+    fn eval_verifier(&self, builder: &mut VerifierConstraintFolder<'_, SC>);
+
+    /// Evaluates the constraints on the rows of the trace, to debug them.
+    fn eval_debug(&self, builder: &mut DebugConstraintBuilder<'_, Val<SC>, SC::Challenge>);
+}
+
+impl<SC, A> DynMachineAir<SC> for A
+where
+    SC: StarkGenericConfig,
+    A: MachineAir<Val<SC>>
+        + Air<SymbolicAirBuilder<Val<SC>>>
+        + Air<LookupBuilder<Val<SC>>>
+        + for<'a> Air<ProverConstraintFolder<'a, SC>>
+        + for<'a> Air<VerifierConstraintFolder<'a, SC>>
+        + for<'a> Air<DebugConstraintBuilder<'a, Val<SC>, SC::Challenge>>,
+{
+    type Record = A::Record;
+
+    type Program = A::Program;
+
+    fn width(&self) -> usize {
+        BaseAir::width(self)
+    }
+
+    fn name(&self) -> String {
+        MachineAir::name(self)
+    }
+
+    fn num_rows(&self, input: &Self::Record) -> Option<usize> {
+        MachineAir::num_rows(self, input)
+    }
+
+    fn generate_trace(
+        &self,
+        input: &Self::Record,
+        output: &mut Self::Record,
+    ) -> RowMajorMatrix<Val<SC>> {
+        MachineAir::generate_trace(self, input, output)
+    }
+
+    fn generate_dependencies(&self, input: &Self::Record, output: &mut Self::Record) {
+        MachineAir::generate_dependencies(self, input, output);
+    }
+
+    fn generate_trace_and_dependencies(
+        &self,
+        input: &Self::Record,
+        output: &mut Self::Record,
+    ) -> RowMajorMatrix<Val<SC>> {
+        MachineAir::generate_trace_and_dependencies(self, input, output)
+    }
+
+    fn depends_on_dependencies(&self) -> bool {
+        MachineAir::depends_on_dependencies(self)
+    }
+
+    fn included(&self, shard: &Self::Record) -> bool {
+        MachineAir::included(self, shard)
+    }
+
+    fn required(&self) -> bool {
+        MachineAir::required(self)
+    }
+
+    fn preprocessed_width(&self) -> usize {
+        MachineAir::preprocessed_width(self)
+    }
+
+    fn generate_preprocessed_trace(
+        &self,
+        program: &Self::Program,
+    ) -> Option<RowMajorMatrix<Val<SC>>> {
+        MachineAir::generate_preprocessed_trace(self, program)
+    }
+
+    fn local_only(&self) -> bool {
+        MachineAir::local_only(self)
+    }
+
+    fn column_names(&self) -> Vec<String> {
+        MachineAir::column_names(self)
+    }
+
+    fn eval_symbolic(&self, builder: &mut SymbolicAirBuilder<Val<SC>>) {
+        Air::eval(self, builder);
+    }
+
+    fn eval_lookups(&self, builder: &mut LookupBuilder<Val<SC>>) {
+        Air::eval(self, builder);
+    }
+
+    fn eval_prover(&self, builder: &mut ProverConstraintFolder<'_, SC>) {
+        Air::eval(self, builder);
+    }
+
+    fn eval_verifier(&self, builder: &mut VerifierConstraintFolder<'_, SC>) {
+        Air::eval(self, builder);
+    }
+
+    fn eval_debug(&self, builder: &mut DebugConstraintBuilder<'_, Val<SC>, SC::Challenge>) {
+        Air::eval(self, builder);
+    }
+}
+
+/// A boxed [DynMachineAir], which is a [MachineAir] that a [StarkMachine] can be made of.
+pub struct DynAir<SC: StarkGenericConfig, R, P>(
+    pub Box<dyn DynMachineAir<SC, Record = R, Program = P>>,
+);
+
+impl<SC: StarkGenericConfig, R, P> BaseAir<Val<SC>> for DynAir<SC, R, P> {
+    fn width(&self) -> usize {
+        self.0.width()
+    }
+}
+
+impl<SC, R, P> MachineAir<Val<SC>> for DynAir<SC, R, P>
+where
+    SC: StarkGenericConfig,
+    R: MachineRecord + 'static,
+    P: MachineProgram<Val<SC>> + 'static,
+{
+    type Record = R;
+
+    type Program = P;
+
+    fn name(&self) -> String {
+        self.0.name()
+    }
+
+    fn num_rows(&self, input: &R) -> Option<usize> {
+        self.0.num_rows(input)
+    }
+
+    fn generate_trace(&self, input: &R, output: &mut R) -> RowMajorMatrix<Val<SC>> {
+        self.0.generate_trace(input, output)
+    }
+
+    fn generate_dependencies(&self, input: &R, output: &mut R) {
+        self.0.generate_dependencies(input, output);
+    }
+
+    fn generate_trace_and_dependencies(
+        &self,
+        input: &R,
+        output: &mut R,
+    ) -> RowMajorMatrix<Val<SC>> {
+        self.0.generate_trace_and_dependencies(input, output)
+    }
+
+    fn depends_on_dependencies(&self) -> bool {
+        self.0.depends_on_dependencies()
+    }
+
+    fn included(&self, shard: &R) -> bool {
+        self.0.included(shard)
+    }
+
+    fn required(&self) -> bool {
+        self.0.required()
+    }
+
+    fn preprocessed_width(&self) -> usize {
+        self.0.preprocessed_width()
+    }
+
+    fn generate_preprocessed_trace(&self, program: &P) -> Option<RowMajorMatrix<Val<SC>>> {
+        self.0.generate_preprocessed_trace(program)
+    }
+
+    fn local_only(&self) -> bool {
+        self.0.local_only()
+    }
+
+    fn column_names(&self) -> Vec<String> {
+        self.0.column_names()
+    }
+}
+
+impl<SC: StarkGenericConfig, R, P> Air<SymbolicAirBuilder<Val<SC>>> for DynAir<SC, R, P> {
+    fn eval(&self, builder: &mut SymbolicAirBuilder<Val<SC>>) {
+        self.0.eval_symbolic(builder);
+    }
+}
+
+impl<SC: StarkGenericConfig, R, P> Air<LookupBuilder<Val<SC>>> for DynAir<SC, R, P> {
+    fn eval(&self, builder: &mut LookupBuilder<Val<SC>>) {
+        self.0.eval_lookups(builder);
+    }
+}
+
+impl<'a, SC: StarkGenericConfig, R, P> Air<ProverConstraintFolder<'a, SC>> for DynAir<SC, R, P> {
+    fn eval(&self, builder: &mut ProverConstraintFolder<'a, SC>) {
+        self.0.eval_prover(builder);
+    }
+}
+
+impl<'a, SC: StarkGenericConfig, R, P> Air<VerifierConstraintFolder<'a, SC>> for DynAir<SC, R, P> {
+    fn eval(&self, builder: &mut VerifierConstraintFolder<'a, SC>) {
+        self.0.eval_verifier(builder);
+    }
+}
+
+impl<'a, SC: StarkGenericConfig, R, P> Air<DebugConstraintBuilder<'a, Val<SC>, SC::Challenge>>
+    for DynAir<SC, R, P>
+{
+    fn eval(&self, builder: &mut DebugConstraintBuilder<'a, Val<SC>, SC::Challenge>) {
+        self.0.eval_debug(builder);
+    }
+}
+
+impl<SC, R, P> StarkMachine<SC, DynAir<SC, R, P>>
+where
+    SC: StarkGenericConfig,
+    R: MachineRecord + 'static,
+    P: MachineProgram<Val<SC>> + 'static,
+{
+    /// Creates a machine of boxed AIRs, in the given order.
+    ///
+    /// The machine is proven and verified as any other, which lets a new AIR be tried out
+    /// without adding it to the enum of a machine.
+    pub fn from_dyn_chips(
+        config: SC,
+        airs: Vec<Box<dyn DynMachineAir<SC, Record = R, Program = P>>>,
+    ) -> Self {
+        let chips = airs.into_iter().map(|air| Chip::new(DynAir(air))).collect();
+        Self::new(config, chips)
+    }
+}
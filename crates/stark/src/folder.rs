@@ -3,7 +3,9 @@ use std::{
     ops::{Add, Mul, MulAssign, Sub},
 };
 
-use p3_air::{AirBuilder, ExtensionBuilder, PairBuilder, PermutationAirBuilder};
+use p3_air::{
+    AirBuilder, AirBuilderWithPublicValues, ExtensionBuilder, PairBuilder, PermutationAirBuilder,
+};
 use p3_field::{ExtensionField, Field, FieldAlgebra};
 use p3_matrix::{dense::RowMajorMatrixView, stack::VerticalPair};
 
@@ -26,6 +28,8 @@ pub struct ProverConstraintFolder<'a, SC: StarkGenericConfig> {
     pub perm_challenges: &'a [PackedChallenge<SC>],
     /// The cumulative sum for the permutation.
     pub cumulative_sum: &'a PackedChallenge<SC>,
+    /// The public values.
+    pub public_values: &'a [Val<SC>],
     /// The selector for the first row.
     pub is_first_row: PackedVal<SC>,
     /// The selector for the last row.
@@ -120,6 +124,14 @@ impl<SC: StarkGenericConfig> PairBuilder for ProverConstraintFolder<'_, SC> {
     }
 }
 
+impl<SC: StarkGenericConfig> AirBuilderWithPublicValues for ProverConstraintFolder<'_, SC> {
+    type PublicVar = Val<SC>;
+
+    fn public_values(&self) -> &[Self::PublicVar] {
+        self.public_values
+    }
+}
+
 impl<SC: StarkGenericConfig> EmptyMessageBuilder for ProverConstraintFolder<'_, SC> {}
 
 /// A folder for verifier constraints.
@@ -138,6 +150,8 @@ pub struct GenericVerifierConstraintFolder<'a, F, EF, Var, Expr> {
     pub perm_challenges: &'a [Var],
     /// The local cumulative sum of the permutation.
     pub local_cumulative_sum: &'a Var,
+    /// The public values.
+    pub public_values: &'a [F],
     /// The selector for the first row.
     pub is_first_row: Var,
     /// The selector for the last row.
@@ -325,6 +339,41 @@ where
     }
 }
 
+impl<F, EF, Var, Expr> AirBuilderWithPublicValues
+    for GenericVerifierConstraintFolder<'_, F, EF, Var, Expr>
+where
+    F: Field,
+    EF: ExtensionField<F>,
+    Expr: FieldAlgebra<F = EF>
+        + From<F>
+        + Add<Var, Output = Expr>
+        + Add<F, Output = Expr>
+        + Sub<Var, Output = Expr>
+        + Sub<F, Output = Expr>
+        + Mul<Var, Output = Expr>
+        + Mul<F, Output = Expr>
+        + MulAssign<EF>,
+    Var: Into<Expr>
+        + Copy
+        + Add<F, Output = Expr>
+        + Add<Var, Output = Expr>
+        + Add<Expr, Output = Expr>
+        + Sub<F, Output = Expr>
+        + Sub<Var, Output = Expr>
+        + Sub<Expr, Output = Expr>
+        + Mul<F, Output = Expr>
+        + Mul<Var, Output = Expr>
+        + Mul<Expr, Output = Expr>
+        + Send
+        + Sync,
+{
+    type PublicVar = F;
+
+    fn public_values(&self) -> &[Self::PublicVar] {
+        self.public_values
+    }
+}
+
 impl<F, EF, Var, Expr> PairBuilder for GenericVerifierConstraintFolder<'_, F, EF, Var, Expr>
 where
     F: Field,
@@ -0,0 +1,136 @@
+//! Known-answer tests of the hash layer: the Poseidon2 permutation, the sponge and the duplex
+//! challenger of the KoalaBear config on fixed inputs.
+//!
+//! The answers live in `fixtures/kat/poseidon2.txt`, one `name: values` line per answer, with
+//! the canonical field elements in decimal. Checking them on every change catches a change of
+//! the round constants or of the Plonky3 dependencies, which would silently make the proofs
+//! incompatible.
+//!
+//! To regenerate the fixtures after an intended change, run
+//!
+//! ```shell
+//! cargo test -p bf-stark regenerate_known_answers -- --ignored
+//! ```
+
+use std::{fs, io, path::PathBuf};
+
+use itertools::Itertools;
+use p3_challenger::{CanObserve, CanSample, CanSampleBits, DuplexChallenger};
+use p3_field::{FieldAlgebra, FieldExtensionAlgebra, PrimeField32};
+use p3_symmetric::{CryptographicHasher, Permutation};
+
+use crate::koala_bear_poseidon2::{my_perm, Challenge, MyHash, Perm, Val};
+
+/// A named output of the hash layer on a fixed input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KnownAnswer {
+    /// The computation, with its input.
+    pub name: String,
+    /// The canonical field elements of the output.
+    pub values: Vec<u32>,
+}
+
+impl KnownAnswer {
+    fn new(name: &str, values: impl IntoIterator<Item = Val>) -> Self {
+        let values = values.into_iter().map(|value| value.as_canonical_u32()).collect();
+        Self { name: name.to_string(), values }
+    }
+}
+
+/// The path of the fixture.
+pub fn kat_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures").join("kat").join("poseidon2.txt")
+}
+
+/// Computes the answers with the current hash layer.
+pub fn compute_known_answers() -> Vec<KnownAnswer> {
+    let perm = my_perm();
+    let hash = MyHash::new(perm.clone());
+    let elements = |n: u32| (0..n).map(Val::from_canonical_u32).collect::<Vec<_>>();
+
+    let input: [Val; 16] = elements(16).try_into().unwrap();
+    let mut answers = vec![
+        KnownAnswer::new("perm(zeros)", perm.permute([Val::ZERO; 16])),
+        KnownAnswer::new("perm(0..16)", perm.permute(input)),
+        KnownAnswer::new("hash([])", hash.hash_iter(elements(0))),
+        KnownAnswer::new("hash(0..8)", hash.hash_iter(elements(8))),
+        KnownAnswer::new("hash(0..100)", hash.hash_iter(elements(100))),
+    ];
+
+    // Observations and samples interleave as in the prover, to go through the duplexing.
+    let mut challenger = DuplexChallenger::<Val, Perm, 16, 8>::new(perm);
+    for value in elements(10) {
+        challenger.observe(value);
+    }
+    let samples: [Val; 3] = challenger.sample_array();
+    answers.push(KnownAnswer::new("challenger.sample()", samples));
+    challenger.observe(Val::from_canonical_u32(42));
+    let challenge: Challenge = challenger.sample();
+    answers.push(KnownAnswer::new(
+        "challenger.sample::<Challenge>()",
+        challenge.as_base_slice().to_vec(),
+    ));
+    let bits = challenger.sample_bits(20) as u32;
+    answers.push(KnownAnswer::new("challenger.sample_bits(20)", [Val::from_canonical_u32(bits)]));
+
+    answers
+}
+
+/// Writes the answers of the current hash layer to the fixture.
+pub fn write_known_answers() -> io::Result<()> {
+    let lines = compute_known_answers()
+        .iter()
+        .map(|answer| format!("{}: {}\n", answer.name, answer.values.iter().join(" ")))
+        .collect::<String>();
+    fs::create_dir_all(kat_path().parent().unwrap())?;
+    fs::write(kat_path(), lines)
+}
+
+/// Loads the answers of the fixture, or none if there is no fixture.
+pub fn load_known_answers() -> io::Result<Vec<KnownAnswer>> {
+    let text = match fs::read_to_string(kat_path()) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e),
+    };
+
+    text.lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let invalid =
+                || io::Error::new(io::ErrorKind::InvalidData, format!("bad line {line:?}"));
+            let (name, values) = line.rsplit_once(": ").ok_or_else(invalid)?;
+            let values = values
+                .split_whitespace()
+                .map(|value| value.parse().map_err(|_| invalid()))
+                .collect::<Result<_, _>>()?;
+            Ok(KnownAnswer { name: name.to_string(), values })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[ignore]
+    fn regenerate_known_answers() {
+        write_known_answers().unwrap();
+    }
+
+    #[test]
+    fn test_known_answers() {
+        let expected = load_known_answers().unwrap();
+        if expected.is_empty() {
+            eprintln!("no known answers in {}, skipping", kat_path().display());
+            return;
+        }
+
+        let answers = compute_known_answers();
+        for expected in &expected {
+            let answer = answers.iter().find(|answer| answer.name == expected.name);
+            assert_eq!(answer, Some(expected), "known answer {} changed", expected.name);
+        }
+    }
+}
@@ -3,11 +3,12 @@
 pub const DIGEST_SIZE: usize = 8;
 
 pub mod koala_bear_poseidon2 {
-    use bf_primitives::RC_16_30;
+    use std::fmt::{Display, Formatter};
+
     use p3_challenger::DuplexChallenger;
     use p3_commit::ExtensionMmcs;
     use p3_dft::Radix2DitParallel;
-    use p3_field::{extension::BinomialExtensionField, Field, FieldAlgebra};
+    use p3_field::{extension::BinomialExtensionField, Field, FieldAlgebra, PrimeField32};
     use p3_fri::{FriConfig, TwoAdicFriPcs};
     use p3_koala_bear::{KoalaBear, Poseidon2KoalaBear};
     use p3_merkle_tree::MerkleTreeMmcs;
@@ -15,7 +16,12 @@ pub mod koala_bear_poseidon2 {
     use p3_symmetric::{Hash, PaddingFreeSponge, TruncatedPermutation};
     use serde::{Deserialize, Serialize};
 
-    use crate::{Com, StarkGenericConfig, ZeroCommitment, DIGEST_SIZE};
+    use bf_primitives::RC_16_30;
+
+    use crate::{
+        serialized_size, Com, ProofSection, ShardProof, StarkGenericConfig, TranscriptChallenger,
+        ZeroCommitment, DIGEST_SIZE,
+    };
 
     pub type Val = KoalaBear;
     pub type Challenge = BinomialExtensionField<Val, 4>;
@@ -28,39 +34,193 @@ pub mod koala_bear_poseidon2 {
         MerkleTreeMmcs<<Val as Field>::Packing, <Val as Field>::Packing, MyHash, MyCompress, 8>;
     pub type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
     pub type Dft = Radix2DitParallel<Val>;
-    pub type Challenger = DuplexChallenger<Val, Perm, 16, 8>;
+    pub type Challenger = TranscriptChallenger<DuplexChallenger<Val, Perm, 16, 8>>;
     type Pcs = TwoAdicFriPcs<Val, Dft, ValMmcs, ChallengeMmcs>;
 
+    /// The number of external (full) rounds of the permutation.
+    pub const ROUNDS_F: usize = 8;
+    /// The number of internal (partial) rounds of the permutation.
+    pub const ROUNDS_P: usize = 13;
+
+    /// The round constants of the Poseidon2 permutation.
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct RoundConstants {
+        /// The constants of the external rounds before the internal rounds.
+        pub initial_external: Vec<[Val; 16]>,
+        /// The constants of the external rounds after the internal rounds.
+        pub terminal_external: Vec<[Val; 16]>,
+        /// The constants of the internal rounds, one per round.
+        pub internal: Vec<Val>,
+    }
+
+    /// An error that occurs when round constants don't fit the permutation.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum RoundConstantsError {
+        /// The number of initial or terminal external rounds is not `ROUNDS_F / 2`.
+        ExternalRoundCount(usize),
+        /// The number of internal rounds is not `ROUNDS_P`.
+        InternalRoundCount(usize),
+        /// An external round doesn't have a constant per element of the state.
+        ExternalRoundWidth(usize),
+        /// A constant is not a canonical field element.
+        NonCanonical(u32),
+    }
+
+    impl Display for RoundConstantsError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            match self {
+                RoundConstantsError::ExternalRoundCount(count) => {
+                    write!(f, "expected {} external rounds, got {count}", ROUNDS_F / 2)
+                }
+                RoundConstantsError::InternalRoundCount(count) => {
+                    write!(f, "expected {ROUNDS_P} internal rounds, got {count}")
+                }
+                RoundConstantsError::ExternalRoundWidth(width) => {
+                    write!(f, "expected 16 constants per external round, got {width}")
+                }
+                RoundConstantsError::NonCanonical(value) => {
+                    write!(f, "{value} is not a canonical field element")
+                }
+            }
+        }
+    }
+
+    impl std::error::Error for RoundConstantsError {}
+
+    impl RoundConstants {
+        /// Checks the number of rounds of the constants.
+        pub fn new(
+            initial_external: Vec<[Val; 16]>,
+            terminal_external: Vec<[Val; 16]>,
+            internal: Vec<Val>,
+        ) -> Result<Self, RoundConstantsError> {
+            for external in [&initial_external, &terminal_external] {
+                if external.len() != ROUNDS_F / 2 {
+                    return Err(RoundConstantsError::ExternalRoundCount(external.len()));
+                }
+            }
+            if internal.len() != ROUNDS_P {
+                return Err(RoundConstantsError::InternalRoundCount(internal.len()));
+            }
+            Ok(Self { initial_external, terminal_external, internal })
+        }
+
+        /// Reads the constants from canonical `u32`s, such as the output of an independent
+        /// generator, checking their shape.
+        pub fn from_canonical_u32s(
+            initial_external: &[Vec<u32>],
+            terminal_external: &[Vec<u32>],
+            internal: &[u32],
+        ) -> Result<Self, RoundConstantsError> {
+            let element = |value: u32| {
+                (value < Val::ORDER_U32)
+                    .then(|| Val::from_canonical_u32(value))
+                    .ok_or(RoundConstantsError::NonCanonical(value))
+            };
+            let external = |rounds: &[Vec<u32>]| {
+                rounds
+                    .iter()
+                    .map(|round| {
+                        let round = round.iter().map(|&value| element(value));
+                        let round = round.collect::<Result<Vec<_>, _>>()?;
+                        <[Val; 16]>::try_from(round)
+                            .map_err(|round| RoundConstantsError::ExternalRoundWidth(round.len()))
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            };
+            Self::new(
+                external(initial_external)?,
+                external(terminal_external)?,
+                internal.iter().map(|&value| element(value)).collect::<Result<_, _>>()?,
+            )
+        }
+
+        /// The constants of [RC_16_30], used by default.
+        #[must_use]
+        pub fn standard() -> Self {
+            let rounds = &RC_16_30[..ROUNDS_F + ROUNDS_P];
+            let (initial_external, rest) = rounds.split_at(ROUNDS_F / 2);
+            let (internal, terminal_external) = rest.split_at(ROUNDS_P);
+            Self {
+                initial_external: initial_external.to_vec(),
+                terminal_external: terminal_external.to_vec(),
+                internal: internal.iter().map(|round| round[0]).collect(),
+            }
+        }
+
+        /// The permutation with these constants.
+        #[must_use]
+        pub fn perm(&self) -> Perm {
+            let external_round_constants = ExternalLayerConstants::new(
+                self.initial_external.clone(),
+                self.terminal_external.clone(),
+            );
+            Perm::new(external_round_constants, self.internal.clone())
+        }
+    }
+
     #[must_use]
     pub fn my_perm() -> Perm {
-        const ROUNDS_F: usize = 8;
-        const ROUNDS_P: usize = 13;
-        let mut round_constants = RC_16_30.to_vec();
-        let internal_start = ROUNDS_F / 2;
-        let internal_end = (ROUNDS_F / 2) + ROUNDS_P;
-        let internal_round_constants = round_constants
-            .drain(internal_start..internal_end)
-            .map(|vec| vec[0])
-            .collect::<Vec<_>>();
-        let external_round_constants = ExternalLayerConstants::new(
-            round_constants[..ROUNDS_F / 2].to_vec(),
-            round_constants[ROUNDS_F / 2..ROUNDS_F].to_vec(),
-        );
-        Perm::new(external_round_constants, internal_round_constants)
+        RoundConstants::standard().perm()
+    }
+
+    /// The parameters of a FRI configuration, without the MMCS.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct FriParameters {
+        pub log_blowup: usize,
+        pub num_queries: usize,
+        pub proof_of_work_bits: usize,
+    }
+
+    impl FriParameters {
+        /// This targets by default 100 bits of security. The number of queries can be
+        /// overridden with the `FRI_QUERIES` environment variable.
+        #[must_use]
+        pub fn standard() -> Self {
+            let num_queries = match std::env::var("FRI_QUERIES") {
+                Ok(value) => value.parse().unwrap(),
+                Err(_) => 84,
+            };
+            Self { log_blowup: 1, num_queries, proof_of_work_bits: 16 }
+        }
+
+        /// A low-security configuration with few queries, meant for development and testing.
+        #[must_use]
+        pub const fn fast() -> Self {
+            Self { log_blowup: 1, num_queries: 16, proof_of_work_bits: 8 }
+        }
+
+        /// The conjectured bits of security: every query brings `log_blowup` bits, and the
+        /// proof of work adds its bits.
+        #[must_use]
+        pub const fn conjectured_security_bits(&self) -> usize {
+            self.log_blowup * self.num_queries + self.proof_of_work_bits
+        }
+
+        #[must_use]
+        pub fn fri_config(&self) -> FriConfig<ChallengeMmcs> {
+            self.fri_config_with_perm(&my_perm())
+        }
+
+        /// The FRI configuration hashing with `perm`.
+        #[must_use]
+        pub fn fri_config_with_perm(&self, perm: &Perm) -> FriConfig<ChallengeMmcs> {
+            let hash = MyHash::new(perm.clone());
+            let compress = MyCompress::new(perm.clone());
+            let challenge_mmcs = ChallengeMmcs::new(ValMmcs::new(hash, compress));
+            FriConfig {
+                log_blowup: self.log_blowup,
+                num_queries: self.num_queries,
+                proof_of_work_bits: self.proof_of_work_bits,
+                mmcs: challenge_mmcs,
+            }
+        }
     }
 
     #[must_use]
     /// This targets by default 100 bits of security.
     pub fn default_fri_config() -> FriConfig<ChallengeMmcs> {
-        let perm = my_perm();
-        let hash = MyHash::new(perm.clone());
-        let compress = MyCompress::new(perm.clone());
-        let challenge_mmcs = ChallengeMmcs::new(ValMmcs::new(hash, compress));
-        let num_queries = match std::env::var("FRI_QUERIES") {
-            Ok(value) => value.parse().unwrap(),
-            Err(_) => 84,
-        };
-        FriConfig { log_blowup: 1, num_queries, proof_of_work_bits: 16, mmcs: challenge_mmcs }
+        FriParameters::standard().fri_config()
     }
 
     #[derive(Deserialize)]
@@ -68,25 +228,52 @@ pub mod koala_bear_poseidon2 {
     pub struct KoalaBearPoseidon2 {
         pub perm: Perm,
         pcs: Pcs,
+        fri_parameters: FriParameters,
+        round_constants: RoundConstants,
     }
 
     impl KoalaBearPoseidon2 {
         #[must_use]
         pub fn new() -> Self {
-            let perm = my_perm();
+            Self::with_fri_parameters(FriParameters::standard())
+        }
+
+        #[must_use]
+        pub fn with_fri_parameters(fri_parameters: FriParameters) -> Self {
+            Self::with_round_constants(fri_parameters, RoundConstants::standard())
+        }
+
+        /// A configuration hashing with a Poseidon2 permutation of the given constants.
+        ///
+        /// The constants are not serialized with the configuration, which deserializes to the
+        /// standard constants.
+        #[must_use]
+        pub fn with_round_constants(
+            fri_parameters: FriParameters,
+            round_constants: RoundConstants,
+        ) -> Self {
+            let perm = round_constants.perm();
             let hash = MyHash::new(perm.clone());
             let compress = MyCompress::new(perm.clone());
             let val_mmcs = ValMmcs::new(hash, compress);
             let dft = Dft::default();
-            let fri_config = default_fri_config();
+            let fri_config = fri_parameters.fri_config_with_perm(&perm);
             let pcs = Pcs::new(dft, val_mmcs, fri_config);
-            Self { pcs, perm }
+            Self { pcs, perm, fri_parameters, round_constants }
+        }
+
+        pub const fn fri_parameters(&self) -> &FriParameters {
+            &self.fri_parameters
+        }
+
+        pub const fn round_constants(&self) -> &RoundConstants {
+            &self.round_constants
         }
     }
 
     impl Clone for KoalaBearPoseidon2 {
         fn clone(&self) -> Self {
-            Self::new()
+            Self::with_round_constants(self.fri_parameters, self.round_constants.clone())
         }
     }
 
@@ -124,7 +311,7 @@ pub mod koala_bear_poseidon2 {
         }
 
         fn challenger(&self) -> Self::Challenger {
-            Challenger::new(self.perm.clone())
+            TranscriptChallenger::new(DuplexChallenger::new(self.perm.clone()))
         }
     }
 
@@ -133,4 +320,92 @@ pub mod koala_bear_poseidon2 {
             DigestHash::from([Val::ZERO; DIGEST_SIZE])
         }
     }
+
+    /// Breaks a shard proof down like [ShardProof::size_breakdown], with the FRI opening proof
+    /// split into its commit phase commitments, query proofs, final polynomial and proof of work
+    /// witness.
+    pub fn shard_proof_size_breakdown(proof: &ShardProof<KoalaBearPoseidon2>) -> ProofSection {
+        let mut breakdown = proof.size_breakdown();
+        let fri = &proof.opening_proof;
+        if let Some(section) = breakdown.child_mut("opening proof") {
+            let input_openings =
+                fri.query_proofs.iter().map(|query| serialized_size(&query.input_proof)).sum();
+            let commit_phase_openings = fri
+                .query_proofs
+                .iter()
+                .map(|query| serialized_size(&query.commit_phase_openings))
+                .sum();
+            let query_proofs = ProofSection::node(
+                format!("query proofs ({})", fri.query_proofs.len()),
+                serialized_size(&fri.query_proofs),
+                vec![
+                    ProofSection::node("input openings", input_openings, vec![]),
+                    ProofSection::node("commit phase openings", commit_phase_openings, vec![]),
+                ],
+            );
+            section.children = vec![
+                ProofSection::leaf("commit phase commitments", &fri.commit_phase_commits),
+                query_proofs,
+                ProofSection::leaf("final polynomial", &fri.final_poly),
+                ProofSection::leaf("proof of work witness", &fri.pow_witness),
+            ];
+        }
+        breakdown
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use p3_symmetric::Permutation;
+
+        use super::*;
+
+        #[test]
+        fn test_conjectured_security_bits() {
+            let parameters =
+                FriParameters { log_blowup: 1, num_queries: 84, proof_of_work_bits: 16 };
+            assert_eq!(parameters.conjectured_security_bits(), 100);
+            assert_eq!(FriParameters::fast().conjectured_security_bits(), 24);
+        }
+
+        #[test]
+        fn test_round_constants() {
+            let standard = RoundConstants::standard();
+            let as_u32s = |round: &[Val; 16]| round.map(|c| c.as_canonical_u32()).to_vec();
+            let loaded = RoundConstants::from_canonical_u32s(
+                &standard.initial_external.iter().map(as_u32s).collect::<Vec<_>>(),
+                &standard.terminal_external.iter().map(as_u32s).collect::<Vec<_>>(),
+                &standard.internal.iter().map(|c| c.as_canonical_u32()).collect::<Vec<_>>(),
+            )
+            .unwrap();
+            assert_eq!(loaded, standard);
+
+            let config = KoalaBearPoseidon2::with_round_constants(FriParameters::fast(), loaded);
+            let input = [Val::ONE; 16];
+            assert_eq!(config.perm.permute(input), my_perm().permute(input));
+            assert_eq!(config.clone().round_constants(), &standard);
+
+            let mut internal = standard.internal.clone();
+            internal.pop();
+            assert_eq!(
+                RoundConstants::new(standard.initial_external.clone(), vec![], internal.clone()),
+                Err(RoundConstantsError::ExternalRoundCount(0))
+            );
+            assert_eq!(
+                RoundConstants::new(
+                    standard.initial_external.clone(),
+                    standard.terminal_external.clone(),
+                    internal
+                ),
+                Err(RoundConstantsError::InternalRoundCount(ROUNDS_P - 1))
+            );
+            assert_eq!(
+                RoundConstants::from_canonical_u32s(&[vec![0; 15]], &[], &[]),
+                Err(RoundConstantsError::ExternalRoundWidth(15))
+            );
+            assert_eq!(
+                RoundConstants::from_canonical_u32s(&[], &[], &[Val::ORDER_U32]),
+                Err(RoundConstantsError::NonCanonical(Val::ORDER_U32))
+            );
+        }
+    }
 }
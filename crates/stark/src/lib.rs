@@ -5,10 +5,14 @@
 extern crate alloc;
 
 pub mod air;
+mod byte;
 mod chip;
 mod config;
 mod debug;
+mod device;
+mod dyn_air;
 mod folder;
+pub mod kat;
 mod kb31_poseidon2;
 mod lookup;
 mod machine;
@@ -16,14 +20,20 @@ mod permutation;
 mod prover;
 mod quotient;
 mod record;
+mod report;
+mod size;
+mod transcript;
 mod types;
 mod verifier;
 mod word;
 
 pub use air::*;
+pub use byte::*;
 pub use chip::*;
 pub use config::*;
 pub use debug::*;
+pub use device::*;
+pub use dyn_air::*;
 pub use folder::*;
 pub use kb31_poseidon2::*;
 pub use lookup::*;
@@ -32,6 +42,9 @@ pub use permutation::*;
 pub use prover::*;
 pub use quotient::*;
 pub use record::*;
+pub use report::*;
+pub use size::*;
+pub use transcript::*;
 pub use types::*;
 pub use verifier::*;
 pub use word::*;
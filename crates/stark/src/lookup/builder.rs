@@ -1,15 +1,19 @@
-use p3_air::{AirBuilder, PairBuilder, PairCol, VirtualPairCol};
+use p3_air::{AirBuilder, AirBuilderWithPublicValues, PairBuilder, PairCol, VirtualPairCol};
 use p3_field::Field;
 use p3_matrix::dense::RowMajorMatrix;
 use p3_uni_stark::{Entry, SymbolicExpression, SymbolicVariable};
 
 use super::Lookup;
-use crate::air::{AirLookup, MessageBuilder};
+use crate::{
+    air::{AirLookup, MessageBuilder},
+    PROOF_MAX_NUM_PVS,
+};
 
 /// A builder for the lookup table Lookups.
 pub struct LookupBuilder<F: Field> {
     preprocessed: RowMajorMatrix<SymbolicVariable<F>>,
     main: RowMajorMatrix<SymbolicVariable<F>>,
+    public_values: Vec<SymbolicVariable<F>>,
     sends: Vec<Lookup<F>>,
     receives: Vec<Lookup<F>>,
 }
@@ -36,9 +40,14 @@ impl<F: Field> LookupBuilder<F> {
             })
             .collect();
 
+        let public_values = (0..PROOF_MAX_NUM_PVS)
+            .map(|index| SymbolicVariable::new(Entry::Public, index))
+            .collect();
+
         Self {
             preprocessed: RowMajorMatrix::new(prep_values, preprocessed_width),
             main: RowMajorMatrix::new(main_values, main_width),
+            public_values,
             sends: vec![],
             receives: vec![],
         }
@@ -86,6 +95,14 @@ impl<F: Field> PairBuilder for LookupBuilder<F> {
     }
 }
 
+impl<F: Field> AirBuilderWithPublicValues for LookupBuilder<F> {
+    type PublicVar = SymbolicVariable<F>;
+
+    fn public_values(&self) -> &[Self::PublicVar] {
+        &self.public_values
+    }
+}
+
 impl<F: Field> MessageBuilder<AirLookup<SymbolicExpression<F>>> for LookupBuilder<F> {
     fn send(&mut self, message: AirLookup<SymbolicExpression<F>>) {
         let values =
@@ -5,7 +5,12 @@ use p3_koala_bear::KoalaBear;
 use p3_matrix::Matrix;
 
 use super::LookupKind;
-use crate::{air::MachineAir, MachineChip, StarkGenericConfig, StarkMachine, StarkProvingKey, Val};
+use crate::{
+    air::MachineAir, named_row, MachineChip, StarkGenericConfig, StarkMachine, StarkProvingKey, Val,
+};
+
+/// The number of rows printed for each chip of a lookup key whose sends and receives differ.
+const MAX_PRINTED_ROWS: usize = 8;
 
 /// The data for an interaction.
 #[derive(Debug)]
@@ -66,7 +71,7 @@ pub fn debug_interactions<SC: StarkGenericConfig, A: MachineAir<Val<SC>>>(
     let mut key_to_count = BTreeMap::new();
 
     let trace = chip.generate_trace(record, &mut A::Record::default());
-    let mut pre_traces = pkey.traces.clone();
+    let mut pre_traces = pkey.traces.to_vec();
     let mut preprocessed_trace =
         pkey.chip_ordering.get(&chip.name()).map(|&index| pre_traces.get_mut(index).unwrap());
     let mut main = trace.clone();
@@ -134,6 +139,7 @@ where
     A: MachineAir<SC::Val>,
 {
     let mut final_map = BTreeMap::new();
+    let mut chip_data = BTreeMap::new();
     let mut total = SC::Val::ZERO;
 
     let chips = machine.chips();
@@ -142,7 +148,8 @@ where
         if !chip.included(shard) {
             continue;
         }
-        let (_, count) = debug_interactions::<SC, A>(chip, pkey, shard, interaction_kinds.clone());
+        let (data, count) =
+            debug_interactions::<SC, A>(chip, pkey, shard, interaction_kinds.clone());
         total_events += count.len();
         for (key, value) in count.iter() {
             let entry = final_map.entry(key.clone()).or_insert((SC::Val::ZERO, BTreeMap::new()));
@@ -151,12 +158,14 @@ where
             *entry.1.entry(chip.name()).or_insert(SC::Val::ZERO) += *value;
         }
         tracing::info!("{} chip has {} distinct events", chip.name(), total_events);
+        chip_data.insert(chip.name(), data);
     }
 
     tracing::info!("Final counts below.");
     tracing::info!("==================");
 
     let mut any_nonzero = false;
+    let mut traces = BTreeMap::new();
     for (key, (value, chip_values)) in final_map.clone() {
         if !Val::<SC>::is_zero(&value) {
             tracing::info!("Lookup key: {} Send-Receive Discrepancy: {}", key, field_to_int(value));
@@ -167,6 +176,25 @@ where
                     chip,
                     field_to_int(chip_value)
                 );
+                let lookups = &chip_data[&chip][&key];
+                let Some(air) = chips.iter().find(|air| air.name() == chip) else { continue };
+                let trace = traces
+                    .entry(chip.clone())
+                    .or_insert_with(|| air.generate_trace(shard, &mut A::Record::default()));
+                let names = air.column_names();
+                for lookup in lookups.iter().take(MAX_PRINTED_ROWS) {
+                    let direction = if lookup.is_send { "sent" } else { "received" };
+                    tracing::info!(
+                        "  row {} {} {} times: {}",
+                        lookup.row,
+                        direction,
+                        field_to_int(lookup.multiplicity),
+                        named_row(&names, &trace.row_slice(lookup.row))
+                    );
+                }
+                if lookups.len() > MAX_PRINTED_ROWS {
+                    tracing::info!("  and {} more rows", lookups.len() - MAX_PRINTED_ROWS);
+                }
             }
         }
     }
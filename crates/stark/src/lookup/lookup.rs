@@ -1,4 +1,5 @@
 use core::fmt::{Debug, Display};
+use std::{collections::BTreeMap, sync::RwLock};
 
 use p3_air::VirtualPairCol;
 use p3_field::Field;
@@ -18,32 +19,45 @@ pub struct Lookup<F: Field> {
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum LookupKind {
     /// Lookup with the memory table, such as read and write.
-    Memory = 1,
+    Memory,
 
     /// Lookup with the program table, loading an instruction at a given pc address.
-    Program = 2,
+    Program,
 
     /// Lookup with the ALU operations.
-    Alu = 3,
+    Alu,
 
     /// Lookup with the Jump operations.
-    Jump = 4,
+    Jump,
 
     /// Lookup with the Memory operations.
-    MemInstr = 5,
+    MemInstr,
 
     /// Lookup with the I/O operations.
-    IO = 6,
+    IO,
 
     /// Lookup with the byte lookup table for byte operations.
-    Byte = 7,
+    Byte,
+
+    /// Lookup with the memory image, loading the initial value of a cell.
+    MemoryImage,
+
+    /// Lookup defined outside of this crate, such as by an external chip. See
+    /// [LookupKind::register_custom].
+    Custom(u8),
 }
 
+/// The argument index of the first custom kind, leaving room for more built-in kinds.
+const FIRST_CUSTOM_ARGUMENT_INDEX: usize = 16;
+
+/// The names of the custom kinds, by their id.
+static CUSTOM_KINDS: RwLock<BTreeMap<u8, &'static str>> = RwLock::new(BTreeMap::new());
+
 impl LookupKind {
-    /// Returns all kinds of lookups.
+    /// Returns all kinds of lookups, including the registered custom kinds.
     #[must_use]
     pub fn all_kinds() -> Vec<LookupKind> {
-        vec![
+        let mut kinds = vec![
             LookupKind::Memory,
             LookupKind::Program,
             LookupKind::Alu,
@@ -51,7 +65,47 @@ impl LookupKind {
             LookupKind::MemInstr,
             LookupKind::IO,
             LookupKind::Byte,
-        ]
+            LookupKind::MemoryImage,
+        ];
+        kinds.extend(Self::custom_kinds());
+        kinds
+    }
+
+    /// Registers the custom kind `id` under `name`, which it is displayed with, and returns it.
+    ///
+    /// Registering is only needed for the name: an unregistered custom kind is balanced all the
+    /// same, and is displayed as `Custom(id)`.
+    ///
+    /// ### Panics
+    ///
+    /// Panics if `id` is already registered under another name.
+    pub fn register_custom(id: u8, name: &'static str) -> LookupKind {
+        let mut kinds = CUSTOM_KINDS.write().unwrap();
+        let registered = *kinds.entry(id).or_insert(name);
+        assert_eq!(registered, name, "the custom lookup kind {id} is already registered");
+        LookupKind::Custom(id)
+    }
+
+    /// Returns the registered custom kinds, by their id.
+    #[must_use]
+    pub fn custom_kinds() -> Vec<LookupKind> {
+        CUSTOM_KINDS.read().unwrap().keys().map(|&id| LookupKind::Custom(id)).collect()
+    }
+
+    /// The index of the argument in the lookup table, which separates the kinds in the
+    /// permutation argument.
+    pub const fn argument_index(&self) -> usize {
+        match self {
+            LookupKind::Memory => 1,
+            LookupKind::Program => 2,
+            LookupKind::Alu => 3,
+            LookupKind::Jump => 4,
+            LookupKind::MemInstr => 5,
+            LookupKind::IO => 6,
+            LookupKind::Byte => 7,
+            LookupKind::MemoryImage => 8,
+            LookupKind::Custom(id) => FIRST_CUSTOM_ARGUMENT_INDEX + *id as usize,
+        }
     }
 }
 
@@ -67,7 +121,7 @@ impl<F: Field> Lookup<F> {
 
     /// The index of the argument in the lookup table.
     pub const fn argument_index(&self) -> usize {
-        self.kind as usize
+        self.kind.argument_index()
     }
 }
 
@@ -87,6 +141,42 @@ impl Display for LookupKind {
             LookupKind::MemInstr => write!(f, "MemInstr"),
             LookupKind::IO => write!(f, "I/O"),
             LookupKind::Byte => write!(f, "Byte"),
+            LookupKind::MemoryImage => write!(f, "MemoryImage"),
+            LookupKind::Custom(id) => match CUSTOM_KINDS.read().unwrap().get(id) {
+                Some(name) => write!(f, "{name}"),
+                None => write!(f, "Custom({id})"),
+            },
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_custom_kinds() {
+        let kind = LookupKind::register_custom(42, "Keccak");
+        assert_eq!(kind.to_string(), "Keccak");
+        assert_eq!(LookupKind::register_custom(42, "Keccak"), kind);
+        assert!(LookupKind::all_kinds().contains(&kind));
+        assert_eq!(LookupKind::Custom(43).to_string(), "Custom(43)");
+
+        let mut indices = LookupKind::all_kinds()
+            .iter()
+            .chain(&[LookupKind::Custom(0), LookupKind::Custom(u8::MAX)])
+            .map(LookupKind::argument_index)
+            .collect::<Vec<_>>();
+        let len = indices.len();
+        indices.sort_unstable();
+        indices.dedup();
+        assert_eq!(indices.len(), len);
+    }
+
+    #[test]
+    #[should_panic(expected = "already registered")]
+    fn test_custom_kind_conflict() {
+        LookupKind::register_custom(7, "Sha256");
+        LookupKind::register_custom(7, "Blake3");
+    }
+}
@@ -1,10 +1,11 @@
-use std::{cmp::Reverse, env, fmt::Debug, time::Instant};
+use std::{cmp::Reverse, env, fmt::Debug, sync::Arc, time::Instant};
 
 use hashbrown::HashMap;
 use itertools::Itertools;
+use num_traits::cast::ToPrimitive;
 use p3_air::Air;
 use p3_challenger::{CanObserve, FieldChallenger};
-use p3_commit::Pcs;
+use p3_commit::{Pcs, PolynomialSpace};
 use p3_field::{Field, FieldAlgebra, FieldExtensionAlgebra, PrimeField32};
 use p3_matrix::{dense::RowMajorMatrix, Dimensions, Matrix};
 use p3_maybe_rayon::prelude::*;
@@ -43,6 +44,8 @@ impl<SC: StarkGenericConfig, A> StarkMachine<SC, A> {
 }
 
 /// A proving key for a STARK.
+///
+/// The preprocessed traces and their PCS data are shared, so cloning a key is cheap.
 #[derive(Clone, Serialize, Deserialize)]
 #[serde(bound(serialize = "PcsProverData<SC>: Serialize"))]
 #[serde(bound(deserialize = "PcsProverData<SC>: DeserializeOwned"))]
@@ -50,9 +53,9 @@ pub struct StarkProvingKey<SC: StarkGenericConfig> {
     /// The commitment to the preprocessed traces.
     pub commit: Com<SC>,
     /// The preprocessed traces.
-    pub traces: Vec<RowMajorMatrix<Val<SC>>>,
+    pub traces: Arc<[RowMajorMatrix<Val<SC>>]>,
     /// The pcs data for the preprocessed traces.
-    pub data: PcsProverData<SC>,
+    pub data: Arc<PcsProverData<SC>>,
     /// The preprocessed chip ordering.
     pub chip_ordering: HashMap<String, usize>,
     /// The preprocessed chip local only information.
@@ -114,6 +117,22 @@ impl<SC: StarkGenericConfig, A: MachineAir<Val<SC>>> StarkMachine<SC, A> {
             .collect()
     }
 
+    /// Checks that the chip information of `vk` is the one of the preprocessed chips of this
+    /// machine, so that a key only brings its commitment and the heights of the traces.
+    pub fn is_vk_consistent(&self, vk: &StarkVerifyingKey<SC>) -> bool {
+        let num_preprocessed =
+            self.chips.iter().filter(|chip| chip.preprocessed_width() > 0).count();
+        vk.chip_information.len() == num_preprocessed
+            && vk.chip_ordering.len() == num_preprocessed
+            && vk.chip_information.iter().enumerate().all(|(i, (name, domain, dimensions))| {
+                vk.chip_ordering.get(name) == Some(&i)
+                    && domain.size() == dimensions.height
+                    && self.chips.iter().any(|chip| {
+                        chip.name() == *name && chip.preprocessed_width() == dimensions.width
+                    })
+            })
+    }
+
     /// Returns an iterator over the chips in the machine that are included in the given record.
     pub fn shard_chips<'a, 'b>(
         &'a self,
@@ -214,8 +233,8 @@ impl<SC: StarkGenericConfig, A: MachineAir<Val<SC>>> StarkMachine<SC, A> {
         (
             StarkProvingKey {
                 commit: commit.clone(),
-                traces,
-                data,
+                traces: traces.into(),
+                data: Arc::new(data),
                 chip_ordering: chip_ordering.clone(),
                 local_only,
             },
@@ -265,21 +284,59 @@ impl<SC: StarkGenericConfig, A: MachineAir<Val<SC>>> StarkMachine<SC, A> {
         SC::Challenger: Clone,
         A: for<'a> Air<VerifierConstraintFolder<'a, SC>>,
     {
-        // Observe the preprocessed commitment.
+        let Some(last_shard) = proof.shard_proofs.last() else {
+            return Err(MachineVerificationError::EmptyProof);
+        };
+
+        if !self.is_vk_consistent(vk) {
+            return Err(MachineVerificationError::InvalidVerificationKey);
+        }
+
+        // Each required chip must be in the last shard. The chip ordering is a map from the
+        // names, which the shard verification checks against the chips, so no chip occurs twice.
+        if let Some(chip) = self
+            .chips
+            .iter()
+            .find(|chip| chip.required() && !last_shard.chip_ordering.contains_key(&chip.name()))
+        {
+            return Err(MachineVerificationError::InvalidChipOccurrence(chip.name()));
+        }
+
+        // The Byte chip of any shard may receive the byte lookups of all of them, so their
+        // multiplicities must not overflow over the whole proof.
+        let max_byte_lookup_mult = proof.shard_proofs.iter().try_fold(0u64, |mult, shard| {
+            let chips = self.shard_chips_ordered(&shard.chip_ordering).collect::<Vec<_>>();
+            mult.checked_add(Verifier::max_byte_lookup_mult(&chips, shard)?)
+        });
+        let order = SC::Val::order().to_u64().unwrap_or(u64::MAX);
+        if !matches!(max_byte_lookup_mult, Some(mult) if mult <= order) {
+            return Err(MachineVerificationError::InvalidShardProof(
+                VerificationError::ByteMultiplicityOverflow,
+            ));
+        }
+
+        // Observe the preprocessed commitment, then the main commitments and the public values
+        // of all the shards, from which the challenges of the lookups are sampled.
         vk.observe_into(challenger);
+        for shard in &proof.shard_proofs {
+            challenger.observe(shard.commitment.main_commit.clone());
+            challenger.observe_slice(&shard.public_values);
+        }
 
-        tracing::debug_span!("verify shard proof").in_scope(|| {
-            let chips =
-                self.shard_chips_ordered(&proof.shard_proof.chip_ordering).collect::<Vec<_>>();
-            Verifier::verify_shard(
-                &self.config,
-                vk,
-                &chips,
-                &mut challenger.clone(),
-                &proof.shard_proof,
-            )
-            .map_err(MachineVerificationError::InvalidShardProof)
-        })?;
+        for shard in &proof.shard_proofs {
+            tracing::debug_span!("verify shard proof").in_scope(|| {
+                let chips = self.shard_chips_ordered(&shard.chip_ordering).collect::<Vec<_>>();
+                Verifier::verify_shard(&self.config, vk, &chips, &mut challenger.clone(), shard)
+                    .map_err(MachineVerificationError::InvalidShardProof)
+            })?;
+        }
+
+        // The lookups balance across the shards.
+        let cumulative_sum =
+            proof.shard_proofs.iter().map(ShardProof::cumulative_sum).sum::<SC::Challenge>();
+        if cumulative_sum != SC::Challenge::ZERO {
+            return Err(MachineVerificationError::NonZeroCumulativeSum);
+        }
         Ok(())
     }
 
@@ -367,6 +424,7 @@ impl<SC: StarkGenericConfig, A: MachineAir<Val<SC>>> StarkMachine<SC, A> {
         }
 
         if env::var("SKIP_CONSTRAINTS").is_err() {
+            let public_values = shard.public_values::<Val<SC>>();
             tracing::info_span!("debug constraints").in_scope(|| {
                 for i in 0..chips.len() {
                     let preprocessed_trace =
@@ -378,6 +436,7 @@ impl<SC: StarkGenericConfig, A: MachineAir<Val<SC>>> StarkMachine<SC, A> {
                         &permutation_traces[i],
                         &permutation_challenges,
                         &cumulative_sums[i],
+                        &public_values,
                     );
                 }
             });
@@ -393,8 +452,8 @@ pub enum MachineVerificationError<SC: StarkGenericConfig> {
     InvalidShardProof(VerificationError<SC>),
     /// An error occurred during the verification of a global proof.
     InvalidGlobalProof(VerificationError<SC>),
-    /// The cumulative sum is non-zero.
-    NonZeroCumulativeSum(usize),
+    /// The cumulative sums of the shards do not add up to zero.
+    NonZeroCumulativeSum,
     /// The public values digest is invalid.
     InvalidPublicValuesDigest,
     /// The debug interactions failed.
@@ -403,15 +462,17 @@ pub enum MachineVerificationError<SC: StarkGenericConfig> {
     EmptyProof,
     /// The public values are invalid.
     InvalidPublicValues(&'static str),
-    /// The number of shards is too large.
-    TooManyShards,
+    /// The number of shards is larger than the verifier accepts.
+    TooManyShards(usize),
     /// The chip occurrence is invalid.
     InvalidChipOccurrence(String),
     /// The CPU is missing in the first shard.
     MissingCpuInFirstShard,
     /// The CPU log degree is too large.
     CpuLogDegreeTooLarge(usize),
-    /// The verification key is not allowed.
+    /// The log degree of the trace of a chip is larger than the verifier accepts.
+    LogDegreeTooLarge(String, usize),
+    /// The verification key is not allowed, or does not match the chips of the machine.
     InvalidVerificationKey,
 }
 
@@ -425,8 +486,8 @@ impl<SC: StarkGenericConfig> Debug for MachineVerificationError<SC> {
             MachineVerificationError::InvalidGlobalProof(e) => {
                 write!(f, "Invalid global proof: {:?}", e)
             }
-            MachineVerificationError::NonZeroCumulativeSum(shard) => {
-                write!(f, "Non-zero cumulative sum. Shard: {}", shard)
+            MachineVerificationError::NonZeroCumulativeSum => {
+                write!(f, "Non-zero cumulative sum")
             }
             MachineVerificationError::InvalidPublicValuesDigest => {
                 write!(f, "Invalid public values digest")
@@ -440,8 +501,8 @@ impl<SC: StarkGenericConfig> Debug for MachineVerificationError<SC> {
             MachineVerificationError::InvalidPublicValues(s) => {
                 write!(f, "Invalid public values: {}", s)
             }
-            MachineVerificationError::TooManyShards => {
-                write!(f, "Too many shards")
+            MachineVerificationError::TooManyShards(shards) => {
+                write!(f, "Too many shards: {}", shards)
             }
             MachineVerificationError::InvalidChipOccurrence(s) => {
                 write!(f, "Invalid chip occurrence: {}", s)
@@ -452,6 +513,9 @@ impl<SC: StarkGenericConfig> Debug for MachineVerificationError<SC> {
             MachineVerificationError::CpuLogDegreeTooLarge(log_degree) => {
                 write!(f, "CPU log degree too large: {}", log_degree)
             }
+            MachineVerificationError::LogDegreeTooLarge(chip, log_degree) => {
+                write!(f, "{} log degree too large: {}", chip, log_degree)
+            }
             MachineVerificationError::InvalidVerificationKey => {
                 write!(f, "Invalid verification key")
             }
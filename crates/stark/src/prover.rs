@@ -1,11 +1,11 @@
-use std::{cmp::Reverse, error::Error, time::Instant};
+use std::{borrow::BorrowMut, cmp::Reverse, collections::HashMap, error::Error, time::Instant};
 
 use core::fmt::Display;
 use itertools::Itertools;
 use p3_air::Air;
 use p3_challenger::{CanObserve, FieldChallenger};
-use p3_commit::{Pcs, PolynomialSpace};
-use p3_field::{FieldAlgebra, FieldExtensionAlgebra, PrimeField32};
+use p3_commit::{LagrangeSelectors, Pcs, PolynomialSpace};
+use p3_field::{Field, FieldAlgebra, FieldExtensionAlgebra, PrimeField32};
 use p3_matrix::{dense::RowMajorMatrix, Matrix};
 use p3_maybe_rayon::prelude::*;
 use p3_util::log2_strict_usize;
@@ -16,10 +16,10 @@ use super::{
     VerifierConstraintFolder,
 };
 use crate::{
-    air::MachineAir, lookup::LookupBuilder, record::MachineRecord, Challenger,
+    air::MachineAir, lookup::LookupBuilder, record::MachineRecord, Challenger, CpuDevice,
     DebugConstraintBuilder, MachineChip, MachineProof, PackedChallenge, PcsProverData,
-    ProverConstraintFolder, ShardCommitment, ShardMainData, ShardOpenedValues, ShardProof,
-    StarkVerifyingKey,
+    ProverConstraintFolder, ProverDevice, ShardCommitment, ShardMainData, ShardOpenedValues,
+    ShardProof, StarkVerifyingKey,
 };
 use crate::{AirOpenedValues, ChipOpenedValues};
 
@@ -39,16 +39,24 @@ pub trait MachineProver<SC: StarkGenericConfig, A: MachineAir<SC::Val>>:
     /// The type used for error handling.
     type Error: Error + Send + Sync;
 
+    /// The device running the hot paths of the prover.
+    type Device: ProverDevice<Val<SC>>;
+
     /// Create a new prover from a given machine.
     fn new(machine: StarkMachine<SC, A>) -> Self;
 
     /// A reference to the machine that this prover is using.
     fn machine(&self) -> &StarkMachine<SC, A>;
 
+    /// A reference to the device that this prover is running on.
+    fn device(&self) -> &Self::Device;
+
     /// Setup the preprocessed data into a proving and verifying key.
     fn setup(&self, program: &A::Program) -> (Self::DeviceProvingKey, StarkVerifyingKey<SC>);
 
     /// Copy the proving key from the host to the device.
+    ///
+    /// Called for every proof, so backends that can share the host memory should do so.
     fn pk_to_device(&self, pk: &StarkProvingKey<SC>) -> Self::DeviceProvingKey;
 
     /// Copy the proving key from the device to the host.
@@ -67,7 +75,12 @@ pub trait MachineProver<SC: StarkGenericConfig, A: MachineAir<SC::Val>>:
                 .map(|chip| {
                     let chip_name = chip.name();
                     let begin = Instant::now();
-                    let trace = chip.generate_trace(record, &mut A::Record::default());
+                    let trace = tracing::debug_span!(
+                        parent: &parent_span,
+                        "generate trace",
+                        chip = %chip_name
+                    )
+                    .in_scope(|| chip.generate_trace(record, &mut A::Record::default()));
                     tracing::debug!(
                         parent: &parent_span,
                         "generated trace for chip {} in {:?}",
@@ -86,6 +99,15 @@ pub trait MachineProver<SC: StarkGenericConfig, A: MachineAir<SC::Val>>:
         traces: Vec<(String, RowMajorMatrix<Val<SC>>)>,
     ) -> ShardMainData<SC, Self::DeviceMatrix, Self::DeviceProverData>;
 
+    /// Generate the dependencies and the main traces, and commit to the traces.
+    fn generate_and_commit(
+        &self,
+        record: &mut A::Record,
+    ) -> ShardMainData<SC, Self::DeviceMatrix, Self::DeviceProverData> {
+        self.machine().generate_dependencies(record, None);
+        self.commit(self.generate_traces(record))
+    }
+
     /// Observe the main commitment and public values and update the challenger.
     fn observe(
         &self,
@@ -101,20 +123,40 @@ pub trait MachineProver<SC: StarkGenericConfig, A: MachineAir<SC::Val>>:
     }
 
     /// Compute the openings of the traces.
+    ///
+    /// The main commitments and the public values of all the shards of the proof must have been
+    /// observed first, see [`MachineProver::observe`].
     fn open(
         &self,
         pk: &Self::DeviceProvingKey,
         data: ShardMainData<SC, Self::DeviceMatrix, Self::DeviceProverData>,
+        public_values: &[Val<SC>],
         challenger: &mut SC::Challenger,
     ) -> Result<ShardProof<SC>, Self::Error>;
 
-    /// Generate a proof for the given record.
+    /// Generate a proof of one shard for the given record.
     fn prove(
         &self,
         pk: &Self::DeviceProvingKey,
         record: &mut A::Record,
         challenger: &mut SC::Challenger,
     ) -> Result<MachineProof<SC>, Self::Error>
+    where
+        A: for<'a> Air<DebugConstraintBuilder<'a, Val<SC>, SC::Challenge>>,
+    {
+        self.prove_shards(pk, std::iter::once(record), challenger)
+    }
+
+    /// Generate a proof of the given records, one shard each, in order.
+    ///
+    /// The records are committed to as they are taken from `records`, so they can be generated
+    /// while the previous ones are proven and dropped once committed to.
+    fn prove_shards<R: BorrowMut<A::Record>>(
+        &self,
+        pk: &Self::DeviceProvingKey,
+        records: impl IntoIterator<Item = R>,
+        challenger: &mut SC::Challenger,
+    ) -> Result<MachineProof<SC>, Self::Error>
     where
         A: for<'a> Air<DebugConstraintBuilder<'a, Val<SC>, SC::Challenge>>;
 
@@ -164,8 +206,13 @@ pub struct CpuProver<SC: StarkGenericConfig, A> {
 }
 
 /// An error that occurs during the execution of the [`CpuProver`].
-#[derive(Debug, Clone, Copy)]
-pub struct CpuProverError;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CpuProverError {
+    /// The record includes none of the chips of the machine, so there is nothing to prove.
+    EmptyRecord,
+    /// The PCS opened a different number of batches, matrices or points than were requested.
+    OpeningShapeMismatch,
+}
 
 impl<SC, A> MachineProver<SC, A> for CpuProver<SC, A>
 where
@@ -185,6 +232,7 @@ where
     type DeviceProverData = PcsProverData<SC>;
     type DeviceProvingKey = StarkProvingKey<SC>;
     type Error = CpuProverError;
+    type Device = CpuDevice;
 
     fn new(machine: StarkMachine<SC, A>) -> Self {
         Self { machine }
@@ -194,10 +242,15 @@ where
         &self.machine
     }
 
+    fn device(&self) -> &CpuDevice {
+        &CpuDevice
+    }
+
     fn setup(&self, program: &A::Program) -> (Self::DeviceProvingKey, StarkVerifyingKey<SC>) {
         self.machine().setup(program)
     }
 
+    /// Shares the preprocessed traces and PCS data of `pk` rather than copying them.
     fn pk_to_device(&self, pk: &StarkProvingKey<SC>) -> Self::DeviceProvingKey {
         pk.clone()
     }
@@ -219,7 +272,8 @@ where
             .iter()
             .map(|(_, trace)| {
                 let domain = pcs.natural_domain_for_degree(trace.height());
-                (domain, trace.to_owned())
+                // The host keeps its copy of the trace to compute the openings.
+                (domain, self.device().copy_to_device(trace.clone()))
             })
             .collect::<Vec<_>>();
 
@@ -235,6 +289,41 @@ where
         ShardMainData { traces, main_commit, main_data, chip_ordering }
     }
 
+    /// The chips whose traces do not depend on the dependencies of the others generate their
+    /// trace and their dependencies in a single pass over their events. The remaining traces,
+    /// such as the byte multiplicities, are generated once all the dependencies are in `record`.
+    fn generate_and_commit(
+        &self,
+        record: &mut A::Record,
+    ) -> ShardMainData<SC, Self::DeviceMatrix, Self::DeviceProverData> {
+        let (independent, dependent): (Vec<_>, Vec<_>) =
+            self.machine().chips().iter().partition(|chip| !chip.depends_on_dependencies());
+
+        let input: &A::Record = record;
+        let (mut named_traces, mut outputs): (Vec<_>, Vec<_>) = independent
+            .par_iter()
+            .filter(|chip| chip.included(input))
+            .map(|chip| {
+                let mut output = A::Record::default();
+                let trace = chip.generate_trace_and_dependencies(input, &mut output);
+                ((chip.name(), trace), output)
+            })
+            .unzip();
+        outputs.iter_mut().for_each(|output| record.append(output));
+
+        let input: &A::Record = record;
+        named_traces.extend(
+            dependent
+                .par_iter()
+                .filter(|chip| chip.included(input))
+                .map(|chip| (chip.name(), chip.generate_trace(input, &mut A::Record::default())))
+                .collect::<Vec<_>>(),
+        );
+        assert!(!named_traces.is_empty());
+
+        self.commit(named_traces)
+    }
+
     /// Prove the program for the given shard and given a commitment to the main data.
     #[allow(clippy::too_many_lines)]
     #[allow(clippy::redundant_closure_for_method_calls)]
@@ -243,6 +332,7 @@ where
         &self,
         pk: &StarkProvingKey<SC>,
         data: ShardMainData<SC, Self::DeviceMatrix, Self::DeviceProverData>,
+        public_values: &[Val<SC>],
         challenger: &mut <SC as StarkGenericConfig>::Challenger,
     ) -> Result<ShardProof<SC>, Self::Error> {
         let chips = self.machine().shard_chips_ordered(&data.chip_ordering).collect::<Vec<_>>();
@@ -262,9 +352,6 @@ where
         let trace_domains =
             degrees.iter().map(|degree| pcs.natural_domain_for_degree(*degree)).collect::<Vec<_>>();
 
-        // Observe the main commitment.
-        challenger.observe(data.main_commit.clone());
-
         // Obtain the challenges used for the local permutation argument.
         let mut local_permutation_challenges: Vec<SC::Challenge> = Vec::new();
         for _ in 0..2 {
@@ -321,8 +408,7 @@ where
                     .into_iter()
                     .zip(trace_domains.iter())
                     .map(|(perm_trace, domain)| {
-                        let trace = perm_trace.flatten_to_base();
-                        (*domain, trace.clone())
+                        (*domain, self.device().copy_to_device(perm_trace.flatten_to_base()))
                     })
                     .collect::<Vec<_>>()
             });
@@ -350,7 +436,26 @@ where
             })
             .collect::<Vec<_>>();
 
-        // Compute the quotient values.
+        // Chips of the same height share their trace and quotient domains, so the selectors are
+        // computed once per distinct pair of domains rather than once per chip.
+        let selectors: HashMap<(usize, usize), LagrangeSelectors<Vec<Val<SC>>>> = log_degrees
+            .iter()
+            .zip_eq(log_quotient_degrees.iter())
+            .zip_eq(trace_domains.iter().zip_eq(quotient_domains.iter()))
+            .map(|((&log_degree, &log_quotient_degree), domains)| {
+                ((log_degree, log_quotient_degree), domains)
+            })
+            .collect::<HashMap<_, _>>()
+            .into_iter()
+            .collect::<Vec<_>>()
+            .into_par_iter()
+            .map(|(key, (trace_domain, quotient_domain))| {
+                (key, trace_domain.selectors_on_coset(*quotient_domain))
+            })
+            .collect();
+
+        // Compute the quotient values. The traces on the quotient domains are read off the
+        // low-degree extensions computed when committing to them, so this takes no DFTs.
         let alpha: SC::Challenge = challenger.sample_ext_element::<SC::Challenge>();
         let parent_span = tracing::debug_span!("compute quotient values");
         let quotient_values = parent_span.in_scope(|| {
@@ -362,7 +467,8 @@ where
                         .in_scope(|| {
                             let preprocessed_trace_on_quotient_domains =
                                 pk.chip_ordering.get(&chips[i].name()).map(|&index| {
-                                    pcs.get_evaluations_on_domain(&pk.data, index, *quotient_domain)
+                                    let data = &*pk.data;
+                                    pcs.get_evaluations_on_domain(data, index, *quotient_domain)
                                         .to_row_major_matrix()
                                 });
                             let main_trace_on_quotient_domains = pcs
@@ -371,15 +477,18 @@ where
                             let permutation_trace_on_quotient_domains = pcs
                                 .get_evaluations_on_domain(&permutation_data, i, *quotient_domain)
                                 .to_row_major_matrix();
+                            let key = (log_degrees[i], log_quotient_degrees[i]);
                             quotient_values(
                                 chips[i],
                                 &cumulative_sums[i],
                                 trace_domains[i],
                                 *quotient_domain,
+                                &selectors[&key],
                                 preprocessed_trace_on_quotient_domains,
                                 main_trace_on_quotient_domains,
                                 permutation_trace_on_quotient_domains,
                                 &packed_perm_challenges,
+                                public_values,
                                 alpha,
                             )
                         })
@@ -460,7 +569,7 @@ where
         let (openings, opening_proof) = tracing::debug_span!("open multi batches").in_scope(|| {
             pcs.open(
                 vec![
-                    (&pk.data, preprocessed_opening_points),
+                    (&*pk.data, preprocessed_opening_points),
                     (&data.main_data, main_trace_opening_points.clone()),
                     (&permutation_data, permutation_trace_opening_points.clone()),
                     (&quotient_data, quotient_opening_points),
@@ -471,49 +580,35 @@ where
 
         // Collect the opened values for each chip.
         let [preprocessed_values, main_values, permutation_values, mut quotient_values] =
-            openings.try_into().unwrap();
-        assert!(main_values.len() == chips.len());
+            openings.try_into().map_err(|_| CpuProverError::OpeningShapeMismatch)?;
+        if preprocessed_values.len() != pk.traces.len()
+            || main_values.len() != chips.len()
+            || permutation_values.len() != chips.len()
+            || quotient_values.len() != num_quotient_chunks
+        {
+            return Err(CpuProverError::OpeningShapeMismatch);
+        }
         let preprocessed_opened_values = preprocessed_values
             .into_iter()
             .zip(pk.local_only.iter())
-            .map(|(op, local_only)| {
-                if !local_only {
-                    let [local, next] = op.try_into().unwrap();
-                    AirOpenedValues { local, next }
-                } else {
-                    let [local] = op.try_into().unwrap();
-                    let width = local.len();
-                    AirOpenedValues { local, next: vec![SC::Challenge::ZERO; width] }
-                }
-            })
-            .collect::<Vec<_>>();
+            .map(|(op, local_only)| air_opened_values(op, *local_only))
+            .collect::<Result<Vec<_>, _>>()?;
 
         let main_opened_values = main_values
             .into_iter()
             .zip(chips.iter())
-            .map(|(op, chip)| {
-                if !chip.local_only() {
-                    let [local, next] = op.try_into().unwrap();
-                    AirOpenedValues { local, next }
-                } else {
-                    let [local] = op.try_into().unwrap();
-                    let width = local.len();
-                    AirOpenedValues { local, next: vec![SC::Challenge::ZERO; width] }
-                }
-            })
-            .collect::<Vec<_>>();
+            .map(|(op, chip)| air_opened_values(op, chip.local_only()))
+            .collect::<Result<Vec<_>, _>>()?;
         let permutation_opened_values = permutation_values
             .into_iter()
-            .map(|op| {
-                let [local, next] = op.try_into().unwrap();
-                AirOpenedValues { local, next }
-            })
-            .collect::<Vec<_>>();
+            .map(|op| air_opened_values(op, false))
+            .collect::<Result<Vec<_>, _>>()?;
         let mut quotient_opened_values = Vec::with_capacity(log_quotient_degrees.len());
         for log_quotient_degree in log_quotient_degrees.iter() {
             let degree = 1 << *log_quotient_degree;
             let slice = quotient_values.drain(0..degree);
-            quotient_opened_values.push(slice.map(|mut op| op.pop().unwrap()).collect::<Vec<_>>());
+            let chunks = slice.map(|mut op| op.pop().ok_or(CpuProverError::OpeningShapeMismatch));
+            quotient_opened_values.push(chunks.collect::<Result<Vec<_>, _>>()?);
         }
 
         let opened_values = main_opened_values
@@ -549,36 +644,57 @@ where
             opened_values: ShardOpenedValues { chips: opened_values },
             opening_proof,
             chip_ordering: data.chip_ordering,
+            public_values: public_values.to_vec(),
         })
     }
 
-    /// Prove the execution record is valid.
+    /// Prove the execution records are valid.
     ///
-    /// Given a proving key `pk` and a matching execution record `record`, this function generates
-    /// a STARK proof that the execution record is valid.
+    /// Given a proving key `pk` and the matching execution records of the shards of an execution,
+    /// this function generates a STARK proof of each shard.
+    ///
+    /// The lookups balance across the shards, so their challenges are sampled once the main
+    /// commitments of all the shards are observed. The main data of each shard is kept until it
+    /// is opened, so that its traces are generated and committed to once.
     #[allow(clippy::needless_for_each)]
-    fn prove(
+    fn prove_shards<R: BorrowMut<A::Record>>(
         &self,
         pk: &StarkProvingKey<SC>,
-        record: &mut A::Record,
+        records: impl IntoIterator<Item = R>,
         challenger: &mut SC::Challenger,
     ) -> Result<MachineProof<SC>, Self::Error>
     where
         A: for<'a> Air<DebugConstraintBuilder<'a, Val<SC>, SC::Challenge>>,
     {
-        // Generate dependencies.
-        self.machine().generate_dependencies(record, None);
-
         // Observe the preprocessed commitment.
         pk.observe_into(challenger);
 
-        let shard_proof = tracing::info_span!("prove_shard").in_scope(|| {
-            let named_traces = self.generate_traces(record);
-            let shard_data = self.commit(named_traces);
-            self.open(pk, shard_data, &mut challenger.clone())
-        })?;
+        let mut shard_data = Vec::new();
+        for mut record in records {
+            let record = record.borrow_mut();
+            // A chip that is not included generates no dependencies, so a record without chips
+            // still has none once its dependencies are generated.
+            if self.get_chips(record).next().is_none() {
+                return Err(CpuProverError::EmptyRecord);
+            }
+            let data =
+                tracing::info_span!("commit_shard").in_scope(|| self.generate_and_commit(record));
+            let values = record.public_values::<Val<SC>>();
+            self.observe(challenger, data.main_commit.clone(), &values);
+            shard_data.push((data, values));
+        }
+        if shard_data.is_empty() {
+            return Err(CpuProverError::EmptyRecord);
+        }
+
+        let mut shard_proofs = Vec::with_capacity(shard_data.len());
+        for (data, public_values) in shard_data {
+            let shard_proof = tracing::info_span!("prove_shard")
+                .in_scope(|| self.open(pk, data, &public_values, &mut challenger.clone()))?;
+            shard_proofs.push(shard_proof);
+        }
 
-        Ok(MachineProof { shard_proof })
+        Ok(MachineProof { shard_proofs })
     }
 }
 
@@ -601,9 +717,30 @@ where
     }
 }
 
+/// The values of a trace opened at `zeta`, and at the next point unless the trace is
+/// `local_only`, in which case the next values are zeros.
+fn air_opened_values<EF: Field>(
+    op: Vec<Vec<EF>>,
+    local_only: bool,
+) -> Result<AirOpenedValues<EF>, CpuProverError> {
+    if local_only {
+        let [local] = op.try_into().map_err(|_| CpuProverError::OpeningShapeMismatch)?;
+        let width = local.len();
+        Ok(AirOpenedValues { local, next: vec![EF::ZERO; width] })
+    } else {
+        let [local, next] = op.try_into().map_err(|_| CpuProverError::OpeningShapeMismatch)?;
+        Ok(AirOpenedValues { local, next })
+    }
+}
+
 impl Display for CpuProverError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "DefaultProverError")
+        match self {
+            CpuProverError::EmptyRecord => write!(f, "the record includes no chip"),
+            CpuProverError::OpeningShapeMismatch => {
+                write!(f, "the PCS openings do not have the requested shape")
+            }
+        }
     }
 }
 
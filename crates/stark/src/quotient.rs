@@ -1,5 +1,5 @@
 use p3_air::Air;
-use p3_commit::PolynomialSpace;
+use p3_commit::{LagrangeSelectors, PolynomialSpace};
 use p3_field::{FieldAlgebra, FieldExtensionAlgebra, PackedValue};
 use p3_matrix::{dense::RowMajorMatrixView, stack::VerticalPair, Matrix};
 use p3_maybe_rayon::prelude::*;
@@ -12,6 +12,9 @@ use super::{
 use crate::air::MachineAir;
 
 /// Computes the quotient values.
+///
+/// `sels` are the selectors of `trace_domain` on `quotient_domain`, which the prover shares
+/// between the chips with the same domains.
 #[allow(clippy::needless_pass_by_value)]
 #[allow(clippy::too_many_arguments)]
 #[allow(clippy::too_many_lines)]
@@ -20,10 +23,12 @@ pub fn quotient_values<SC, A, Mat>(
     local_cumulative_sum: &SC::Challenge,
     trace_domain: Domain<SC>,
     quotient_domain: Domain<SC>,
+    sels: &LagrangeSelectors<Vec<Val<SC>>>,
     preprocessed_trace_on_quotient_domain: Option<Mat>,
     main_trace_on_quotient_domain: Mat,
     permutation_trace_on_quotient_domain: Mat,
     perm_challenges: &[PackedChallenge<SC>],
+    public_values: &[Val<SC>],
     alpha: SC::Challenge,
 ) -> Vec<SC::Challenge>
 where
@@ -36,7 +41,6 @@ where
         preprocessed_trace_on_quotient_domain.as_ref().map_or(1, p3_matrix::Matrix::width);
     let main_width = main_trace_on_quotient_domain.width();
     let perm_width = permutation_trace_on_quotient_domain.width();
-    let sels = trace_domain.selectors_on_coset(quotient_domain);
 
     let qdb = log2_strict_usize(quotient_domain.size()) - log2_strict_usize(trace_domain.size());
     let next_step = 1 << qdb;
@@ -142,6 +146,7 @@ where
                 ),
                 perm_challenges,
                 cumulative_sum: &packed_local_cumulative_sum,
+                public_values,
                 is_first_row,
                 is_last_row,
                 is_transition,
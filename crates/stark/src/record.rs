@@ -1,5 +1,10 @@
+use p3_field::Field;
+
 /// A record that can be proven by a machine.
 pub trait MachineRecord: Default + Sized + Send + Sync + Clone {
     /// Appends two records together.
     fn append(&mut self, other: &mut Self);
+
+    /// The public values of the proof of this record.
+    fn public_values<F: Field>(&self) -> Vec<F>;
 }
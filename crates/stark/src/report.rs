@@ -0,0 +1,212 @@
+use std::fmt::{self, Display};
+
+use p3_air::{Air, BaseAir, VirtualPairCol};
+use p3_field::Field;
+use p3_uni_stark::{
+    get_symbolic_constraints, Entry, SymbolicAirBuilder, SymbolicExpression, SymbolicVariable,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    air::MachineAir, lookup::Lookup, Chip, StarkGenericConfig, StarkMachine, Val, PROOF_MAX_NUM_PVS,
+};
+
+/// The constraints of every chip of a machine, rendered for review.
+///
+/// The report serializes to JSON with serde, and [`Display`] renders it as text. The columns
+/// are named by their index in the trace, `local.main[i]` or `next.main[i]` for the main trace,
+/// which follows the order of the fields of the chip's columns struct.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConstraintReport {
+    /// The chips, in the order of the machine.
+    pub chips: Vec<ChipConstraints>,
+}
+
+/// The constraints and the lookups of a chip.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChipConstraints {
+    /// The name of the chip.
+    pub name: String,
+    /// The width of the preprocessed trace.
+    pub preprocessed_width: usize,
+    /// The width of the main trace.
+    pub main_width: usize,
+    /// The polynomials that must vanish on every row.
+    pub constraints: Vec<RenderedConstraint>,
+    /// The lookups the chip sends.
+    pub sends: Vec<RenderedLookup>,
+    /// The lookups the chip receives.
+    pub receives: Vec<RenderedLookup>,
+}
+
+/// A constraint of a chip.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenderedConstraint {
+    /// The degree of the constraint, in the trace polynomials.
+    pub degree: usize,
+    /// The polynomial that must vanish.
+    pub expr: String,
+}
+
+/// A lookup of a chip.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenderedLookup {
+    /// The kind of the lookup.
+    pub kind: String,
+    /// The values looked up.
+    pub values: Vec<String>,
+    /// The multiplicity of the lookup.
+    pub multiplicity: String,
+}
+
+impl<SC: StarkGenericConfig, A: MachineAir<Val<SC>>> StarkMachine<SC, A> {
+    /// Renders the constraints of every chip of the machine, for audits.
+    pub fn constraint_report(&self) -> ConstraintReport
+    where
+        A: Air<SymbolicAirBuilder<Val<SC>>>,
+    {
+        ConstraintReport { chips: self.chips().iter().map(Chip::constraints).collect() }
+    }
+}
+
+impl<F: Field, A: MachineAir<F>> Chip<F, A> {
+    /// Renders the constraints and the lookups of the chip.
+    ///
+    /// The permutation constraints that enforce the lookups are left out, since they are the
+    /// same for every chip.
+    pub fn constraints(&self) -> ChipConstraints
+    where
+        A: Air<SymbolicAirBuilder<F>>,
+    {
+        let preprocessed_width = self.preprocessed_width();
+        let constraints =
+            get_symbolic_constraints(self.air(), preprocessed_width, PROOF_MAX_NUM_PVS)
+                .iter()
+                .map(|expr| RenderedConstraint {
+                    degree: expr.degree_multiple(),
+                    expr: render(expr),
+                })
+                .collect();
+
+        let symbolic_row = |entry, width| {
+            (0..width).map(|column| SymbolicVariable::new(entry, column)).collect::<Vec<_>>()
+        };
+        let preprocessed = symbolic_row(Entry::Preprocessed { offset: 0 }, preprocessed_width);
+        let main = symbolic_row(Entry::Main { offset: 0 }, self.width());
+        let render_lookup = |lookup: &Lookup<F>| {
+            let apply = |col: &VirtualPairCol<F>| {
+                render(
+                    &col.apply::<SymbolicExpression<F>, SymbolicVariable<F>>(&preprocessed, &main),
+                )
+            };
+            RenderedLookup {
+                kind: lookup.kind.to_string(),
+                values: lookup.values.iter().map(apply).collect(),
+                multiplicity: apply(&lookup.multiplicity),
+            }
+        };
+
+        ChipConstraints {
+            name: self.name(),
+            preprocessed_width,
+            main_width: self.width(),
+            constraints,
+            sends: self.sends().iter().map(render_lookup).collect(),
+            receives: self.receives().iter().map(render_lookup).collect(),
+        }
+    }
+}
+
+/// Renders a symbolic expression, with as few parentheses as the precedence allows.
+pub fn render<F: Field>(expr: &SymbolicExpression<F>) -> String {
+    match expr {
+        SymbolicExpression::Variable(v) => match v.entry {
+            Entry::Preprocessed { offset } => format!("{}.preprocessed[{}]", row(offset), v.index),
+            Entry::Main { offset } => format!("{}.main[{}]", row(offset), v.index),
+            Entry::Permutation { offset } => format!("{}.permutation[{}]", row(offset), v.index),
+            Entry::Public => format!("public[{}]", v.index),
+            Entry::Challenge => format!("challenge[{}]", v.index),
+        },
+        SymbolicExpression::IsFirstRow => "is_first_row".to_string(),
+        SymbolicExpression::IsLastRow => "is_last_row".to_string(),
+        SymbolicExpression::IsTransition => "is_transition".to_string(),
+        SymbolicExpression::Constant(c) => c.to_string(),
+        SymbolicExpression::Add { x, y, .. } => format!("{} + {}", render(x), render(y)),
+        SymbolicExpression::Sub { x, y, .. } => format!("{} - {}", render(x), render_operand(y)),
+        SymbolicExpression::Neg { x, .. } => format!("-{}", render_operand(x)),
+        SymbolicExpression::Mul { x, y, .. } => {
+            format!("{} * {}", render_operand(x), render_operand(y))
+        }
+    }
+}
+
+/// Renders an operand of a product, a difference or a negation.
+fn render_operand<F: Field>(expr: &SymbolicExpression<F>) -> String {
+    match expr {
+        SymbolicExpression::Add { .. } | SymbolicExpression::Sub { .. } => {
+            format!("({})", render(expr))
+        }
+        _ => render(expr),
+    }
+}
+
+const fn row(offset: usize) -> &'static str {
+    match offset {
+        0 => "local",
+        _ => "next",
+    }
+}
+
+impl Display for ConstraintReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for chip in &self.chips {
+            writeln!(
+                f,
+                "{} (preprocessed width {}, main width {})",
+                chip.name, chip.preprocessed_width, chip.main_width
+            )?;
+            for constraint in &chip.constraints {
+                writeln!(f, "  [degree {}] {} = 0", constraint.degree, constraint.expr)?;
+            }
+            for (direction, lookups) in [("send", &chip.sends), ("receive", &chip.receives)] {
+                for lookup in lookups {
+                    writeln!(
+                        f,
+                        "  {direction} {}({}) with multiplicity {}",
+                        lookup.kind,
+                        lookup.values.join(", "),
+                        lookup.multiplicity
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_field::FieldAlgebra;
+    use p3_koala_bear::KoalaBear;
+
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        type F = KoalaBear;
+
+        let x = SymbolicVariable::<F>::new(Entry::Main { offset: 0 }, 0);
+        let y = SymbolicVariable::<F>::new(Entry::Main { offset: 1 }, 2);
+        let expr = (x + y) * (x - F::ONE) - (y - x);
+        assert_eq!(
+            render(&expr),
+            "(local.main[0] + next.main[2]) * (local.main[0] - 1) \
+             - (next.main[2] - local.main[0])"
+        );
+
+        let public = SymbolicVariable::<F>::new(Entry::Public, 3);
+        let expr = SymbolicExpression::IsLastRow * (SymbolicExpression::from(x) - public);
+        assert_eq!(render(&expr), "is_last_row * (local.main[0] - public[3])");
+        assert_eq!(expr.degree_multiple(), 2);
+    }
+}
@@ -0,0 +1,128 @@
+use std::fmt::{self, Display};
+
+use serde::Serialize;
+
+use crate::{AirOpenedValues, ChipOpenedValues, ShardProof, StarkGenericConfig};
+
+/// The serialized size of a section of a proof, and of its subsections.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProofSection {
+    pub name: String,
+    /// The number of bytes of the section when serialized with bincode.
+    pub bytes: u64,
+    pub children: Vec<ProofSection>,
+}
+
+/// The number of bytes of `value` when serialized with bincode.
+pub fn serialized_size<T: Serialize + ?Sized>(value: &T) -> u64 {
+    bincode::serialized_size(value).expect("failed to compute the serialized size")
+}
+
+impl ProofSection {
+    /// A section without subsections.
+    pub fn leaf<T: Serialize + ?Sized>(name: impl Into<String>, value: &T) -> Self {
+        Self { name: name.into(), bytes: serialized_size(value), children: vec![] }
+    }
+
+    /// A section made of `children`, whose size is `bytes`.
+    ///
+    /// `bytes` can exceed the sum of the children because of length prefixes.
+    pub fn node(name: impl Into<String>, bytes: u64, children: Vec<ProofSection>) -> Self {
+        Self { name: name.into(), bytes, children }
+    }
+
+    /// Finds the direct subsection called `name`.
+    pub fn child_mut(&mut self, name: &str) -> Option<&mut ProofSection> {
+        self.children.iter_mut().find(|child| child.name == name)
+    }
+
+    fn fmt_indented(&self, f: &mut fmt::Formatter<'_>, depth: usize, total: u64) -> fmt::Result {
+        let percent = if total == 0 { 0.0 } else { 100.0 * self.bytes as f64 / total as f64 };
+        let name = format!("{:indent$}{}", "", self.name, indent = 2 * depth);
+        writeln!(f, "{name:<40} {:>12} {percent:>7.2}%", self.bytes)?;
+        for child in &self.children {
+            child.fmt_indented(f, depth + 1, total)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for ProofSection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<40} {:>12} {:>8}", "section", "bytes", "share")?;
+        self.fmt_indented(f, 0, self.bytes)
+    }
+}
+
+fn air_opened_values_section<T: Serialize>(
+    name: &str,
+    values: &AirOpenedValues<T>,
+) -> ProofSection {
+    ProofSection::node(
+        name,
+        serialized_size(values),
+        vec![ProofSection::leaf("local", &values.local), ProofSection::leaf("next", &values.next)],
+    )
+}
+
+fn chip_opened_values_section<T: Serialize>(
+    name: &str,
+    values: &ChipOpenedValues<T>,
+) -> ProofSection {
+    ProofSection::node(
+        name,
+        serialized_size(values),
+        vec![
+            air_opened_values_section("preprocessed", &values.preprocessed),
+            air_opened_values_section("main", &values.main),
+            air_opened_values_section("permutation", &values.permutation),
+            ProofSection::leaf("quotient", &values.quotient),
+            ProofSection::leaf("cumulative sum", &values.cumulative_sum),
+            ProofSection::leaf("log degree", &values.log_degree),
+        ],
+    )
+}
+
+impl<SC: StarkGenericConfig> ShardProof<SC> {
+    /// Breaks the serialized proof down by component: commitments, opened values per chip,
+    /// opening proof and chip ordering.
+    pub fn size_breakdown(&self) -> ProofSection {
+        let commitments = ProofSection::node(
+            "commitments",
+            serialized_size(&self.commitment),
+            vec![
+                ProofSection::leaf("main", &self.commitment.main_commit),
+                ProofSection::leaf("permutation", &self.commitment.permutation_commit),
+                ProofSection::leaf("quotient", &self.commitment.quotient_commit),
+            ],
+        );
+
+        let mut chip_names = vec![String::new(); self.opened_values.chips.len()];
+        for (name, &index) in &self.chip_ordering {
+            if let Some(slot) = chip_names.get_mut(index) {
+                slot.clone_from(name);
+            }
+        }
+        let opened_values = ProofSection::node(
+            "opened values",
+            serialized_size(&self.opened_values),
+            self.opened_values
+                .chips
+                .iter()
+                .zip(chip_names.iter())
+                .map(|(values, name)| chip_opened_values_section(name, values))
+                .collect(),
+        );
+
+        ProofSection::node(
+            "shard proof",
+            serialized_size(self),
+            vec![
+                commitments,
+                opened_values,
+                ProofSection::leaf("opening proof", &self.opening_proof),
+                ProofSection::leaf("chip ordering", &self.chip_ordering),
+            ],
+        )
+    }
+}
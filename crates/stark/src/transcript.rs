@@ -0,0 +1,200 @@
+use std::sync::{Arc, Mutex};
+
+use p3_challenger::{CanObserve, CanSample, CanSampleBits, FieldChallenger, GrindingChallenger};
+use p3_field::{FieldExtensionAlgebra, PrimeField32};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    koala_bear_poseidon2::{Challenge, DigestHash, Val},
+    DIGEST_SIZE,
+};
+
+/// An interaction with a Fiat-Shamir challenger. The field elements are canonical.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TranscriptEvent {
+    /// A field element observed by the challenger.
+    Observe(u32),
+    /// A field element sampled from the challenger. An extension field element is sampled as
+    /// its base field coefficients.
+    Sample(u32),
+    /// A number of `bits` bits sampled from the challenger.
+    SampleBits { bits: usize, value: usize },
+}
+
+/// The interactions with a Fiat-Shamir challenger, in order.
+///
+/// The transcripts of two implementations of the prover or the verifier are the same exactly
+/// if they observe the same values and draw the same challenges.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Transcript {
+    /// The interactions, in order.
+    pub events: Vec<TranscriptEvent>,
+}
+
+impl Transcript {
+    /// The index of the first event where the transcripts differ, if they do.
+    ///
+    /// A transcript that is a strict prefix of the other differs at its end.
+    pub fn first_divergence(&self, other: &Transcript) -> Option<usize> {
+        let (len, other_len) = (self.events.len(), other.events.len());
+        self.events
+            .iter()
+            .zip(&other.events)
+            .position(|(a, b)| a != b)
+            .or_else(|| (len != other_len).then_some(len.min(other_len)))
+    }
+
+    /// The sampled challenges, without the observations.
+    pub fn samples(&self) -> impl Iterator<Item = &TranscriptEvent> {
+        self.events.iter().filter(|event| !matches!(event, TranscriptEvent::Observe(_)))
+    }
+}
+
+/// A handle on the transcript recorded by a [TranscriptChallenger] and its clones.
+#[derive(Debug, Clone)]
+pub struct TranscriptRecorder(Arc<Mutex<Transcript>>);
+
+impl TranscriptRecorder {
+    /// Returns the transcript recorded so far.
+    pub fn transcript(&self) -> Transcript {
+        self.0.lock().unwrap().clone()
+    }
+
+    fn push(&self, event: TranscriptEvent) {
+        self.0.lock().unwrap().events.push(event);
+    }
+}
+
+/// A challenger which can record its [Transcript].
+///
+/// It behaves as the inner challenger, and records nothing until [TranscriptChallenger::record]
+/// is called. The clones of a recording challenger record to the same transcript, as the
+/// prover and the verifier clone the challenger for each shard.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TranscriptChallenger<C> {
+    inner: C,
+    #[serde(skip)]
+    recorder: Option<TranscriptRecorder>,
+}
+
+impl<C> TranscriptChallenger<C> {
+    /// Wraps `inner`, without recording.
+    pub const fn new(inner: C) -> Self {
+        Self { inner, recorder: None }
+    }
+
+    /// Starts recording the interactions with this challenger and its future clones into a
+    /// new transcript.
+    pub fn record(&mut self) -> TranscriptRecorder {
+        let recorder = TranscriptRecorder(Arc::default());
+        self.recorder = Some(recorder.clone());
+        recorder
+    }
+
+    /// The challenger that is recorded.
+    pub const fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    fn push(&self, event: TranscriptEvent) {
+        if let Some(recorder) = &self.recorder {
+            recorder.push(event);
+        }
+    }
+}
+
+impl<C: CanObserve<Val>> CanObserve<Val> for TranscriptChallenger<C> {
+    fn observe(&mut self, value: Val) {
+        self.push(TranscriptEvent::Observe(value.as_canonical_u32()));
+        self.inner.observe(value);
+    }
+}
+
+impl<C: CanObserve<DigestHash>> CanObserve<DigestHash> for TranscriptChallenger<C> {
+    fn observe(&mut self, value: DigestHash) {
+        let elements: [Val; DIGEST_SIZE] = value.into();
+        for element in elements {
+            self.push(TranscriptEvent::Observe(element.as_canonical_u32()));
+        }
+        self.inner.observe(DigestHash::from(elements));
+    }
+}
+
+impl<C: CanSample<Val>> CanSample<Val> for TranscriptChallenger<C> {
+    fn sample(&mut self) -> Val {
+        let value = self.inner.sample();
+        self.push(TranscriptEvent::Sample(value.as_canonical_u32()));
+        value
+    }
+}
+
+impl<C: CanSample<Challenge>> CanSample<Challenge> for TranscriptChallenger<C> {
+    fn sample(&mut self) -> Challenge {
+        let value = self.inner.sample();
+        for coefficient in value.as_base_slice() {
+            self.push(TranscriptEvent::Sample(coefficient.as_canonical_u32()));
+        }
+        value
+    }
+}
+
+impl<C: CanSampleBits<usize>> CanSampleBits<usize> for TranscriptChallenger<C> {
+    fn sample_bits(&mut self, bits: usize) -> usize {
+        let value = self.inner.sample_bits(bits);
+        self.push(TranscriptEvent::SampleBits { bits, value });
+        value
+    }
+}
+
+impl<C: FieldChallenger<Val>> FieldChallenger<Val> for TranscriptChallenger<C> {}
+
+impl<C: GrindingChallenger<Witness = Val>> GrindingChallenger for TranscriptChallenger<C> {
+    type Witness = Val;
+
+    fn grind(&mut self, bits: usize) -> Val {
+        // Search with the inner challenger, then check the witness as the verifier does, so that
+        // both transcripts have the same events.
+        let witness = self.inner.clone().grind(bits);
+        assert!(self.check_witness(bits, witness));
+        witness
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_field::FieldAlgebra;
+
+    use super::*;
+    use crate::{koala_bear_poseidon2::KoalaBearPoseidon2, StarkGenericConfig};
+
+    #[test]
+    fn test_transcript() {
+        let config = KoalaBearPoseidon2::new();
+        let mut challenger = config.challenger();
+        challenger.observe(Val::from_canonical_u32(7));
+
+        let recorder = challenger.record();
+        challenger.observe(Val::from_canonical_u32(1));
+        let mut clone = challenger.clone();
+        let sample: Val = clone.sample();
+        let bits = clone.sample_bits(4);
+
+        let transcript = recorder.transcript();
+        assert_eq!(
+            transcript.events,
+            vec![
+                TranscriptEvent::Observe(1),
+                TranscriptEvent::Sample(sample.as_canonical_u32()),
+                TranscriptEvent::SampleBits { bits: 4, value: bits },
+            ]
+        );
+        assert_eq!(transcript.samples().count(), 2);
+
+        let mut other = transcript.clone();
+        assert_eq!(transcript.first_divergence(&other), None);
+        other.events.pop();
+        assert_eq!(transcript.first_divergence(&other), Some(2));
+        other.events[0] = TranscriptEvent::Observe(2);
+        assert_eq!(other.first_divergence(&transcript), Some(0));
+    }
+}
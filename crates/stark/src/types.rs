@@ -1,12 +1,15 @@
 #![allow(missing_docs)]
 
-use std::fmt::Debug;
+use std::{
+    fmt::Debug,
+    io::{Read, Write},
+};
 
 use hashbrown::HashMap;
 use p3_matrix::{dense::RowMajorMatrixView, stack::VerticalPair};
 use serde::{Deserialize, Serialize};
 
-use super::{Challenge, Com, OpeningProof, StarkGenericConfig};
+use super::{Challenge, Com, OpeningProof, StarkGenericConfig, Val};
 
 pub type QuotientOpenedValues<T> = Vec<T>;
 
@@ -61,12 +64,17 @@ pub struct ShardOpenedValues<T> {
 }
 
 /// The maximum number of elements that can be stored in the public values vec.
-pub const PROOF_MAX_NUM_PVS: usize = 0;
+///
+/// A shard has as many public values: the digests of the inputs read and of the outputs written
+/// in the shard, their numbers, the number of cycles, the states of the execution at the start
+/// of the shard and after it, whether it is the last shard, and the length of the program.
+pub const PROOF_MAX_NUM_PVS: usize = 31;
 
 #[derive(Serialize, Deserialize, Clone)]
 #[serde(bound = "")]
 pub struct ShardProof<SC: StarkGenericConfig> {
     pub commitment: ShardCommitment<Com<SC>>,
+    pub public_values: Vec<Val<SC>>,
     pub opened_values: ShardOpenedValues<Challenge<SC>>,
     pub opening_proof: OpeningProof<SC>,
     pub chip_ordering: HashMap<String, usize>,
@@ -88,13 +96,24 @@ impl<T: Send + Sync + Clone> AirOpenedValues<T> {
 }
 
 impl<SC: StarkGenericConfig> ShardProof<SC> {
+    /// Serializes the proof with bincode into `writer`, without buffering it in memory.
+    pub fn write_to(&self, writer: impl Write) -> bincode::Result<()> {
+        bincode::serialize_into(writer, self)
+    }
+
+    /// Deserializes a proof written by [`ShardProof::write_to`].
+    pub fn read_from(reader: impl Read) -> bincode::Result<Self> {
+        bincode::deserialize_from(reader)
+    }
+
     pub fn cumulative_sum(&self) -> Challenge<SC> {
         self.opened_values.chips.iter().map(|c| c.cumulative_sum).sum()
     }
 
-    pub fn log_degree_cpu(&self) -> usize {
-        let idx = self.chip_ordering.get("Cpu").expect("Cpu chip not found");
-        self.opened_values.chips[*idx].log_degree
+    /// The log degree of the Cpu chip, or `None` if the proof has no opening for it.
+    pub fn log_degree_cpu(&self) -> Option<usize> {
+        let idx = self.chip_ordering.get("Cpu")?;
+        self.opened_values.chips.get(*idx).map(|values| values.log_degree)
     }
 
     pub fn contains_cpu(&self) -> bool {
@@ -105,7 +124,8 @@ impl<SC: StarkGenericConfig> ShardProof<SC> {
 #[derive(Serialize, Deserialize, Clone)]
 #[serde(bound = "")]
 pub struct MachineProof<SC: StarkGenericConfig> {
-    pub shard_proof: ShardProof<SC>,
+    /// The proofs of the shards of the execution, in order.
+    pub shard_proofs: Vec<ShardProof<SC>>,
 }
 
 impl<SC: StarkGenericConfig> Debug for MachineProof<SC> {
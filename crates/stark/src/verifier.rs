@@ -5,16 +5,15 @@ use std::{
 };
 
 use itertools::Itertools;
-use num_traits::cast::ToPrimitive;
-use p3_air::Air;
+use p3_air::{Air, BaseAir};
 use p3_challenger::{CanObserve, FieldChallenger};
 use p3_commit::{LagrangeSelectors, Pcs, PolynomialSpace};
-use p3_field::{Field, FieldAlgebra, FieldExtensionAlgebra};
+use p3_field::{Field, FieldAlgebra, FieldExtensionAlgebra, TwoAdicField};
 
 use super::{
     folder::VerifierConstraintFolder,
     types::{AirOpenedValues, ChipOpenedValues, ShardCommitment, ShardProof},
-    Domain, OpeningError, StarkGenericConfig, StarkVerifyingKey, Val,
+    Domain, OpeningError, StarkGenericConfig, StarkVerifyingKey, Val, PROOF_MAX_NUM_PVS,
 };
 use crate::{air::MachineAir, MachineChip};
 
@@ -36,30 +35,54 @@ impl<SC: StarkGenericConfig, A: MachineAir<Val<SC>>> Verifier<SC, A> {
     {
         use itertools::izip;
 
-        let ShardProof { commitment, opened_values, opening_proof, chip_ordering, .. } = proof;
+        let ShardProof {
+            commitment,
+            opened_values,
+            opening_proof,
+            chip_ordering,
+            public_values,
+            ..
+        } = proof;
 
         let pcs = config.pcs();
 
+        // The chip ordering comes from the prover: check that it names exactly `chips`, in
+        // order, so that no chip is unknown or renamed, and that no preprocessed chip is missing.
+        if chip_ordering.len() != chips.len()
+            || chips.iter().enumerate().any(|(i, chip)| chip_ordering.get(&chip.name()) != Some(&i))
+        {
+            return Err(VerificationError::InvalidChipOrdering);
+        }
+        if let Some((name, _, _)) =
+            vk.chip_information.iter().find(|(name, _, _)| !chip_ordering.contains_key(name))
+        {
+            return Err(VerificationError::MissingPreprocessedChip(name.clone()));
+        }
+        if let Some(chip) = chips.iter().find(|chip| {
+            chip.preprocessed_width() > 0 && !vk.chip_ordering.contains_key(&chip.name())
+        }) {
+            return Err(VerificationError::MissingPreprocessedChip(chip.name()));
+        }
+
         if chips.len() != opened_values.chips.len() {
             return Err(VerificationError::ChipOpeningLengthMismatch);
         }
 
-        // Assert that the byte multiplicities don't overflow.
-        let mut max_byte_lookup_mult = 0u64;
-        chips.iter().zip(opened_values.chips.iter()).for_each(|(chip, val)| {
-            max_byte_lookup_mult = max_byte_lookup_mult
-                .checked_add(
-                    (chip.num_sent_byte_lookups() as u64)
-                        .checked_mul(1u64.checked_shl(val.log_degree as u32).unwrap())
-                        .unwrap(),
-                )
-                .unwrap();
-        });
+        if public_values.len() != PROOF_MAX_NUM_PVS {
+            return Err(VerificationError::PublicValuesLengthMismatch(public_values.len()));
+        }
 
-        assert!(
-            max_byte_lookup_mult <= SC::Val::order().to_u64().unwrap(),
-            "Byte multiplicities overflow"
-        );
+        // Check the shape of the openings before using them, so that a malformed proof is
+        // rejected rather than indexed out of bounds.
+        for (chip, values) in chips.iter().zip(opened_values.chips.iter()) {
+            // The trace and quotient domains of the chip must be subgroups of the field.
+            if values.log_degree + chip.log_quotient_degree() > SC::Val::TWO_ADICITY {
+                return Err(VerificationError::LogDegreeTooLarge(chip.name(), values.log_degree));
+            }
+            // The widths come from the chips, not from the key.
+            Self::verify_opening_shape(chip, values, chip.preprocessed_width())
+                .map_err(|e| VerificationError::OpeningShapeError(chip.name(), e))?;
+        }
 
         let log_degrees = opened_values.chips.iter().map(|val| val.log_degree).collect::<Vec<_>>();
 
@@ -73,8 +96,7 @@ impl<SC: StarkGenericConfig, A: MachineAir<Val<SC>>> Verifier<SC, A> {
 
         let ShardCommitment { main_commit, permutation_commit, quotient_commit } = commitment;
 
-        challenger.observe(main_commit.clone());
-
+        // The main commitments and the public values of all the shards are already observed.
         let local_permutation_challenges =
             (0..2).map(|_| challenger.sample_ext_element::<SC::Challenge>()).collect::<Vec<_>>();
 
@@ -203,13 +225,69 @@ impl<SC: StarkGenericConfig, A: MachineAir<Val<SC>>> Verifier<SC, A> {
                 zeta,
                 alpha,
                 &permutation_challenges,
+                public_values,
             )
             .map_err(|_| VerificationError::OodEvaluationMismatch(chip.name()))?;
         }
-        // Verify that the local cumulative sum is zero.
-        let cumulative_sum = proof.cumulative_sum();
-        if cumulative_sum != SC::Challenge::ZERO {
-            return Err(VerificationError::CumulativeSumsError("local cumulative sum is not zero"));
+
+        Ok(())
+    }
+
+    /// The largest multiplicity the byte lookups sent by the chips of a shard can add up to,
+    /// or `None` if it does not fit in a `u64`.
+    pub fn max_byte_lookup_mult(
+        chips: &[&MachineChip<SC, A>],
+        proof: &ShardProof<SC>,
+    ) -> Option<u64> {
+        chips.iter().zip(proof.opened_values.chips.iter()).try_fold(0u64, |mult, (chip, values)| {
+            let rows = 1u64.checked_shl(values.log_degree as u32)?;
+            mult.checked_add((chip.num_sent_byte_lookups() as u64).checked_mul(rows)?)
+        })
+    }
+
+    /// Checks that the opened values of `chip` have the widths of its traces.
+    fn verify_opening_shape(
+        chip: &MachineChip<SC, A>,
+        opening: &ChipOpenedValues<SC::Challenge>,
+        preprocessed_width: usize,
+    ) -> Result<(), OpeningShapeError> {
+        for values in [&opening.preprocessed.local, &opening.preprocessed.next] {
+            if values.len() != preprocessed_width {
+                return Err(OpeningShapeError::PreprocessedWidthMismatch(
+                    preprocessed_width,
+                    values.len(),
+                ));
+            }
+        }
+
+        let main_width = chip.width();
+        for values in [&opening.main.local, &opening.main.next] {
+            if values.len() != main_width {
+                return Err(OpeningShapeError::MainWidthMismatch(main_width, values.len()));
+            }
+        }
+
+        let permutation_width = chip.permutation_width() * SC::Challenge::D;
+        for values in [&opening.permutation.local, &opening.permutation.next] {
+            if values.len() != permutation_width {
+                return Err(OpeningShapeError::PermutationWidthMismatch(
+                    permutation_width,
+                    values.len(),
+                ));
+            }
+        }
+
+        if opening.quotient.len() != chip.quotient_width() {
+            return Err(OpeningShapeError::QuotientWidthMismatch(
+                chip.quotient_width(),
+                opening.quotient.len(),
+            ));
+        }
+        if let Some(chunk) = opening.quotient.iter().find(|chunk| chunk.len() != SC::Challenge::D) {
+            return Err(OpeningShapeError::QuotientChunkSizeMismatch(
+                SC::Challenge::D,
+                chunk.len(),
+            ));
         }
 
         Ok(())
@@ -225,6 +303,7 @@ impl<SC: StarkGenericConfig, A: MachineAir<Val<SC>>> Verifier<SC, A> {
         zeta: SC::Challenge,
         alpha: SC::Challenge,
         permutation_challenges: &[SC::Challenge],
+        public_values: &[Val<SC>],
     ) -> Result<(), OodEvaluationMismatch>
     where
         A: for<'a> Air<VerifierConstraintFolder<'a, SC>>,
@@ -234,8 +313,14 @@ impl<SC: StarkGenericConfig, A: MachineAir<Val<SC>>> Verifier<SC, A> {
         // Recompute the quotient at zeta from the chunks.
         let quotient = Self::recompute_quotient(opening, &qc_domains, zeta);
         // Calculate the evaluations of the constraints at zeta.
-        let folded_constraints =
-            Self::eval_constraints(chip, opening, &sels, alpha, permutation_challenges);
+        let folded_constraints = Self::eval_constraints(
+            chip,
+            opening,
+            &sels,
+            alpha,
+            permutation_challenges,
+            public_values,
+        );
 
         // Check that the constraints match the quotient, i.e.
         //     folded_constraints(zeta) / Z_H(zeta) = quotient(zeta)
@@ -253,6 +338,7 @@ impl<SC: StarkGenericConfig, A: MachineAir<Val<SC>>> Verifier<SC, A> {
         selectors: &LagrangeSelectors<SC::Challenge>,
         alpha: SC::Challenge,
         permutation_challenges: &[SC::Challenge],
+        public_values: &[Val<SC>],
     ) -> SC::Challenge
     where
         A: for<'a> Air<VerifierConstraintFolder<'a, SC>>,
@@ -277,6 +363,7 @@ impl<SC: StarkGenericConfig, A: MachineAir<Val<SC>>> Verifier<SC, A> {
             perm: perm_opening.view(),
             perm_challenges: permutation_challenges,
             local_cumulative_sum: &opening.cumulative_sum,
+            public_values,
             is_first_row: selectors.is_first_row,
             is_last_row: selectors.is_last_row,
             is_transition: selectors.is_transition,
@@ -362,6 +449,17 @@ pub enum VerificationError<SC: StarkGenericConfig> {
     ChipOpeningLengthMismatch,
     /// Cumulative sums error
     CumulativeSumsError(&'static str),
+    /// The number of public values does not match [`PROOF_MAX_NUM_PVS`].
+    PublicValuesLengthMismatch(usize),
+    /// The chip ordering of the proof names chips that are not in the machine, or not in order.
+    InvalidChipOrdering,
+    /// A chip with a preprocessed trace is missing from the proof.
+    MissingPreprocessedChip(String),
+    /// The log degree of the trace of a chip is too large for its domains to be subgroups of
+    /// the field.
+    LogDegreeTooLarge(String, usize),
+    /// The multiplicities of the byte lookups may overflow the field.
+    ByteMultiplicityOverflow,
 }
 
 impl Debug for OpeningShapeError {
@@ -413,6 +511,22 @@ impl<SC: StarkGenericConfig> Debug for VerificationError<SC> {
                 write!(f, "Chip opening length mismatch")
             }
             VerificationError::CumulativeSumsError(s) => write!(f, "cumulative sums error: {}", s),
+            VerificationError::PublicValuesLengthMismatch(len) => {
+                let expected = PROOF_MAX_NUM_PVS;
+                write!(f, "Public values length mismatch: expected {}, got {}", expected, len)
+            }
+            VerificationError::InvalidChipOrdering => {
+                write!(f, "Invalid chip ordering")
+            }
+            VerificationError::MissingPreprocessedChip(chip) => {
+                write!(f, "Missing preprocessed chip {}", chip)
+            }
+            VerificationError::LogDegreeTooLarge(chip, log_degree) => {
+                write!(f, "Log degree of chip {} too large: {}", chip, log_degree)
+            }
+            VerificationError::ByteMultiplicityOverflow => {
+                write!(f, "Byte multiplicities overflow")
+            }
         }
     }
 }
@@ -437,6 +551,22 @@ impl<SC: StarkGenericConfig> Display for VerificationError<SC> {
                 write!(f, "Chip opening length mismatch")
             }
             VerificationError::CumulativeSumsError(s) => write!(f, "cumulative sums error: {}", s),
+            VerificationError::PublicValuesLengthMismatch(len) => {
+                let expected = PROOF_MAX_NUM_PVS;
+                write!(f, "Public values length mismatch: expected {}, got {}", expected, len)
+            }
+            VerificationError::InvalidChipOrdering => {
+                write!(f, "Invalid chip ordering")
+            }
+            VerificationError::MissingPreprocessedChip(chip) => {
+                write!(f, "Missing preprocessed chip {}", chip)
+            }
+            VerificationError::LogDegreeTooLarge(chip, log_degree) => {
+                write!(f, "Log degree of chip {} too large: {}", chip, log_degree)
+            }
+            VerificationError::ByteMultiplicityOverflow => {
+                write!(f, "Byte multiplicities overflow")
+            }
         }
     }
 }
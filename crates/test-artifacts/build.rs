@@ -0,0 +1,70 @@
+//! Compiles the programs under `guests/` into string constants.
+//!
+//! Every `guests/<name>.bf` becomes `pub const <NAME>_BF: &str`, included with
+//! `bf_derive::include_bf!` so that comments and whitespace are stripped and unbalanced brackets
+//! fail the build. Every `guests/<name>.bfl` is compiled with `bf-lang` into a constant of the
+//! same form, and larger programs are generated here.
+
+use std::{env, fmt::Write, fs, path::Path};
+
+const GUESTS_DIR: &str = "guests";
+
+/// The number of bytes sorted by `SORT_BF`.
+const SORT_LEN: usize = 10;
+
+fn main() {
+    println!("cargo:rerun-if-changed={GUESTS_DIR}");
+
+    let mut paths = fs::read_dir(GUESTS_DIR)
+        .expect("failed to read guests directory")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "bf" || ext == "bfl"))
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    let mut out = String::new();
+    for path in paths {
+        println!("cargo:rerun-if-changed={}", path.display());
+
+        let name = path.file_stem().unwrap().to_str().unwrap().to_uppercase().replace('-', "_");
+        let file_name = path.file_name().unwrap().to_str().unwrap();
+        writeln!(out, "/// Compiled from `{GUESTS_DIR}/{file_name}`.").unwrap();
+        if path.extension().unwrap() == "bfl" {
+            let source = fs::read_to_string(&path).unwrap();
+            let code =
+                bf_lang::compile(&source).unwrap_or_else(|err| panic!("{}: {err}", path.display()));
+            writeln!(out, "pub const {name}_BF: &str = {code:?};").unwrap();
+        } else {
+            let include = format!("{GUESTS_DIR}/{file_name}");
+            writeln!(out, "pub const {name}_BF: &str = bf_derive::include_bf!({include:?});")
+                .unwrap();
+        }
+    }
+
+    let code = bf_lang::compile(&sort_network(SORT_LEN)).unwrap();
+    writeln!(out, "/// Generated: sorts {SORT_LEN} bytes of stdin with a bubble sort network.")
+        .unwrap();
+    writeln!(out, "pub const SORT_BF: &str = {code:?};").unwrap();
+
+    let dest = Path::new(&env::var("OUT_DIR").unwrap()).join("programs.rs");
+    fs::write(dest, out).unwrap();
+}
+
+/// A `bf-lang` program reading `len` bytes and writing them in ascending order, with one
+/// compare-and-swap per pair of a bubble sort so that the run takes many cycles per byte.
+fn sort_network(len: usize) -> String {
+    let mut source = String::new();
+    for i in 0..len {
+        writeln!(source, "let x{i} = read();").unwrap();
+    }
+    for end in (1..len).rev() {
+        for i in 0..end {
+            let j = i + 1;
+            writeln!(source, "if x{j} < x{i} {{ let t = x{i}; x{i} = x{j}; x{j} = t; }}").unwrap();
+        }
+    }
+    for i in 0..len {
+        writeln!(source, "write(x{i});").unwrap();
+    }
+    source
+}
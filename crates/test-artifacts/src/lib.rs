@@ -1,5 +1,3 @@
-pub const FIBO_BF: &str = include_str!("../guests/fibo.bf");
-pub const HELLO_BF: &str = include_str!("../guests/hello.bf");
-pub const MOVE_BF: &str = include_str!("../guests/move.bf");
-pub const LOOP_BF: &str = include_str!("../guests/loop.bf");
-pub const PRINTA_BF: &str = include_str!("../guests/printa.bf");
+//! Brainfuck programs used in tests, compiled from `guests/` or generated by the build script.
+
+include!(concat!(env!("OUT_DIR"), "/programs.rs"));
@@ -0,0 +1,37 @@
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+
+use anyhow::Result;
+use clap::Parser;
+
+use bf_sdk::utils::setup_logger;
+use bf_verify_server::{router, KeyRegistry};
+
+/// Serves proof verification over HTTP.
+#[derive(Parser)]
+#[command(name = "bf-verify-server", author, version, about, long_about = None)]
+struct Args {
+    /// A directory of bincode-serialized verifying keys with the `.vk` extension.
+    #[arg(long, env = "BF_VERIFY_KEYS")]
+    keys: PathBuf,
+
+    /// The address to listen on.
+    #[arg(long, env = "BF_VERIFY_ADDR", default_value = "127.0.0.1:3030")]
+    addr: SocketAddr,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    setup_logger();
+    let args = Args::parse();
+
+    let registry = KeyRegistry::load_dir(&args.keys)?;
+    tracing::info!("serving {} verifying keys on {}", registry.len(), args.addr);
+
+    let listener = tokio::net::TcpListener::bind(args.addr).await?;
+    axum::serve(listener, router(Arc::new(registry)))
+        .with_graceful_shutdown(async {
+            tokio::signal::ctrl_c().await.ok();
+        })
+        .await?;
+    Ok(())
+}
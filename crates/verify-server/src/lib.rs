@@ -0,0 +1,202 @@
+//! A stateless HTTP service verifying proofs against a registry of verifying keys.
+//!
+//! The service exposes:
+//!
+//! - `POST /verify` with a JSON body `{ "proof": "<hex>", "vk_digest": "<hex>" }`, where `proof`
+//!   is a proof as written by [BfProofWithPublicValues::to_bytes](bf_sdk::BfProofWithPublicValues)
+//!   and `vk_digest` is the [vk_digest] of a registered key. It answers
+//!   `{ "valid": true, "output": "<hex>" }` or `{ "valid": false, "error": "..." }`.
+//! - `GET /keys`, listing the digests of the registered keys.
+//! - `GET /health`.
+
+use std::{fs, path::Path, sync::Arc};
+
+use anyhow::{Context, Result};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    routing::{get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+
+use bf_sdk::{vk_digest, BfProofWithPublicValues, BfVerifyingKey, VerifyingKeyDigest};
+
+/// The verifying keys accepted by the service, keyed by their digest.
+#[derive(Default)]
+pub struct KeyRegistry {
+    keys: Vec<(VerifyingKeyDigest, BfVerifyingKey)>,
+}
+
+impl KeyRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads every bincode-serialized verifying key with the `.vk` extension in `dir`.
+    pub fn load_dir(dir: &Path) -> Result<Self> {
+        let mut registry = Self::new();
+        let entries =
+            fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))?;
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "vk") {
+                let bytes = fs::read(&path)?;
+                let vk = bincode::deserialize(&bytes)
+                    .with_context(|| format!("invalid verifying key {}", path.display()))?;
+                let digest = registry.insert(vk);
+                tracing::info!("loaded {} as {}", path.display(), hex::encode(digest));
+            }
+        }
+        Ok(registry)
+    }
+
+    /// Registers a verifying key, returning its digest.
+    pub fn insert(&mut self, vk: BfVerifyingKey) -> VerifyingKeyDigest {
+        let digest = vk_digest(&vk);
+        if self.get(&digest).is_none() {
+            self.keys.push((digest, vk));
+        }
+        digest
+    }
+
+    pub fn get(&self, digest: &VerifyingKeyDigest) -> Option<&BfVerifyingKey> {
+        self.keys.iter().find(|(d, _)| d == digest).map(|(_, vk)| vk)
+    }
+
+    /// The digests of the registered keys.
+    pub fn digests(&self) -> impl Iterator<Item = &VerifyingKeyDigest> {
+        self.keys.iter().map(|(digest, _)| digest)
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+/// The body of `POST /verify`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerifyRequest {
+    /// The hex-encoded proof.
+    pub proof: String,
+    /// The hex-encoded digest of the verifying key.
+    pub vk_digest: String,
+}
+
+/// The answer of `POST /verify`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct VerifyResponse {
+    pub valid: bool,
+    /// The hex-encoded output of the program, if the proof is valid.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl VerifyResponse {
+    fn error(status: StatusCode, error: impl ToString) -> (StatusCode, Json<Self>) {
+        (status, Json(Self { error: Some(error.to_string()), ..Default::default() }))
+    }
+}
+
+/// Builds the routes of the service.
+pub fn router(registry: Arc<KeyRegistry>) -> Router {
+    Router::new()
+        .route("/verify", post(verify))
+        .route("/keys", get(keys))
+        .route("/health", get(|| async { "ok" }))
+        .with_state(registry)
+}
+
+async fn verify(
+    State(registry): State<Arc<KeyRegistry>>,
+    Json(request): Json<VerifyRequest>,
+) -> (StatusCode, Json<VerifyResponse>) {
+    let digest = match hex::decode(&request.vk_digest).map(<VerifyingKeyDigest>::try_from) {
+        Ok(Ok(digest)) => digest,
+        _ => return VerifyResponse::error(StatusCode::BAD_REQUEST, "invalid vk digest"),
+    };
+    if registry.get(&digest).is_none() {
+        return VerifyResponse::error(StatusCode::NOT_FOUND, "unknown vk digest");
+    }
+    let Ok(bytes) = hex::decode(&request.proof) else {
+        return VerifyResponse::error(StatusCode::BAD_REQUEST, "invalid proof encoding");
+    };
+    let proof = match BfProofWithPublicValues::from_bytes(&bytes) {
+        Ok(proof) => proof,
+        Err(e) => return VerifyResponse::error(StatusCode::BAD_REQUEST, e),
+    };
+
+    // Verification is CPU bound, so keep it off the async workers.
+    let result = tokio::task::spawn_blocking(move || {
+        let vk = registry.get(&digest).expect("the key was checked above");
+        bf_sdk::verify_proof(&proof, vk).map(|()| proof.output)
+    })
+    .await;
+
+    match result {
+        Ok(Ok(output)) => (
+            StatusCode::OK,
+            Json(VerifyResponse { valid: true, output: Some(hex::encode(output)), error: None }),
+        ),
+        Ok(Err(e)) => VerifyResponse::error(StatusCode::OK, e),
+        Err(e) => VerifyResponse::error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+async fn keys(State(registry): State<Arc<KeyRegistry>>) -> Json<Vec<String>> {
+    Json(registry.digests().map(hex::encode).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{body::Body, http::Request};
+    use tower::ServiceExt;
+
+    use bf_sdk::ProverClient;
+
+    use super::*;
+
+    async fn post_verify(router: Router, request: &VerifyRequest) -> (StatusCode, VerifyResponse) {
+        let request = Request::post("/verify")
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::to_vec(request).unwrap()))
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_verify() {
+        let client = ProverClient::new();
+        let (pk, vk) = client.setup("++.");
+        let proof = client.prove(&pk, vec![]).run().unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("add.vk"), bincode::serialize(&vk).unwrap()).unwrap();
+        let registry = KeyRegistry::load_dir(dir.path()).unwrap();
+        assert_eq!(registry.len(), 1);
+        let router = router(Arc::new(registry));
+
+        let mut request = VerifyRequest {
+            proof: hex::encode(proof.to_bytes().unwrap()),
+            vk_digest: hex::encode(vk_digest(&vk)),
+        };
+        let (status, response) = post_verify(router.clone(), &request).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(response.valid, "{:?}", response.error);
+        assert_eq!(response.output.as_deref(), Some("02"));
+
+        request.vk_digest = hex::encode([0u8; 32]);
+        let (status, response) = post_verify(router, &request).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(!response.valid);
+    }
+}
@@ -0,0 +1,113 @@
+//! The executor compiled to WASM, for running programs in the browser.
+//!
+//! Build the package with
+//!
+//! ```shell
+//! wasm-pack build crates/wasm --target web
+//! ```
+//!
+//! Programs run on the same [Executor] as the prover, so they behave exactly as when executed
+//! natively. See `www/index.html` for a playground using this API.
+
+use bf_core_executor::{ExecutionError, Executor, Program, TAPE_LEN};
+use wasm_bindgen::prelude::*;
+
+/// A program being executed.
+#[wasm_bindgen]
+pub struct Playground {
+    executor: Executor,
+}
+
+#[wasm_bindgen]
+impl Playground {
+    /// Loads a program, skipping whitespace, with the given stdin.
+    #[wasm_bindgen(constructor)]
+    pub fn new(program: &str, stdin: &[u8]) -> Result<Playground, JsError> {
+        let program = Program::from(program).map_err(|e| JsError::new(&e.to_string()))?;
+        Ok(Self { executor: Executor::new(program, stdin.to_vec()) })
+    }
+
+    /// Executes a single instruction, returning whether the program has halted.
+    pub fn step(&mut self) -> Result<bool, JsError> {
+        Ok(self.executor.step()?)
+    }
+
+    /// Executes at most `max_cycles` instructions, returning whether the program has halted.
+    pub fn run(&mut self, max_cycles: u64) -> Result<bool, JsError> {
+        Ok(self.run_cycles(max_cycles)?)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn halted(&self) -> bool {
+        self.executor.is_halted()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn pc(&self) -> u32 {
+        self.executor.state.pc
+    }
+
+    #[wasm_bindgen(getter, js_name = memPtr)]
+    pub fn mem_ptr(&self) -> u32 {
+        self.executor.state.mem_ptr
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn cycles(&self) -> u64 {
+        self.executor.state.global_clk
+    }
+
+    /// The bytes written to stdout so far.
+    #[wasm_bindgen(getter)]
+    pub fn output(&self) -> Vec<u8> {
+        self.executor.state.output_stream.clone()
+    }
+
+    /// The `len` memory cells starting at `start`. The memory pointer wraps around at
+    /// [`TAPE_LEN`], so `start` can be given as `TAPE_LEN` minus the distance to the left of 0.
+    pub fn tape(&self, start: u32, len: u32) -> Vec<u8> {
+        (0..len)
+            .map(|i| {
+                let addr = ((u64::from(start) + u64::from(i)) % u64::from(TAPE_LEN)) as u32;
+                self.executor.state.memory_access.get(addr).map_or(0, |record| record.value)
+            })
+            .collect()
+    }
+}
+
+impl Playground {
+    fn run_cycles(&mut self, max_cycles: u64) -> Result<bool, ExecutionError> {
+        for _ in 0..max_cycles {
+            if self.executor.step()? {
+                return Ok(true);
+            }
+        }
+        Ok(self.executor.is_halted())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_playground() {
+        // `JsError` is not `Debug`, so results are matched instead of unwrapped.
+        let Ok(mut playground) = Playground::new("+++[>++<-]>.", &[]) else {
+            panic!("failed to load the program");
+        };
+        assert!(matches!(playground.step(), Ok(false)));
+        assert_eq!(playground.tape(0, 2), [1, 0]);
+
+        assert!(matches!(playground.run(5), Ok(false)));
+        assert!(matches!(playground.run(1000), Ok(true)));
+        assert_eq!(playground.output(), [6]);
+        assert_eq!(playground.tape(0, 2), [0, 6]);
+        assert_eq!(playground.mem_ptr(), 1);
+
+        // The same program run natively.
+        let mut executor = Executor::new(Program::from("+++[>++<-]>.").unwrap(), vec![]);
+        executor.run().unwrap();
+        assert_eq!(playground.cycles(), executor.state.global_clk);
+    }
+}
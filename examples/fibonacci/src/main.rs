@@ -1,7 +1,15 @@
 use bf_sdk::{utils, ProverClient};
 
 /// The ELF we want to execute inside the zkVM.
-const ELF: &str = ",>+>+<<[->>[->+>+<<]<[->>+<<]>>[-<+>]>[-<<<+>>>]<<<<]>>.";
+const ELF: &str = bf_sdk::bf! {
+    ,>+>+<<                          // read n, start with 1 and 1
+    [
+        ->>                          // one step fewer
+        [->+>+<<]<[->>+<<]>>         // copy the last term, add the other
+        [-<+>]>[-<<<+>>>]<<<<        // the sum is the new last term
+    ]
+    >>.                              // write the last term
+};
 
 fn main() {
     // Setup logging.
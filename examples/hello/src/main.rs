@@ -1,7 +1,13 @@
 use bf_sdk::{utils, ProverClient};
 
 /// The ELF we want to execute inside the zkVM.
-const ELF: &str = ">++++++++[<+++++++++>-]<.>++++[<+++++++>-]<+.+++++++..+++.>>++++++[<+++++++>-]<+";
+const ELF: &str = bf_sdk::bf! {
+    >++++++++[<+++++++++>-]<.        // 'H'
+    >++++[<+++++++>-]<+.             // 'e'
+    +++++++..                        // 'll'
+    +++.                             // 'o'
+    >>++++++[<+++++++>-]<+           // 43, not written
+};
 
 fn main() {
     // Setup logging.